@@ -0,0 +1,146 @@
+use crate::splitter::{Splitter, SplitterDescriptor};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Length of the hex-encoded content hash used as the catalog version.
+const VERSION_LENGTH: usize = 16;
+
+/// Every option a client can discover, served as one versioned document.
+#[derive(Debug, Serialize)]
+pub struct Catalog {
+    pub catalog_version: String,
+    #[serde(flatten)]
+    pub content: CatalogContent,
+}
+
+/// The versioned part of the catalog.
+#[derive(Debug, Serialize)]
+pub struct CatalogContent {
+    pub splitters: Vec<SplitterDescriptor>,
+    pub post_processing: Vec<OptionInfo>,
+}
+
+/// Describes a request option applied on top of whichever splitter runs.
+#[derive(Debug, Serialize)]
+pub struct OptionInfo {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    pub description: &'static str,
+}
+
+/// Wraps the splitter list the way `GET /api/splitters` returns it.
+#[derive(Debug, Serialize)]
+pub struct SplittersResponse<'a> {
+    pub splitters: &'a [SplitterDescriptor],
+    pub post_processing: &'a [OptionInfo],
+}
+
+impl Catalog {
+    /// Assembles the catalog and stamps it with a version derived from its content.
+    pub fn build() -> Self {
+        let content = CatalogContent {
+            splitters: Splitter::descriptors(),
+            post_processing: post_processing(),
+        };
+        let catalog_version = content_version(&content);
+        Self {
+            catalog_version,
+            content,
+        }
+    }
+
+    /// Returns the ETag header value for this catalog.
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.catalog_version)
+    }
+
+    /// Returns the splitter section as served by `GET /api/splitters`.
+    pub fn splitters(&self) -> SplittersResponse<'_> {
+        SplittersResponse {
+            splitters: &self.content.splitters,
+            post_processing: &self.content.post_processing,
+        }
+    }
+}
+
+/// Hashes the serialized content so any change produces a new version.
+fn content_version(content: &CatalogContent) -> String {
+    let json = serde_json::to_vec(content).expect("catalog content is always serializable");
+    let digest = Sha256::digest(&json);
+    digest
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>()[..VERSION_LENGTH]
+        .to_string()
+}
+
+fn post_processing() -> Vec<OptionInfo> {
+    vec![
+        OptionInfo {
+            name: "min_words",
+            type_name: "number (optional)",
+            description: "Merges slides with fewer words than this into the previous slide",
+        },
+        OptionInfo {
+            name: "dedupe",
+            type_name: "boolean (default: false)",
+            description: "Removes slides identical to an earlier slide (ignoring surrounding whitespace), keeping the first",
+        },
+        OptionInfo {
+            name: "strip_markdown",
+            type_name: "boolean (default: false)",
+            description: "Converts Markdown to plain text: drops emphasis and heading markers, keeps link text, turns list markers into bullets",
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const GOLDEN_CATALOG: &str = include_str!("../tests/golden/catalog.json");
+
+    /// Pins the serialized catalog. If this fails after an intentional change,
+    /// update `tests/golden/catalog.json` with the new output.
+    #[rstest]
+    fn test_catalog_matches_golden_file() {
+        let json = serde_json::to_string_pretty(&Catalog::build()).unwrap();
+        assert_eq!(json, GOLDEN_CATALOG.trim_end());
+    }
+
+    #[rstest]
+    fn test_catalog_version_is_deterministic() {
+        assert_eq!(
+            Catalog::build().catalog_version,
+            Catalog::build().catalog_version
+        );
+        assert_eq!(Catalog::build().catalog_version.len(), VERSION_LENGTH);
+    }
+
+    #[rstest]
+    fn test_etag_is_quoted_version() {
+        let catalog = Catalog::build();
+        assert_eq!(catalog.etag(), format!("\"{}\"", catalog.catalog_version));
+    }
+
+    #[rstest]
+    fn test_splitters_endpoint_is_filter_over_catalog() {
+        let catalog = Catalog::build();
+        let full: serde_json::Value = serde_json::to_value(&catalog).unwrap();
+        let filtered: serde_json::Value = serde_json::to_value(catalog.splitters()).unwrap();
+        assert_eq!(filtered["splitters"], full["splitters"]);
+        assert_eq!(filtered["post_processing"], full["post_processing"]);
+    }
+
+    #[rstest]
+    fn test_splitter_config_omitted_when_empty() {
+        let json = serde_json::to_value(Catalog::build().splitters()).unwrap();
+        assert!(json["splitters"][0].get("config").is_none());
+        assert_eq!(
+            json["splitters"][2]["config"]["max_words"],
+            "number (default: 50)"
+        );
+    }
+}
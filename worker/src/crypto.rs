@@ -0,0 +1,294 @@
+use crate::{error::AppError, oauth::Token};
+use aes_gcm::{
+    Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, generic_array::typenum::Unsigned},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use worker::RouteContext;
+
+/// Secret the key that encrypts stored tokens is derived from.
+pub const KEY_SECRET: &str = "TOKEN_ENCRYPTION_KEY";
+
+/// Secret session cookies are signed with.
+pub const SIGNING_KEY_SECRET: &str = "SESSION_SIGNING_KEY";
+
+/// The signing key being rotated out; cookies it signed are still accepted.
+pub const PREVIOUS_SIGNING_KEY_SECRET: &str = "SESSION_SIGNING_KEY_PREVIOUS";
+
+/// Length in bytes of an AES-GCM nonce.
+const NONCE_LENGTH: usize = <Aes256Gcm as aes_gcm::AeadCore>::NonceSize::USIZE;
+
+/// Encrypts tokens before they are written to KV and decrypts them on read,
+/// so KV read access alone does not yield working Google credentials.
+///
+/// A sealed token is stored as `nonce.ciphertext`, both base64url encoded,
+/// with a fresh random nonce for every write.
+pub struct TokenCipher {
+    cipher: Aes256Gcm,
+}
+
+impl TokenCipher {
+    /// Derives the AES-256 key from the secret with SHA-256.
+    pub fn new(secret: &str) -> Self {
+        let key = Sha256::digest(secret.as_bytes());
+        Self {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    /// Builds the cipher from the `TOKEN_ENCRYPTION_KEY` secret, reporting a
+    /// missing or empty secret as a configuration error.
+    pub fn from_ctx(ctx: &RouteContext<()>) -> Result<Self, AppError> {
+        match ctx.var(KEY_SECRET) {
+            Ok(secret) if !secret.to_string().is_empty() => Ok(Self::new(&secret.to_string())),
+            _ => Err(AppError::SecretNotConfigured(KEY_SECRET.to_string())),
+        }
+    }
+
+    /// Encrypts a token for storage.
+    pub fn seal(&self, token: &Token) -> Result<String, AppError> {
+        let plaintext = serde_json::to_vec(token).map_err(anyhow::Error::from)?;
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| anyhow::anyhow!("failed to encrypt token"))?;
+        Ok(format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(nonce),
+            URL_SAFE_NO_PAD.encode(ciphertext)
+        ))
+    }
+
+    /// Decrypts a stored token, or returns `None` when the data was not
+    /// sealed with this key or has been tampered with.
+    ///
+    /// Tokens stored as plain JSON before encryption was added are still
+    /// accepted, and are sealed the next time they are written.
+    pub fn open(&self, data: &str) -> Option<Token> {
+        if let Ok(token) = serde_json::from_str(data) {
+            return Some(token);
+        }
+        let (nonce, ciphertext) = data.split_once('.')?;
+        let nonce = URL_SAFE_NO_PAD.decode(nonce).ok()?;
+        if nonce.len() != NONCE_LENGTH {
+            return None;
+        }
+        let ciphertext = URL_SAFE_NO_PAD.decode(ciphertext).ok()?;
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .ok()?;
+        serde_json::from_slice(&plaintext).ok()
+    }
+}
+
+/// Signs session IDs for the `sid` cookie with HMAC-SHA256, so a cookie that
+/// was not issued by the worker is rejected without a KV read, and rotating
+/// the key ends every session.
+///
+/// A signed cookie is `session_id.signature`, the signature base64url
+/// encoded. Cookies signed with the previous key stay valid, so the key can
+/// be rotated without signing everyone out at once.
+pub struct SessionSigner {
+    current: Vec<u8>,
+    previous: Option<Vec<u8>>,
+}
+
+impl SessionSigner {
+    pub fn new(current: &str, previous: Option<&str>) -> Self {
+        Self {
+            current: current.as_bytes().to_vec(),
+            previous: previous.map(|key| key.as_bytes().to_vec()),
+        }
+    }
+
+    /// Builds the signer from the `SESSION_SIGNING_KEY` secret and, while a
+    /// rotation is under way, `SESSION_SIGNING_KEY_PREVIOUS`.
+    pub fn from_ctx(ctx: &RouteContext<()>) -> Result<Self, AppError> {
+        let secret = |name| {
+            ctx.var(name)
+                .ok()
+                .map(|secret| secret.to_string())
+                .filter(|secret| !secret.is_empty())
+        };
+        let current = secret(SIGNING_KEY_SECRET)
+            .ok_or_else(|| AppError::SecretNotConfigured(SIGNING_KEY_SECRET.to_string()))?;
+        let previous = secret(PREVIOUS_SIGNING_KEY_SECRET);
+        Ok(Self::new(&current, previous.as_deref()))
+    }
+
+    /// Returns the cookie value for a session ID, signed with the current key.
+    pub fn sign(&self, session_id: &str) -> String {
+        let signature = signature(&self.current, session_id);
+        format!("{session_id}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Returns the session ID from a cookie value whose signature was made
+    /// with the current or the previous key.
+    pub fn verify<'a>(&self, cookie: &'a str) -> Option<&'a str> {
+        let (session_id, given) = cookie.rsplit_once('.')?;
+        let given = URL_SAFE_NO_PAD.decode(given).ok()?;
+        let signed_with = |key: &[u8]| constant_time_eq(&signature(key, session_id), &given);
+        let valid = signed_with(&self.current) || self.previous.as_deref().is_some_and(signed_with);
+        valid.then_some(session_id)
+    }
+}
+
+/// HMAC-SHA256 of the session ID under the key.
+fn signature(key: &[u8], session_id: &str) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(session_id.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Compares two byte strings in time that depends only on their lengths, so
+/// timing does not reveal how much of a forged signature was right.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn token() -> Token {
+        Token {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_in: 3_600,
+            token_type: "Bearer".to_string(),
+            scope: "slides drive".to_string(),
+            created_at: 1_000,
+            profile: None,
+        }
+    }
+
+    fn json(token: &Token) -> serde_json::Value {
+        serde_json::to_value(token).unwrap()
+    }
+
+    #[rstest]
+    fn test_round_trip() {
+        let cipher = TokenCipher::new("secret");
+        let sealed = cipher.seal(&token()).unwrap();
+        assert!(!sealed.contains("access"));
+        assert_eq!(json(&cipher.open(&sealed).unwrap()), json(&token()));
+    }
+
+    #[rstest]
+    fn test_each_seal_uses_a_fresh_nonce() {
+        let cipher = TokenCipher::new("secret");
+        assert_ne!(
+            cipher.seal(&token()).unwrap(),
+            cipher.seal(&token()).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn test_tampered_ciphertext_is_rejected() {
+        let cipher = TokenCipher::new("secret");
+        let sealed = cipher.seal(&token()).unwrap();
+        let (nonce, ciphertext) = sealed.split_once('.').unwrap();
+        let mut bytes = URL_SAFE_NO_PAD.decode(ciphertext).unwrap();
+        bytes[0] ^= 1;
+        let tampered = format!("{nonce}.{}", URL_SAFE_NO_PAD.encode(bytes));
+        assert!(cipher.open(&tampered).is_none());
+    }
+
+    #[rstest]
+    fn test_other_key_is_rejected() {
+        let sealed = TokenCipher::new("secret").seal(&token()).unwrap();
+        assert!(TokenCipher::new("rotated").open(&sealed).is_none());
+    }
+
+    #[rstest]
+    fn test_plaintext_token_is_still_readable() {
+        let plaintext = serde_json::to_string(&token()).unwrap();
+        let opened = TokenCipher::new("secret").open(&plaintext).unwrap();
+        assert_eq!(json(&opened), json(&token()));
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::no_separator("abc")]
+    #[case::bad_base64("!!!.!!!")]
+    #[case::short_nonce("AAAA.AAAA")]
+    #[case::json_not_token(r#"{"foo": "bar"}"#)]
+    fn test_malformed_data_is_rejected(#[case] data: &str) {
+        assert!(TokenCipher::new("secret").open(data).is_none());
+    }
+
+    #[rstest]
+    fn test_signed_cookie_verifies() {
+        let signer = SessionSigner::new("current", None);
+        let cookie = signer.sign("abc123");
+        assert!(cookie.starts_with("abc123."));
+        assert_eq!(signer.verify(&cookie), Some("abc123"));
+    }
+
+    #[rstest]
+    #[case::other_session("xyz789")]
+    #[case::extended_session("abc1234")]
+    fn test_signature_for_another_session_is_rejected(#[case] session_id: &str) {
+        let signer = SessionSigner::new("current", None);
+        let cookie = signer.sign("abc123");
+        let (_, signature) = cookie.split_once('.').unwrap();
+        assert_eq!(signer.verify(&format!("{session_id}.{signature}")), None);
+    }
+
+    #[rstest]
+    fn test_tampered_signature_is_rejected() {
+        let signer = SessionSigner::new("current", None);
+        let cookie = signer.sign("abc123");
+        let (_, signature) = cookie.split_once('.').unwrap();
+        let mut bytes = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        bytes[0] ^= 1;
+        let tampered = format!("abc123.{}", URL_SAFE_NO_PAD.encode(bytes));
+        assert_eq!(signer.verify(&tampered), None);
+    }
+
+    #[rstest]
+    #[case::unsigned("abc123")]
+    #[case::empty_signature("abc123.")]
+    #[case::bad_base64("abc123.!!!")]
+    #[case::truncated_signature("abc123.AAAA")]
+    fn test_malformed_cookie_is_rejected(#[case] cookie: &str) {
+        assert_eq!(SessionSigner::new("current", None).verify(cookie), None);
+    }
+
+    #[rstest]
+    fn test_previous_key_is_accepted_during_rotation() {
+        let cookie = SessionSigner::new("old", None).sign("abc123");
+        let rotated = SessionSigner::new("new", Some("old"));
+        assert_eq!(rotated.verify(&cookie), Some("abc123"));
+        // New cookies are signed with the current key only
+        assert_eq!(
+            SessionSigner::new("new", None).verify(&rotated.sign("abc123")),
+            Some("abc123")
+        );
+    }
+
+    #[rstest]
+    fn test_retired_key_is_rejected() {
+        let cookie = SessionSigner::new("old", None).sign("abc123");
+        assert_eq!(SessionSigner::new("new", None).verify(&cookie), None);
+        assert_eq!(
+            SessionSigner::new("new", Some("newer")).verify(&cookie),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case::equal(b"abc", b"abc", true)]
+    #[case::different(b"abc", b"abd", false)]
+    #[case::prefix(b"ab", b"abc", false)]
+    #[case::empty(b"", b"", true)]
+    fn test_constant_time_eq(#[case] a: &[u8], #[case] b: &[u8], #[case] equal: bool) {
+        assert_eq!(constant_time_eq(a, b), equal);
+    }
+}
@@ -0,0 +1,640 @@
+//! Minimal Google Drive client: copying, sharing, deleting and exporting
+//! files, and resolving Drive-hosted images to URLs the Slides API can fetch.
+
+use crate::{error::AppError, oauth::Token};
+use serde::{Deserialize, Serialize};
+use worker::{Fetch, Headers, Method, Request as WorkerRequest, RequestInit, Response, Result};
+
+const DRIVE_API_BASE: &str = "https://www.googleapis.com/drive/v3";
+
+/// Prefix marking an image reference as a Drive file ID, as in `drive:1AbC`.
+const FILE_REF_PREFIX: &str = "drive:";
+
+/// Longest side, in pixels, of the thumbnail requested for Drive images.
+const IMAGE_SIZE: u32 = 1600;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CopyFileRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+/// Access granted to a collaborator the deck is shared with.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareRole {
+    #[default]
+    Reader,
+    Commenter,
+    Writer,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PermissionRequest<'a> {
+    #[serde(rename = "type")]
+    grantee_type: &'static str,
+    role: ShareRole,
+    email_address: &'a str,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DriveFile {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileMetadata {
+    mime_type: String,
+    thumbnail_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileName {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorResponse {
+    error: GoogleError,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleError {
+    message: String,
+    #[serde(default)]
+    errors: Vec<GoogleErrorDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorDetail {
+    #[serde(default)]
+    reason: String,
+}
+
+/// Reason Drive gives for refusing to export a file over its size limit.
+const EXPORT_SIZE_LIMIT_REASON: &str = "exportSizeLimitExceeded";
+
+/// A format Drive can export a presentation to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Pdf,
+    Pptx,
+}
+
+impl ExportFormat {
+    /// MIME type requested from Drive and sent back to the client.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "application/pdf",
+            ExportFormat::Pptx => {
+                "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+            }
+        }
+    }
+
+    /// File extension of the downloaded file, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Pptx => "pptx",
+        }
+    }
+
+    /// Name of the format in error messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::Pptx => "PowerPoint",
+        }
+    }
+}
+
+/// Returns the file ID of a `drive:<fileId>` reference.
+///
+/// Drive IDs only contain letters, digits, `-` and `_`; anything else is not
+/// a Drive reference.
+pub fn file_id(reference: &str) -> Option<&str> {
+    let id = reference.strip_prefix(FILE_REF_PREFIX)?;
+    is_file_id(id).then_some(id)
+}
+
+/// Whether `id` could be a Drive file ID, and so is safe to put in a URL.
+pub fn is_file_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Copies a Drive file, returning the ID of the copy.
+pub async fn copy_file(
+    token: &Token,
+    file_id: &str,
+    name: Option<&str>,
+) -> std::result::Result<String, AppError> {
+    let url = format!("{}/files/{}/copy", DRIVE_API_BASE, file_id);
+
+    let copy_request = CopyFileRequest {
+        name: name.map(str::to_string),
+    };
+
+    let body = serde_json::to_string(&copy_request).map_err(anyhow::Error::from)?;
+
+    let headers = Headers::new();
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(other_error)?;
+    headers
+        .set("Authorization", &format!("Bearer {}", token.access_token))
+        .map_err(other_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(&url, &init).map_err(other_error)?;
+    let mut response = Fetch::Request(request).send().await.map_err(other_error)?;
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(copy_error(response.status_code(), &error_text));
+    }
+
+    let copy: DriveFile = response.json().await.map_err(other_error)?;
+    Ok(copy.id)
+}
+
+/// Exports a presentation in `format`, returning its Drive name and the
+/// export response, whose body is still streaming.
+pub async fn export_file(
+    token: &Token,
+    file_id: &str,
+    format: ExportFormat,
+) -> std::result::Result<(String, Response), AppError> {
+    let name_url = format!("{}/files/{}?fields=name", DRIVE_API_BASE, file_id);
+    let mut response = authorized_get(token, &name_url).await?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(export_error(
+            response.status_code(),
+            file_id,
+            format,
+            &error_text,
+        ));
+    }
+    let file: FileName = response.json().await.map_err(other_error)?;
+
+    let export_url = format!(
+        "{}/files/{}/export?mimeType={}",
+        DRIVE_API_BASE,
+        file_id,
+        format.mime_type()
+    );
+    let mut response = authorized_get(token, &export_url).await?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(export_error(
+            response.status_code(),
+            file_id,
+            format,
+            &error_text,
+        ));
+    }
+
+    Ok((file.name, response))
+}
+
+async fn authorized_get(token: &Token, url: &str) -> std::result::Result<Response, AppError> {
+    let headers = Headers::new();
+    headers
+        .set("Authorization", &format!("Bearer {}", token.access_token))
+        .map_err(other_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Get).with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(url, &init).map_err(other_error)?;
+    Fetch::Request(request).send().await.map_err(other_error)
+}
+
+/// Wraps a failure to reach Google at all, such as a failed fetch.
+pub fn other_error(e: worker::Error) -> AppError {
+    AppError::Other(anyhow::anyhow!(e.to_string()))
+}
+
+/// Maps a failed export to an application error: a rejected token means
+/// signing in again, a file over Drive's export limit is reported as such,
+/// and a file the app may not see is reported as missing.
+fn export_error(status: u16, file_id: &str, format: ExportFormat, error_text: &str) -> AppError {
+    match status {
+        401 => AppError::AuthRequired,
+        403 if exceeds_export_limit(error_text) => AppError::ExportTooLarge(format!(
+            "presentation {file_id} is over Google's export limit of about 100 MB; \
+             remove large images or videos, or split the deck, and try again"
+        )),
+        403 | 404 => AppError::NotFound(format!(
+            "presentation {file_id} was not found or is not shared with this app"
+        )),
+        _ => AppError::GoogleSlides(format!(
+            "Failed to export {file_id} as {}: {}",
+            format.name(),
+            error_message(error_text)
+        )),
+    }
+}
+
+/// Whether a Drive error says the file is too large to export.
+fn exceeds_export_limit(error_text: &str) -> bool {
+    serde_json::from_str::<GoogleErrorResponse>(error_text).is_ok_and(|response| {
+        response
+            .error
+            .errors
+            .iter()
+            .any(|detail| detail.reason == EXPORT_SIZE_LIMIT_REASON)
+    })
+}
+
+/// Builds a `Content-Disposition` header downloading `title` in `format`.
+///
+/// The plain `filename` keeps only characters safe in a quoted header value;
+/// `filename*` carries the full title, percent-encoded, for clients that
+/// support it.
+pub fn content_disposition(title: &str, format: ExportFormat) -> String {
+    let title = match title.trim() {
+        "" => "presentation",
+        title => title,
+    };
+    let fallback = title
+        .chars()
+        .map(|c| match c {
+            ' ' | '-' | '_' | '.' | '(' | ')' => c,
+            c if c.is_ascii_alphanumeric() => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    let encoded = title
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect::<String>();
+    let extension = format.extension();
+    format!(
+        "attachment; filename=\"{fallback}.{extension}\"; filename*=UTF-8''{encoded}.{extension}"
+    )
+}
+
+/// Gives one user access to a Drive file, optionally emailing them about it.
+pub async fn share_file(
+    token: &Token,
+    file_id: &str,
+    email: &str,
+    role: ShareRole,
+    send_notification_email: bool,
+) -> Result<()> {
+    let url = permissions_url(file_id, send_notification_email);
+
+    let permission = PermissionRequest {
+        grantee_type: "user",
+        role,
+        email_address: email,
+    };
+
+    let body =
+        serde_json::to_string(&permission).map_err(|e| worker::Error::from(e.to_string()))?;
+
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Authorization", &format!("Bearer {}", token.access_token))?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(&url, &init)?;
+    let mut response = Fetch::Request(request).send().await?;
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await?;
+        return Err(worker::Error::from(error_message(&error_text)));
+    }
+
+    Ok(())
+}
+
+fn permissions_url(file_id: &str, send_notification_email: bool) -> String {
+    format!(
+        "{}/files/{}/permissions?sendNotificationEmail={}",
+        DRIVE_API_BASE, file_id, send_notification_email
+    )
+}
+
+/// Deletes a Drive file the app created.
+pub async fn delete_file(token: &Token, file_id: &str) -> Result<()> {
+    let url = format!("{}/files/{}", DRIVE_API_BASE, file_id);
+
+    let headers = Headers::new();
+    headers.set("Authorization", &format!("Bearer {}", token.access_token))?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Delete).with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(&url, &init)?;
+    let mut response = Fetch::Request(request).send().await?;
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await?;
+        return Err(AppError::GoogleSlides(format!(
+            "Failed to delete Drive file {file_id}: {}",
+            error_message(&error_text)
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Resolves a Drive image to a short-lived thumbnail URL that the Slides API
+/// can fetch with the file owner's access.
+pub async fn image_url(token: &Token, file_id: &str) -> Result<String> {
+    let url = format!(
+        "{}/files/{}?fields=mimeType,thumbnailLink",
+        DRIVE_API_BASE, file_id
+    );
+
+    let headers = Headers::new();
+    headers.set("Authorization", &format!("Bearer {}", token.access_token))?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Get).with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(&url, &init)?;
+    let mut response = Fetch::Request(request).send().await?;
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await?;
+        return Err(file_error(response.status_code(), file_id, &error_text).into());
+    }
+
+    let metadata: FileMetadata = response.json().await?;
+    Ok(thumbnail_url(file_id, metadata)?)
+}
+
+/// Picks the sized thumbnail URL of an image file.
+fn thumbnail_url(file_id: &str, metadata: FileMetadata) -> std::result::Result<String, AppError> {
+    if !metadata.mime_type.starts_with("image/") {
+        return Err(AppError::DriveFileUnavailable(format!(
+            "{file_id} is not an image ({})",
+            metadata.mime_type
+        )));
+    }
+    let link = metadata.thumbnail_link.ok_or_else(|| {
+        AppError::DriveFileUnavailable(format!("{file_id} has no image preview yet"))
+    })?;
+    // Thumbnail links end in a size suffix such as `=s220`
+    Ok(match link.rsplit_once("=s") {
+        Some((base, size)) if size.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{base}=s{IMAGE_SIZE}")
+        }
+        _ => link,
+    })
+}
+
+/// Maps a failed Drive file lookup to an application error.
+///
+/// Drive answers 404 for files the app may not see and 403 for files it may
+/// not read. With the `drive.file` scope both usually mean the file was not
+/// created by or opened with this app, which the message spells out.
+fn file_error(status: u16, file_id: &str, error_text: &str) -> AppError {
+    match status {
+        403 | 404 => AppError::DriveFileUnavailable(format!(
+            "{file_id} was not found or is not shared with this app; \
+             it can only read Drive files it created or that were opened with it"
+        )),
+        _ => AppError::GoogleSlides(format!(
+            "Failed to look up Drive file {file_id}: {}",
+            error_message(error_text)
+        )),
+    }
+}
+
+/// Maps a failed Drive copy response to an application error.
+///
+/// Client errors (usually no access to the theme presentation) are reported
+/// as a bad theme with Drive's own message; anything else is a server failure.
+fn copy_error(status: u16, error_text: &str) -> AppError {
+    let message = error_message(error_text);
+    if (400..500).contains(&status) {
+        AppError::ThemeUnavailable(message)
+    } else {
+        AppError::GoogleSlides(format!("Failed to copy theme presentation: {message}"))
+    }
+}
+
+/// Extracts the message from a Google API error body, or returns the body.
+fn error_message(error_text: &str) -> String {
+    serde_json::from_str::<GoogleErrorResponse>(error_text)
+        .map(|response| response.error.message)
+        .unwrap_or_else(|_| error_text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::plain("drive:1AbC-d_EF23", Some("1AbC-d_EF23"))]
+    #[case::no_prefix("1AbC-d_EF23", None)]
+    #[case::empty("drive:", None)]
+    #[case::path("drive:folder/file", None)]
+    #[case::url("drive:https://example.com/a.png", None)]
+    #[case::whitespace("drive:1AbC d", None)]
+    #[case::uppercase_prefix("DRIVE:1AbC", None)]
+    fn test_file_id(#[case] reference: &str, #[case] expected: Option<&str>) {
+        assert_eq!(file_id(reference), expected);
+    }
+
+    #[rstest]
+    #[case::not_found(404)]
+    #[case::forbidden(403)]
+    fn test_file_access_errors_explain_scope(#[case] status: u16) {
+        let err = file_error(
+            status,
+            "abc",
+            r#"{"error":{"message":"File not found: abc."}}"#,
+        );
+        assert!(matches!(err, AppError::DriveFileUnavailable(_)));
+        assert!(err.to_string().contains("opened with it"));
+        assert_eq!(err.status_code(), 400);
+    }
+
+    #[rstest]
+    fn test_file_server_error_keeps_details() {
+        let err = file_error(500, "abc", r#"{"error":{"message":"Backend Error"}}"#);
+        assert!(matches!(err, AppError::GoogleSlides(_)));
+        assert!(err.to_string().contains("Backend Error"));
+    }
+
+    #[rstest]
+    #[case::sized(
+        "https://lh3.googleusercontent.com/drive-storage/AJQWt=s220",
+        "https://lh3.googleusercontent.com/drive-storage/AJQWt=s1600"
+    )]
+    #[case::no_size_suffix(
+        "https://lh3.googleusercontent.com/drive-storage/AJQWt",
+        "https://lh3.googleusercontent.com/drive-storage/AJQWt"
+    )]
+    fn test_thumbnail_url_requests_large_size(#[case] link: &str, #[case] expected: &str) {
+        let metadata = FileMetadata {
+            mime_type: "image/png".to_string(),
+            thumbnail_link: Some(link.to_string()),
+        };
+        assert_eq!(thumbnail_url("abc", metadata).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::not_an_image("application/pdf", Some("https://lh3.example/x=s220"), "not an image")]
+    #[case::no_thumbnail("image/png", None, "no image preview")]
+    fn test_thumbnail_url_errors(
+        #[case] mime_type: &str,
+        #[case] link: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let metadata = FileMetadata {
+            mime_type: mime_type.to_string(),
+            thumbnail_link: link.map(str::to_string),
+        };
+        let err = thumbnail_url("abc", metadata).unwrap_err();
+        assert!(err.to_string().contains(expected));
+    }
+
+    #[rstest]
+    #[case::not_found(404)]
+    #[case::forbidden(403)]
+    fn test_copy_client_errors_are_theme_unavailable(#[case] status: u16) {
+        let body = r#"{"error":{"code":404,"message":"File not found: abc.","errors":[]}}"#;
+        let err = copy_error(status, body);
+        assert!(
+            matches!(&err, AppError::ThemeUnavailable(message) if message == "File not found: abc.")
+        );
+        assert_eq!(err.status_code(), 400);
+    }
+
+    #[rstest]
+    #[case::unauthorized(401, 401)]
+    #[case::forbidden(403, 404)]
+    #[case::not_found(404, 404)]
+    #[case::server_error(500, 500)]
+    fn test_export_error_status(#[case] status: u16, #[case] expected: u16) {
+        let err = export_error(
+            status,
+            "abc",
+            ExportFormat::Pdf,
+            r#"{"error":{"message":"Nope"}}"#,
+        );
+        assert_eq!(err.status_code(), expected);
+    }
+
+    #[rstest]
+    fn test_export_error_keeps_server_details() {
+        let err = export_error(
+            500,
+            "abc",
+            ExportFormat::Pptx,
+            r#"{"error":{"message":"Export too large"}}"#,
+        );
+        assert!(err.to_string().contains("Export too large"));
+        assert!(err.to_string().contains("as PowerPoint"));
+    }
+
+    #[rstest]
+    fn test_export_error_reports_size_limit() {
+        let error_text = r#"{"error":{"code":403,"message":"This file is too large to be exported.","errors":[{"domain":"global","reason":"exportSizeLimitExceeded","message":"This file is too large to be exported."}]}}"#;
+        let err = export_error(403, "abc", ExportFormat::Pptx, error_text);
+        assert_eq!(err.code(), "export_too_large");
+        assert_eq!(err.status_code(), 422);
+        assert!(err.to_string().contains("100 MB"), "{err}");
+    }
+
+    #[rstest]
+    #[case::plain(
+        "Weekly Review",
+        "attachment; filename=\"Weekly Review.pdf\"; filename*=UTF-8''Weekly%20Review.pdf"
+    )]
+    #[case::quotes_and_slashes(
+        "Q3 \"final\"/v2",
+        "attachment; filename=\"Q3 _final__v2.pdf\"; filename*=UTF-8''Q3%20%22final%22%2Fv2.pdf"
+    )]
+    #[case::non_ascii(
+        "Café",
+        "attachment; filename=\"Caf_.pdf\"; filename*=UTF-8''Caf%C3%A9.pdf"
+    )]
+    #[case::blank(
+        "  ",
+        "attachment; filename=\"presentation.pdf\"; filename*=UTF-8''presentation.pdf"
+    )]
+    fn test_pdf_content_disposition(#[case] title: &str, #[case] expected: &str) {
+        assert_eq!(content_disposition(title, ExportFormat::Pdf), expected);
+    }
+
+    #[rstest]
+    fn test_pptx_content_disposition() {
+        assert_eq!(
+            content_disposition("Weekly Review", ExportFormat::Pptx),
+            "attachment; filename=\"Weekly Review.pptx\"; filename*=UTF-8''Weekly%20Review.pptx"
+        );
+    }
+
+    #[rstest]
+    #[case::id("1AbC-d_EF23", true)]
+    #[case::empty("", false)]
+    #[case::path("abc/../def", false)]
+    #[case::query("abc?alt=media", false)]
+    fn test_is_file_id(#[case] id: &str, #[case] expected: bool) {
+        assert_eq!(is_file_id(id), expected);
+    }
+
+    #[rstest]
+    #[case::notify(
+        true,
+        "https://www.googleapis.com/drive/v3/files/abc/permissions?sendNotificationEmail=true"
+    )]
+    #[case::silent(
+        false,
+        "https://www.googleapis.com/drive/v3/files/abc/permissions?sendNotificationEmail=false"
+    )]
+    fn test_permissions_url(#[case] notify: bool, #[case] expected: &str) {
+        assert_eq!(permissions_url("abc", notify), expected);
+    }
+
+    #[rstest]
+    #[case::reader(ShareRole::Reader, "reader")]
+    #[case::commenter(ShareRole::Commenter, "commenter")]
+    #[case::writer(ShareRole::Writer, "writer")]
+    fn test_permission_request_body(#[case] role: ShareRole, #[case] expected: &str) {
+        let permission = PermissionRequest {
+            grantee_type: "user",
+            role,
+            email_address: "sam@example.com",
+        };
+        assert_eq!(
+            serde_json::to_value(&permission).unwrap(),
+            serde_json::json!({"type": "user", "role": expected, "emailAddress": "sam@example.com"})
+        );
+    }
+
+    #[rstest]
+    fn test_copy_server_error_keeps_details() {
+        let err = copy_error(503, "backend unavailable");
+        assert!(matches!(err, AppError::GoogleSlides(_)));
+        assert!(err.to_string().contains("backend unavailable"));
+    }
+}
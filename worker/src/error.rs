@@ -11,16 +11,101 @@ pub enum AppError {
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
+    #[error(
+        "The presentation no longer exists; it may have been deleted while slides were being added"
+    )]
+    PresentationDeleted,
+
+    #[error(
+        "Presentation {0} was not found; check presentation_id and that you can open it in Google Slides"
+    )]
+    PresentationNotFound(String),
+
+    #[error("Cannot use the theme presentation: {0}")]
+    ThemeUnavailable(String),
+
+    #[error("Google Drive file unavailable: {0}")]
+    DriveFileUnavailable(String),
+
+    #[error("Export too large: {0}")]
+    ExportTooLarge(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     #[error("Authentication required")]
     AuthRequired,
 
-    #[error("Session expired")]
+    #[error("Session expired; sign in again")]
     SessionExpired,
 
+    #[error("Google did not grant offline access; sign in again and allow access")]
+    ConsentRequired,
+
+    #[error("Login expired; try again")]
+    LoginExpired,
+
+    #[error("storage not configured: missing KV binding `{0}`")]
+    StorageNotConfigured(String),
+
+    #[error("secret not configured: missing `{0}`")]
+    SecretNotConfigured(String),
+
+    /// A failure to create a deck, with what happened to the partially
+    /// created presentation. Reported with the status and code of `error`.
+    #[error("{error}; {outcome}")]
+    Cleanup {
+        error: Box<AppError>,
+        outcome: String,
+    },
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+impl AppError {
+    /// Returns the HTTP status code this error should be reported with.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            AppError::InvalidRequest(_)
+            | AppError::ThemeUnavailable(_)
+            | AppError::DriveFileUnavailable(_)
+            | AppError::LoginExpired => 400,
+            AppError::AuthRequired | AppError::SessionExpired | AppError::ConsentRequired => 401,
+            AppError::PresentationDeleted
+            | AppError::PresentationNotFound(_)
+            | AppError::NotFound(_) => 404,
+            AppError::ExportTooLarge(_) => 422,
+            AppError::StorageNotConfigured(_) | AppError::SecretNotConfigured(_) => 503,
+            AppError::OAuth(_) | AppError::GoogleSlides(_) | AppError::Other(_) => 500,
+            AppError::Cleanup { error, .. } => error.status_code(),
+        }
+    }
+
+    /// Returns the machine-readable code reported in error responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::OAuth(_) => "oauth_error",
+            AppError::GoogleSlides(_) => "google_api_error",
+            AppError::InvalidRequest(_) => "invalid_request",
+            AppError::PresentationDeleted => "presentation_deleted",
+            AppError::PresentationNotFound(_) => "presentation_not_found",
+            AppError::ThemeUnavailable(_) => "theme_unavailable",
+            AppError::DriveFileUnavailable(_) => "drive_file_unavailable",
+            AppError::ExportTooLarge(_) => "export_too_large",
+            AppError::NotFound(_) => "not_found",
+            AppError::AuthRequired => "auth_required",
+            AppError::SessionExpired => "session_expired",
+            AppError::ConsentRequired => "consent_required",
+            AppError::LoginExpired => "login_expired",
+            AppError::StorageNotConfigured(_) => "storage_not_configured",
+            AppError::SecretNotConfigured(_) => "secret_not_configured",
+            AppError::Other(_) => "internal_error",
+            AppError::Cleanup { error, .. } => error.code(),
+        }
+    }
+}
+
 impl From<AppError> for worker::Error {
     fn from(err: AppError) -> Self {
         worker::Error::from(err.to_string())
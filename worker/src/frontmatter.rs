@@ -0,0 +1,143 @@
+//! Leading YAML front matter, as written by Obsidian, Jekyll and Hugo.
+//!
+//! Only flat `key: value` pairs are read; nested mappings and lists are
+//! skipped, which is enough for deck metadata such as `title` and `author`.
+
+use std::collections::BTreeMap;
+
+/// Line that opens a front matter block.
+const OPENING: &str = "---";
+
+/// Lines that may close a front matter block.
+const CLOSINGS: &[&str] = &["---", "..."];
+
+/// Key/value metadata parsed from a front matter block.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub fields: BTreeMap<String, String>,
+}
+
+impl FrontMatter {
+    /// Returns the value of a key, if present and non-empty.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .get(key)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The document title, if the front matter sets one.
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+}
+
+/// Separates a leading front matter block from the rest of the document.
+///
+/// Returns `None` and the unchanged text when the document does not start
+/// with a `---` line or the block is never closed.
+pub fn split(text: &str) -> (Option<FrontMatter>, &str) {
+    let body = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut lines = body.split_inclusive('\n');
+    if lines.next().map(str::trim_end) != Some(OPENING) {
+        return (None, text);
+    }
+
+    let mut front_matter = FrontMatter::default();
+    let mut offset = body.find('\n').map_or(body.len(), |end| end + 1);
+    for line in lines {
+        offset += line.len();
+        if CLOSINGS.contains(&line.trim_end()) {
+            return (Some(front_matter), &body[offset..]);
+        }
+        if let Some((key, value)) = parse_field(line) {
+            front_matter.fields.insert(key.to_string(), value);
+        }
+    }
+
+    (None, text)
+}
+
+/// Parses a top-level `key: value` line, removing quotes around the value.
+fn parse_field(line: &str) -> Option<(&str, String)> {
+    if line.starts_with(char::is_whitespace) || line.trim_start().starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty() || key.starts_with('-') {
+        return None;
+    }
+    Some((key, unquote(value.trim()).to_string()))
+}
+
+/// Strips one pair of matching single or double quotes.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|&quote| {
+            value
+                .strip_prefix(quote)
+                .and_then(|rest| rest.strip_suffix(quote))
+        })
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_split_parses_fields_and_strips_block() {
+        let text = "---\ntitle: Quarterly Review\nauthor: \"Sam Lee\"\ntags:\n  - finance\n---\n# Agenda\nNumbers";
+        let (front_matter, body) = split(text);
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.title(), Some("Quarterly Review"));
+        assert_eq!(front_matter.get("author"), Some("Sam Lee"));
+        assert_eq!(front_matter.get("tags"), None);
+        assert_eq!(body, "# Agenda\nNumbers");
+    }
+
+    #[rstest]
+    #[case::no_front_matter("# Title\nBody")]
+    #[case::rule_later_in_document("Intro\n---\ntitle: nope\n---\n")]
+    #[case::unclosed_block("---\ntitle: Draft\nno closing line")]
+    #[case::empty("")]
+    fn test_split_without_front_matter_is_unchanged(#[case] text: &str) {
+        assert_eq!(split(text), (None, text));
+    }
+
+    #[rstest]
+    #[case::dots_closing("---\ntitle: A\n...\nBody", "Body")]
+    #[case::crlf("---\r\ntitle: A\r\n---\r\nBody", "Body")]
+    #[case::byte_order_mark("\u{feff}---\ntitle: A\n---\nBody", "Body")]
+    #[case::nothing_after("---\ntitle: A\n---", "")]
+    fn test_split_variants(#[case] text: &str, #[case] expected_body: &str) {
+        let (front_matter, body) = split(text);
+        assert_eq!(front_matter.unwrap().title(), Some("A"));
+        assert_eq!(body, expected_body);
+    }
+
+    #[rstest]
+    #[case::plain("title: Hello", Some(("title", "Hello")))]
+    #[case::single_quoted("title: 'Hello: World'", Some(("title", "Hello: World")))]
+    #[case::empty_value("title:", Some(("title", "")))]
+    #[case::indented("  nested: value", None)]
+    #[case::comment("# title: nope", None)]
+    #[case::list_item("- item: value", None)]
+    #[case::no_colon("just text", None)]
+    fn test_parse_field(#[case] line: &str, #[case] expected: Option<(&str, &str)>) {
+        let parsed = parse_field(line);
+        assert_eq!(
+            parsed.as_ref().map(|(key, value)| (*key, value.as_str())),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_empty_title_is_none() {
+        let (front_matter, _) = split("---\ntitle: \"\"\n---\nBody");
+        assert_eq!(front_matter.unwrap().title(), None);
+    }
+}
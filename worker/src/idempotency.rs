@@ -0,0 +1,104 @@
+//! `Idempotency-Key` support, so a retried create request returns the deck
+//! made by the first attempt instead of creating another one.
+//!
+//! KV has no compare-and-set, so two concurrent requests with the same key
+//! can both create a deck; the response stored last wins.
+
+use crate::{error::AppError, textmetrics};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Request header carrying the client's idempotency key.
+pub const HEADER: &str = "Idempotency-Key";
+
+/// Response header set when a stored response is replayed.
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// How long a stored response is replayed for.
+pub const TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Longest idempotency key accepted, in characters.
+const MAX_KEY_LENGTH: usize = 255;
+
+/// A response kept in KV for replaying to retries.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+/// Builds the KV key under which a session's response for `key` is stored.
+///
+/// The session ID scopes the key so one user can never replay another's
+/// response; the client's key is hashed to keep the KV key short.
+pub fn kv_key(session_id: &str, key: &str) -> Result<String, AppError> {
+    let key = key.trim();
+    if key.is_empty() || textmetrics::chars(key) > MAX_KEY_LENGTH {
+        return Err(AppError::InvalidRequest(format!(
+            "{HEADER} must be 1 to {MAX_KEY_LENGTH} characters"
+        )));
+    }
+    let digest = Sha256::digest(key.as_bytes());
+    Ok(format!(
+        "idem:{session_id}:{}",
+        URL_SAFE_NO_PAD.encode(digest)
+    ))
+}
+
+/// Returns the response to replay for a KV lookup, if one was stored.
+///
+/// A value that no longer parses is treated as missing, so the request runs
+/// again rather than failing.
+pub fn stored_response(lookup: Option<&str>) -> Option<StoredResponse> {
+    serde_json::from_str(lookup?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_kv_key_is_scoped_to_session() {
+        let key = kv_key("session1", "retry-42").unwrap();
+        assert!(key.starts_with("idem:session1:"));
+        assert!(!key.contains("retry-42"));
+        assert_eq!(key, kv_key("session1", "retry-42").unwrap());
+        assert_ne!(key, kv_key("session2", "retry-42").unwrap());
+        assert_ne!(key, kv_key("session1", "retry-43").unwrap());
+    }
+
+    #[rstest]
+    fn test_kv_key_ignores_surrounding_whitespace() {
+        assert_eq!(kv_key("s", " abc ").unwrap(), kv_key("s", "abc").unwrap());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::blank("   ")]
+    #[case::too_long(&"k".repeat(MAX_KEY_LENGTH + 1))]
+    fn test_kv_key_rejects_invalid_keys(#[case] key: &str) {
+        let err = kv_key("s", key).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains(HEADER));
+    }
+
+    #[rstest]
+    fn test_stored_response_replays_round_trip() {
+        let stored = StoredResponse {
+            status: 200,
+            body: serde_json::json!({"presentation_id": "abc", "slide_count": 2}),
+        };
+        let value = serde_json::to_string(&stored).unwrap();
+        assert_eq!(stored_response(Some(&value)), Some(stored));
+    }
+
+    #[rstest]
+    #[case::missing(None)]
+    #[case::corrupt(Some("not json"))]
+    #[case::other_shape(Some(r#"{"presentation_id":"abc"}"#))]
+    fn test_stored_response_runs_request_again(#[case] lookup: Option<&str>) {
+        assert_eq!(stored_response(lookup), None);
+    }
+}
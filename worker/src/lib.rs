@@ -1,11 +1,34 @@
+mod catalog;
+mod crypto;
+mod drive;
 mod error;
+mod frontmatter;
+mod idempotency;
 mod oauth;
+mod responses;
 mod slides;
 mod splitter;
+mod storage;
+#[cfg(test)]
+mod test_support;
+mod textmetrics;
 
-use crate::slides::CreateSlidesRequest;
+use crate::{
+    catalog::Catalog,
+    crypto::{SessionSigner, TokenCipher},
+    drive::ExportFormat,
+    error::AppError,
+    responses::{
+        CreateFromTemplateResponse, CreateSlidesBatchResponse, CreateSlidesResponse, ErrorResponse,
+        HealthReport, MeResponse,
+    },
+    slides::{
+        CreateFromTemplateRequest, CreateSlidesBatchRequest, CreateSlidesRequest,
+        PreviewSplitRequest, StylePreset,
+    },
+};
 use std::collections::HashMap;
-use tracing::{Level, info};
+use tracing::{Level, info, warn};
 use worker::*;
 
 /// Creates a cookie string with the given name, value, and max-age (in seconds).
@@ -13,6 +36,11 @@ fn cookie(name: &str, value: &str, max_age: u64) -> String {
     format!("{name}={value}; Path=/; HttpOnly; SameSite=Lax; Secure; Max-Age={max_age}")
 }
 
+/// Creates a cookie string telling the browser to delete the named cookie.
+fn expired_cookie(name: &str) -> String {
+    cookie(name, "", 0)
+}
+
 /// Retrieves the value of a cookie by name from the "Cookie" header string.
 fn get_cookie(cookies: &str, name: &str) -> Option<String> {
     cookies
@@ -24,6 +52,323 @@ fn get_cookie(cookies: &str, name: &str) -> Option<String> {
         .find_map(|(k, v)| if k == name { Some(v.to_string()) } else { None })
 }
 
+/// Returns the session ID from the signed `sid` cookie, or `None` when there
+/// is no cookie. A cookie whose signature does not verify is rejected as
+/// unauthenticated before KV is read.
+fn signed_session_id(
+    cookies: &str,
+    signer: &SessionSigner,
+) -> std::result::Result<Option<String>, AppError> {
+    match get_cookie(cookies, "sid") {
+        Some(cookie) => signer
+            .verify(&cookie)
+            .map(|session_id| Some(session_id.to_string()))
+            .ok_or(AppError::AuthRequired),
+        None => Ok(None),
+    }
+}
+
+/// How long a session, and the token stored for it, lasts.
+const SESSION_TTL_SECS: u64 = 14 * 24 * 60 * 60;
+
+/// Returns the session's token, refreshed first when it is about to expire.
+///
+/// A refreshed token is written back under the same session ID. When Google
+/// no longer accepts the refresh token, or the session never had one, the
+/// session is deleted, so the user is asked to sign in again instead of
+/// failing every request.
+async fn fresh_token(
+    ctx: &RouteContext<()>,
+    kv: &KvStore,
+    cipher: &TokenCipher,
+    session_id: &str,
+    token: oauth::Token,
+) -> std::result::Result<oauth::Token, AppError> {
+    if !token.is_expired(oauth::config::security::REFRESH_SKEW_SECS) {
+        return Ok(token);
+    }
+    match oauth::refresh(ctx, &token).await {
+        Ok(token) => {
+            // The refreshed token still serves this request
+            if let Err(e) = store_token(kv, cipher, session_id, &token).await {
+                warn!("Failed to store refreshed token: {e}");
+            }
+            Ok(token)
+        }
+        Err(e @ (AppError::SessionExpired | AppError::ConsentRequired)) => {
+            if let Err(e) = kv.delete(session_id).await {
+                warn!("Failed to delete expired session: {e}");
+            }
+            Err(e)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Loads the signed-in user's session for an API call: the tokens store, the
+/// session ID and its token, refreshed when about to expire. No session
+/// cookie, or one whose session no longer exists, means signing in.
+async fn session_token(
+    req: &Request,
+    ctx: &RouteContext<()>,
+) -> std::result::Result<(KvStore, String, oauth::Token), AppError> {
+    let (kv, cipher, session_id, token) = stored_session(req, ctx).await?;
+    let token = fresh_token(ctx, &kv, &cipher, &session_id, token).await?;
+    Ok((kv, session_id, token))
+}
+
+/// Loads the session as [`session_token`] does, but leaves its token as
+/// stored, so nothing is sent to Google.
+async fn stored_session(
+    req: &Request,
+    ctx: &RouteContext<()>,
+) -> std::result::Result<(KvStore, TokenCipher, String, oauth::Token), AppError> {
+    let cookies = req
+        .headers()
+        .get("Cookie")
+        .map_err(drive::other_error)?
+        .unwrap_or_default();
+    let signer = SessionSigner::from_ctx(ctx)?;
+    let session_id = signed_session_id(&cookies, &signer)?.ok_or(AppError::AuthRequired)?;
+    let kv = storage::kv_store(ctx, storage::TOKENS)?;
+    let cipher = TokenCipher::from_ctx(ctx)?;
+    let token = load_token(&kv, &cipher, &session_id)
+        .await
+        .map_err(drive::other_error)?
+        .ok_or(AppError::AuthRequired)?;
+    Ok((kv, cipher, session_id, token))
+}
+
+/// Reports a failed API call as JSON with the error's status code.
+fn error_response(e: &AppError, message: &str) -> Result<Response> {
+    let body = ErrorResponse::from_app_error(e, message);
+    Ok(Response::from_json(&body)?.with_status(e.status_code()))
+}
+
+/// Reads a session's token, or `None` when the session does not exist or its
+/// token cannot be decrypted.
+async fn load_token(
+    kv: &KvStore,
+    cipher: &TokenCipher,
+    session_id: &str,
+) -> Result<Option<oauth::Token>> {
+    let token_data = kv.get(session_id).text().await?;
+    Ok(token_data.and_then(|data| cipher.open(&data)))
+}
+
+/// Saves a session's token, encrypted, restarting the session's lifetime.
+async fn store_token(
+    kv: &KvStore,
+    cipher: &TokenCipher,
+    session_id: &str,
+    token: &oauth::Token,
+) -> Result<()> {
+    kv.put(session_id, cipher.seal(token)?)?
+        .expiration_ttl(SESSION_TTL_SECS)
+        .execute()
+        .await?;
+    Ok(())
+}
+
+/// Reports who is signed in. Requests without a usable session get
+/// `{"authenticated": false}` rather than an error.
+async fn me(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let cookies = req.headers().get("Cookie")?.unwrap_or_default();
+    let signer = match SessionSigner::from_ctx(&ctx) {
+        Ok(signer) => signer,
+        Err(e) => return Response::error(e.to_string(), e.status_code()),
+    };
+    let session_id = match visitor(&cookies, &signer) {
+        Visitor::SignedIn(session_id) => session_id,
+        anonymous => {
+            let mut response = Response::from_json(&MeResponse::anonymous())?;
+            if let Some(cookie) = anonymous.set_cookie() {
+                response.headers_mut().set("Set-Cookie", &cookie)?;
+            }
+            return Ok(response);
+        }
+    };
+    let kv = match storage::kv_store(&ctx, storage::TOKENS) {
+        Ok(kv) => kv,
+        Err(e) => return Response::error(e.to_string(), e.status_code()),
+    };
+    let cipher = match TokenCipher::from_ctx(&ctx) {
+        Ok(cipher) => cipher,
+        Err(e) => return Response::error(e.to_string(), e.status_code()),
+    };
+    let Some(token) = load_token(&kv, &cipher, &session_id).await? else {
+        return Response::from_json(&MeResponse::anonymous());
+    };
+    let mut token = match fresh_token(&ctx, &kv, &cipher, &session_id, token).await {
+        Ok(token) => token,
+        // The session was ended, so nobody is signed in any more
+        Err(e) if e.status_code() == 401 => return Response::from_json(&MeResponse::anonymous()),
+        Err(e) => return error_response(&e, "Failed to read session"),
+    };
+
+    // Fetched once, then kept with the token for later page loads
+    if token.profile.is_none() {
+        match oauth::userinfo(&token).await {
+            Ok(profile) => {
+                token.profile = Some(profile);
+                if let Err(e) = store_token(&kv, &cipher, &session_id, &token).await {
+                    warn!("Failed to cache profile: {e}");
+                }
+            }
+            Err(e) => warn!("Failed to fetch profile: {e}"),
+        }
+    }
+
+    Response::from_json(&MeResponse::signed_in(&token))
+}
+
+/// Who `/api/me` answers for, going by the `sid` cookie.
+#[derive(Debug, PartialEq)]
+enum Visitor {
+    /// No session cookie was sent.
+    Anonymous,
+    /// The cookie's signature does not verify, as with the unsigned cookies
+    /// set before sessions were signed. Nobody is signed in.
+    BadCookie,
+    SignedIn(String),
+}
+
+impl Visitor {
+    /// The `Set-Cookie` header for an anonymous answer: a bad cookie is
+    /// expired, so the browser stops sending it.
+    fn set_cookie(&self) -> Option<String> {
+        matches!(self, Visitor::BadCookie).then(|| expired_cookie("sid"))
+    }
+}
+
+/// Reads the `sid` cookie for `/api/me`.
+fn visitor(cookies: &str, signer: &SessionSigner) -> Visitor {
+    match signed_session_id(cookies, signer) {
+        Ok(Some(session_id)) => Visitor::SignedIn(session_id),
+        Ok(None) => Visitor::Anonymous,
+        Err(_) => Visitor::BadCookie,
+    }
+}
+
+/// Reports a failed export as JSON with the error's status code.
+fn export_error(e: &AppError, format: ExportFormat) -> Result<Response> {
+    let message = match format {
+        ExportFormat::Pdf => "Failed to export PDF",
+        ExportFormat::Pptx => "Failed to export PowerPoint",
+    };
+    error_response(e, message)
+}
+
+/// Streams Drive's export of the presentation named in the path back as a
+/// download named after its title.
+async fn export_presentation(
+    req: Request,
+    ctx: RouteContext<()>,
+    format: ExportFormat,
+) -> Result<Response> {
+    let presentation_id = ctx.param("id").cloned().unwrap_or_default();
+    if !drive::is_file_id(&presentation_id) {
+        return export_error(
+            &AppError::NotFound(format!("presentation {presentation_id} was not found")),
+            format,
+        );
+    }
+
+    // Without a valid session the client gets a JSON 401, not a redirect
+    let (_, _, token) = match session_token(&req, &ctx).await {
+        Ok(session) => session,
+        Err(e) => return export_error(&e, format),
+    };
+
+    match drive::export_file(&token, &presentation_id, format).await {
+        Ok((title, response)) => {
+            // Passing the export response on streams its body
+            let headers = Headers::new();
+            headers.set("Content-Type", format.mime_type())?;
+            headers.set(
+                "Content-Disposition",
+                &drive::content_disposition(&title, format),
+            )?;
+            Ok(response.with_headers(headers))
+        }
+        Err(e) => export_error(&e, format),
+    }
+}
+
+/// Signs the user out, ending their session if it still exists, and sends
+/// them to the home page with the session cookie cleared. A cookie with a bad
+/// signature is only cleared.
+async fn logout(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+    let cookies = req.headers().get("Cookie")?.unwrap_or_default();
+    let signed =
+        SessionSigner::from_ctx(&ctx).and_then(|signer| signed_session_id(&cookies, &signer));
+    if let Ok(Some(session_id)) = signed {
+        let kv = storage::kv_store(&ctx, storage::TOKENS);
+        match (kv, TokenCipher::from_ctx(&ctx)) {
+            (Ok(kv), Ok(cipher)) => oauth::logout(&kv, &cipher, &session_id).await,
+            (Err(e), _) | (_, Err(e)) => warn!("Failed to end session: {e}"),
+        }
+    }
+
+    let mut resp = Response::redirect(home_url(req.url()?, None))?;
+    resp.headers_mut()
+        .set("Set-Cookie", &expired_cookie("sid"))?;
+
+    Ok(resp)
+}
+
+/// The home page on the same origin as `url`, with an optional `auth` flag
+/// the frontend shows a message for.
+fn home_url(mut url: Url, auth: Option<&str>) -> Url {
+    url.set_path("/");
+    url.set_query(None);
+    url.set_fragment(None);
+    if let Some(flag) = auth {
+        url.query_pairs_mut().append_pair("auth", flag);
+    }
+    url
+}
+
+/// Explains which permissions were left unchecked on Google's consent screen
+/// and why each is needed, with a link to sign in again.
+fn missing_scopes_page(missing: &[&oauth::config::oauth::RequiredScope]) -> String {
+    let items: String = missing
+        .iter()
+        .map(|required| {
+            format!(
+                "        <li><strong>{}</strong><br>{}</li>\n",
+                required.permission, required.reason
+            )
+        })
+        .collect();
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Permission Required</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; max-width: 600px; margin: 50px auto; padding: 20px; }}
+        li {{ margin-bottom: 10px; }}
+    </style>
+</head>
+<body>
+    <h1>Permission Required</h1>
+    <p>Text2Deck needs these permissions, which were not granted on Google's consent screen:</p>
+    <ul>
+{items}    </ul>
+    <p><a href="/oauth/start">Sign in again</a> and leave them checked.</p>
+</body>
+</html>
+"#
+    )
+}
+
+/// Reads the deployment's limits; an unset `MAX_SLIDES` variable keeps the defaults.
+fn limits(ctx: &RouteContext<()>) -> slides::Limits {
+    let max_slides = ctx.var("MAX_SLIDES").ok().map(|var| var.to_string());
+    slides::Limits::from_env_var(max_slides.as_deref())
+}
+
 #[event(start)]
 pub fn init() {
     tracing_subscriber::fmt()
@@ -79,57 +424,179 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
             "#;
             Response::from_html(instructions)
         })
-        .get("/health", |_, _| Response::ok("OK"))
-        .get_async("/oauth/start", |_, ctx| async move {
+        .get("/health", |req, ctx| {
+            let deep = req
+                .url()?
+                .query_pairs()
+                .any(|(k, v)| k == "deep" && v == "true");
+            if !deep {
+                return Response::ok("OK");
+            }
+
+            let report = HealthReport::new(storage::binding_status(&ctx));
+            Ok(Response::from_json(&report)?.with_status(report.status_code()))
+        })
+        .get_async("/oauth/start", |req, ctx| async move {
             let (auth_url, state, verifier) = oauth::start(&ctx).await?;
+            let user_agent = req.headers().get("User-Agent")?.unwrap_or_default();
+            let state_value =
+                oauth::state_cookie_value(&state, oauth::user_agent_family(&user_agent));
+
+            // The verifier stays server-side, keyed by state, until the callback
+            let kv = match storage::kv_store(&ctx, storage::TOKENS) {
+                Ok(kv) => kv,
+                Err(e) => return Response::error(e.to_string(), e.status_code()),
+            };
+            oauth::save_login(&kv, &state, &verifier).await?;
 
             let mut resp = Response::redirect(auth_url)?;
-            let headers = resp.headers_mut();
-            headers.set("Set-Cookie", &cookie("state", &state, 600))?;
-            headers.append("Set-Cookie", &cookie("verifier", &verifier, 600))?;
+            resp.headers_mut().set(
+                "Set-Cookie",
+                &cookie(
+                    "state",
+                    &state_value,
+                    oauth::config::security::LOGIN_TTL_SECS,
+                ),
+            )?;
 
             Ok(resp)
         })
+        .get_async("/api/me", me)
+        .post_async("/api/logout", logout)
         .get_async("/oauth/callback", |req, ctx| async move {
             let url = req.url()?;
             let query_pairs: HashMap<_, _> = url.query_pairs().into_owned().collect();
 
-            let code = query_pairs.get("code").ok_or("missing code")?.to_string();
-            let state = query_pairs.get("state").ok_or("missing state")?.to_string();
+            let (code, state) = match oauth::CallbackQuery::parse(&query_pairs)? {
+                oauth::CallbackQuery::Code { code, state } => (code, state),
+                // Cancelling on the consent screen is routine; anything else is worth a look
+                oauth::CallbackQuery::Error {
+                    error,
+                    description,
+                    state,
+                } => {
+                    let flag = oauth::auth_failure_flag(&error);
+                    if flag == "denied" {
+                        info!("User declined consent");
+                    } else {
+                        warn!(
+                            error = error.as_str(),
+                            description = description.as_deref().unwrap_or_default(),
+                            "OAuth callback returned an error"
+                        );
+                    }
+                    // The login cannot be completed any more, so its verifier goes now
+                    if let (Some(state), Ok(kv)) = (state, storage::kv_store(&ctx, storage::TOKENS))
+                    {
+                        let _ = oauth::take_login(&kv, &state).await;
+                    }
+                    let mut resp = Response::redirect(home_url(url, Some(flag)))?;
+                    resp.headers_mut()
+                        .set("Set-Cookie", &expired_cookie("state"))?;
+                    return Ok(resp);
+                }
+            };
 
             let cookies = req.headers().get("Cookie")?.unwrap_or_default();
-            let state_c = get_cookie(&cookies, "state").ok_or("no state cookie")?;
+            let state_cookie = get_cookie(&cookies, "state").ok_or("no state cookie")?;
+            let (state_c, ua_family) = oauth::parse_state_cookie(&state_cookie);
             if state != state_c {
                 return Response::error("state mismatch", 400);
             }
 
-            let verifier = get_cookie(&cookies, "verifier").ok_or("no verifier cookie")?;
-            let token = oauth::exchange(&ctx, &code, &verifier).await?;
-            let session_id = oauth::generate_session_id();
-            let kv = ctx.kv("TOKENS")?;
+            let user_agent = req.headers().get("User-Agent")?.unwrap_or_default();
+            let current_family = oauth::user_agent_family(&user_agent);
+            if ua_family.is_some_and(|family| family != current_family) {
+                warn!(
+                    expected = ua_family,
+                    actual = current_family,
+                    "OAuth callback user agent family differs from /oauth/start"
+                );
+            }
+
+            // Configuration errors fail here, before the single-use code and
+            // login are consumed, so fixing them does not need a new sign-in
+            let kv = match storage::kv_store(&ctx, storage::TOKENS) {
+                Ok(kv) => kv,
+                Err(e) => return Response::error(e.to_string(), e.status_code()),
+            };
+            let cipher = match TokenCipher::from_ctx(&ctx) {
+                Ok(cipher) => cipher,
+                Err(e) => return Response::error(e.to_string(), e.status_code()),
+            };
+            let signer = match SessionSigner::from_ctx(&ctx) {
+                Ok(signer) => signer,
+                Err(e) => return Response::error(e.to_string(), e.status_code()),
+            };
+
+            // Reject authorization codes that have already been presented once.
+            let code_key = oauth::code_replay_key(&code);
+            let lookup = kv.get(&code_key).text().await.map_err(worker::Error::from);
+            match oauth::check_code_use(lookup) {
+                oauth::CodeUse::Replayed => return Response::error("code already used", 400),
+                oauth::CodeUse::Unverified => {
+                    warn!("Could not check authorization code replay; continuing");
+                }
+                oauth::CodeUse::First => {
+                    let marked = match kv.put(&code_key, "1") {
+                        Ok(put) => {
+                            put.expiration_ttl(oauth::config::security::CODE_REPLAY_TTL_SECS)
+                                .execute()
+                                .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = marked {
+                        warn!("Failed to record authorization code use: {e}");
+                    }
+                }
+            }
 
-            const TWO_WEEKS_SECS: u64 = 14 * 24 * 60 * 60;
-            kv.put(&session_id, &token)?
-                .expiration_ttl(TWO_WEEKS_SECS)
-                .execute()
-                .await?;
+            let verifier = match oauth::take_login(&kv, &state).await {
+                Ok(verifier) => verifier,
+                Err(e) => return Response::error(e.to_string(), e.status_code()),
+            };
+            let mut token = match oauth::exchange(&ctx, &code, &verifier).await {
+                Ok(token) => token,
+                Err(e) => return Response::error(e.to_string(), e.status_code()),
+            };
+            // Without a scope in the response nothing is known to be granted,
+            // so Google is asked; no session is made if that fails
+            if token.scope.trim().is_empty() {
+                token.scope = match oauth::token_scope(&token).await {
+                    Ok(scope) => scope,
+                    Err(e) => return Response::error(e.to_string(), e.status_code()),
+                };
+            }
+
+            // Without these every Slides or Drive call would fail with a 403
+            let missing = token.missing_scopes();
+            if !missing.is_empty() {
+                let scopes: Vec<_> = missing.iter().map(|required| required.scope).collect();
+                info!(
+                    missing = scopes.join(" ").as_str(),
+                    "User did not grant required scopes"
+                );
+                return Ok(Response::from_html(missing_scopes_page(&missing))?.with_status(403));
+            }
+            let session_id = oauth::generate_session_id();
+            store_token(&kv, &cipher, &session_id, &token).await?;
 
             let mut resp = Response::redirect(Url::parse("/app")?)?;
-            resp.headers_mut()
-                .set("Set-Cookie", &cookie("sid", &session_id, TWO_WEEKS_SECS))?;
+            resp.headers_mut().set(
+                "Set-Cookie",
+                &cookie("sid", &signer.sign(&session_id), SESSION_TTL_SECS),
+            )?;
 
             Ok(resp)
         })
         .post_async("/api/create-slides", |mut req, ctx| async move {
-            // Get session ID from cookie
-            let cookies = req.headers().get("Cookie")?.unwrap_or_default();
-            let session_id = get_cookie(&cookies, "sid").ok_or("no session cookie")?;
-
-            // Get token from KV store
-            let kv = ctx.kv("TOKENS")?;
-            let token_data = kv.get(&session_id).text().await?.ok_or("invalid session")?;
-            let token: oauth::Token = serde_json::from_str(&token_data)
-                .map_err(|e| worker::Error::from(format!("Failed to parse token: {}", e)))?;
+            // The token is only refreshed once it is needed, so a dry run
+            // never calls Google
+            let (kv, cipher, session_id, token) = match stored_session(&req, &ctx).await {
+                Ok(session) => session,
+                Err(e) => return error_response(&e, "Failed to create slides"),
+            };
 
             // Parse request body
             let slides_request: CreateSlidesRequest = req
@@ -137,61 +604,134 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
                 .await
                 .map_err(|e| worker::Error::from(format!("Invalid request body: {}", e)))?;
 
+            // A dry run only builds the requests, so it never calls Google
+            if slides_request.dry_run {
+                return match slides::dry_run(&slides_request, &limits(&ctx)) {
+                    Ok(result) => Response::from_json(&result),
+                    Err(e) => error_response(&e, "Failed to create slides"),
+                };
+            }
+            let token = match fresh_token(&ctx, &kv, &cipher, &session_id, token).await {
+                Ok(token) => token,
+                Err(e) => return error_response(&e, "Failed to create slides"),
+            };
+
+            // A retry with the same Idempotency-Key gets the first attempt's response
+            let idempotency_key = match req.headers().get(idempotency::HEADER)? {
+                Some(key) => match idempotency::kv_key(&session_id, &key) {
+                    Ok(kv_key) => Some(kv_key),
+                    Err(e) => return Response::error(e.to_string(), e.status_code()),
+                },
+                None => None,
+            };
+            if let Some(kv_key) = &idempotency_key {
+                let lookup = kv.get(kv_key).text().await?;
+                if let Some(stored) = idempotency::stored_response(lookup.as_deref()) {
+                    let mut resp = Response::from_json(&stored.body)?.with_status(stored.status);
+                    resp.headers_mut()
+                        .set(idempotency::REPLAYED_HEADER, "true")?;
+                    return Ok(resp);
+                }
+            }
+
             // Create slides
-            match slides::create_slides_from_text(&token, &slides_request).await {
-                Ok(presentation_id) => {
-                    let presentation_url = format!(
-                        "https://docs.google.com/presentation/d/{}/edit",
-                        presentation_id
-                    );
-                    let response = serde_json::json!({
-                        "presentation_id": presentation_id,
-                        "presentation_url": presentation_url,
-                        "message": "Slides created successfully"
-                    });
+            match slides::create_slides_from_text(&token, &slides_request, &limits(&ctx)).await {
+                Ok(result) => {
+                    let response = CreateSlidesResponse::from(result);
+                    // Only successes are stored, so a failed attempt can be retried
+                    if let Some(kv_key) = &idempotency_key {
+                        let stored = idempotency::StoredResponse {
+                            status: 200,
+                            body: serde_json::to_value(&response)?,
+                        };
+                        let put = match kv.put(kv_key, &stored) {
+                            Ok(put) => put.expiration_ttl(idempotency::TTL_SECS).execute().await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = put {
+                            warn!("Failed to store idempotent response: {e}");
+                        }
+                    }
                     Response::from_json(&response)
                 }
-                Err(e) => {
-                    let error_response = serde_json::json!({
-                        "error": e.to_string(),
-                        "message": "Failed to create slides"
-                    });
-                    Ok(Response::from_json(&error_response)?.with_status(400))
-                }
+                Err(e) => error_response(&e, "Failed to create slides"),
+            }
+        })
+        .post_async("/api/create-slides/batch", |mut req, ctx| async move {
+            let (_, _, token) = match session_token(&req, &ctx).await {
+                Ok(session) => session,
+                Err(e) => return error_response(&e, "Failed to create slides"),
+            };
+
+            // Parse request body
+            let batch_request: CreateSlidesBatchRequest = req
+                .json()
+                .await
+                .map_err(|e| worker::Error::from(format!("Invalid request body: {}", e)))?;
+
+            // Decks that fail are reported alongside the others, so only a
+            // batch of the wrong size is an error
+            match slides::create_slides_batch(&token, &batch_request, &limits(&ctx)).await {
+                Ok(results) => Response::from_json(&CreateSlidesBatchResponse::from(results)),
+                Err(e) => error_response(&e, "Failed to create slides"),
             }
         })
+        .post_async("/api/create-from-template", |mut req, ctx| async move {
+            let (_, _, token) = match session_token(&req, &ctx).await {
+                Ok(session) => session,
+                Err(e) => return error_response(&e, "Failed to create slides from template"),
+            };
+
+            // Parse request body
+            let template_request: CreateFromTemplateRequest = req
+                .json()
+                .await
+                .map_err(|e| worker::Error::from(format!("Invalid request body: {}", e)))?;
+
+            match slides::create_from_template(&token, &template_request).await {
+                Ok(result) => Response::from_json(&CreateFromTemplateResponse::from(result)),
+                Err(e) => error_response(&e, "Failed to create slides from template"),
+            }
+        })
+        .post_async("/api/preview-split", |mut req, ctx| async move {
+            // Never touches Google, so no session is needed
+            let preview_request: PreviewSplitRequest = match req.json().await {
+                Ok(preview_request) => preview_request,
+                Err(e) => return Response::error(format!("Invalid request body: {}", e), 400),
+            };
+
+            match slides::preview_split(&preview_request, &limits(&ctx)) {
+                Ok(preview) => Response::from_json(&preview),
+                Err(e) => error_response(&e, "Failed to preview split"),
+            }
+        })
+        .get_async("/api/presentations/:id/pdf", |req, ctx| {
+            export_presentation(req, ctx, ExportFormat::Pdf)
+        })
+        .get_async("/api/presentations/:id/pptx", |req, ctx| {
+            export_presentation(req, ctx, ExportFormat::Pptx)
+        })
+        .get("/api/catalog", |req, _| {
+            let catalog = Catalog::build();
+            let etag = catalog.etag();
+
+            let mut resp = if req.headers().get("If-None-Match")?.as_deref() == Some(etag.as_str())
+            {
+                Response::empty()?.with_status(304)
+            } else {
+                Response::from_json(&catalog)?
+            };
+            let headers = resp.headers_mut();
+            headers.set("ETag", &etag)?;
+            headers.set("Cache-Control", "public, max-age=86400")?;
+
+            Ok(resp)
+        })
         .get("/api/splitters", |_, _| {
-            let splitters = serde_json::json!({
-                "splitters": [
-                    {
-                        "type": "newline",
-                        "name": "New Line Splitter",
-                        "description": "Splits text by individual lines"
-                    },
-                    {
-                        "type": "empty_line",
-                        "name": "Empty Line Splitter",
-                        "description": "Splits text by empty lines (paragraphs)"
-                    },
-                    {
-                        "type": "max_words",
-                        "name": "Max Words Splitter",
-                        "description": "Splits text by maximum word count per slide",
-                        "config": {
-                            "max_words": "number (default: 50)"
-                        }
-                    },
-                    {
-                        "type": "max_chars",
-                        "name": "Max Characters Splitter",
-                        "description": "Splits text by maximum character count per slide",
-                        "config": {
-                            "max_chars": "number (default: 500)"
-                        }
-                    }
-                ]
-            });
-            Response::from_json(&splitters)
+            Response::from_json(&Catalog::build().splitters())
+        })
+        .get("/api/presets", |_, _| {
+            Response::from_json(&StylePreset::descriptors())
         })
         .run(req, env)
         .await
@@ -230,6 +770,26 @@ mod tests {
         assert_eq!(cookie(name, value, max_age), expected);
     }
 
+    #[rstest]
+    fn test_expired_cookie_clears_session() {
+        let header = expired_cookie("sid");
+        assert_eq!(
+            header,
+            "sid=; Path=/; HttpOnly; SameSite=Lax; Secure; Max-Age=0"
+        );
+    }
+
+    #[rstest]
+    #[case::logout("https://slides.example/api/logout", None, "https://slides.example/")]
+    #[case::denied(
+        "https://slides.example/oauth/callback?error=access_denied&state=abc#x",
+        Some("denied"),
+        "https://slides.example/?auth=denied"
+    )]
+    fn test_home_url(#[case] url: &str, #[case] auth: Option<&str>, #[case] expected: &str) {
+        assert_eq!(home_url(Url::parse(url).unwrap(), auth).as_str(), expected);
+    }
+
     #[rstest]
     #[case::single_cookie("session=abc123", "session", Some("abc123"))]
     #[case::multiple_cookies_first(
@@ -262,4 +822,73 @@ mod tests {
         let expected = expected.map(|s| s.to_string());
         assert_eq!(result, expected);
     }
+
+    #[rstest]
+    fn test_missing_scopes_page_lists_only_missing_permissions() {
+        let drive = &oauth::config::oauth::REQUIRED_SCOPES[1];
+        let page = missing_scopes_page(&[drive]);
+        assert!(page.contains(drive.permission));
+        assert!(page.contains(drive.reason));
+        assert!(!page.contains(oauth::config::oauth::REQUIRED_SCOPES[0].permission));
+        assert!(page.contains(r#"href="/oauth/start""#));
+    }
+
+    #[rstest]
+    fn test_signed_session_id_accepts_valid_signature() {
+        let signer = SessionSigner::new("current", None);
+        let cookies = format!("theme=dark; sid={}", signer.sign("abc123"));
+        let session_id = signed_session_id(&cookies, &signer).unwrap();
+        assert_eq!(session_id.as_deref(), Some("abc123"));
+    }
+
+    #[rstest]
+    fn test_signed_session_id_accepts_previous_key() {
+        let cookies = format!("sid={}", SessionSigner::new("old", None).sign("abc123"));
+        let signer = SessionSigner::new("new", Some("old"));
+        let session_id = signed_session_id(&cookies, &signer).unwrap();
+        assert_eq!(session_id.as_deref(), Some("abc123"));
+    }
+
+    #[rstest]
+    #[case::unsigned("sid=abc123")]
+    #[case::tampered_id("sid=xyz789.c2lnbmF0dXJl")]
+    #[case::wrong_signature("sid=abc123.AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")]
+    fn test_signed_session_id_rejects_bad_signature(#[case] cookies: &str) {
+        let err = signed_session_id(cookies, &SessionSigner::new("current", None)).unwrap_err();
+        assert!(matches!(err, AppError::AuthRequired));
+        assert_eq!(err.status_code(), 401);
+    }
+
+    #[rstest]
+    fn test_signed_session_id_without_cookie() {
+        let signer = SessionSigner::new("current", None);
+        assert!(signed_session_id("theme=dark", &signer).unwrap().is_none());
+    }
+
+    #[rstest]
+    fn test_me_treats_legacy_unsigned_cookie_as_anonymous() {
+        let visitor = visitor(
+            "theme=dark; sid=abc123",
+            &SessionSigner::new("current", None),
+        );
+        assert_eq!(visitor, Visitor::BadCookie);
+        assert_eq!(visitor.set_cookie(), Some(expired_cookie("sid")));
+    }
+
+    #[rstest]
+    fn test_me_keeps_cookie_without_session() {
+        let visitor = visitor("theme=dark", &SessionSigner::new("current", None));
+        assert_eq!(visitor, Visitor::Anonymous);
+        assert_eq!(visitor.set_cookie(), None);
+    }
+
+    #[rstest]
+    fn test_me_reads_signed_cookie() {
+        let signer = SessionSigner::new("current", None);
+        let cookies = format!("sid={}", signer.sign("abc123"));
+        assert_eq!(
+            visitor(&cookies, &signer),
+            Visitor::SignedIn("abc123".to_string())
+        );
+    }
 }
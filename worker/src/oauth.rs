@@ -1,34 +1,183 @@
+use crate::{crypto::TokenCipher, error::AppError};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use rand::{Rng, distr::Alphanumeric};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::warn;
 use worker::{
-    Date, Error, Fetch, Headers, Method, Request, RequestInit, Result, RouteContext, Url,
+    Date, Error, Fetch, Headers, KvStore, Method, Request, RequestInit, Result, RouteContext, Url,
 };
 
 pub mod config {
     pub mod oauth {
         pub const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
         pub const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
-        pub const SCOPES: &str = "https://www.googleapis.com/auth/presentations https://www.googleapis.com/auth/drive.file";
+        pub const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+        pub const USERINFO_URL: &str = "https://openidconnect.googleapis.com/v1/userinfo";
+        pub const TOKENINFO_URL: &str = "https://oauth2.googleapis.com/tokeninfo";
+
+        /// Scopes identifying the user, for `/api/me`.
+        pub const IDENTITY_SCOPES: &[&str] = &["openid", "email", "profile"];
+
+        /// A scope the app cannot work without, and why, for explaining a
+        /// permission the user did not grant.
+        pub struct RequiredScope {
+            pub scope: &'static str,
+            pub permission: &'static str,
+            pub reason: &'static str,
+        }
+
+        pub const REQUIRED_SCOPES: &[RequiredScope] = &[
+            RequiredScope {
+                scope: "https://www.googleapis.com/auth/presentations",
+                permission: "See, edit, create, and delete all your Google Slides presentations",
+                reason: "Your text is turned into slides in a new presentation.",
+            },
+            RequiredScope {
+                scope: "https://www.googleapis.com/auth/drive.file",
+                permission: "See, edit, create, and delete only the specific Google Drive files you use with this app",
+                reason: "Decks are copied from templates and exported as PDF or PowerPoint through Drive.",
+            },
+        ];
+
+        /// Every scope requested at sign-in, space separated.
+        pub fn scopes() -> String {
+            let required = REQUIRED_SCOPES.iter().map(|required| required.scope);
+            IDENTITY_SCOPES
+                .iter()
+                .copied()
+                .chain(required)
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
     }
 
     pub mod security {
+        /// How long before it expires an access token is refreshed, so it
+        /// cannot expire partway through a request.
+        pub const REFRESH_SKEW_SECS: u64 = 60;
         pub const STATE_LENGTH: usize = 24;
+        pub const CODE_REPLAY_TTL_SECS: u64 = 10 * 60;
+        /// How long a login started at `/oauth/start` can be completed.
+        pub const LOGIN_TTL_SECS: u64 = 10 * 60;
         pub const ID_LENGTH: usize = 32;
         pub const VERIFIER_LENGTH: usize = 64;
     }
 }
 
 /// Represents an OAuth 2.0 access token response from Google.
+///
+/// Google only sends a refresh token on the first consent, and may leave out
+/// the scope and token type, so those are optional; `created_at` is set by
+/// the worker rather than Google.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub access_token: String,
-    pub refresh_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
     pub expires_in: u64,
+    #[serde(default = "default_token_type")]
     pub token_type: String,
+    #[serde(default)]
     pub scope: String,
+    #[serde(default)]
     pub created_at: u64,
+    /// The user's Google profile, cached by `/api/me` so it is fetched once
+    /// per session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+}
+
+/// Who signed in, from Google's OpenID Connect userinfo endpoint. Each field
+/// is absent when the user did not grant the scope that provides it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub picture: Option<String>,
+}
+
+fn default_token_type() -> String {
+    "Bearer".to_string()
+}
+
+impl Token {
+    /// Unix time, in seconds, at which the access token expires.
+    pub fn expires_at(&self) -> u64 {
+        self.created_at.saturating_add(self.expires_in)
+    }
+
+    /// The scopes the user granted.
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scope.split_whitespace().collect()
+    }
+
+    /// The required scopes the user did not grant on the consent screen.
+    ///
+    /// A token that does not say what was granted is missing every required
+    /// scope; [`token_scope`] asks Google for it first.
+    pub fn missing_scopes(&self) -> Vec<&'static config::oauth::RequiredScope> {
+        let granted = self.scopes();
+        config::oauth::REQUIRED_SCOPES
+            .iter()
+            .filter(|required| !granted.contains(&required.scope))
+            .collect()
+    }
+
+    /// Whether the access token has expired or will within `skew_secs`.
+    pub fn is_expired(&self, skew_secs: u64) -> bool {
+        self.is_expired_at(now_secs(), skew_secs)
+    }
+
+    fn is_expired_at(&self, now: u64, skew_secs: u64) -> bool {
+        now.saturating_add(skew_secs) >= self.expires_at()
+    }
+
+    /// The token after a refresh at `now`. Google usually leaves out the
+    /// refresh token, and may leave out the scope, so those are kept.
+    fn refreshed(&self, response: RefreshResponse, now: u64) -> Token {
+        Token {
+            access_token: response.access_token,
+            refresh_token: response
+                .refresh_token
+                .or_else(|| self.refresh_token.clone()),
+            expires_in: response.expires_in,
+            token_type: response.token_type,
+            scope: response.scope.unwrap_or_else(|| self.scope.clone()),
+            created_at: now,
+            profile: self.profile.clone(),
+        }
+    }
+}
+
+/// Token endpoint response to a `refresh_token` grant.
+#[derive(Debug, Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    expires_in: u64,
+    #[serde(default = "default_token_type")]
+    token_type: String,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// Token endpoint error body, as defined by RFC 6749.
+#[derive(Debug, Deserialize)]
+struct OAuthErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Current Unix time in seconds.
+fn now_secs() -> u64 {
+    Date::now().as_millis() / 1000
 }
 
 /// Generates a cryptographically secure random string of the specified length.
@@ -50,6 +199,63 @@ fn generate_pkce_challenge(verifier: &str) -> String {
     URL_SAFE_NO_PAD.encode(digest)
 }
 
+/// Builds the KV key used to mark an authorization code as already used.
+///
+/// The code itself is hashed so it never ends up in storage.
+pub fn code_replay_key(code: &str) -> String {
+    let digest = Sha256::digest(code.as_bytes());
+    format!("code:{}", URL_SAFE_NO_PAD.encode(digest))
+}
+
+/// Outcome of checking an authorization code against previously used codes.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CodeUse {
+    /// The code has not been seen before.
+    First,
+    /// The code was already presented to the callback.
+    Replayed,
+    /// The replay check could not be performed (e.g. KV unavailable).
+    Unverified,
+}
+
+/// Classifies the result of looking up a code's replay key in KV.
+pub fn check_code_use(lookup: Result<Option<String>>) -> CodeUse {
+    match lookup {
+        Ok(Some(_)) => CodeUse::Replayed,
+        Ok(None) => CodeUse::First,
+        Err(_) => CodeUse::Unverified,
+    }
+}
+
+/// Reduces a `User-Agent` header to a coarse browser family.
+pub fn user_agent_family(user_agent: &str) -> &'static str {
+    // Order matters: Edge and Chrome UAs also mention Safari.
+    if user_agent.contains("Edg/") {
+        "edge"
+    } else if user_agent.contains("Firefox/") {
+        "firefox"
+    } else if user_agent.contains("Chrome/") || user_agent.contains("CriOS/") {
+        "chrome"
+    } else if user_agent.contains("Safari/") {
+        "safari"
+    } else {
+        "other"
+    }
+}
+
+/// Builds the state cookie payload carrying the state and user agent family.
+pub fn state_cookie_value(state: &str, ua_family: &str) -> String {
+    format!("{state}.{ua_family}")
+}
+
+/// Splits a state cookie payload into the state and user agent family.
+pub fn parse_state_cookie(value: &str) -> (&str, Option<&str>) {
+    match value.split_once('.') {
+        Some((state, family)) => (state, Some(family)),
+        None => (value, None),
+    }
+}
+
 /// Initiates the OAuth 2.0 authorization flow with Google.
 pub async fn start(ctx: &RouteContext<()>) -> Result<(Url, String, String)> {
     let client_id = ctx.var("GOOGLE_CLIENT_ID")?.to_string();
@@ -64,7 +270,7 @@ pub async fn start(ctx: &RouteContext<()>) -> Result<(Url, String, String)> {
         .append_pair("client_id", &client_id)
         .append_pair("redirect_uri", &redirect_uri)
         .append_pair("response_type", "code")
-        .append_pair("scope", config::oauth::SCOPES)
+        .append_pair("scope", &config::oauth::scopes())
         .append_pair("state", &state)
         .append_pair("code_challenge", &challenge)
         .append_pair("code_challenge_method", "S256")
@@ -74,11 +280,149 @@ pub async fn start(ctx: &RouteContext<()>) -> Result<(Url, String, String)> {
     Ok((url, state, verifier))
 }
 
+/// A login started at `/oauth/start`, kept in KV under its state until the
+/// callback completes it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub verifier: String,
+    pub created_at: u64,
+}
+
+impl PendingLogin {
+    /// Whether the login is too old to complete at `now`. KV expiry is not
+    /// exact, so the age is checked as well.
+    fn is_expired_at(&self, now: u64) -> bool {
+        now > self.created_at + config::security::LOGIN_TTL_SECS
+    }
+}
+
+/// Builds the KV key a pending login is stored under.
+fn login_key(state: &str) -> String {
+    format!("login:{state}")
+}
+
+/// Stores the PKCE verifier for a login under its state until the callback.
+pub async fn save_login(kv: &KvStore, state: &str, verifier: &str) -> Result<()> {
+    let login = PendingLogin {
+        verifier: verifier.to_string(),
+        created_at: now_secs(),
+    };
+    kv.put(&login_key(state), &login)?
+        .expiration_ttl(config::security::LOGIN_TTL_SECS)
+        .execute()
+        .await?;
+    Ok(())
+}
+
+/// The storage completing a login needs; a trait so the steps can be checked
+/// against a fake.
+trait LoginStore {
+    async fn get_login(&self, key: &str) -> Result<Option<String>>;
+    async fn delete_login(&self, key: &str) -> Result<()>;
+}
+
+/// Pending logins kept in KV.
+struct KvLogins<'a>(&'a KvStore);
+
+impl LoginStore for KvLogins<'_> {
+    async fn get_login(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.0.get(key).text().await?)
+    }
+
+    async fn delete_login(&self, key: &str) -> Result<()> {
+        Ok(self.0.delete(key).await?)
+    }
+}
+
+/// Returns the PKCE verifier of the login started with `state`, removing
+/// the login so it can only be completed once.
+///
+/// Missing, expired and unreadable logins are all reported as
+/// [`AppError::LoginExpired`].
+pub async fn take_login(kv: &KvStore, state: &str) -> std::result::Result<String, AppError> {
+    take_pending_login(&KvLogins(kv), state, now_secs()).await
+}
+
+/// Does the work of [`take_login`] through any [`LoginStore`].
+async fn take_pending_login(
+    logins: &impl LoginStore,
+    state: &str,
+    now: u64,
+) -> std::result::Result<String, AppError> {
+    let key = login_key(state);
+    let Some(data) = logins.get_login(&key).await.map_err(oauth_error)? else {
+        return Err(AppError::LoginExpired);
+    };
+    // Deleted before the exchange, so a replayed callback finds nothing
+    logins.delete_login(&key).await.map_err(oauth_error)?;
+    match serde_json::from_str::<PendingLogin>(&data) {
+        Ok(login) if !login.is_expired_at(now) => Ok(login.verifier),
+        _ => Err(AppError::LoginExpired),
+    }
+}
+
+/// What Google sent back to the OAuth callback.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CallbackQuery {
+    /// The user gave consent; the code is exchanged for tokens.
+    Code { code: String, state: String },
+    /// Google reported an error instead, such as `access_denied` when the
+    /// user cancels on the consent screen. `state` is echoed back when the
+    /// login got as far as Google.
+    Error {
+        error: String,
+        description: Option<String>,
+        state: Option<String>,
+    },
+}
+
+impl CallbackQuery {
+    /// Reads the callback's query parameters. An `error` wins over a code.
+    pub fn parse(query: &HashMap<String, String>) -> Result<Self> {
+        if let Some(error) = query.get("error") {
+            return Ok(CallbackQuery::Error {
+                error: error.clone(),
+                description: query.get("error_description").cloned(),
+                state: query.get("state").cloned(),
+            });
+        }
+        let code = query.get("code").ok_or("missing code")?.to_string();
+        let state = query.get("state").ok_or("missing state")?.to_string();
+        Ok(CallbackQuery::Code { code, state })
+    }
+}
+
+/// Value of the `auth` query flag the user is sent home with after a failed
+/// sign-in: `denied` when they cancelled, `error` for anything else.
+pub fn auth_failure_flag(error: &str) -> &'static str {
+    if error == "access_denied" {
+        "denied"
+    } else {
+        "error"
+    }
+}
+
 /// Exchanges an authorization code for access and refresh tokens.
-pub async fn exchange(ctx: &RouteContext<()>, code: &str, verifier: &str) -> Result<Token> {
-    let client_id = ctx.var("GOOGLE_CLIENT_ID")?.to_string();
-    let client_secret = ctx.var("GOOGLE_CLIENT_SECRET")?.to_string();
-    let redirect_uri = ctx.var("GOOGLE_REDIRECT_URI")?.to_string();
+///
+/// Errors from the token endpoint are reported with Google's error code and
+/// description rather than as a failure to parse the token.
+pub async fn exchange(
+    ctx: &RouteContext<()>,
+    code: &str,
+    verifier: &str,
+) -> std::result::Result<Token, AppError> {
+    let client_id = ctx
+        .var("GOOGLE_CLIENT_ID")
+        .map_err(oauth_error)?
+        .to_string();
+    let client_secret = ctx
+        .var("GOOGLE_CLIENT_SECRET")
+        .map_err(oauth_error)?
+        .to_string();
+    let redirect_uri = ctx
+        .var("GOOGLE_REDIRECT_URI")
+        .map_err(oauth_error)?
+        .to_string();
 
     let params = [
         ("code", code),
@@ -89,7 +433,249 @@ pub async fn exchange(ctx: &RouteContext<()>, code: &str, verifier: &str) -> Res
         ("code_verifier", verifier),
     ];
 
-    let body = serde_urlencoded::to_string(&params).map_err(|e| Error::from(e.to_string()))?;
+    let body = serde_urlencoded::to_string(params).map_err(|e| AppError::OAuth(e.to_string()))?;
+
+    let headers = Headers::new();
+    headers
+        .set("Content-Type", "application/x-www-form-urlencoded")
+        .map_err(oauth_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    let request = Request::new_with_init(config::oauth::TOKEN_URL, &init).map_err(oauth_error)?;
+    let mut response = Fetch::Request(request).send().await.map_err(oauth_error)?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(exchange_error(&error_text));
+    }
+
+    let mut token: Token = response.json().await.map_err(oauth_error)?;
+    token.created_at = now_secs();
+
+    Ok(token)
+}
+
+/// Gets a new access token for `token` with its refresh token.
+///
+/// A refresh token Google no longer accepts, because it was revoked or has
+/// expired, is a [`AppError::SessionExpired`], and a token without one is a
+/// [`AppError::ConsentRequired`]: either way only signing in again helps.
+pub async fn refresh(
+    ctx: &RouteContext<()>,
+    token: &Token,
+) -> std::result::Result<Token, AppError> {
+    let refresh_token = token
+        .refresh_token
+        .as_deref()
+        .ok_or(AppError::ConsentRequired)?;
+    let client_id = ctx
+        .var("GOOGLE_CLIENT_ID")
+        .map_err(oauth_error)?
+        .to_string();
+    let client_secret = ctx
+        .var("GOOGLE_CLIENT_SECRET")
+        .map_err(oauth_error)?
+        .to_string();
+
+    let params = [
+        ("client_id", client_id.as_str()),
+        ("client_secret", &client_secret),
+        ("refresh_token", refresh_token),
+        ("grant_type", "refresh_token"),
+    ];
+    let body = serde_urlencoded::to_string(params).map_err(|e| AppError::OAuth(e.to_string()))?;
+
+    let headers = Headers::new();
+    headers
+        .set("Content-Type", "application/x-www-form-urlencoded")
+        .map_err(oauth_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    let request = Request::new_with_init(config::oauth::TOKEN_URL, &init).map_err(oauth_error)?;
+    let mut response = Fetch::Request(request).send().await.map_err(oauth_error)?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(refresh_error(&error_text));
+    }
+
+    let refreshed: RefreshResponse = response.json().await.map_err(oauth_error)?;
+    Ok(token.refreshed(refreshed, now_secs()))
+}
+
+fn oauth_error(e: Error) -> AppError {
+    AppError::OAuth(e.to_string())
+}
+
+/// Maps a failed refresh to an application error.
+fn refresh_error(error_text: &str) -> AppError {
+    match serde_json::from_str::<OAuthErrorResponse>(error_text) {
+        Ok(response) if response.error == "invalid_grant" => AppError::SessionExpired,
+        Ok(response) => AppError::OAuth(format!(
+            "Failed to refresh the access token: {}",
+            response.error_description.unwrap_or(response.error)
+        )),
+        Err(_) => AppError::OAuth(format!("Failed to refresh the access token: {error_text}")),
+    }
+}
+
+/// Maps a failed authorization code exchange to an application error,
+/// explaining the two errors most often hit while setting the app up.
+fn exchange_error(error_text: &str) -> AppError {
+    let Ok(response) = serde_json::from_str::<OAuthErrorResponse>(error_text) else {
+        return AppError::OAuth(format!(
+            "Failed to exchange the authorization code: {error_text}"
+        ));
+    };
+    let explanation = match response.error.as_str() {
+        "invalid_grant" => {
+            "the authorization code is invalid, expired or already used, or the \
+             PKCE verifier does not match; sign in again"
+        }
+        "redirect_uri_mismatch" => {
+            "GOOGLE_REDIRECT_URI does not match an authorized redirect URI of the \
+             OAuth client; add it in the Google Cloud console"
+        }
+        _ => "Google rejected the request",
+    };
+    let details = match response.error_description {
+        Some(description) => format!("{}: {description}", response.error),
+        None => response.error,
+    };
+    AppError::OAuth(format!(
+        "Failed to exchange the authorization code: {explanation} ({details})"
+    ))
+}
+
+/// Fetches the signed-in user's profile.
+pub async fn userinfo(token: &Token) -> std::result::Result<Profile, AppError> {
+    let headers = Headers::new();
+    headers
+        .set("Authorization", &format!("Bearer {}", token.access_token))
+        .map_err(oauth_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Get).with_headers(headers);
+
+    let request =
+        Request::new_with_init(config::oauth::USERINFO_URL, &init).map_err(oauth_error)?;
+    let mut response = Fetch::Request(request).send().await.map_err(oauth_error)?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(AppError::OAuth(format!(
+            "Failed to fetch the user's profile (status {}): {error_text}",
+            response.status_code()
+        )));
+    }
+    response.json().await.map_err(oauth_error)
+}
+
+/// What Google's tokeninfo endpoint reports about an access token.
+#[derive(Debug, Deserialize)]
+struct TokenInfo {
+    #[serde(default)]
+    scope: String,
+}
+
+/// Asks Google which scopes an access token was granted, for tokens from the
+/// exchange that leave out their scope.
+pub async fn token_scope(token: &Token) -> std::result::Result<String, AppError> {
+    // Sent in the body so the token stays out of request URLs
+    let body = serde_urlencoded::to_string([("access_token", token.access_token.as_str())])
+        .map_err(|e| AppError::OAuth(e.to_string()))?;
+
+    let headers = Headers::new();
+    headers
+        .set("Content-Type", "application/x-www-form-urlencoded")
+        .map_err(oauth_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    let request =
+        Request::new_with_init(config::oauth::TOKENINFO_URL, &init).map_err(oauth_error)?;
+    let mut response = Fetch::Request(request).send().await.map_err(oauth_error)?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(AppError::OAuth(format!(
+            "Failed to check the granted scopes (status {}): {error_text}",
+            response.status_code()
+        )));
+    }
+    let info: TokenInfo = response.json().await.map_err(oauth_error)?;
+    Ok(info.scope)
+}
+
+/// The session storage and Google call that signing out needs; a trait so
+/// the steps can be checked against a fake.
+trait SessionEnd {
+    async fn session_token(&self, session_id: &str) -> Result<Option<Token>>;
+    async fn delete_session(&self, session_id: &str) -> Result<()>;
+    async fn revoke(&self, token: &str) -> Result<()>;
+}
+
+/// Sessions kept encrypted in KV, with tokens revoked at Google.
+struct KvSessions<'a>(&'a KvStore, &'a TokenCipher);
+
+impl SessionEnd for KvSessions<'_> {
+    async fn session_token(&self, session_id: &str) -> Result<Option<Token>> {
+        let data = self.0.get(session_id).text().await?;
+        Ok(data.and_then(|data| self.1.open(&data)))
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<()> {
+        Ok(self.0.delete(session_id).await?)
+    }
+
+    async fn revoke(&self, token: &str) -> Result<()> {
+        revoke(token).await
+    }
+}
+
+/// Ends a session: deletes it from KV and revokes its token at Google.
+///
+/// Every step is best-effort and only logged when it fails, so signing out
+/// always succeeds, including for sessions that are already gone.
+pub async fn logout(kv: &KvStore, cipher: &TokenCipher, session_id: &str) {
+    end_session(&KvSessions(kv, cipher), session_id).await
+}
+
+/// Does the work of [`logout`] through any [`SessionEnd`].
+async fn end_session(sessions: &impl SessionEnd, session_id: &str) {
+    let token = match sessions.session_token(session_id).await {
+        Ok(token) => token,
+        Err(e) => {
+            warn!("Failed to read session for logout: {e}");
+            None
+        }
+    };
+    if let Err(e) = sessions.delete_session(session_id).await {
+        warn!("Failed to delete session: {e}");
+    }
+    // Revoking the refresh token revokes its access tokens too
+    if let Some(token) = token {
+        let revoked = token
+            .refresh_token
+            .as_deref()
+            .unwrap_or(&token.access_token);
+        if let Err(e) = sessions.revoke(revoked).await {
+            warn!("Failed to revoke token: {e}");
+        }
+    }
+}
+
+/// Asks Google to revoke an access or refresh token.
+async fn revoke(token: &str) -> Result<()> {
+    let body =
+        serde_urlencoded::to_string([("token", token)]).map_err(|e| Error::from(e.to_string()))?;
 
     let headers = Headers::new();
     headers.set("Content-Type", "application/x-www-form-urlencoded")?;
@@ -99,11 +685,571 @@ pub async fn exchange(ctx: &RouteContext<()>, code: &str, verifier: &str) -> Res
         .with_body(Some(body.into()))
         .with_headers(headers);
 
-    let request = Request::new_with_init(config::oauth::TOKEN_URL, &init)?;
+    let request = Request::new_with_init(config::oauth::REVOKE_URL, &init)?;
     let mut response = Fetch::Request(request).send().await?;
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(Error::from(format!(
+            "revocation failed with status {}: {error_text}",
+            response.status_code()
+        )));
+    }
+    Ok(())
+}
 
-    let mut token: Token = response.json().await?;
-    token.created_at = Date::now().as_millis() / 1000;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::resolve;
+    use rstest::rstest;
 
-    Ok(token)
+    fn token(created_at: u64, expires_in: u64) -> Token {
+        Token {
+            access_token: "old-access".to_string(),
+            refresh_token: Some("old-refresh".to_string()),
+            expires_in,
+            token_type: "Bearer".to_string(),
+            scope: "slides drive".to_string(),
+            created_at,
+            profile: None,
+        }
+    }
+
+    #[rstest]
+    #[case::fresh(1_000, false)]
+    #[case::inside_skew(1_000 + 3_600 - 60, true)]
+    #[case::just_outside_skew(1_000 + 3_600 - 61, false)]
+    #[case::expired(1_000 + 3_600 + 5, true)]
+    fn test_token_expiry(#[case] now: u64, #[case] expired: bool) {
+        let token = token(1_000, 3_600);
+        assert_eq!(token.expires_at(), 4_600);
+        assert_eq!(
+            token.is_expired_at(now, config::security::REFRESH_SKEW_SECS),
+            expired
+        );
+    }
+
+    #[rstest]
+    fn test_token_expiry_does_not_overflow() {
+        let token = token(u64::MAX - 10, 3_600);
+        assert_eq!(token.expires_at(), u64::MAX);
+        assert!(!token.is_expired_at(1_000, config::security::REFRESH_SKEW_SECS));
+    }
+
+    #[rstest]
+    fn test_refreshed_keeps_omitted_fields() {
+        let response: RefreshResponse = serde_json::from_str(
+            r#"{"access_token":"new-access","expires_in":3599,"token_type":"Bearer"}"#,
+        )
+        .unwrap();
+        let refreshed = token(1_000, 3_600).refreshed(response, 5_000);
+        assert_eq!(refreshed.access_token, "new-access");
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("old-refresh"));
+        assert_eq!(refreshed.scope, "slides drive");
+        assert_eq!(refreshed.expires_in, 3599);
+        assert_eq!(refreshed.created_at, 5_000);
+    }
+
+    #[rstest]
+    fn test_refreshed_keeps_cached_profile() {
+        let response: RefreshResponse =
+            serde_json::from_str(r#"{"access_token":"new-access","expires_in":3599}"#).unwrap();
+        let profile = Profile {
+            email: Some("sam@example.com".to_string()),
+            ..Profile::default()
+        };
+        let old = Token {
+            profile: Some(profile.clone()),
+            ..token(1_000, 3_600)
+        };
+        assert_eq!(old.refreshed(response, 5_000).profile, Some(profile));
+    }
+
+    #[rstest]
+    fn test_profile_from_userinfo() {
+        let profile: Profile = serde_json::from_str(
+            r#"{"sub":"1234","name":"Sam Lee","given_name":"Sam","picture":"https://lh3.googleusercontent.com/a/x","email":"sam@example.com","email_verified":true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            profile,
+            Profile {
+                email: Some("sam@example.com".to_string()),
+                name: Some("Sam Lee".to_string()),
+                picture: Some("https://lh3.googleusercontent.com/a/x".to_string()),
+            }
+        );
+        let profile: Profile = serde_json::from_str(r#"{"sub":"1234"}"#).unwrap();
+        assert_eq!(profile, Profile::default());
+    }
+
+    #[rstest]
+    #[case::google("openid email https://www.googleapis.com/auth/presentations", vec!["openid", "email", "https://www.googleapis.com/auth/presentations"])]
+    #[case::empty("", vec![])]
+    fn test_token_scopes(#[case] scope: &str, #[case] expected: Vec<&str>) {
+        let token = Token {
+            scope: scope.to_string(),
+            ..token(1_000, 3_600)
+        };
+        assert_eq!(token.scopes(), expected);
+    }
+
+    #[rstest]
+    fn test_scopes_request_identity_and_required_scopes() {
+        assert_eq!(
+            config::oauth::scopes(),
+            "openid email profile https://www.googleapis.com/auth/presentations https://www.googleapis.com/auth/drive.file"
+        );
+    }
+
+    #[rstest]
+    #[case::all_granted(
+        "openid email profile https://www.googleapis.com/auth/presentations https://www.googleapis.com/auth/drive.file",
+        vec![]
+    )]
+    #[case::any_order(
+        "https://www.googleapis.com/auth/drive.file openid https://www.googleapis.com/auth/presentations",
+        vec![]
+    )]
+    #[case::extra_scopes(
+        "https://www.googleapis.com/auth/presentations https://www.googleapis.com/auth/drive.file https://www.googleapis.com/auth/userinfo.email https://www.googleapis.com/auth/calendar",
+        vec![]
+    )]
+    #[case::not_reported(
+        "",
+        vec!["https://www.googleapis.com/auth/presentations", "https://www.googleapis.com/auth/drive.file"]
+    )]
+    #[case::no_drive(
+        "openid email profile https://www.googleapis.com/auth/presentations",
+        vec!["https://www.googleapis.com/auth/drive.file"]
+    )]
+    #[case::no_slides(
+        "openid email profile https://www.googleapis.com/auth/drive.file",
+        vec!["https://www.googleapis.com/auth/presentations"]
+    )]
+    #[case::identity_only(
+        "openid email profile",
+        vec!["https://www.googleapis.com/auth/presentations", "https://www.googleapis.com/auth/drive.file"]
+    )]
+    fn test_missing_scopes(#[case] scope: &str, #[case] expected: Vec<&str>) {
+        let token = Token {
+            scope: scope.to_string(),
+            ..token(1_000, 3_600)
+        };
+        let missing: Vec<_> = token
+            .missing_scopes()
+            .iter()
+            .map(|required| required.scope)
+            .collect();
+        assert_eq!(missing, expected);
+    }
+
+    #[rstest]
+    fn test_token_without_scope_field_is_missing_every_scope() {
+        let token: Token = serde_json::from_str(
+            r#"{"access_token": "access", "expires_in": 3600, "token_type": "Bearer"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            token.missing_scopes().len(),
+            config::oauth::REQUIRED_SCOPES.len()
+        );
+    }
+
+    #[rstest]
+    fn test_token_info_scope() {
+        let info: TokenInfo = serde_json::from_str(
+            r#"{"azp": "client", "scope": "openid https://www.googleapis.com/auth/drive.file", "expires_in": "3599"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            info.scope,
+            "openid https://www.googleapis.com/auth/drive.file"
+        );
+        let info: TokenInfo = serde_json::from_str(r#"{"azp": "client"}"#).unwrap();
+        assert!(info.scope.is_empty());
+    }
+
+    #[rstest]
+    fn test_refreshed_takes_new_refresh_token() {
+        let response: RefreshResponse = serde_json::from_str(
+            r#"{"access_token":"new-access","refresh_token":"new-refresh","expires_in":3599,"token_type":"Bearer","scope":"slides"}"#,
+        )
+        .unwrap();
+        let refreshed = token(1_000, 3_600).refreshed(response, 5_000);
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("new-refresh"));
+        assert_eq!(refreshed.scope, "slides");
+    }
+
+    #[rstest]
+    fn test_token_from_first_consent() {
+        let token: Token = serde_json::from_str(
+            r#"{"access_token":"ya29.a0","expires_in":3599,"refresh_token":"1//0g","scope":"https://www.googleapis.com/auth/presentations","token_type":"Bearer"}"#,
+        )
+        .unwrap();
+        assert_eq!(token.refresh_token.as_deref(), Some("1//0g"));
+        assert_eq!(token.token_type, "Bearer");
+        assert_eq!(token.created_at, 0);
+    }
+
+    #[rstest]
+    fn test_token_without_refresh_token_scope_or_type() {
+        let token: Token =
+            serde_json::from_str(r#"{"access_token":"ya29.a0","expires_in":3599}"#).unwrap();
+        assert_eq!(token.refresh_token, None);
+        assert_eq!(token.token_type, "Bearer");
+        assert_eq!(token.scope, "");
+    }
+
+    #[rstest]
+    fn test_token_ignores_unknown_fields() {
+        let token: Token = serde_json::from_str(
+            r#"{"access_token":"ya29.a0","expires_in":3599,"token_type":"Bearer","id_token":"eyJ","refresh_token_expires_in":604799}"#,
+        )
+        .unwrap();
+        assert_eq!(token.access_token, "ya29.a0");
+        assert_eq!(token.refresh_token, None);
+    }
+
+    #[rstest]
+    fn test_stored_token_round_trips() {
+        let stored = serde_json::to_string(&token(1_000, 3_600)).unwrap();
+        let token: Token = serde_json::from_str(&stored).unwrap();
+        assert_eq!(token.refresh_token.as_deref(), Some("old-refresh"));
+        assert_eq!(token.created_at, 1_000);
+    }
+
+    #[rstest]
+    fn test_refreshed_without_any_refresh_token() {
+        let response: RefreshResponse =
+            serde_json::from_str(r#"{"access_token":"new-access","expires_in":3599}"#).unwrap();
+        let old = Token {
+            refresh_token: None,
+            ..token(1_000, 3_600)
+        };
+        let refreshed = old.refreshed(response, 5_000);
+        assert_eq!(refreshed.refresh_token, None);
+        assert_eq!(refreshed.token_type, "Bearer");
+    }
+
+    #[rstest]
+    #[case::invalid_grant(
+        r#"{"error":"invalid_grant","error_description":"Token has been expired or revoked."}"#,
+        401,
+        "session_expired"
+    )]
+    #[case::other_error(r#"{"error":"invalid_client"}"#, 500, "oauth_error")]
+    #[case::not_json("Bad Gateway", 500, "oauth_error")]
+    fn test_refresh_error(#[case] error_text: &str, #[case] status: u16, #[case] code: &str) {
+        let err = refresh_error(error_text);
+        assert_eq!(err.status_code(), status);
+        assert_eq!(err.code(), code);
+    }
+
+    /// Session storage that records every step of a logout.
+    struct FakeSessions {
+        token: Option<Token>,
+        fail: bool,
+        calls: std::cell::RefCell<Vec<String>>,
+    }
+
+    impl FakeSessions {
+        fn new(token: Option<Token>) -> Self {
+            Self {
+                token,
+                fail: false,
+                calls: Default::default(),
+            }
+        }
+
+        fn step(&self, call: String) -> Result<()> {
+            self.calls.borrow_mut().push(call);
+            if self.fail {
+                Err(Error::from("unavailable"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl SessionEnd for FakeSessions {
+        async fn session_token(&self, session_id: &str) -> Result<Option<Token>> {
+            self.step(format!("get {session_id}"))?;
+            Ok(self.token.clone())
+        }
+
+        async fn delete_session(&self, session_id: &str) -> Result<()> {
+            self.step(format!("delete {session_id}"))
+        }
+
+        async fn revoke(&self, token: &str) -> Result<()> {
+            self.step(format!("revoke {token}"))
+        }
+    }
+
+    #[rstest]
+    fn test_logout_deletes_session_and_revokes_refresh_token() {
+        let sessions = FakeSessions::new(Some(token(1_000, 3_600)));
+        resolve(end_session(&sessions, "s1"));
+        assert_eq!(
+            *sessions.calls.borrow(),
+            ["get s1", "delete s1", "revoke old-refresh"]
+        );
+    }
+
+    #[rstest]
+    fn test_logout_revokes_access_token_without_refresh_token() {
+        let token = Token {
+            refresh_token: None,
+            ..token(1_000, 3_600)
+        };
+        let sessions = FakeSessions::new(Some(token));
+        resolve(end_session(&sessions, "s1"));
+        assert_eq!(
+            *sessions.calls.borrow(),
+            ["get s1", "delete s1", "revoke old-access"]
+        );
+    }
+
+    #[rstest]
+    fn test_logout_of_missing_session_only_deletes() {
+        let sessions = FakeSessions::new(None);
+        resolve(end_session(&sessions, "gone"));
+        assert_eq!(*sessions.calls.borrow(), ["get gone", "delete gone"]);
+    }
+
+    #[rstest]
+    fn test_logout_keeps_going_when_steps_fail() {
+        let sessions = FakeSessions {
+            fail: true,
+            ..FakeSessions::new(Some(token(1_000, 3_600)))
+        };
+        resolve(end_session(&sessions, "s1"));
+        // Without the stored token there is nothing to revoke
+        assert_eq!(*sessions.calls.borrow(), ["get s1", "delete s1"]);
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[rstest]
+    fn test_callback_query_with_code() {
+        let parsed = CallbackQuery::parse(&query(&[("code", "4/0A"), ("state", "abc")])).unwrap();
+        assert_eq!(
+            parsed,
+            CallbackQuery::Code {
+                code: "4/0A".to_string(),
+                state: "abc".to_string()
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::denied(&[("error", "access_denied"), ("state", "abc")], "access_denied", None, Some("abc"), "denied")]
+    #[case::policy(
+        &[("error", "admin_policy_enforced"), ("error_description", "Blocked by admin")],
+        "admin_policy_enforced",
+        Some("Blocked by admin"),
+        None,
+        "error"
+    )]
+    #[case::error_wins_over_code(&[("error", "interaction_required"), ("code", "4/0A")], "interaction_required", None, None, "error")]
+    fn test_callback_query_with_error(
+        #[case] pairs: &[(&str, &str)],
+        #[case] error: &str,
+        #[case] description: Option<&str>,
+        #[case] state: Option<&str>,
+        #[case] flag: &str,
+    ) {
+        let parsed = CallbackQuery::parse(&query(pairs)).unwrap();
+        assert_eq!(
+            parsed,
+            CallbackQuery::Error {
+                error: error.to_string(),
+                description: description.map(String::from),
+                state: state.map(String::from),
+            }
+        );
+        assert_eq!(auth_failure_flag(error), flag);
+    }
+
+    #[rstest]
+    #[case::no_code(&[("state", "abc")], "missing code")]
+    #[case::no_state(&[("code", "4/0A")], "missing state")]
+    fn test_callback_query_rejects_incomplete(
+        #[case] pairs: &[(&str, &str)],
+        #[case] message: &str,
+    ) {
+        let err = CallbackQuery::parse(&query(pairs)).unwrap_err();
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[rstest]
+    #[case::invalid_grant(
+        r#"{"error": "invalid_grant", "error_description": "Bad Request"}"#,
+        "OAuth error: Failed to exchange the authorization code: the authorization code is invalid, expired or already used, or the PKCE verifier does not match; sign in again (invalid_grant: Bad Request)"
+    )]
+    #[case::redirect_uri_mismatch(
+        r#"{"error": "redirect_uri_mismatch", "error_description": "Bad Request"}"#,
+        "OAuth error: Failed to exchange the authorization code: GOOGLE_REDIRECT_URI does not match an authorized redirect URI of the OAuth client; add it in the Google Cloud console (redirect_uri_mismatch: Bad Request)"
+    )]
+    #[case::other_error(
+        r#"{"error": "invalid_client", "error_description": "The OAuth client was not found."}"#,
+        "OAuth error: Failed to exchange the authorization code: Google rejected the request (invalid_client: The OAuth client was not found.)"
+    )]
+    #[case::no_description(
+        r#"{"error": "unsupported_grant_type"}"#,
+        "OAuth error: Failed to exchange the authorization code: Google rejected the request (unsupported_grant_type)"
+    )]
+    #[case::not_json(
+        "<html>Bad Gateway</html>",
+        "OAuth error: Failed to exchange the authorization code: <html>Bad Gateway</html>"
+    )]
+    fn test_exchange_error(#[case] error_text: &str, #[case] message: &str) {
+        let err = exchange_error(error_text);
+        assert_eq!(err.code(), "oauth_error");
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[rstest]
+    fn test_code_replay_key_is_stable_and_hashed() {
+        let key = code_replay_key("4/0Adeu5BW-secret-code");
+        assert_eq!(key, code_replay_key("4/0Adeu5BW-secret-code"));
+        assert_ne!(key, code_replay_key("4/0Adeu5BW-other-code"));
+        assert!(key.starts_with("code:"));
+        assert!(!key.contains("secret"));
+    }
+
+    #[rstest]
+    #[case::first_use(Ok(None), CodeUse::First)]
+    #[case::replay(Ok(Some("1".to_string())), CodeUse::Replayed)]
+    #[case::kv_unavailable(Err(Error::from("no binding")), CodeUse::Unverified)]
+    fn test_check_code_use(#[case] lookup: Result<Option<String>>, #[case] expected: CodeUse) {
+        assert_eq!(check_code_use(lookup), expected);
+    }
+
+    #[rstest]
+    #[case::chrome(
+        "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0 Safari/537.36",
+        "chrome"
+    )]
+    #[case::edge(
+        "Mozilla/5.0 (Windows NT 10.0) AppleWebKit/537.36 Chrome/126.0 Safari/537.36 Edg/126.0",
+        "edge"
+    )]
+    #[case::firefox(
+        "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0",
+        "firefox"
+    )]
+    #[case::safari(
+        "Mozilla/5.0 (Macintosh) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15",
+        "safari"
+    )]
+    #[case::empty("", "other")]
+    fn test_user_agent_family(#[case] user_agent: &str, #[case] expected: &str) {
+        assert_eq!(user_agent_family(user_agent), expected);
+    }
+
+    #[rstest]
+    #[case::with_family("abc123.chrome", ("abc123", Some("chrome")))]
+    #[case::legacy_without_family("abc123", ("abc123", None))]
+    fn test_parse_state_cookie(#[case] value: &str, #[case] expected: (&str, Option<&str>)) {
+        assert_eq!(parse_state_cookie(value), expected);
+    }
+
+    #[rstest]
+    fn test_state_cookie_roundtrip() {
+        let value = state_cookie_value("abc123", "firefox");
+        assert_eq!(parse_state_cookie(&value), ("abc123", Some("firefox")));
+    }
+
+    /// Pending login storage backed by a map, recording every step.
+    #[derive(Default)]
+    struct FakeLogins {
+        entries: std::cell::RefCell<HashMap<String, String>>,
+        calls: std::cell::RefCell<Vec<String>>,
+    }
+
+    impl FakeLogins {
+        fn with_login(state: &str, created_at: u64) -> Self {
+            let logins = Self::default();
+            let login = PendingLogin {
+                verifier: "verifier".to_string(),
+                created_at,
+            };
+            logins
+                .entries
+                .borrow_mut()
+                .insert(login_key(state), serde_json::to_string(&login).unwrap());
+            logins
+        }
+    }
+
+    impl LoginStore for FakeLogins {
+        async fn get_login(&self, key: &str) -> Result<Option<String>> {
+            self.calls.borrow_mut().push(format!("get {key}"));
+            Ok(self.entries.borrow().get(key).cloned())
+        }
+
+        async fn delete_login(&self, key: &str) -> Result<()> {
+            self.calls.borrow_mut().push(format!("delete {key}"));
+            self.entries.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[case::just_started(1_000)]
+    #[case::at_ttl(1_000 + 600)]
+    fn test_take_login_returns_verifier_and_deletes_it(#[case] now: u64) {
+        let logins = FakeLogins::with_login("s1", 1_000);
+        let verifier = resolve(take_pending_login(&logins, "s1", now)).unwrap();
+        assert_eq!(verifier, "verifier");
+        assert_eq!(*logins.calls.borrow(), ["get login:s1", "delete login:s1"]);
+        assert!(logins.entries.borrow().is_empty());
+    }
+
+    #[rstest]
+    fn test_take_login_is_single_use() {
+        let logins = FakeLogins::with_login("s1", 1_000);
+        assert!(resolve(take_pending_login(&logins, "s1", 1_000)).is_ok());
+        let err = resolve(take_pending_login(&logins, "s1", 1_000)).unwrap_err();
+        assert!(matches!(err, AppError::LoginExpired));
+    }
+
+    #[rstest]
+    fn test_take_login_rejects_expired_login() {
+        // KV has not removed the entry yet, but it is past its lifetime
+        let logins = FakeLogins::with_login("s1", 1_000);
+        let err = resolve(take_pending_login(&logins, "s1", 1_000 + 601)).unwrap_err();
+        assert!(matches!(err, AppError::LoginExpired));
+        assert_eq!(err.status_code(), 400);
+        assert_eq!(err.to_string(), "Login expired; try again");
+        assert!(logins.entries.borrow().is_empty());
+    }
+
+    #[rstest]
+    fn test_take_login_rejects_unknown_state() {
+        let logins = FakeLogins::with_login("s1", 1_000);
+        let err = resolve(take_pending_login(&logins, "other", 1_000)).unwrap_err();
+        assert!(matches!(err, AppError::LoginExpired));
+        // Another login's entry is left alone
+        assert_eq!(logins.entries.borrow().len(), 1);
+    }
+
+    #[rstest]
+    fn test_take_login_rejects_unreadable_entry() {
+        let logins = FakeLogins::default();
+        logins
+            .entries
+            .borrow_mut()
+            .insert(login_key("s1"), "not json".to_string());
+        let err = resolve(take_pending_login(&logins, "s1", 1_000)).unwrap_err();
+        assert!(matches!(err, AppError::LoginExpired));
+        assert!(logins.entries.borrow().is_empty());
+    }
 }
@@ -0,0 +1,462 @@
+//! JSON bodies returned by the API handlers.
+//!
+//! Field names are part of the API the frontend relies on; the snapshot
+//! tests below pin them.
+
+use crate::{
+    error::AppError,
+    oauth::Token,
+    slides::{
+        self, CreateFromTemplateResult, CreateSlidesResult, CreatedSlide, ImageSlideError,
+        ShareError, SlideStats, SlideTotals,
+    },
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Body of a successful `POST /api/create-slides`.
+#[derive(Debug, Serialize)]
+pub struct CreateSlidesResponse {
+    pub presentation_id: String,
+    pub presentation_url: String,
+    pub title: String,
+    pub message: &'static str,
+    pub created_slides: Vec<CreatedSlide>,
+    pub slide_count: usize,
+    pub slides: Vec<SlideStats>,
+    pub totals: SlideTotals,
+    pub duplicates_removed: usize,
+    pub chunks_dropped: usize,
+    pub skipped_slides: usize,
+    pub themed: bool,
+    pub image_slides: Vec<usize>,
+    pub image_errors: Vec<ImageSlideError>,
+    pub share_errors: Vec<ShareError>,
+}
+
+impl From<CreateSlidesResult> for CreateSlidesResponse {
+    fn from(result: CreateSlidesResult) -> Self {
+        Self {
+            presentation_url: slides::presentation_url(&result.presentation_id),
+            presentation_id: result.presentation_id,
+            title: result.title,
+            message: "Slides created successfully",
+            created_slides: result.created_slides,
+            slide_count: result.slide_count,
+            slides: result.slides,
+            totals: result.totals,
+            duplicates_removed: result.duplicates_removed,
+            chunks_dropped: result.chunks_dropped,
+            skipped_slides: result.skipped_slides,
+            themed: result.themed,
+            image_slides: result.image_slides,
+            image_errors: result.image_errors,
+            share_errors: result.share_errors,
+        }
+    }
+}
+
+/// Body of `POST /api/create-slides/batch`: every deck's outcome, in request
+/// order, and how many were created.
+#[derive(Debug, Serialize)]
+pub struct CreateSlidesBatchResponse {
+    pub decks: Vec<BatchDeckResponse>,
+    pub summary: BatchSummary,
+}
+
+/// One deck of a batch, with its index in the request.
+#[derive(Debug, Serialize)]
+pub struct BatchDeckResponse {
+    pub index: usize,
+    #[serde(flatten)]
+    pub outcome: BatchDeckOutcome,
+}
+
+/// The created deck, shaped like a `POST /api/create-slides` response, or
+/// why it could not be created.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum BatchDeckOutcome {
+    Created(Box<CreateSlidesResponse>),
+    Failed(ErrorResponse),
+}
+
+/// Counts of the decks in a batch.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub requested: usize,
+    pub created: usize,
+    pub failed: usize,
+}
+
+impl From<Vec<Result<CreateSlidesResult, AppError>>> for CreateSlidesBatchResponse {
+    fn from(results: Vec<Result<CreateSlidesResult, AppError>>) -> Self {
+        let decks: Vec<_> = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| BatchDeckResponse {
+                index,
+                outcome: match result {
+                    Ok(result) => BatchDeckOutcome::Created(Box::new(result.into())),
+                    Err(e) => BatchDeckOutcome::Failed(ErrorResponse::from_app_error(
+                        &e,
+                        "Failed to create slides",
+                    )),
+                },
+            })
+            .collect();
+        let created = decks
+            .iter()
+            .filter(|deck| matches!(deck.outcome, BatchDeckOutcome::Created(_)))
+            .count();
+        Self {
+            summary: BatchSummary {
+                requested: decks.len(),
+                created,
+                failed: decks.len() - created,
+            },
+            decks,
+        }
+    }
+}
+
+/// Body of a successful `POST /api/create-from-template`.
+#[derive(Debug, Serialize)]
+pub struct CreateFromTemplateResponse {
+    pub presentation_id: String,
+    pub presentation_url: String,
+    pub message: &'static str,
+    pub replaced: BTreeMap<String, usize>,
+    pub skipped: Vec<String>,
+}
+
+impl From<CreateFromTemplateResult> for CreateFromTemplateResponse {
+    fn from(result: CreateFromTemplateResult) -> Self {
+        Self {
+            presentation_url: slides::presentation_url(&result.presentation_id),
+            presentation_id: result.presentation_id,
+            message: "Slides created from template",
+            replaced: result.replaced,
+            skipped: result.skipped,
+        }
+    }
+}
+
+/// Body of `GET /api/me`: who is signed in, if anyone.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct MeResponse {
+    pub authenticated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+    /// Unix time, in seconds, at which the current access token expires.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_expires_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+}
+
+impl MeResponse {
+    /// Reports that nobody is signed in.
+    pub fn anonymous() -> Self {
+        Self {
+            authenticated: false,
+            email: None,
+            name: None,
+            picture: None,
+            token_expires_at: None,
+            scopes: None,
+        }
+    }
+
+    /// Reports a signed-in session; the profile fields are left out when the
+    /// profile could not be fetched.
+    pub fn signed_in(token: &Token) -> Self {
+        let profile = token.profile.clone().unwrap_or_default();
+        Self {
+            authenticated: true,
+            email: profile.email,
+            name: profile.name,
+            picture: profile.picture,
+            token_expires_at: Some(token.expires_at()),
+            scopes: Some(token.scopes().into_iter().map(String::from).collect()),
+        }
+    }
+}
+
+/// Body of `GET /health?deep=true`.
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// `"ok"`, or `"degraded"` when a KV binding is missing.
+    pub status: &'static str,
+    pub kv_bindings: BTreeMap<&'static str, &'static str>,
+}
+
+impl HealthReport {
+    /// Builds the report from each binding's name and whether it is configured.
+    pub fn new(bindings: Vec<(&'static str, bool)>) -> Self {
+        let kv_bindings: BTreeMap<_, _> = bindings
+            .into_iter()
+            .map(|(binding, ok)| (binding, if ok { "ok" } else { "missing" }))
+            .collect();
+        let healthy = kv_bindings.values().all(|status| *status == "ok");
+        Self {
+            status: if healthy { "ok" } else { "degraded" },
+            kv_bindings,
+        }
+    }
+
+    /// Returns 200 when healthy and 503 when degraded.
+    pub fn status_code(&self) -> u16 {
+        if self.status == "ok" { 200 } else { 503 }
+    }
+}
+
+/// Body of every JSON error response.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ErrorResponse {
+    /// What went wrong, for people.
+    pub error: String,
+    /// What went wrong, for programs; absent when the cause is not classified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    /// Which operation failed.
+    pub message: &'static str,
+}
+
+impl ErrorResponse {
+    /// Reports an unclassified error.
+    pub fn new(error: impl ToString, message: &'static str) -> Self {
+        Self {
+            error: error.to_string(),
+            code: None,
+            message,
+        }
+    }
+
+    /// Reports an [`AppError`] along with its code.
+    pub fn from_app_error(error: &AppError, message: &'static str) -> Self {
+        Self {
+            code: Some(error.code()),
+            ..Self::new(error, message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oauth::Profile;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_create_slides_response_json() {
+        let result = CreateSlidesResult {
+            presentation_id: "deck1".to_string(),
+            title: "Deck".to_string(),
+            created_slides: vec![CreatedSlide {
+                object_id: "slide_0".to_string(),
+                url: "https://docs.google.com/presentation/d/deck1/edit#slide=id.slide_0"
+                    .to_string(),
+            }],
+            slide_count: 1,
+            slides: vec![SlideStats {
+                index: 0,
+                words: 2,
+                chars: 11,
+                warning: None,
+            }],
+            totals: SlideTotals {
+                words: 2,
+                chars: 11,
+            },
+            duplicates_removed: 0,
+            chunks_dropped: 0,
+            skipped_slides: 1,
+            themed: false,
+            image_slides: Vec::new(),
+            image_errors: Vec::new(),
+            share_errors: vec![ShareError {
+                email: "sam@example.com".to_string(),
+                error: "forbidden".to_string(),
+            }],
+        };
+        let json = serde_json::to_value(CreateSlidesResponse::from(result)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "presentation_id": "deck1",
+                "presentation_url": "https://docs.google.com/presentation/d/deck1/edit",
+                "title": "Deck",
+                "message": "Slides created successfully",
+                "created_slides": [{
+                    "object_id": "slide_0",
+                    "url": "https://docs.google.com/presentation/d/deck1/edit#slide=id.slide_0"
+                }],
+                "slide_count": 1,
+                "slides": [{"index": 0, "words": 2, "chars": 11}],
+                "totals": {"words": 2, "chars": 11},
+                "duplicates_removed": 0,
+                "chunks_dropped": 0,
+                "skipped_slides": 1,
+                "themed": false,
+                "image_slides": [],
+                "image_errors": [],
+                "share_errors": [{"email": "sam@example.com", "error": "forbidden"}]
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_create_slides_batch_response_json() {
+        let result = CreateSlidesResult {
+            presentation_id: "deck1".to_string(),
+            title: "Deck".to_string(),
+            created_slides: Vec::new(),
+            slide_count: 0,
+            slides: Vec::new(),
+            totals: SlideTotals { words: 0, chars: 0 },
+            duplicates_removed: 0,
+            chunks_dropped: 0,
+            skipped_slides: 0,
+            themed: false,
+            image_slides: Vec::new(),
+            image_errors: Vec::new(),
+            share_errors: Vec::new(),
+        };
+        let response = CreateSlidesBatchResponse::from(vec![
+            Err(AppError::GoogleSlides("quota".to_string())),
+            Ok(result),
+        ]);
+        assert_eq!(
+            response.summary,
+            BatchSummary {
+                requested: 2,
+                created: 1,
+                failed: 1
+            }
+        );
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json["decks"][0],
+            serde_json::json!({
+                "index": 0,
+                "error": "Google Slides API error: quota",
+                "code": "google_api_error",
+                "message": "Failed to create slides"
+            })
+        );
+        assert_eq!(json["decks"][1]["index"], 1);
+        assert_eq!(json["decks"][1]["presentation_id"], "deck1");
+        assert_eq!(
+            json["summary"],
+            serde_json::json!({"requested": 2, "created": 1, "failed": 1})
+        );
+    }
+
+    #[rstest]
+    fn test_create_from_template_response_json() {
+        let result = CreateFromTemplateResult {
+            presentation_id: "copy1".to_string(),
+            replaced: BTreeMap::from([("{{name}}".to_string(), 2)]),
+            skipped: vec!["{{date}}".to_string()],
+        };
+        let json = serde_json::to_value(CreateFromTemplateResponse::from(result)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "presentation_id": "copy1",
+                "presentation_url": "https://docs.google.com/presentation/d/copy1/edit",
+                "message": "Slides created from template",
+                "replaced": {"{{name}}": 2},
+                "skipped": ["{{date}}"]
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::healthy(
+        vec![("TOKENS", true)],
+        200,
+        serde_json::json!({"status": "ok", "kv_bindings": {"TOKENS": "ok"}})
+    )]
+    #[case::degraded(
+        vec![("TOKENS", false)],
+        503,
+        serde_json::json!({"status": "degraded", "kv_bindings": {"TOKENS": "missing"}})
+    )]
+    fn test_health_report_json(
+        #[case] bindings: Vec<(&'static str, bool)>,
+        #[case] status_code: u16,
+        #[case] expected: serde_json::Value,
+    ) {
+        let report = HealthReport::new(bindings);
+        assert_eq!(report.status_code(), status_code);
+        assert_eq!(serde_json::to_value(&report).unwrap(), expected);
+    }
+
+    #[rstest]
+    fn test_me_response_anonymous_json() {
+        let json = serde_json::to_value(MeResponse::anonymous()).unwrap();
+        assert_eq!(json, serde_json::json!({"authenticated": false}));
+    }
+
+    #[rstest]
+    fn test_me_response_signed_in_json() {
+        let token = Token {
+            access_token: "ya29.a0".to_string(),
+            refresh_token: None,
+            expires_in: 3_600,
+            token_type: "Bearer".to_string(),
+            scope: "openid email".to_string(),
+            created_at: 1_000,
+            profile: Some(Profile {
+                email: Some("sam@example.com".to_string()),
+                name: Some("Sam Lee".to_string()),
+                picture: None,
+            }),
+        };
+        let json = serde_json::to_value(MeResponse::signed_in(&token)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "authenticated": true,
+                "email": "sam@example.com",
+                "name": "Sam Lee",
+                "token_expires_at": 4_600,
+                "scopes": ["openid", "email"]
+            })
+        );
+    }
+
+    #[rstest]
+    fn test_error_response_without_code_keeps_legacy_shape() {
+        let json =
+            serde_json::to_value(ErrorResponse::new("quota", "Failed to create slides")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"error": "quota", "message": "Failed to create slides"})
+        );
+    }
+
+    #[rstest]
+    fn test_error_response_from_app_error_json() {
+        let error = AppError::NotFound("presentation abc was not found".to_string());
+        let json = serde_json::to_value(ErrorResponse::from_app_error(
+            &error,
+            "Failed to export PDF",
+        ))
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "error": "Not found: presentation abc was not found",
+                "code": "not_found",
+                "message": "Failed to export PDF"
+            })
+        );
+    }
+}
@@ -1,224 +1,7630 @@
-use crate::{oauth::Token, splitter::Splitter};
+use crate::{
+    drive::{self, ShareRole},
+    error::AppError,
+    frontmatter::{self, FrontMatter},
+    oauth::Token,
+    splitter::{self, Chunk, Media, SlideSpec, Splitter},
+    textmetrics,
+};
+use futures::{StreamExt, TryStreamExt, stream};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
-use worker::{Fetch, Headers, Method, Request as WorkerRequest, RequestInit, Result};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+use tracing::warn;
+use validator::{Validate, ValidateEmail, ValidationError};
+use worker::{Date, Delay, Fetch, Headers, Method, Request as WorkerRequest, RequestInit, Result};
 
 const API_BASE: &str = "https://slides.googleapis.com/v1";
 
 /// Represents a request to create slides from text content.
 #[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_splitter_for_content"))]
+#[validate(schema(function = "validate_mode"))]
+#[validate(schema(function = "validate_locale_target"))]
 pub struct CreateSlidesRequest {
-    #[validate(length(min = 1, max = 100))]
+    /// Presentation title; may be empty when the content's front matter has one.
+    #[serde(default)]
+    #[validate(custom(function = "validate_title"))]
     pub title: String,
 
+    /// Prefer the front matter `title` over `title` even when the latter is set.
+    #[serde(default)]
+    pub use_frontmatter_title: bool,
+
+    #[validate(custom(function = "validate_content"))]
+    pub content: Content,
+
+    /// Required for text content; must be omitted when `content` is already split.
+    #[serde(default)]
+    #[validate(custom(function = "validate_splitter"))]
+    pub splitter: Option<Splitter>,
+
+    /// Chunks with fewer words than this are merged into the previous slide.
+    #[serde(default)]
+    pub min_words: Option<usize>,
+
+    /// Converts NBSP and tabs to spaces and collapses space runs before splitting.
+    #[serde(default)]
+    pub normalize_whitespace: bool,
+
+    /// Line that separates a slide's content from its speaker notes.
+    #[serde(default = "default_notes_marker")]
     #[validate(length(min = 1))]
-    pub content: String,
+    pub notes_marker: String,
 
-    pub splitter: Splitter,
+    /// Speaker notes for each slide by index; empty entries add nothing.
+    #[serde(default)]
+    pub notes: Vec<String>,
+
+    /// Uses each chunk's first line as the slide title and the rest as its body.
+    #[serde(default)]
+    pub title_from_first_line: bool,
+
+    /// Layout of every created slide; must have TITLE and BODY placeholders.
+    #[serde(default)]
+    #[validate(custom(function = "validate_layout"))]
+    pub layout: SlideLayoutReference,
+
+    /// Slide limit and what to do with chunks beyond it.
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub limit: SlideLimit,
+
+    /// Removes chunks identical to an earlier one, keeping the first occurrence.
+    #[serde(default)]
+    pub dedupe: bool,
+
+    /// Converts Markdown in each chunk to plain text before insertion.
+    #[serde(default)]
+    pub strip_markdown: bool,
+
+    /// Renders `**bold**`, `*italic*` and `` `code` `` as styled text.
+    #[serde(default)]
+    pub rich_text: bool,
+
+    /// Turns URLs and `[label](url)` links into clickable links.
+    #[serde(default = "default_link_detection")]
+    pub link_detection: bool,
+
+    /// Template prefixed to each slide's first line, e.g. `"Step {n}: "`.
+    #[serde(default)]
+    #[validate(custom(function = "validate_numbering_prefix"))]
+    pub numbering_prefix: Option<String>,
+
+    /// Slides with more words than this are flagged as long in the response.
+    #[serde(default = "default_long_slide_words")]
+    #[validate(range(min = 1))]
+    pub long_slide_words: usize,
+
+    /// Presentation copied to inherit its theme instead of starting blank.
+    #[serde(default)]
+    #[validate(custom(function = "validate_theme_presentation_id"))]
+    pub theme_presentation_id: Option<String>,
+
+    /// Whether to make a new presentation or refill `presentation_id`.
+    #[serde(default)]
+    pub mode: Mode,
+
+    /// Presentation whose slides `mode: "replace"` replaces, keeping its URL.
+    #[serde(default)]
+    pub presentation_id: Option<String>,
+
+    /// Style applied to each slide's title and body text.
+    #[serde(default)]
+    #[validate(nested)]
+    pub text_style: Option<TextStyle>,
+
+    /// Hex color such as `#202124` filling every slide's background.
+    #[serde(default)]
+    #[validate(custom(function = "validate_background_color"))]
+    pub background_color: Option<String>,
+
+    /// Bundle of background color and text style defaults; `text_style` and
+    /// `background_color` override its individual values.
+    #[serde(default)]
+    pub preset: Option<StylePreset>,
+
+    /// Opens the deck with a title slide; counts towards `max_slides`.
+    #[serde(default)]
+    pub title_slide: Option<TitleSlide>,
+
+    /// Shrinks body text that would overflow its placeholder.
+    #[serde(default = "default_autofit")]
+    pub autofit: bool,
+
+    /// Stamps "n / total" in the bottom-right corner of every slide.
+    #[serde(default)]
+    pub slide_numbers: bool,
+
+    /// With `slide_numbers`, leaves the first slide (usually the title slide)
+    /// unnumbered; the others keep their position in the deck.
+    #[serde(default)]
+    pub skip_first: bool,
+
+    /// Gives every H1 heading a section header slide of its own.
+    #[serde(default)]
+    pub sections: bool,
+
+    /// Ends the deck with a slide showing this text, or [`DEFAULT_CLOSING_TEXT`]
+    /// when blank; counts towards `max_slides`.
+    #[serde(default)]
+    pub closing_slide: Option<String>,
+
+    /// Writing direction of slide text, for right-to-left scripts.
+    #[serde(default)]
+    pub text_direction: TextDirection,
+
+    /// BCP-47 language tag such as `fr-CA` for the new presentation, used by
+    /// spell-check and date formats. Theme copies and replaced decks keep
+    /// their own locale, so it cannot be combined with either.
+    #[serde(default)]
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: Option<String>,
+
+    /// Sends batchUpdate calls one at a time instead of up to
+    /// [`MAX_CONCURRENT_BATCHES`] at once, for users hitting rate limits.
+    #[serde(default)]
+    pub sequential_batches: bool,
+
+    /// Deletes the new presentation when adding its slides fails, so failed
+    /// requests leave nothing behind in Drive.
+    #[serde(default = "default_cleanup_on_failure")]
+    pub cleanup_on_failure: bool,
+
+    /// Email addresses the finished deck is shared with.
+    #[serde(default)]
+    #[validate(custom(function = "validate_share_with"))]
+    pub share_with: Vec<String>,
+
+    /// Access given to everyone in `share_with`.
+    #[serde(default)]
+    pub share_role: ShareRole,
+
+    /// Lets Drive email the people in `share_with` about the deck.
+    #[serde(default = "default_send_notification_email")]
+    pub send_notification_email: bool,
+
+    /// Builds the slides' batchUpdate request without calling Google; see
+    /// [`dry_run`].
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
-/// Google Slides API structures
-#[derive(Debug, Serialize, Deserialize)]
-struct CreatePresentationRequest {
-    title: String,
+/// Writing direction of the text on each slide.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextDirection {
+    /// Left to right, the theme's default.
+    #[default]
+    Ltr,
+    /// Right to left and right-aligned, for Hebrew, Arabic and similar scripts.
+    Rtl,
+    /// Right to left on slides whose letters are mostly from such scripts.
+    Auto,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Presentation {
-    presentation_id: String,
-    title: String,
-    slides: Vec<Slide>,
+/// Closing slide text used when `closing_slide` is blank.
+const DEFAULT_CLOSING_TEXT: &str = "Thank you";
+
+/// Metadata shown under the presentation title on the opening slide.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TitleSlide {
+    pub subtitle: Option<String>,
+    pub author: Option<String>,
+    /// Shown as given, or today's date for [`AUTO_DATE`].
+    pub date: Option<String>,
 }
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Slide {
-    object_id: String,
-    slide_properties: SlideProperties,
+
+/// `title_slide.date` value replaced with the current date.
+const AUTO_DATE: &str = "auto";
+
+impl TitleSlide {
+    /// Joins the subtitle, author and date onto separate lines, skipping
+    /// empty ones. `today` is only called for [`AUTO_DATE`].
+    fn subtitle_text(&self, today: impl FnOnce() -> String) -> String {
+        let date = match self.date.as_deref() {
+            Some(AUTO_DATE) => Some(today()),
+            date => date.map(str::to_string),
+        };
+        [self.subtitle.clone(), self.author.clone(), date]
+            .into_iter()
+            .flatten()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SlideProperties {
-    layout_object_id: String,
+/// Formats a Unix timestamp in milliseconds as a `YYYY-MM-DD` UTC date.
+fn iso_date(millis: u64) -> String {
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let days = (millis / 86_400_000) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct BatchUpdateRequest {
-    requests: Vec<UpdateRequest>,
+impl CreateSlidesRequest {
+    /// The preset's text style with any fields set in `text_style` replacing
+    /// its values.
+    fn effective_text_style(&self) -> Option<TextStyle> {
+        let preset = self.preset.map(StylePreset::text_style);
+        match (&self.text_style, preset) {
+            (Some(style), Some(preset)) => Some(style.clone().or(preset)),
+            (style, preset) => style.clone().or(preset),
+        }
+    }
+
+    /// `background_color`, or else the preset's.
+    fn effective_background_color(&self) -> Option<&str> {
+        self.background_color
+            .as_deref()
+            .or_else(|| self.preset.map(StylePreset::background_color))
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct UpdateRequest {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    create_slide: Option<CreateSlideRequest>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    insert_text: Option<InsertTextRequest>,
+/// Text style applied on top of the theme; unset fields keep the theme's.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, PartialEq)]
+pub struct TextStyle {
+    #[validate(range(min = 1.0, max = 400.0))]
+    pub font_size_pt: Option<f64>,
+    #[validate(length(min = 1))]
+    pub font_family: Option<String>,
+    pub bold: Option<bool>,
+    /// Hex color such as `#1A73E8`.
+    #[validate(custom(function = "validate_hex_color"))]
+    pub foreground_color: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct CreateSlideRequest {
-    object_id: Option<String>,
-    insertion_index: Option<i32>,
-    slide_layout_reference: Option<SlideLayoutReference>,
+impl TextStyle {
+    /// Keeps the fields set here and takes the rest from `fallback`.
+    fn or(self, fallback: TextStyle) -> TextStyle {
+        TextStyle {
+            font_size_pt: self.font_size_pt.or(fallback.font_size_pt),
+            font_family: self.font_family.or(fallback.font_family),
+            bold: self.bold.or(fallback.bold),
+            foreground_color: self.foreground_color.or(fallback.foreground_color),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SlideLayoutReference {
-    layout_id: String,
+/// Ready-made color schemes, as listed by `GET /api/presets`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StylePreset {
+    Light,
+    Dark,
+    HighContrast,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct InsertTextRequest {
-    object_id: String,
-    insertion_index: i32,
-    text: String,
-    cell_location: Option<TableCellLocation>,
+/// Describes a style preset for clients.
+#[derive(Debug, Serialize)]
+pub struct PresetDescriptor {
+    pub name: StylePreset,
+    pub description: &'static str,
+    pub background_color: &'static str,
+    pub text_style: TextStyle,
+}
+
+/// Wraps the preset list the way `GET /api/presets` returns it.
+#[derive(Debug, Serialize)]
+pub struct PresetsResponse {
+    pub presets: Vec<PresetDescriptor>,
 }
 
+impl StylePreset {
+    const ALL: [StylePreset; 3] = [Self::Light, Self::Dark, Self::HighContrast];
+
+    fn background_color(self) -> &'static str {
+        match self {
+            Self::Light => "#FFFFFF",
+            Self::Dark => "#202124",
+            Self::HighContrast => "#000000",
+        }
+    }
+
+    fn text_style(self) -> TextStyle {
+        let (foreground_color, font_size_pt, bold) = match self {
+            Self::Light => ("#202124", None, None),
+            Self::Dark => ("#FFFFFF", None, None),
+            Self::HighContrast => ("#FFFF00", Some(28.0), Some(true)),
+        };
+        TextStyle {
+            font_size_pt,
+            font_family: None,
+            bold,
+            foreground_color: Some(foreground_color.to_string()),
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Light => "Dark grey text on a white background",
+            Self::Dark => "White text on a dark grey background",
+            Self::HighContrast => "Large bold yellow text on a black background",
+        }
+    }
+
+    /// Every preset with the values it applies.
+    pub fn descriptors() -> PresetsResponse {
+        PresetsResponse {
+            presets: Self::ALL
+                .into_iter()
+                .map(|preset| PresetDescriptor {
+                    name: preset,
+                    description: preset.description(),
+                    background_color: preset.background_color(),
+                    text_style: preset.text_style(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The text to turn into slides.
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct TableCellLocation {
-    row_index: i32,
-    column_index: i32,
+#[serde(untagged)]
+pub enum Content {
+    /// Text that the request's splitter divides into slides.
+    Text(String),
+    /// Slides already split by the client, one element per slide.
+    Slides(Vec<String>),
 }
 
-/// Creates a new Google Slides presentation and populates it with content chunks.
-pub async fn create_slides_from_text(
-    token: &Token,
+/// Rejects empty text, an empty slide list, and blank slides.
+fn validate_content(content: &Content) -> std::result::Result<(), ValidationError> {
+    let message = match content {
+        Content::Text(text) if strip_control_chars(text).is_empty() => {
+            "content must not be empty".to_string()
+        }
+        Content::Slides(slides) if slides.is_empty() => {
+            "content must contain at least one slide".to_string()
+        }
+        Content::Slides(slides) => match slides
+            .iter()
+            .position(|slide| strip_control_chars(slide).trim().is_empty())
+        {
+            Some(index) => format!("slide {} in content is empty", index + 1),
+            None => return Ok(()),
+        },
+        Content::Text(_) => return Ok(()),
+    };
+    Err(ValidationError::new("content").with_message(message.into()))
+}
+
+/// Requires a splitter for text content and rejects one for pre-split slides,
+/// where it would have nothing to do.
+fn validate_splitter_for_content(
     request: &CreateSlidesRequest,
-) -> Result<String> {
-    // Validate request
-    request
-        .validate()
-        .map_err(|e| worker::Error::from(e.to_string()))?;
+) -> std::result::Result<(), ValidationError> {
+    let message = match (&request.content, &request.splitter) {
+        (Content::Text(_), None) => "splitter is required when content is text",
+        (Content::Slides(_), Some(_)) => {
+            "splitter cannot be combined with content that is already split into slides"
+        }
+        _ => return Ok(()),
+    };
+    Err(ValidationError::new("splitter").with_message(message.into()))
+}
 
-    // Split the content into chunks
-    let chunks = request.splitter.split(&request.content);
+/// What a request does with its slides.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Create a new presentation.
+    #[default]
+    Create,
+    /// Delete every slide of `presentation_id` and add the new ones.
+    Replace,
+}
+
+/// Requires `presentation_id` exactly when replacing, and rules out theme
+/// copies, which always make a new presentation.
+fn validate_mode(request: &CreateSlidesRequest) -> std::result::Result<(), ValidationError> {
+    let message = match (request.mode, &request.presentation_id) {
+        (Mode::Replace, None) => "presentation_id is required when mode is replace",
+        (Mode::Replace, Some(id)) if id.trim().is_empty() => "presentation_id must not be empty",
+        (Mode::Replace, Some(_)) if request.theme_presentation_id.is_some() => {
+            "theme_presentation_id cannot be combined with mode replace"
+        }
+        (Mode::Create, Some(_)) => "presentation_id is only used when mode is replace",
+        _ => return Ok(()),
+    };
+    Err(ValidationError::new("mode").with_message(message.into()))
+}
 
-    if chunks.is_empty() {
-        return Err(worker::Error::from("No content chunks generated"));
+/// Rejects a locale for presentations that are copied or replaced rather
+/// than created, since only a new presentation can be given one.
+fn validate_locale_target(
+    request: &CreateSlidesRequest,
+) -> std::result::Result<(), ValidationError> {
+    if request.locale.is_none() {
+        return Ok(());
     }
+    let message = if request.theme_presentation_id.is_some() {
+        "locale cannot be combined with theme_presentation_id; the copy keeps the theme's locale"
+    } else if request.presentation_id.is_some() {
+        "locale cannot be combined with presentation_id; a replaced deck keeps its locale"
+    } else {
+        return Ok(());
+    };
+    Err(ValidationError::new("locale").with_message(message.into()))
+}
 
-    if chunks.len() > 100 {
-        return Err(worker::Error::from("Too many slides (max 100)"));
+/// Turns the request content into chunks, one per slide, and returns the
+/// front matter found at the start of text content.
+fn split_content(
+    request: &CreateSlidesRequest,
+) -> std::result::Result<(Option<FrontMatter>, Vec<String>), AppError> {
+    let normalize = |text: &str| {
+        if request.normalize_whitespace {
+            splitter::normalize_whitespace(text)
+        } else {
+            text.to_string()
+        }
+    };
+    match (&request.content, &request.splitter) {
+        (Content::Text(text), Some(splitter)) => {
+            Ok(split_text(text, splitter, request.normalize_whitespace))
+        }
+        (Content::Slides(slides), _) => Ok((
+            None,
+            slides
+                .iter()
+                .map(|slide| normalize(&strip_control_chars(slide)))
+                .collect(),
+        )),
+        (Content::Text(_), None) => Err(AppError::InvalidRequest(
+            "splitter is required when content is text".to_string(),
+        )),
     }
+}
 
-    // Create the presentation
-    let presentation_id = create_presentation(token, &request.title).await?;
+/// Splits text content into chunks after removing control characters and
+/// the front matter, which supplies metadata and is never split into slides.
+fn split_text(
+    text: &str,
+    splitter: &Splitter,
+    normalize_whitespace: bool,
+) -> (Option<FrontMatter>, Vec<String>) {
+    let text = strip_control_chars(text);
+    let (front_matter, content) = frontmatter::split(&text);
+    let chunks = if normalize_whitespace {
+        splitter.split(&splitter::normalize_whitespace(content))
+    } else {
+        splitter.split(content)
+    };
+    (front_matter, chunks)
+}
+
+/// Removes C0 control characters other than line breaks and tabs, which the
+/// Slides API rejects or renders as boxes. Carriage returns go too, so CRLF
+/// line endings become plain line breaks.
+fn strip_control_chars(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !c.is_ascii_control() || c == '\n' || c == '\t' || c == '\u{7f}')
+        .collect()
+}
 
-    // Add slides for each chunk (skip the first slide as it's created by default)
-    populate_slides(token, &presentation_id, &chunks).await?;
+/// Word count above which a slide is flagged as long by default.
+const DEFAULT_LONG_SLIDE_WORDS: usize = 100;
 
-    Ok(presentation_id)
+fn default_long_slide_words() -> usize {
+    DEFAULT_LONG_SLIDE_WORDS
 }
 
-/// Creates a new Google Slides presentation with the given title.
-async fn create_presentation(token: &Token, title: &str) -> Result<String> {
-    let url = format!("{}/presentations", API_BASE);
+/// Outcome of [`create_slides_from_text`], with how the content was distributed.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct CreateSlidesResult {
+    pub presentation_id: String,
+    /// Title from the request or the content's front matter.
+    pub title: String,
+    /// Every slide created, in deck order, including title and closing slides.
+    pub created_slides: Vec<CreatedSlide>,
+    pub slide_count: usize,
+    pub slides: Vec<SlideStats>,
+    pub totals: SlideTotals,
+    /// Chunks dropped by `dedupe` as repeats of an earlier chunk.
+    pub duplicates_removed: usize,
+    /// Chunks beyond the slide limit that `overflow: "truncate"` dropped.
+    pub chunks_dropped: usize,
+    /// Slides marked `[skip]`, hidden when presenting.
+    pub skipped_slides: usize,
+    /// Whether the deck was copied from `theme_presentation_id`.
+    pub themed: bool,
+    /// Indexes of the slides that show an image instead of text.
+    pub image_slides: Vec<usize>,
+    /// Image slides whose image Google could not insert; they are left blank.
+    pub image_errors: Vec<ImageSlideError>,
+    /// Addresses in `share_with` the deck could not be shared with.
+    pub share_errors: Vec<ShareError>,
+}
 
-    let create_request = CreatePresentationRequest {
-        title: title.to_string(),
-    };
+/// A slide added to the deck and the link that opens the deck on it.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct CreatedSlide {
+    pub object_id: String,
+    pub url: String,
+}
 
-    let body = serde_wasm_bindgen::to_value(&create_request)
-        .map_err(|e| worker::Error::from(e.to_string()))?;
+/// Returns the link that opens a presentation in the Slides editor.
+pub fn presentation_url(presentation_id: &str) -> String {
+    format!("https://docs.google.com/presentation/d/{presentation_id}/edit")
+}
 
-    let headers = Headers::new();
-    headers.set("Content-Type", "application/json")?;
-    headers.set("Authorization", &format!("Bearer {}", token.access_token))?;
+/// Returns the link that opens a presentation on one of its slides.
+fn slide_url(presentation_id: &str, object_id: &str) -> String {
+    format!("{}#slide=id.{object_id}", presentation_url(presentation_id))
+}
 
-    let mut init = RequestInit::new();
-    init.with_method(Method::Post)
-        .with_body(Some(body.into()))
-        .with_headers(headers);
+/// Why the deck could not be shared with one address.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ShareError {
+    pub email: String,
+    pub error: String,
+}
 
-    let request = WorkerRequest::new_with_init(&url, &init)?;
-    let mut response = Fetch::Request(request).send().await?;
+/// Why the image for one slide could not be inserted.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ImageSlideError {
+    pub index: usize,
+    pub error: String,
+}
 
-    if response.status_code() < 200 || response.status_code() >= 300 {
-        let error_text = response.text().await?;
-        return Err(worker::Error::from(format!(
-            "Failed to create presentation: {}",
-            error_text
-        )));
+/// Size of one slide's visible text (title and body, not speaker notes).
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SlideStats {
+    pub index: usize,
+    pub words: usize,
+    pub chars: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<SlideWarning>,
+}
+
+/// Something about a slide the UI may want to highlight.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlideWarning {
+    /// More words than the request's `long_slide_words`.
+    Long,
+}
+
+/// Sums of [`SlideStats`] over the whole deck.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SlideTotals {
+    pub words: usize,
+    pub chars: usize,
+}
+
+/// Measures each chunk, flagging those with more than `long_slide_words` words.
+fn slide_stats(chunks: &[Chunk], long_slide_words: usize) -> Vec<SlideStats> {
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let visible = chunk.title.iter().chain([&chunk.body]);
+            let words = visible
+                .clone()
+                .map(|text| textmetrics::words(text).count())
+                .sum();
+            let chars = visible.map(|text| textmetrics::chars(text)).sum();
+            SlideStats {
+                index,
+                words,
+                chars,
+                warning: (words > long_slide_words).then_some(SlideWarning::Long),
+            }
+        })
+        .collect()
+}
+
+/// Totals the per-slide statistics.
+fn slide_totals(stats: &[SlideStats]) -> SlideTotals {
+    SlideTotals {
+        words: stats.iter().map(|slide| slide.words).sum(),
+        chars: stats.iter().map(|slide| slide.chars).sum(),
     }
+}
+
+/// Represents a request to preview how content would be split into slides.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct PreviewSplitRequest {
+    #[validate(length(min = 1))]
+    pub content: String,
+
+    #[validate(custom(function = "validate_splitter"))]
+    pub splitter: Splitter,
+
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub limit: SlideLimit,
 
-    let presentation: Presentation = response.json().await?;
-    Ok(presentation.presentation_id)
+    /// Normalizes whitespace before splitting, as in [`CreateSlidesRequest`].
+    #[serde(default)]
+    pub normalize_whitespace: bool,
 }
 
-/// Populates the presentation with slides containing the provided text chunks.
-async fn populate_slides(token: &Token, presentation_id: &str, chunks: &[String]) -> Result<()> {
-    let url = format!("{}/presentations/{}:batchUpdate", API_BASE, presentation_id);
+/// The chunks a splitter produced, as returned by `POST /api/preview-split`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PreviewSplitResponse {
+    pub chunks: Vec<String>,
+    pub count: usize,
+    /// Chunks beyond the slide limit that `overflow: "truncate"` dropped.
+    pub chunks_dropped: usize,
+}
 
-    let mut requests = Vec::new();
+/// Splits the content without touching Google, preparing the text and
+/// applying the slide limit as [`create_slides_from_text`] does, so the
+/// preview matches what would be created. Options that only creation has,
+/// such as `dedupe` and `min_words`, are not applied.
+pub fn preview_split(
+    request: &PreviewSplitRequest,
+    limits: &Limits,
+) -> std::result::Result<PreviewSplitResponse, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::InvalidRequest(e.to_string()))?;
 
-    // For each chunk, create a new slide (except the first one, use the default slide)
-    for (index, chunk) in chunks.iter().enumerate() {
-        if index > 0 {
-            // Create a new slide for chunks after the first one
-            requests.push(UpdateRequest {
-                create_slide: Some(CreateSlideRequest {
-                    object_id: Some(format!("slide_{}", index)),
-                    insertion_index: Some(index as i32 + 1),
-                    slide_layout_reference: Some(SlideLayoutReference {
-                        layout_id: "TITLE_AND_BODY".to_string(),
-                    }),
-                }),
-                insert_text: None,
-            });
+    let (_, chunks) = split_text(
+        &request.content,
+        &request.splitter,
+        request.normalize_whitespace,
+    );
+    let (chunks, chunks_dropped) = request.limit.apply(chunks, 0, limits)?;
+
+    Ok(PreviewSplitResponse {
+        count: chunks.len(),
+        chunks,
+        chunks_dropped,
+    })
+}
+
+/// Represents a request to create a deck by filling in a template's tokens.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateFromTemplateRequest {
+    /// Presentation copied and filled in; the template itself is left untouched.
+    #[validate(length(min = 1))]
+    pub template_id: String,
+
+    /// Name of the copy; Drive names it "Copy of ..." when omitted.
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Replacement text keyed by token name, e.g. `"title"` for `{{title}}`.
+    pub replacements: HashMap<String, String>,
+
+    #[serde(default)]
+    #[validate(nested)]
+    pub placeholder: PlaceholderSyntax,
+}
+
+/// Delimiters around token names in a template.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, PartialEq, Eq)]
+pub struct PlaceholderSyntax {
+    #[validate(length(min = 1))]
+    pub open: String,
+    pub close: String,
+}
+
+impl Default for PlaceholderSyntax {
+    fn default() -> Self {
+        Self {
+            open: "{{".to_string(),
+            close: "}}".to_string(),
         }
+    }
+}
 
-        // Add text to the slide
-        // Note: In a real implementation, you would need to get the actual text box object IDs
-        // This is a simplified version that assumes standard layout object IDs
-        let text_box_id = if index == 0 {
-            "g_placeholder_1".to_string() // Default slide title placeholder
-        } else {
-            format!("g_placeholder_{}_1", index + 1) // Title placeholder for new slides
-        };
+impl PlaceholderSyntax {
+    /// Returns the text a token appears as in the template.
+    fn token(&self, name: &str) -> String {
+        format!("{}{}{}", self.open, name, self.close)
+    }
+}
 
-        requests.push(UpdateRequest {
-            create_slide: None,
-            insert_text: Some(InsertTextRequest {
-                object_id: text_box_id,
-                insertion_index: 0,
-                text: chunk.clone(),
-                cell_location: None,
-            }),
-        });
+/// Result of filling in a template.
+#[derive(Debug, Serialize)]
+pub struct CreateFromTemplateResult {
+    pub presentation_id: String,
+    /// Occurrences replaced per token, for tokens found in the template.
+    pub replaced: BTreeMap<String, usize>,
+    /// Tokens from the request that the template does not contain.
+    pub skipped: Vec<String>,
+}
+
+/// Default server-side maximum number of slides created from one request.
+const MAX_SLIDES: usize = 100;
+
+/// Highest slide limit a deployment may configure with `MAX_SLIDES`.
+const MAX_SLIDES_CEILING: usize = 250;
+
+/// Limits configured per deployment rather than per request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Most slides one request may create, title and closing slides included.
+    pub max_slides: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_slides: MAX_SLIDES,
+        }
+    }
+}
+
+impl Limits {
+    /// Builds the limits from the `MAX_SLIDES` environment variable.
+    ///
+    /// Values above [`MAX_SLIDES_CEILING`] are capped to it; a missing, zero
+    /// or unparsable value keeps the default.
+    pub fn from_env_var(max_slides: Option<&str>) -> Self {
+        let max_slides = max_slides
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .filter(|&max_slides| max_slides > 0)
+            .map_or(MAX_SLIDES, |max_slides| max_slides.min(MAX_SLIDES_CEILING));
+        Self { max_slides }
     }
+}
 
-    let batch_request = BatchUpdateRequest { requests };
+/// What to do with chunks beyond the slide limit.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Reject the request.
+    #[default]
+    Error,
+    /// Drop the chunks beyond the limit.
+    Truncate,
+    /// Append the chunks beyond the limit to the last slide.
+    Merge,
+}
 
-    let body =
-        serde_json::to_string(&batch_request).map_err(|e| worker::Error::from(e.to_string()))?;
+/// How many slides a request may create and how to handle the excess.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Validate, PartialEq, Eq)]
+pub struct SlideLimit {
+    /// Defaults to the server limit and may not exceed it.
+    #[serde(default)]
+    #[validate(range(min = 1, max = MAX_SLIDES_CEILING))]
+    pub max_slides: Option<usize>,
 
-    let headers = Headers::new();
-    headers.set("Content-Type", "application/json")?;
-    headers.set("Authorization", &format!("Bearer {}", token.access_token))?;
+    #[serde(default)]
+    pub overflow: Overflow,
+}
 
-    let mut init = RequestInit::new();
-    init.with_method(Method::Post)
-        .with_body(Some(body.into()))
-        .with_headers(headers);
+impl SlideLimit {
+    /// Fits the chunks within `max_slides` according to `overflow`, leaving
+    /// room for `reserved` slides that are not made from chunks.
+    ///
+    /// Returns the chunks to create slides from and how many were dropped.
+    /// Splits that produce no chunks and a `max_slides` above the server
+    /// limit are always rejected.
+    fn apply<T: Mergeable>(
+        &self,
+        mut chunks: Vec<T>,
+        reserved: usize,
+        limits: &Limits,
+    ) -> std::result::Result<(Vec<T>, usize), AppError> {
+        if chunks.is_empty() {
+            return Err(AppError::InvalidRequest(
+                "No content chunks generated".to_string(),
+            ));
+        }
+        let max_slides = match self.max_slides {
+            Some(max_slides) if max_slides > limits.max_slides => {
+                return Err(AppError::InvalidRequest(format!(
+                    "max_slides {max_slides} exceeds the server limit of {}",
+                    limits.max_slides
+                )));
+            }
+            Some(max_slides) => max_slides,
+            None => limits.max_slides,
+        }
+        // Guards against limits that skipped validation.
+        .max(1);
+        let available = max_slides.saturating_sub(reserved);
+        if chunks.len() <= available {
+            return Ok((chunks, 0));
+        }
+        match self.overflow {
+            _ if available == 0 => Err(AppError::InvalidRequest(format!(
+                "No room for content slides (max {max_slides}, including the title slide)"
+            ))),
+            Overflow::Error => Err(AppError::InvalidRequest(format!(
+                "Too many slides: {} (max {max_slides})",
+                chunks.len() + reserved
+            ))),
+            Overflow::Truncate => {
+                let dropped = chunks.len() - available;
+                chunks.truncate(available);
+                Ok((chunks, dropped))
+            }
+            Overflow::Merge => {
+                let overflow = chunks.split_off(available);
+                let last = chunks.last_mut().expect("available is at least 1");
+                for chunk in overflow {
+                    last.merge(chunk);
+                }
+                Ok((chunks, 0))
+            }
+        }
+    }
+}
 
-    let request = WorkerRequest::new_with_init(&url, &init)?;
-    let mut response = Fetch::Request(request).send().await?;
+/// Slide content that `overflow: "merge"` can append more slides to.
+trait Mergeable {
+    fn merge(&mut self, next: Self);
+}
 
-    if response.status_code() < 200 || response.status_code() >= 300 {
-        let error_text = response.text().await?;
-        return Err(worker::Error::from(format!(
-            "Failed to update slides: {}",
-            error_text
-        )));
+impl Mergeable for String {
+    fn merge(&mut self, next: Self) {
+        self.push('\n');
+        self.push_str(&next);
     }
+}
 
-    Ok(())
+impl Mergeable for SlideSpec {
+    /// Merging into a section turns it into a content slide led by its heading.
+    fn merge(&mut self, next: Self) {
+        let mut text = std::mem::replace(self, SlideSpec::Content(String::new())).into_text();
+        text.merge(next.into_text());
+        *self = SlideSpec::Content(text);
+    }
+}
+
+fn default_link_detection() -> bool {
+    true
+}
+
+fn default_autofit() -> bool {
+    true
+}
+
+fn default_cleanup_on_failure() -> bool {
+    true
+}
+
+fn default_send_notification_email() -> bool {
+    true
+}
+
+/// Rejects `share_with` entries that are not email addresses.
+fn validate_share_with(emails: &[String]) -> std::result::Result<(), ValidationError> {
+    match emails.iter().find(|email| !email.validate_email()) {
+        Some(email) => Err(ValidationError::new("share_with")
+            .with_message(format!("{email} in share_with is not an email address").into())),
+        None => Ok(()),
+    }
+}
+
+fn default_notes_marker() -> String {
+    splitter::DEFAULT_NOTES_MARKER.to_string()
+}
+
+/// Maximum length of a presentation title, in characters.
+const MAX_TITLE_LENGTH: usize = 100;
+
+/// Invisible characters dropped from titles. Joiners are kept, since emoji
+/// sequences and some scripts need them.
+const INVISIBLE_TITLE_CHARS: &[char] = &['\u{200b}', '\u{2060}', '\u{feff}'];
+
+/// Cleans a title for use as a Drive file name: control and invisible
+/// characters are dropped and whitespace runs, line breaks included, become
+/// one space, with none at either end.
+fn clean_title(title: &str) -> String {
+    let visible = title
+        .chars()
+        .map(|c| if c.is_ascii_control() { ' ' } else { c })
+        .filter(|c| !INVISIBLE_TITLE_CHARS.contains(c))
+        .collect::<String>();
+    visible.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Applies the title length limit to the cleaned title, so surrounding
+/// whitespace and stray control characters do not count towards it.
+fn validate_title(title: &str) -> std::result::Result<(), ValidationError> {
+    let length = textmetrics::chars(&clean_title(title));
+    if length > MAX_TITLE_LENGTH {
+        return Err(ValidationError::new("title").with_message(
+            format!(
+                "title must be at most {MAX_TITLE_LENGTH} characters after removing extra whitespace, got {length}"
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Picks the presentation title from the request and the content's front
+/// matter, cleaned with [`clean_title`].
+fn resolve_title(
+    request: &CreateSlidesRequest,
+    front_matter: Option<&FrontMatter>,
+) -> std::result::Result<String, AppError> {
+    let from_front_matter = front_matter.and_then(FrontMatter::title).map(clean_title);
+    let request_title = clean_title(&request.title);
+    let title = match from_front_matter {
+        Some(title) if request.use_frontmatter_title || request_title.is_empty() => title,
+        _ => request_title,
+    };
+    if title.is_empty() {
+        return Err(AppError::InvalidRequest(
+            "title is required when the content has no front matter title".to_string(),
+        ));
+    }
+    if textmetrics::chars(&title) > MAX_TITLE_LENGTH {
+        return Err(AppError::InvalidRequest(format!(
+            "title must be at most {MAX_TITLE_LENGTH} characters"
+        )));
+    }
+    Ok(title)
+}
+
+/// Surfaces splitter configuration problems as request validation errors.
+fn validate_splitter(splitter: &Splitter) -> std::result::Result<(), ValidationError> {
+    splitter
+        .validate()
+        .map_err(|e| ValidationError::new("splitter").with_message(e.to_string().into()))
+}
+
+/// Rejects an empty layout object ID, which the API would fail on.
+fn validate_layout(layout: &SlideLayoutReference) -> std::result::Result<(), ValidationError> {
+    match layout {
+        SlideLayoutReference::LayoutId(id) if id.trim().is_empty() => {
+            Err(ValidationError::new("layout").with_message("layout_id must not be empty".into()))
+        }
+        SlideLayoutReference::PredefinedLayout(PredefinedLayout::TitleOnly) => {
+            Err(ValidationError::new("layout")
+                .with_message("TITLE_ONLY has no BODY placeholder".into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_theme_presentation_id(id: &str) -> std::result::Result<(), ValidationError> {
+    if id.trim().is_empty() {
+        Err(ValidationError::new("theme_presentation_id")
+            .with_message("theme_presentation_id must not be empty".into()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Accepts simple BCP-47 language tags: a two or three letter language
+/// followed by any number of two to eight character subtags, like `en`,
+/// `pt-BR` or `zh-Hant-TW`.
+fn validate_locale(locale: &str) -> std::result::Result<(), ValidationError> {
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let valid = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (2..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("locale").with_message(
+            format!("locale must be a BCP-47 language tag such as en-US, got {locale:?}").into(),
+        ))
+    }
+}
+
+/// Placeholder replaced with the 1-based slide number in `numbering_prefix`.
+const SLIDE_NUMBER_PLACEHOLDER: &str = "{n}";
+
+/// Accepts `#RRGGBB` colors.
+fn validate_hex_color(color: &str) -> std::result::Result<(), ValidationError> {
+    check_hex_color("foreground_color", color)
+}
+
+/// Accepts `#RRGGBB` colors.
+fn validate_background_color(color: &str) -> std::result::Result<(), ValidationError> {
+    check_hex_color("background_color", color)
+}
+
+fn check_hex_color(field: &'static str, color: &str) -> std::result::Result<(), ValidationError> {
+    if parse_hex_color(color).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new(field)
+            .with_message(format!("{field} must be a #RRGGBB hex color").into()))
+    }
+}
+
+/// Parses `#RRGGBB` into red, green and blue components between 0 and 1.
+fn parse_hex_color(color: &str) -> Option<RgbColor> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |start: usize| {
+        u8::from_str_radix(&hex[start..start + 2], 16)
+            .ok()
+            .map(|value| f64::from(value) / 255.0)
+    };
+    Some(RgbColor {
+        red: component(0)?,
+        green: component(2)?,
+        blue: component(4)?,
+    })
+}
+
+/// Rejects numbering templates that would not actually number anything.
+fn validate_numbering_prefix(template: &str) -> std::result::Result<(), ValidationError> {
+    if template.contains(SLIDE_NUMBER_PLACEHOLDER) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("numbering_prefix")
+            .with_message("numbering_prefix must contain {n}".into()))
+    }
+}
+
+/// Prefixes the first line of every chunk, its title if it has one, with the
+/// numbering template.
+fn apply_numbering_prefix(chunks: &mut [Chunk], template: &str) {
+    for (index, chunk) in chunks.iter_mut().enumerate() {
+        if chunk.media.is_some() {
+            continue;
+        }
+        let prefix = template.replace(SLIDE_NUMBER_PLACEHOLDER, &(index + 1).to_string());
+        chunk
+            .title
+            .as_mut()
+            .unwrap_or(&mut chunk.body)
+            .insert_str(0, &prefix);
+    }
+}
+
+/// Adds the request's notes to the slides at the same index, after any notes
+/// already taken from the notes marker.
+fn apply_notes(chunks: &mut [Chunk], notes: &[String]) -> std::result::Result<(), AppError> {
+    if notes.len() > chunks.len() {
+        return Err(AppError::InvalidRequest(format!(
+            "{} notes given for {} slides; notes must not outnumber slides",
+            notes.len(),
+            chunks.len()
+        )));
+    }
+    for (chunk, note) in chunks.iter_mut().zip(notes) {
+        let note = note.trim();
+        if note.is_empty() {
+            continue;
+        }
+        if !chunk.notes.is_empty() {
+            chunk.notes.push_str("\n\n");
+        }
+        chunk.notes.push_str(note);
+    }
+    Ok(())
+}
+
+/// Google Slides API structures
+#[derive(Debug, Serialize, Deserialize)]
+struct CreatePresentationRequest {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locale: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Presentation {
+    presentation_id: String,
+    title: String,
+    slides: Vec<Slide>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    page_size: Option<Size>,
+}
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Slide {
+    object_id: String,
+    slide_properties: SlideProperties,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlideProperties {
+    layout_object_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes_page: Option<NotesPage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotesPage {
+    notes_properties: NotesProperties,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotesProperties {
+    speaker_notes_object_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchUpdateRequest {
+    requests: Vec<UpdateRequest>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BatchUpdateResponse {
+    #[serde(default)]
+    replies: Vec<Reply>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reply {
+    replace_all_text: Option<ReplaceAllTextResponse>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplaceAllTextResponse {
+    // Omitted by the API when nothing matched
+    #[serde(default)]
+    occurrences_changed: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_slide: Option<CreateSlideRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    insert_text: Option<InsertTextRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delete_object: Option<DeleteObjectRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replace_all_text: Option<ReplaceAllTextRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_text_style: Option<UpdateTextStyleRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_paragraph_bullets: Option<CreateParagraphBulletsRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_image: Option<CreateImageRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_table: Option<CreateTableRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_page_properties: Option<UpdatePagePropertiesRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_shape: Option<CreateShapeRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_shape_properties: Option<UpdateShapePropertiesRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_paragraph_style: Option<UpdateParagraphStyleRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_video: Option<CreateVideoRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_video_properties: Option<UpdateVideoPropertiesRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_slide_properties: Option<UpdateSlidePropertiesRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateSlidePropertiesRequest {
+    object_id: String,
+    slide_properties: SkippedSlideProperties,
+    fields: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkippedSlideProperties {
+    is_skipped: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateVideoRequest {
+    object_id: String,
+    element_properties: PageElementProperties,
+    source: String,
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateVideoPropertiesRequest {
+    object_id: String,
+    video_properties: VideoProperties,
+    fields: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VideoProperties {
+    /// Seconds into the video at which playback starts.
+    start: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateParagraphStyleRequest {
+    object_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cell_location: Option<TableCellLocation>,
+    style: ParagraphStyle,
+    text_range: TextRange,
+    fields: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ParagraphStyle {
+    direction: String,
+    alignment: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateShapePropertiesRequest {
+    object_id: String,
+    shape_properties: ShapeProperties,
+    fields: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ShapeProperties {
+    autofit: Autofit,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Autofit {
+    autofit_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateShapeRequest {
+    object_id: String,
+    shape_type: String,
+    element_properties: PageElementProperties,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdatePagePropertiesRequest {
+    object_id: String,
+    page_properties: PageProperties,
+    fields: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageProperties {
+    page_background_fill: PageBackgroundFill,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageBackgroundFill {
+    solid_fill: SolidFill,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SolidFill {
+    color: OpaqueColor,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateTableRequest {
+    object_id: String,
+    element_properties: PageElementProperties,
+    rows: i32,
+    columns: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateImageRequest {
+    object_id: String,
+    url: String,
+    element_properties: PageElementProperties,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageElementProperties {
+    page_object_id: String,
+    size: Size,
+    transform: AffineTransform,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Size {
+    width: Dimension,
+    height: Dimension,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AffineTransform {
+    scale_x: f64,
+    scale_y: f64,
+    translate_x: f64,
+    translate_y: f64,
+    unit: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateParagraphBulletsRequest {
+    object_id: String,
+    text_range: TextRange,
+    bullet_preset: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateTextStyleRequest {
+    object_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cell_location: Option<TableCellLocation>,
+    style: ApiTextStyle,
+    text_range: TextRange,
+    fields: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiTextStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    font_size: Option<Dimension>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    font_family: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    foreground_color: Option<OptionalColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<Link>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Link {
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Dimension {
+    magnitude: f64,
+    unit: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OptionalColor {
+    opaque_color: OpaqueColor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpaqueColor {
+    rgb_color: RgbColor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RgbColor {
+    red: f64,
+    green: f64,
+    blue: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextRange {
+    #[serde(rename = "type")]
+    range_type: String,
+    start_index: usize,
+    end_index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplaceAllTextRequest {
+    contains_text: SubstringMatchCriteria,
+    replace_text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubstringMatchCriteria {
+    text: String,
+    match_case: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSlideRequest {
+    object_id: Option<String>,
+    insertion_index: Option<i32>,
+    slide_layout_reference: Option<SlideLayoutReference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    placeholder_id_mappings: Vec<LayoutPlaceholderIdMapping>,
+}
+
+/// Layout used for new slides, serialized as the API's `slideLayoutReference`.
+///
+/// Requests may spell the keys in snake_case or camelCase.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SlideLayoutReference {
+    /// One of the layouts every theme provides.
+    #[serde(rename = "predefinedLayout", alias = "predefined_layout")]
+    PredefinedLayout(PredefinedLayout),
+    /// Object ID of a layout in the presentation's master.
+    #[serde(rename = "layoutId", alias = "layout_id")]
+    LayoutId(String),
+    /// Splits each body across two text boxes side by side. Slides use the
+    /// TITLE_ONLY layout (BLANK when untitled), so this is never sent to the
+    /// API.
+    #[serde(rename = "two_column")]
+    TwoColumn,
+}
+
+impl Default for SlideLayoutReference {
+    fn default() -> Self {
+        Self::PredefinedLayout(PredefinedLayout::default())
+    }
+}
+
+/// Predefined layouts that have the TITLE and BODY placeholders slides are
+/// filled through.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PredefinedLayout {
+    #[default]
+    TitleAndBody,
+    TitleAndTwoColumns,
+    OneColumnText,
+    SectionTitleAndDescription,
+    /// Used for slides with a title but no body; not accepted as `layout`.
+    TitleOnly,
+    /// Used for the title slide; not accepted as `layout`, so never deserialized.
+    #[serde(skip_deserializing)]
+    Title,
+    /// Used for section and closing slides; not accepted as `layout`, so never
+    /// deserialized.
+    #[serde(skip_deserializing)]
+    SectionHeader,
+    /// Used for image slides; not accepted as `layout`, so never deserialized.
+    #[serde(skip_deserializing)]
+    Blank,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutPlaceholderIdMapping {
+    layout_placeholder: Placeholder,
+    object_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Placeholder {
+    #[serde(rename = "type")]
+    placeholder_type: String,
+    index: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteObjectRequest {
+    object_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InsertTextRequest {
+    object_id: String,
+    insertion_index: i32,
+    text: String,
+    cell_location: Option<TableCellLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TableCellLocation {
+    row_index: i32,
+    column_index: i32,
+}
+
+/// Most bytes of batchUpdate JSON a dry run returns.
+const DRY_RUN_JSON_LIMIT: usize = 200_000;
+
+/// Outcome of [`dry_run`]: the slides and requests a real run would create.
+#[derive(Debug, Serialize)]
+pub struct DryRunResult {
+    pub title: String,
+    pub slide_count: usize,
+    pub slides: Vec<DryRunSlide>,
+    /// Deck-wide notes on what the dry run shows or leaves out.
+    pub warnings: Vec<String>,
+    pub duplicates_removed: usize,
+    pub chunks_dropped: usize,
+    pub skipped_slides: usize,
+    /// How many requests the batchUpdate holds, including any cut off.
+    pub request_count: usize,
+    /// The batchUpdate body, with requests beyond [`DRY_RUN_JSON_LIMIT`] cut off.
+    pub batch_update: serde_json::Value,
+    pub batch_update_truncated: bool,
+}
+
+/// One slide's text and statistics in a dry run.
+#[derive(Debug, Serialize)]
+pub struct DryRunSlide {
+    #[serde(flatten)]
+    pub stats: SlideStats,
+    pub title: Option<String>,
+    pub body: String,
+    pub notes: String,
+}
+
+/// Runs the same pipeline as [`create_slides_from_text`], including layout
+/// and style requests, but stops before calling Google and returns what
+/// would have been sent. The requests assume a new, blank presentation.
+pub fn dry_run(
+    request: &CreateSlidesRequest,
+    limits: &Limits,
+) -> std::result::Result<DryRunResult, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::InvalidRequest(e.to_string()))?;
+
+    let prepared = prepare_slides(request, limits)?;
+    let requests = content_requests(&prepared.chunks, "", None, &prepared.options);
+    let (batch_update, batch_update_truncated) =
+        truncated_batch_update(&requests, DRY_RUN_JSON_LIMIT);
+
+    let stats = slide_stats(&prepared.chunks, request.long_slide_words);
+    let skipped_slides = skipped_slides(&prepared.chunks);
+    let mut warnings = Vec::new();
+    let long = stats.iter().filter(|slide| slide.warning.is_some()).count();
+    if long > 0 {
+        warnings.push(format!(
+            "{long} slide(s) have more than {} words",
+            request.long_slide_words
+        ));
+    }
+    if prepared.chunks_dropped > 0 {
+        warnings.push(format!(
+            "{} chunk(s) beyond max_slides were dropped",
+            prepared.chunks_dropped
+        ));
+    }
+    if prepared
+        .chunks
+        .iter()
+        .any(|chunk| matches!(chunk.media, Some(Media::Image(_))))
+    {
+        warnings
+            .push("images are inserted in later requests, not shown in batch_update".to_string());
+    }
+    if prepared.chunks.iter().any(|chunk| !chunk.notes.is_empty()) {
+        warnings.push(
+            "speaker notes are added in a later request, not shown in batch_update".to_string(),
+        );
+    }
+    if batch_update_truncated {
+        warnings.push(format!(
+            "batch_update was cut off at {DRY_RUN_JSON_LIMIT} bytes"
+        ));
+    }
+
+    let slides = stats
+        .into_iter()
+        .zip(prepared.chunks)
+        .map(|(stats, chunk)| DryRunSlide {
+            stats,
+            title: chunk.title,
+            body: chunk.body,
+            notes: chunk.notes,
+        })
+        .collect::<Vec<_>>();
+    Ok(DryRunResult {
+        title: prepared.title,
+        slide_count: slides.len(),
+        slides,
+        warnings,
+        duplicates_removed: prepared.duplicates_removed,
+        chunks_dropped: prepared.chunks_dropped,
+        skipped_slides,
+        request_count: requests.len(),
+        batch_update,
+        batch_update_truncated,
+    })
+}
+
+/// Serializes `requests` as a batchUpdate body, keeping only the leading
+/// requests that fit in `max_bytes` of JSON. Returns whether any were cut.
+fn truncated_batch_update(
+    requests: &[UpdateRequest],
+    max_bytes: usize,
+) -> (serde_json::Value, bool) {
+    let mut kept = Vec::new();
+    let mut bytes = 0;
+    for request in requests {
+        let json = serde_json::to_value(request).expect("update requests are always serializable");
+        bytes += json.to_string().len();
+        if bytes > max_bytes {
+            return (serde_json::json!({ "requests": kept }), true);
+        }
+        kept.push(json);
+    }
+    (serde_json::json!({ "requests": kept }), false)
+}
+
+/// Slides worked out from a request, ready to be added to a presentation.
+struct PreparedSlides<'a> {
+    title: String,
+    chunks: Vec<Chunk>,
+    duplicates_removed: usize,
+    chunks_dropped: usize,
+    options: SlideOptions<'a>,
+}
+
+/// Runs every step of [`create_slides_from_text`] that does not touch Google:
+/// splitting, post-processing, the slide limit and per-slide layout.
+fn prepare_slides<'a>(
+    request: &'a CreateSlidesRequest,
+    limits: &Limits,
+) -> std::result::Result<PreparedSlides<'a>, AppError> {
+    // Split the content into chunks
+    let (front_matter, mut chunks) = split_content(request)?;
+    let title = resolve_title(request, front_matter.as_ref())?;
+
+    let mut duplicates_removed = 0;
+    if request.dedupe {
+        (chunks, duplicates_removed) = splitter::dedupe_chunks(chunks);
+    }
+    if let Some(min_words) = request.min_words {
+        chunks = splitter::merge_short_chunks(chunks, min_words);
+    }
+
+    let extra_slides =
+        usize::from(request.title_slide.is_some()) + usize::from(request.closing_slide.is_some());
+    let specs = if request.sections {
+        splitter::section_specs(chunks)
+    } else {
+        chunks.into_iter().map(SlideSpec::Content).collect()
+    };
+    let (specs, chunks_dropped) = request.limit.apply(specs, extra_slides, limits)?;
+
+    let mut chunks = specs
+        .into_iter()
+        .enumerate()
+        .map(|(index, spec)| {
+            let chunk = match spec {
+                SlideSpec::Section(heading) => return Ok(Chunk::section(heading)),
+                SlideSpec::Content(chunk) => chunk,
+            };
+            // Works on the text whatever splitter produced it
+            let (skipped, text) = match splitter::strip_skip_marker(&chunk) {
+                Some(rest) => (true, rest),
+                None => (false, chunk.as_str()),
+            };
+            Ok(Chunk {
+                skipped,
+                ..content_chunk(request, index, text)?
+            })
+        })
+        .collect::<std::result::Result<Vec<_>, AppError>>()?;
+
+    if request.strip_markdown {
+        for chunk in &mut chunks {
+            chunk.title = chunk.title.as_deref().map(splitter::strip_markdown);
+            chunk.body = splitter::strip_markdown(&chunk.body);
+            chunk.notes = splitter::strip_markdown(&chunk.notes);
+        }
+    }
+
+    if let Some(template) = &request.numbering_prefix {
+        apply_numbering_prefix(&mut chunks, template);
+    }
+
+    apply_notes(&mut chunks, &request.notes)?;
+
+    let options = SlideOptions {
+        layout: &request.layout,
+        text_style: request.effective_text_style(),
+        background_color: request.effective_background_color(),
+        inline: InlineOptions {
+            emphasis: request.rich_text,
+            links: request.link_detection,
+        },
+        title_slide: request
+            .title_slide
+            .as_ref()
+            .map(|title_slide| TitleSlideText {
+                title: title.to_string(),
+                subtitle: title_slide.subtitle_text(|| iso_date(Date::now().as_millis())),
+            }),
+        autofit: request.autofit,
+        slide_numbers: request.slide_numbers.then_some(SlideNumbers {
+            skip_first: request.skip_first,
+        }),
+        text_direction: request.text_direction,
+        sequential_batches: request.sequential_batches,
+        closing_slide: request
+            .closing_slide
+            .as_deref()
+            .map(|text| match text.trim() {
+                "" => DEFAULT_CLOSING_TEXT.to_string(),
+                text => text.to_string(),
+            }),
+    };
+
+    Ok(PreparedSlides {
+        title: title.to_string(),
+        chunks,
+        duplicates_removed,
+        chunks_dropped,
+        options,
+    })
+}
+
+/// Turns a chunk's text into the chunk for its slide: a table, video or
+/// image when that is all it holds, otherwise a title and body.
+fn content_chunk(
+    request: &CreateSlidesRequest,
+    index: usize,
+    text: &str,
+) -> std::result::Result<Chunk, AppError> {
+    let (visible, notes) = splitter::split_notes(text, &request.notes_marker);
+    if let Some(rows) = markdown_table(visible) {
+        check_table_size(index, &rows)?;
+        return Ok(Chunk {
+            notes: notes.to_string(),
+            ..Chunk::table(rows)
+        });
+    }
+    if let Some((id, start)) = youtube_video(visible) {
+        return Ok(Chunk {
+            notes: notes.to_string(),
+            ..Chunk::video(id.to_string(), start)
+        });
+    }
+    if let Some((url, caption)) = image_url(visible) {
+        // Nothing but the image fits an image slide, so keep the rest as notes
+        let notes = [caption, notes.trim()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        return Ok(Chunk {
+            notes,
+            ..Chunk::image(url.to_string())
+        });
+    }
+    let chunk = match &request.splitter {
+        _ if request.title_from_first_line => Chunk::from_first_line(visible),
+        Some(splitter) => splitter.title_chunk(visible.to_string()),
+        None => Chunk::untitled(visible.to_string()),
+    };
+    Ok(Chunk {
+        notes: notes.to_string(),
+        ..chunk
+    })
+}
+
+/// Creates a new Google Slides presentation and populates it with content chunks.
+pub async fn create_slides_from_text(
+    token: &Token,
+    request: &CreateSlidesRequest,
+    limits: &Limits,
+) -> std::result::Result<CreateSlidesResult, AppError> {
+    create_slides(token, request, limits).await
+}
+
+/// Most decks a single [`CreateSlidesBatchRequest`] may create.
+pub const MAX_BATCH_DECKS: usize = 10;
+
+/// Request to create several decks with one session, such as a deck per chapter.
+#[derive(Debug, Deserialize)]
+pub struct CreateSlidesBatchRequest {
+    /// Between 1 and [`MAX_BATCH_DECKS`] decks, created in order.
+    pub decks: Vec<CreateSlidesRequest>,
+}
+
+/// Creates each deck of `request` in turn, as [`create_slides_from_text`]
+/// would. A deck that fails is reported in its place without stopping the
+/// ones after it; only a batch of the wrong size, or one with a dry run in
+/// it, fails as a whole.
+pub async fn create_slides_batch(
+    token: &Token,
+    request: &CreateSlidesBatchRequest,
+    limits: &Limits,
+) -> std::result::Result<Vec<std::result::Result<CreateSlidesResult, AppError>>, AppError> {
+    create_decks(token, request, limits).await
+}
+
+/// Does the work of [`create_slides_batch`] through any [`SlidesApi`].
+async fn create_decks(
+    api: &impl SlidesApi,
+    request: &CreateSlidesBatchRequest,
+    limits: &Limits,
+) -> std::result::Result<Vec<std::result::Result<CreateSlidesResult, AppError>>, AppError> {
+    if !(1..=MAX_BATCH_DECKS).contains(&request.decks.len()) {
+        return Err(AppError::InvalidRequest(format!(
+            "decks must hold 1 to {MAX_BATCH_DECKS} decks, got {}",
+            request.decks.len()
+        )));
+    }
+    // Checked before anything is created, so a batch never half runs
+    if let Some(index) = request.decks.iter().position(|deck| deck.dry_run) {
+        return Err(AppError::InvalidRequest(format!(
+            "decks[{index}]: dry_run is not supported when creating decks in a batch"
+        )));
+    }
+    // One deck at a time, so a batch never has more calls to Google in
+    // flight than a single deck
+    let mut results = Vec::with_capacity(request.decks.len());
+    for deck in &request.decks {
+        results.push(create_slides(api, deck, limits).await);
+    }
+    Ok(results)
+}
+
+/// Does the work of [`create_slides_from_text`] through any [`SlidesApi`].
+async fn create_slides(
+    api: &impl SlidesApi,
+    request: &CreateSlidesRequest,
+    limits: &Limits,
+) -> std::result::Result<CreateSlidesResult, AppError> {
+    // Validate request
+    request
+        .validate()
+        .map_err(|e| AppError::InvalidRequest(e.to_string()))?;
+
+    let PreparedSlides {
+        title,
+        chunks,
+        duplicates_removed,
+        chunks_dropped,
+        options,
+    } = prepare_slides(request, limits)?;
+    let title = title.as_str();
+
+    // Create the presentation, copy the theme's or fetch the one being
+    // replaced; whatever slides it already has are replaced below
+    let presentation = match (&request.presentation_id, &request.theme_presentation_id) {
+        (Some(presentation_id), _) => api.get_presentation(presentation_id).await?,
+        (None, Some(theme_id)) => copy_presentation(api, theme_id, title).await?,
+        (None, None) => {
+            let create_request = CreatePresentationRequest {
+                title: title.to_string(),
+                locale: request.locale.clone(),
+            };
+            api.create_presentation(&create_request).await?
+        }
+    };
+
+    // Add a slide for each chunk, replacing the slides that already exist
+    let populated = match populate_slides(api, &presentation, &chunks, &options).await {
+        Ok(populated) => populated,
+        // Never delete a presentation the user already had
+        Err(e) if request.mode == Mode::Replace => return Err(e),
+        // Nothing is left to delete
+        Err(e @ AppError::PresentationDeleted) => return Err(e),
+        Err(e) => {
+            let presentation_id = &presentation.presentation_id;
+            return Err(
+                discard_presentation(api, presentation_id, e, request.cleanup_on_failure).await,
+            );
+        }
+    };
+
+    // Sharing problems are reported per address rather than failing the deck
+    let mut share_errors = Vec::new();
+    for email in &request.share_with {
+        let shared = api
+            .share_file(
+                &presentation.presentation_id,
+                email,
+                request.share_role,
+                request.send_notification_email,
+            )
+            .await;
+        if let Err(e) = shared {
+            share_errors.push(ShareError {
+                email: email.clone(),
+                error: e.to_string(),
+            });
+        }
+    }
+
+    let image_slides = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| matches!(chunk.media, Some(Media::Image(_))))
+        .map(|(index, _)| index)
+        .collect();
+    let created_slides = populated
+        .slide_ids
+        .into_iter()
+        .map(|object_id| CreatedSlide {
+            url: slide_url(&presentation.presentation_id, &object_id),
+            object_id,
+        })
+        .collect();
+    let slides = slide_stats(&chunks, request.long_slide_words);
+    Ok(CreateSlidesResult {
+        presentation_id: presentation.presentation_id,
+        title: title.to_string(),
+        created_slides,
+        slide_count: slides.len(),
+        totals: slide_totals(&slides),
+        slides,
+        duplicates_removed,
+        chunks_dropped,
+        skipped_slides: skipped_slides(&chunks),
+        themed: request.theme_presentation_id.is_some(),
+        image_slides,
+        image_errors: populated.image_errors,
+        share_errors,
+    })
+}
+
+/// Deletes Drive files; a trait so cleanup can run against a fake client.
+trait FileDeleter {
+    async fn delete_file(&self, file_id: &str) -> Result<()>;
+}
+
+impl FileDeleter for Token {
+    async fn delete_file(&self, file_id: &str) -> Result<()> {
+        drive::delete_file(self, file_id).await
+    }
+}
+
+/// The Slides and Drive calls made while creating a deck; a trait so the
+/// requests built for given chunks can be checked against a fake client.
+trait SlidesApi: FileDeleter {
+    async fn create_presentation(
+        &self,
+        request: &CreatePresentationRequest,
+    ) -> std::result::Result<Presentation, AppError>;
+    async fn get_presentation(
+        &self,
+        presentation_id: &str,
+    ) -> std::result::Result<Presentation, AppError>;
+    async fn batch_update(
+        &self,
+        presentation_id: &str,
+        requests: Vec<UpdateRequest>,
+    ) -> std::result::Result<BatchUpdateResponse, AppError>;
+    async fn copy_file(
+        &self,
+        file_id: &str,
+        name: Option<&str>,
+    ) -> std::result::Result<String, AppError>;
+    async fn share_file(
+        &self,
+        file_id: &str,
+        email: &str,
+        role: ShareRole,
+        send_notification_email: bool,
+    ) -> Result<()>;
+    async fn image_url(&self, file_id: &str) -> Result<String>;
+}
+
+impl SlidesApi for Token {
+    async fn create_presentation(
+        &self,
+        request: &CreatePresentationRequest,
+    ) -> std::result::Result<Presentation, AppError> {
+        create_presentation(self, request).await
+    }
+
+    async fn get_presentation(
+        &self,
+        presentation_id: &str,
+    ) -> std::result::Result<Presentation, AppError> {
+        get_presentation(self, presentation_id).await
+    }
+
+    async fn batch_update(
+        &self,
+        presentation_id: &str,
+        requests: Vec<UpdateRequest>,
+    ) -> std::result::Result<BatchUpdateResponse, AppError> {
+        batch_update(self, presentation_id, requests).await
+    }
+
+    async fn copy_file(
+        &self,
+        file_id: &str,
+        name: Option<&str>,
+    ) -> std::result::Result<String, AppError> {
+        drive::copy_file(self, file_id, name).await
+    }
+
+    async fn share_file(
+        &self,
+        file_id: &str,
+        email: &str,
+        role: ShareRole,
+        send_notification_email: bool,
+    ) -> Result<()> {
+        drive::share_file(self, file_id, email, role, send_notification_email).await
+    }
+
+    async fn image_url(&self, file_id: &str) -> Result<String> {
+        drive::image_url(self, file_id).await
+    }
+}
+
+/// Deletes a presentation whose slides could not be added, unless `cleanup`
+/// is off, and extends `error` with what happened to it.
+///
+/// Cleanup is best-effort: a failed delete is logged and reported alongside
+/// the original error rather than replacing it, and the error keeps its type.
+async fn discard_presentation(
+    deleter: &impl FileDeleter,
+    presentation_id: &str,
+    error: AppError,
+    cleanup: bool,
+) -> AppError {
+    let outcome = if !cleanup {
+        format!("the partially created presentation {presentation_id} was kept")
+    } else {
+        match deleter.delete_file(presentation_id).await {
+            Ok(()) => "the partially created presentation was deleted".to_string(),
+            Err(e) => {
+                warn!("Failed to delete presentation {presentation_id}: {e}");
+                format!(
+                    "the partially created presentation {presentation_id} could not be deleted: {e}"
+                )
+            }
+        }
+    };
+    AppError::Cleanup {
+        error: Box::new(error),
+        outcome,
+    }
+}
+
+/// Creates a new Google Slides presentation with the given title and locale.
+async fn create_presentation(
+    token: &Token,
+    create_request: &CreatePresentationRequest,
+) -> std::result::Result<Presentation, AppError> {
+    let url = format!("{}/presentations", API_BASE);
+
+    let body = serde_wasm_bindgen::to_value(create_request)
+        .map_err(|e| AppError::Other(anyhow::anyhow!(e.to_string())))?;
+
+    let headers = Headers::new();
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(drive::other_error)?;
+    headers
+        .set("Authorization", &format!("Bearer {}", token.access_token))
+        .map_err(drive::other_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(&url, &init).map_err(drive::other_error)?;
+    let mut response = Fetch::Request(request)
+        .send()
+        .await
+        .map_err(drive::other_error)?;
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(AppError::GoogleSlides(format!(
+            "Failed to create presentation: {}",
+            error_text
+        )));
+    }
+
+    response.json().await.map_err(drive::other_error)
+}
+
+/// Copies a presentation through the Drive API so the copy keeps its theme.
+async fn copy_presentation(
+    api: &impl SlidesApi,
+    presentation_id: &str,
+    title: &str,
+) -> std::result::Result<Presentation, AppError> {
+    let copy_id = api.copy_file(presentation_id, Some(title)).await?;
+    api.get_presentation(&copy_id).await
+}
+
+/// Copies the template and replaces every token in the copy with its text.
+pub async fn create_from_template(
+    token: &Token,
+    request: &CreateFromTemplateRequest,
+) -> std::result::Result<CreateFromTemplateResult, AppError> {
+    request
+        .validate()
+        .map_err(|e| AppError::InvalidRequest(e.to_string()))?;
+
+    let presentation_id =
+        drive::copy_file(token, &request.template_id, request.title.as_deref()).await?;
+
+    let mut tokens = request.replacements.keys().cloned().collect::<Vec<_>>();
+    tokens.sort();
+    let response = batch_update(
+        token,
+        &presentation_id,
+        replace_text_requests(&tokens, &request.replacements, &request.placeholder),
+    )
+    .await?;
+
+    let (replaced, skipped) = replacement_counts(tokens, &response.replies);
+    Ok(CreateFromTemplateResult {
+        presentation_id,
+        replaced,
+        skipped,
+    })
+}
+
+/// Builds one case-sensitive `replaceAllText` request per token, in order.
+fn replace_text_requests(
+    tokens: &[String],
+    replacements: &HashMap<String, String>,
+    syntax: &PlaceholderSyntax,
+) -> Vec<UpdateRequest> {
+    tokens
+        .iter()
+        .map(|name| UpdateRequest {
+            replace_all_text: Some(ReplaceAllTextRequest {
+                contains_text: SubstringMatchCriteria {
+                    text: syntax.token(name),
+                    match_case: true,
+                },
+                replace_text: replacements[name].clone(),
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Pairs each token with its reply, separating tokens that replaced
+/// something from tokens the template does not contain.
+fn replacement_counts(
+    tokens: Vec<String>,
+    replies: &[Reply],
+) -> (BTreeMap<String, usize>, Vec<String>) {
+    let mut replaced = BTreeMap::new();
+    let mut skipped = Vec::new();
+    for (index, name) in tokens.into_iter().enumerate() {
+        let count = replies
+            .get(index)
+            .and_then(|reply| reply.replace_all_text.as_ref())
+            .map_or(0, |reply| reply.occurrences_changed);
+        if count == 0 {
+            skipped.push(name);
+        } else {
+            replaced.insert(name, count);
+        }
+    }
+    (replaced, skipped)
+}
+
+/// Size limits for one batchUpdate call; larger request lists are sent in
+/// several calls.
+#[derive(Debug, Clone, Copy)]
+struct BatchLimits {
+    max_requests: usize,
+    /// Serialized size of the requests, in bytes.
+    max_bytes: usize,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            max_requests: 50,
+            max_bytes: 500_000,
+        }
+    }
+}
+
+impl UpdateRequest {
+    /// The IDs of the objects the request creates and of the existing pages
+    /// and elements it changes, or `None` when its effect depends on the rest
+    /// of the deck, like a slide inserted at an index or text replaced
+    /// everywhere.
+    fn object_ids(&self) -> Option<(Vec<&str>, Vec<&str>)> {
+        // Destructured so a new request type cannot be overlooked here
+        let UpdateRequest {
+            create_slide,
+            insert_text,
+            delete_object,
+            replace_all_text,
+            update_text_style,
+            create_paragraph_bullets,
+            create_image,
+            create_table,
+            update_page_properties,
+            create_shape,
+            update_shape_properties,
+            update_paragraph_style,
+            create_video,
+            update_video_properties,
+            update_slide_properties,
+        } = self;
+        if create_slide.is_some() || replace_all_text.is_some() {
+            return None;
+        }
+        let elements = [
+            create_image
+                .as_ref()
+                .map(|r| (&r.object_id, &r.element_properties)),
+            create_table
+                .as_ref()
+                .map(|r| (&r.object_id, &r.element_properties)),
+            create_shape
+                .as_ref()
+                .map(|r| (&r.object_id, &r.element_properties)),
+            create_video
+                .as_ref()
+                .map(|r| (&r.object_id, &r.element_properties)),
+        ];
+        let created = elements
+            .iter()
+            .flatten()
+            .map(|(object_id, _)| object_id.as_str())
+            .collect();
+        let targets = [
+            insert_text.as_ref().map(|r| &r.object_id),
+            delete_object.as_ref().map(|r| &r.object_id),
+            update_text_style.as_ref().map(|r| &r.object_id),
+            create_paragraph_bullets.as_ref().map(|r| &r.object_id),
+            update_page_properties.as_ref().map(|r| &r.object_id),
+            update_shape_properties.as_ref().map(|r| &r.object_id),
+            update_paragraph_style.as_ref().map(|r| &r.object_id),
+            update_video_properties.as_ref().map(|r| &r.object_id),
+            update_slide_properties.as_ref().map(|r| &r.object_id),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            elements
+                .iter()
+                .flatten()
+                .map(|(_, properties)| &properties.page_object_id),
+        )
+        .map(String::as_str)
+        .collect();
+        Some((created, targets))
+    }
+
+    /// Whether the request belongs with the one before it, such as text
+    /// inserted into a slide or shape created just before.
+    fn continues_previous(&self) -> bool {
+        self.insert_text.is_some()
+            || self.update_text_style.is_some()
+            || self.create_paragraph_bullets.is_some()
+            || self.create_table.is_some()
+            || self.update_paragraph_style.is_some()
+            || self.create_video.is_some()
+            || self.update_video_properties.is_some()
+            || self.update_slide_properties.is_some()
+    }
+}
+
+/// Splits requests into consecutive batches within `limits`, keeping order.
+///
+/// A request that creates a slide or shape is never separated from the text
+/// and styling that follow it, so a failed batch cannot leave a slide half
+/// filled. Such a group larger than the limits is sent as a batch of its own.
+fn batches(requests: Vec<UpdateRequest>, limits: BatchLimits) -> Vec<Vec<UpdateRequest>> {
+    pack_batches(request_groups(requests), limits)
+}
+
+/// Groups each request that creates a slide or shape with the requests that
+/// continue it.
+fn request_groups(requests: Vec<UpdateRequest>) -> Vec<Vec<UpdateRequest>> {
+    let mut groups: Vec<Vec<UpdateRequest>> = Vec::new();
+    for request in requests {
+        match groups.last_mut() {
+            Some(group) if request.continues_previous() => group.push(request),
+            _ => groups.push(vec![request]),
+        }
+    }
+    groups
+}
+
+/// Moves every `createSlide` out of `requests` so the slides can be created
+/// first, in order, then batches what is left. Each slide's remaining
+/// requests stay together.
+fn slides_first(
+    requests: Vec<UpdateRequest>,
+    limits: BatchLimits,
+) -> (Vec<Vec<UpdateRequest>>, Vec<Vec<UpdateRequest>>) {
+    let mut slides = Vec::new();
+    let mut groups = Vec::new();
+    for mut group in request_groups(requests) {
+        if group[0].create_slide.is_some() {
+            slides.push(group.remove(0));
+        }
+        if !group.is_empty() {
+            groups.push(group);
+        }
+    }
+    (
+        pack_batches(request_groups(slides), limits),
+        pack_batches(groups, limits),
+    )
+}
+
+/// Whether `batches` can be sent in any order, or all at once: none creates
+/// slides or replaces text across the deck, and none touches an object that
+/// another batch creates.
+fn order_independent(batches: &[Vec<UpdateRequest>]) -> bool {
+    let Some(ids) = batches
+        .iter()
+        .map(|batch| {
+            batch
+                .iter()
+                .map(UpdateRequest::object_ids)
+                .collect::<Option<Vec<_>>>()
+        })
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+    let created: Vec<HashSet<&str>> = ids
+        .iter()
+        .map(|batch| {
+            batch
+                .iter()
+                .flat_map(|(created, _)| created.iter().copied())
+                .collect()
+        })
+        .collect();
+    ids.iter().enumerate().all(|(index, batch)| {
+        batch.iter().flat_map(|(_, targets)| targets).all(|target| {
+            created
+                .iter()
+                .enumerate()
+                .all(|(other, ids)| other == index || !ids.contains(target))
+        })
+    })
+}
+
+/// Packs groups of requests into batches within `limits`, in order.
+fn pack_batches(groups: Vec<Vec<UpdateRequest>>, limits: BatchLimits) -> Vec<Vec<UpdateRequest>> {
+    let mut batches = Vec::new();
+    let mut batch: Vec<UpdateRequest> = Vec::new();
+    let mut batch_bytes = 0;
+    for group in groups {
+        let group_bytes = group
+            .iter()
+            .map(|request| serde_json::to_vec(request).map_or(0, |json| json.len()))
+            .sum::<usize>();
+        let fits = batch.len() + group.len() <= limits.max_requests
+            && batch_bytes + group_bytes <= limits.max_bytes;
+        if !batch.is_empty() && !fits {
+            batches.push(std::mem::take(&mut batch));
+            batch_bytes = 0;
+        }
+        batch.extend(group);
+        batch_bytes += group_bytes;
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    batches
+}
+
+/// Reports which batch, and which of the slides it created, failed. Only
+/// Google's own failures are reworded; other errors, such as a deleted
+/// presentation, are passed on as they are.
+fn batch_failure(
+    number: usize,
+    batch_count: usize,
+    slide_ids: &[String],
+    error: AppError,
+) -> AppError {
+    let AppError::GoogleSlides(message) = error else {
+        return error;
+    };
+    let slides = match slide_ids {
+        [] => String::new(),
+        [only] => format!(" (slide {only})"),
+        [first, .., last] => format!(" (slides {first} to {last})"),
+    };
+    AppError::GoogleSlides(format!(
+        "batch {} of {}{} failed: {}",
+        number + 1,
+        batch_count,
+        slides,
+        message
+    ))
+}
+
+/// How the slides made from chunks are laid out and styled.
+#[derive(Debug)]
+struct SlideOptions<'a> {
+    layout: &'a SlideLayoutReference,
+    text_style: Option<TextStyle>,
+    background_color: Option<&'a str>,
+    inline: InlineOptions,
+    title_slide: Option<TitleSlideText>,
+    /// Text of the final slide, if the deck gets one.
+    closing_slide: Option<String>,
+    /// Shrink overflowing body text.
+    autofit: bool,
+    slide_numbers: Option<SlideNumbers>,
+    text_direction: TextDirection,
+    /// Send batches one at a time even when they could be sent together.
+    sequential_batches: bool,
+}
+
+/// Where "n / total" slide numbers are stamped.
+#[derive(Debug, Clone, Copy)]
+struct SlideNumbers {
+    /// Leave the first slide unnumbered.
+    skip_first: bool,
+}
+
+/// The resolved text of the opening title slide.
+#[derive(Debug)]
+struct TitleSlideText {
+    title: String,
+    subtitle: String,
+}
+
+/// What [`populate_slides`] added to the presentation.
+struct PopulatedSlides {
+    /// Object IDs of the created slides, in deck order.
+    slide_ids: Vec<String>,
+    image_errors: Vec<ImageSlideError>,
+}
+
+/// Populates the presentation with slides containing the provided text chunks.
+///
+/// The slides the presentation already has are only deleted once every new
+/// slide has its content, so a failure part way leaves them in place.
+async fn populate_slides(
+    api: &impl SlidesApi,
+    presentation: &Presentation,
+    chunks: &[Chunk],
+    options: &SlideOptions<'_>,
+) -> std::result::Result<PopulatedSlides, AppError> {
+    let id_prefix = slide_id_prefix(&presentation.slides);
+    let requests = content_requests(chunks, &id_prefix, presentation.page_size.as_ref(), options);
+    let created_ids = created_slide_ids(&requests);
+    let presentation_id = &presentation.presentation_id;
+    if options.sequential_batches {
+        // Sent in order, so absolute insertion indexes stay correct across batches
+        let batches = batches(requests, BatchLimits::default());
+        let total = batches.len();
+        send_batches(api, presentation_id, batches, 0, total, 1).await?;
+    } else {
+        // Slides are created in order first; what fills them can then go in
+        // parallel unless one batch needs an object another creates
+        let (slides, content) = slides_first(requests, BatchLimits::default());
+        let concurrency = if order_independent(&content) {
+            MAX_CONCURRENT_BATCHES
+        } else {
+            1
+        };
+        let total = slides.len() + content.len();
+        let first_content = slides.len();
+        send_batches(api, presentation_id, slides, 0, total, 1).await?;
+        send_batches(
+            api,
+            presentation_id,
+            content,
+            first_content,
+            total,
+            concurrency,
+        )
+        .await?;
+    }
+
+    // Speaker notes shapes only get IDs once the slides exist
+    if chunks.iter().any(|chunk| !chunk.notes.is_empty()) {
+        let populated = api
+            .get_presentation(&presentation.presentation_id)
+            .await
+            .map_err(deleted_since_fetched)?;
+        let requests = notes_requests(chunks, &populated.slides, &id_prefix);
+        api.batch_update(&presentation.presentation_id, requests)
+            .await?;
+    }
+
+    let old_slides: Vec<_> = presentation
+        .slides
+        .iter()
+        .map(|slide| delete_object(&slide.object_id))
+        .collect();
+    if !old_slides.is_empty() {
+        api.batch_update(&presentation.presentation_id, old_slides)
+            .await?;
+    }
+
+    // One batch per image, since a batch fails as a whole on one bad image URL
+    let mut image_errors = Vec::new();
+    for (index, request) in image_requests(chunks, &id_prefix) {
+        if let Err(e) = insert_image(api, &presentation.presentation_id, request).await {
+            image_errors.push(ImageSlideError {
+                index,
+                error: e.to_string(),
+            });
+        }
+    }
+
+    Ok(PopulatedSlides {
+        slide_ids: created_ids,
+        image_errors,
+    })
+}
+
+/// Most batchUpdate calls in flight at once, to stay well inside the
+/// Workers subrequest limits.
+const MAX_CONCURRENT_BATCHES: usize = 3;
+
+/// Sends `batches` with at most `concurrency` calls in flight. The first
+/// failure stops the calls not yet started. `first` is how many of the
+/// deck's `total` batches were sent before these, for error messages.
+async fn send_batches(
+    api: &impl SlidesApi,
+    presentation_id: &str,
+    batches: Vec<Vec<UpdateRequest>>,
+    first: usize,
+    total: usize,
+    concurrency: usize,
+) -> std::result::Result<(), AppError> {
+    stream::iter(batches.into_iter().enumerate())
+        .map(Ok::<_, AppError>)
+        .try_for_each_concurrent(concurrency, |(index, batch)| async move {
+            let slide_ids = created_slide_ids(&batch);
+            match api.batch_update(presentation_id, batch).await {
+                Ok(_) => Ok(()),
+                Err(e) if total == 1 => Err(e),
+                Err(e) => Err(batch_failure(first + index, total, &slide_ids, e)),
+            }
+        })
+        .await
+}
+
+/// Object IDs of the slides that `requests` create, in request order.
+fn created_slide_ids(requests: &[UpdateRequest]) -> Vec<String> {
+    requests
+        .iter()
+        .filter_map(|request| request.create_slide.as_ref()?.object_id.clone())
+        .collect()
+}
+
+/// Builds the requests that create and style the slides' text, with every
+/// slide ID starting with `id_prefix`. `page_size` places slide numbers and
+/// two-column text boxes; 16:9 is assumed without it.
+fn content_requests(
+    chunks: &[Chunk],
+    id_prefix: &str,
+    page_size: Option<&Size>,
+    options: &SlideOptions<'_>,
+) -> Vec<UpdateRequest> {
+    let mut requests = slide_requests(chunks, id_prefix, options.layout, page_size);
+    if let Some(title_slide) = &options.title_slide {
+        // Content slides follow the title slide
+        for create_slide in requests
+            .iter_mut()
+            .filter_map(|request| request.create_slide.as_mut())
+        {
+            create_slide.insertion_index = create_slide.insertion_index.map(|index| index + 1);
+        }
+        requests.splice(0..0, title_slide_requests(title_slide, id_prefix));
+    }
+    if let Some(text) = &options.closing_slide {
+        let slide_count = requests
+            .iter()
+            .filter(|request| request.create_slide.is_some())
+            .count();
+        requests.extend(closing_slide_requests(text, slide_count, id_prefix));
+    }
+    let slide_ids = created_slide_ids(&requests);
+    if options.autofit {
+        let autofit = autofit_requests(&requests);
+        requests.extend(autofit);
+    }
+    if options.inline.any() {
+        requests = with_inline_formatting(requests, options.inline);
+    }
+    // Applied second so the base style lands before, not over, the emphasis
+    if let Some(text_style) = &options.text_style {
+        requests = with_text_style(requests, text_style);
+    }
+    if options.text_direction != TextDirection::Ltr {
+        requests = with_text_direction(requests, options.text_direction);
+    }
+    if let Some(color) = options.background_color.and_then(parse_hex_color) {
+        requests.extend(background_requests(&slide_ids, color));
+    }
+    if let Some(numbers) = options.slide_numbers {
+        requests.extend(slide_number_requests(
+            &slide_ids,
+            page_size,
+            numbers,
+            options.text_style.as_ref(),
+        ));
+    }
+    requests
+}
+
+/// Makes every BODY placeholder among the created slides shrink its text on
+/// overflow. Slides without one, such as BLANK and TITLE_ONLY, are skipped.
+fn autofit_requests(requests: &[UpdateRequest]) -> Vec<UpdateRequest> {
+    requests
+        .iter()
+        .filter_map(|request| request.create_slide.as_ref())
+        .flat_map(|create_slide| &create_slide.placeholder_id_mappings)
+        .filter(|mapping| mapping.layout_placeholder.placeholder_type == "BODY")
+        .map(|mapping| UpdateRequest {
+            update_shape_properties: Some(UpdateShapePropertiesRequest {
+                object_id: mapping.object_id.clone(),
+                shape_properties: ShapeProperties {
+                    autofit: Autofit {
+                        autofit_type: "TEXT_AUTOFIT_SHRINK_ON_OVERFLOW".to_string(),
+                    },
+                },
+                fields: "autofit.autofitType".to_string(),
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Font size of slide numbers, in points.
+const SLIDE_NUMBER_FONT_PT: f64 = 10.0;
+
+/// Size of the slide number text box and its distance from the page's
+/// bottom-right corner, in EMU.
+const SLIDE_NUMBER_WIDTH_EMU: f64 = 914_400.0;
+const SLIDE_NUMBER_HEIGHT_EMU: f64 = 320_040.0;
+const SLIDE_NUMBER_MARGIN_EMU: f64 = 182_880.0;
+
+/// Adds a small "n / total" text box near the bottom-right corner of each
+/// slide, in the active text style's color and font.
+fn slide_number_requests(
+    slide_ids: &[String],
+    page_size: Option<&Size>,
+    numbers: SlideNumbers,
+    text_style: Option<&TextStyle>,
+) -> Vec<UpdateRequest> {
+    let (page_width, page_height) = page_dimensions(page_size);
+    let style = TextStyle {
+        font_size_pt: Some(SLIDE_NUMBER_FONT_PT),
+        ..text_style.cloned().unwrap_or_default()
+    };
+    let total = slide_ids.len();
+    let requests = slide_ids
+        .iter()
+        .enumerate()
+        .skip(usize::from(numbers.skip_first))
+        .flat_map(|(index, slide_id)| {
+            let object_id = format!("{}_number", slide_id);
+            [
+                UpdateRequest {
+                    create_shape: Some(CreateShapeRequest {
+                        object_id: object_id.clone(),
+                        shape_type: "TEXT_BOX".to_string(),
+                        element_properties: PageElementProperties {
+                            page_object_id: slide_id.clone(),
+                            size: Size {
+                                width: emu(SLIDE_NUMBER_WIDTH_EMU),
+                                height: emu(SLIDE_NUMBER_HEIGHT_EMU),
+                            },
+                            transform: AffineTransform {
+                                scale_x: 1.0,
+                                scale_y: 1.0,
+                                translate_x: page_width
+                                    - SLIDE_NUMBER_MARGIN_EMU
+                                    - SLIDE_NUMBER_WIDTH_EMU,
+                                translate_y: page_height
+                                    - SLIDE_NUMBER_MARGIN_EMU
+                                    - SLIDE_NUMBER_HEIGHT_EMU,
+                                unit: "EMU".to_string(),
+                            },
+                        },
+                    }),
+                    ..Default::default()
+                },
+                insert_text(object_id, &format!("{} / {}", index + 1, total)),
+            ]
+        })
+        .collect();
+    with_text_style(requests, &style)
+}
+
+/// Object ID of the final closing slide, after the deck's ID prefix.
+const CLOSING_SLIDE_ID: &str = "closing_slide";
+
+/// Appends the closing slide in the SECTION_HEADER layout at `insertion_index`,
+/// after every other slide.
+fn closing_slide_requests(
+    text: &str,
+    insertion_index: usize,
+    id_prefix: &str,
+) -> Vec<UpdateRequest> {
+    let slide_id = format!("{id_prefix}{CLOSING_SLIDE_ID}");
+    let title_id = format!("{}_title", slide_id);
+    vec![
+        UpdateRequest {
+            create_slide: Some(CreateSlideRequest {
+                object_id: Some(slide_id),
+                insertion_index: Some(insertion_index as i32),
+                slide_layout_reference: Some(SlideLayoutReference::PredefinedLayout(
+                    PredefinedLayout::SectionHeader,
+                )),
+                placeholder_id_mappings: vec![placeholder_mapping("TITLE", &title_id)],
+            }),
+            ..Default::default()
+        },
+        insert_text(title_id, text),
+    ]
+}
+
+/// Object ID of the opening title slide, after the deck's ID prefix.
+const TITLE_SLIDE_ID: &str = "title_slide";
+
+/// Creates the opening slide in the TITLE layout, with the presentation title
+/// and the joined subtitle lines.
+fn title_slide_requests(title_slide: &TitleSlideText, id_prefix: &str) -> Vec<UpdateRequest> {
+    let slide_id = format!("{id_prefix}{TITLE_SLIDE_ID}");
+    let title_id = format!("{}_title", slide_id);
+    let subtitle_id = format!("{}_subtitle", slide_id);
+    let mut requests = vec![UpdateRequest {
+        create_slide: Some(CreateSlideRequest {
+            object_id: Some(slide_id),
+            insertion_index: Some(0),
+            slide_layout_reference: Some(SlideLayoutReference::PredefinedLayout(
+                PredefinedLayout::Title,
+            )),
+            placeholder_id_mappings: vec![
+                placeholder_mapping("CENTERED_TITLE", &title_id),
+                placeholder_mapping("SUBTITLE", &subtitle_id),
+            ],
+        }),
+        ..Default::default()
+    }];
+    // The API rejects inserting empty text.
+    if !title_slide.title.is_empty() {
+        requests.push(insert_text(title_id, &title_slide.title));
+    }
+    if !title_slide.subtitle.is_empty() {
+        requests.push(insert_text(subtitle_id, &title_slide.subtitle));
+    }
+    requests
+}
+
+/// Sends one `createImage` request, first resolving a `drive:` reference to
+/// a URL Google can fetch.
+async fn insert_image(
+    api: &impl SlidesApi,
+    presentation_id: &str,
+    mut request: UpdateRequest,
+) -> Result<()> {
+    let drive_image = request.create_image.as_mut().and_then(|create_image| {
+        let file_id = drive::file_id(&create_image.url)?.to_string();
+        Some((create_image, file_id))
+    });
+    if let Some((create_image, file_id)) = drive_image {
+        create_image.url = api.image_url(&file_id).await?;
+    }
+    api.batch_update(presentation_id, vec![request]).await?;
+    Ok(())
+}
+
+/// Fetches a presentation, including the speaker notes IDs of its slides.
+async fn get_presentation(
+    token: &Token,
+    presentation_id: &str,
+) -> std::result::Result<Presentation, AppError> {
+    let url = format!("{}/presentations/{}", API_BASE, presentation_id);
+
+    let headers = Headers::new();
+    headers
+        .set("Authorization", &format!("Bearer {}", token.access_token))
+        .map_err(drive::other_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Get).with_headers(headers);
+
+    let request = WorkerRequest::new_with_init(&url, &init).map_err(drive::other_error)?;
+    let mut response = Fetch::Request(request)
+        .send()
+        .await
+        .map_err(drive::other_error)?;
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(get_presentation_error(
+            response.status_code(),
+            presentation_id,
+            &error_text,
+        ));
+    }
+
+    response.json().await.map_err(drive::other_error)
+}
+
+/// Sends a batchUpdate with the given requests and returns the replies.
+async fn batch_update(
+    token: &Token,
+    presentation_id: &str,
+    requests: Vec<UpdateRequest>,
+) -> std::result::Result<BatchUpdateResponse, AppError> {
+    let url = format!("{}/presentations/{}:batchUpdate", API_BASE, presentation_id);
+
+    let batch_request = BatchUpdateRequest { requests };
+
+    let body = serde_json::to_string(&batch_request).map_err(anyhow::Error::from)?;
+
+    let headers = Headers::new();
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(drive::other_error)?;
+    headers
+        .set("Authorization", &format!("Bearer {}", token.access_token))
+        .map_err(drive::other_error)?;
+
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(body.into()))
+        .with_headers(headers);
+
+    // A batch is applied whole or not at all, so one that was rate limited
+    // or hit a server error can be sent again
+    let mut attempt = 1;
+    let mut response = loop {
+        let request = WorkerRequest::new_with_init(&url, &init).map_err(drive::other_error)?;
+        let response = Fetch::Request(request)
+            .send()
+            .await
+            .map_err(drive::other_error)?;
+        match retry_delay(response.status_code(), attempt) {
+            Some(delay) => {
+                Delay::from(delay).await;
+                attempt += 1;
+            }
+            None => break response,
+        }
+    };
+
+    if response.status_code() < 200 || response.status_code() >= 300 {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(batch_update_error(response.status_code(), &error_text));
+    }
+
+    response.json().await.map_err(drive::other_error)
+}
+
+/// Most times a batchUpdate is sent when Google keeps rate limiting it or
+/// failing with a server error.
+const BATCH_UPDATE_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry of a batchUpdate; each later retry waits
+/// twice as long as the one before.
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// How long to wait before sending again a batchUpdate that got `status` on
+/// attempt `attempt`, counting from 1, or `None` when it is not retried.
+fn retry_delay(status: u16, attempt: u32) -> Option<Duration> {
+    let retryable = status == 429 || (500..600).contains(&status);
+    (retryable && attempt < BATCH_UPDATE_ATTEMPTS)
+        .then(|| Duration::from_millis(RETRY_BASE_DELAY_MS << (attempt - 1)))
+}
+
+/// Builds the batchUpdate requests that add one slide per chunk in the given layout.
+///
+/// Each slide's placeholders get known object IDs so the title goes into the
+/// TITLE placeholder and the body into the BODY placeholder, inserted in that
+/// order right after the slide is created. Chunks without a title leave the
+/// title placeholder empty, and chunks with a title but no body get a
+/// TITLE_ONLY slide. Slide IDs start with `id_prefix`.
+fn slide_requests(
+    chunks: &[Chunk],
+    id_prefix: &str,
+    layout: &SlideLayoutReference,
+    page_size: Option<&Size>,
+) -> Vec<UpdateRequest> {
+    let mut requests = Vec::new();
+    let two_column = *layout == SlideLayoutReference::TwoColumn;
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let slide_id = slide_id(id_prefix, index);
+        let title_id = format!("{}_title", slide_id);
+        let body_id = format!("{}_body", slide_id);
+
+        let title = chunk.title.as_deref().filter(|title| !title.is_empty());
+        let (layout, placeholder_id_mappings) = if chunk.media == Some(Media::Section) {
+            (
+                SlideLayoutReference::PredefinedLayout(PredefinedLayout::SectionHeader),
+                vec![placeholder_mapping("TITLE", &title_id)],
+            )
+        } else if chunk.media.is_some() {
+            (
+                SlideLayoutReference::PredefinedLayout(PredefinedLayout::Blank),
+                Vec::new(),
+            )
+        } else if title.is_some() && (chunk.body.is_empty() || two_column) {
+            (
+                SlideLayoutReference::PredefinedLayout(PredefinedLayout::TitleOnly),
+                vec![placeholder_mapping("TITLE", &title_id)],
+            )
+        } else if two_column {
+            (
+                SlideLayoutReference::PredefinedLayout(PredefinedLayout::Blank),
+                Vec::new(),
+            )
+        } else {
+            (
+                layout.clone(),
+                vec![
+                    placeholder_mapping("TITLE", &title_id),
+                    placeholder_mapping("BODY", &body_id),
+                ],
+            )
+        };
+
+        requests.push(UpdateRequest {
+            create_slide: Some(CreateSlideRequest {
+                object_id: Some(slide_id.clone()),
+                insertion_index: Some(index as i32),
+                slide_layout_reference: Some(layout),
+                placeholder_id_mappings,
+            }),
+            ..Default::default()
+        });
+        if chunk.skipped {
+            requests.push(skip_slide(&slide_id));
+        }
+
+        if let Some(Media::Table(rows)) = &chunk.media {
+            requests.extend(table_requests(&slide_id, rows));
+        }
+        if let Some(Media::Video { id, start }) = &chunk.media {
+            requests.extend(video_requests(
+                &slide_id,
+                id,
+                *start,
+                page_dimensions(page_size),
+            ));
+        }
+        if let Some(title) = title {
+            requests.push(insert_text(title_id, title));
+        }
+        // The API rejects inserting empty text.
+        if chunk.body.is_empty() {
+            continue;
+        }
+        if two_column && chunk.media.is_none() {
+            requests.extend(two_column_requests(
+                &slide_id,
+                &chunk.body,
+                page_dimensions(page_size),
+                title.is_some(),
+            ));
+        } else {
+            requests.extend(body_text_requests(&body_id, &chunk.body));
+        }
+    }
+
+    requests
+}
+
+/// Object ID of the slide for the chunk at `index`.
+fn slide_id(id_prefix: &str, index: usize) -> String {
+    format!("{id_prefix}slide_{index}")
+}
+
+/// Picks the prefix for the IDs of the slides added to a presentation, so
+/// they never clash with the `slides` it already has, which stay until the
+/// new ones are filled. Decks this worker made are refilled with `r1_`, and
+/// those refilled again go back to no prefix.
+fn slide_id_prefix(slides: &[Slide]) -> String {
+    let mut generation = 0;
+    loop {
+        let prefix = match generation {
+            0 => String::new(),
+            n => format!("r{n}_"),
+        };
+        if !slides
+            .iter()
+            .any(|slide| is_generated_slide_id(&slide.object_id, &prefix))
+        {
+            return prefix;
+        }
+        generation += 1;
+    }
+}
+
+/// Whether `object_id` is one of the slide IDs generated with `prefix`.
+fn is_generated_slide_id(object_id: &str, prefix: &str) -> bool {
+    let Some(id) = object_id.strip_prefix(prefix) else {
+        return false;
+    };
+    id == TITLE_SLIDE_ID
+        || id == CLOSING_SLIDE_ID
+        || id
+            .strip_prefix("slide_")
+            .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Inserts `text` into `object_id`, turning its list lines into bullets.
+fn body_text_requests(object_id: &str, text: &str) -> Vec<UpdateRequest> {
+    let (body, bullet_ranges) = bulleted_text(text);
+    let mut requests = vec![insert_text(object_id.to_string(), &body)];
+    // Bullets drop their paragraphs' leading tabs, shifting any text after them
+    requests.extend(
+        bullet_ranges
+            .into_iter()
+            .rev()
+            .map(|(start, end)| create_bullets(object_id, start, end)),
+    );
+    requests
+}
+
+/// Share of the page height left above the columns for a slide's title.
+const COLUMN_TITLE_SHARE: f64 = 0.25;
+
+/// Space between the two columns, in EMU (a quarter inch).
+const COLUMN_GAP_EMU: f64 = 228_600.0;
+
+/// Creates two text boxes side by side on `slide_id` and fills them with the
+/// halves of `body`. An empty half gets no text box.
+fn two_column_requests(
+    slide_id: &str,
+    body: &str,
+    page: (f64, f64),
+    titled: bool,
+) -> Vec<UpdateRequest> {
+    let (left, right) = split_columns(body);
+    [left, right]
+        .into_iter()
+        .enumerate()
+        .filter(|(_, text)| !text.is_empty())
+        .flat_map(|(column, text)| {
+            let object_id = format!("{}_column_{}", slide_id, column);
+            let mut requests = vec![UpdateRequest {
+                create_shape: Some(CreateShapeRequest {
+                    object_id: object_id.clone(),
+                    shape_type: "TEXT_BOX".to_string(),
+                    element_properties: column_element(slide_id, column, page, titled),
+                }),
+                ..Default::default()
+            }];
+            requests.extend(body_text_requests(&object_id, text));
+            requests
+        })
+        .collect()
+}
+
+/// Counts the chunks marked [`splitter::SKIP_MARKER`].
+fn skipped_slides(chunks: &[Chunk]) -> usize {
+    chunks.iter().filter(|chunk| chunk.skipped).count()
+}
+
+/// Hides a slide when presenting; it stays in the deck for editing.
+fn skip_slide(slide_id: &str) -> UpdateRequest {
+    UpdateRequest {
+        update_slide_properties: Some(UpdateSlidePropertiesRequest {
+            object_id: slide_id.to_string(),
+            slide_properties: SkippedSlideProperties { is_skipped: true },
+            fields: "isSkipped".to_string(),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Places column `column` (0 or 1) of a two-column slide on a page of
+/// `(width, height)` EMU. Below a title the columns start a quarter of the
+/// way down the page.
+fn column_element(
+    slide_id: &str,
+    column: usize,
+    (page_width, page_height): (f64, f64),
+    titled: bool,
+) -> PageElementProperties {
+    let top = if titled {
+        page_height * COLUMN_TITLE_SHARE
+    } else {
+        SLIDE_MARGIN_EMU
+    };
+    let width = (page_width - 2.0 * SLIDE_MARGIN_EMU - COLUMN_GAP_EMU) / 2.0;
+    PageElementProperties {
+        page_object_id: slide_id.to_string(),
+        size: Size {
+            width: emu(width),
+            height: emu(page_height - top - SLIDE_MARGIN_EMU),
+        },
+        transform: AffineTransform {
+            scale_x: 1.0,
+            scale_y: 1.0,
+            translate_x: SLIDE_MARGIN_EMU + column as f64 * (width + COLUMN_GAP_EMU),
+            translate_y: top,
+            unit: "EMU".to_string(),
+        },
+    }
+}
+
+/// Splits `text` into two halves of roughly equal length.
+///
+/// Splits at the line break nearest the middle, or failing that at the
+/// whitespace nearest it, so no word is divided. Text without either stays
+/// in the first half.
+fn split_columns(text: &str) -> (&str, &str) {
+    let middle = text.len() / 2;
+    let nearest = |is_break: fn(char) -> bool| {
+        text.char_indices()
+            .filter(|&(_, c)| is_break(c))
+            .min_by_key(|&(index, _)| index.abs_diff(middle))
+            .map(|(index, _)| index)
+    };
+    if let Some(index) = nearest(|c| c == '\n') {
+        (
+            text[..index].trim_end_matches('\n'),
+            text[index..].trim_start_matches('\n'),
+        )
+    } else if let Some(index) = nearest(char::is_whitespace) {
+        (text[..index].trim_end(), text[index..].trim_start())
+    } else {
+        (text, "")
+    }
+}
+
+/// Width and height of a page of `page_size`, or of a default 16:9 slide.
+fn page_dimensions(page_size: Option<&Size>) -> (f64, f64) {
+    // The API reports page sizes in EMU
+    page_size.map_or((SLIDE_WIDTH_EMU, SLIDE_HEIGHT_EMU), |size| {
+        (size.width.magnitude, size.height.magnitude)
+    })
+}
+
+fn delete_object(object_id: &str) -> UpdateRequest {
+    UpdateRequest {
+        delete_object: Some(DeleteObjectRequest {
+            object_id: object_id.to_string(),
+        }),
+        ..Default::default()
+    }
+}
+
+fn placeholder_mapping(placeholder_type: &str, object_id: &str) -> LayoutPlaceholderIdMapping {
+    LayoutPlaceholderIdMapping {
+        layout_placeholder: Placeholder {
+            placeholder_type: placeholder_type.to_string(),
+            index: 0,
+        },
+        object_id: object_id.to_string(),
+    }
+}
+
+fn insert_text(object_id: String, text: &str) -> UpdateRequest {
+    UpdateRequest {
+        insert_text: Some(InsertTextRequest {
+            object_id,
+            insertion_index: 0,
+            text: text.to_string(),
+            cell_location: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Follows every text insertion with an `updateTextStyle` over the inserted
+/// range. Leaves the requests unchanged when the style sets nothing.
+fn with_text_style(requests: Vec<UpdateRequest>, style: &TextStyle) -> Vec<UpdateRequest> {
+    let (api_style, fields) = api_text_style(style);
+    if fields.is_empty() {
+        return requests;
+    }
+
+    let mut styled = Vec::with_capacity(requests.len() * 2);
+    for request in requests {
+        let update = request.insert_text.as_ref().map(|insert| UpdateRequest {
+            update_text_style: Some(UpdateTextStyleRequest {
+                object_id: insert.object_id.clone(),
+                cell_location: insert.cell_location.clone(),
+                style: api_style.clone(),
+                text_range: TextRange {
+                    range_type: "FIXED_RANGE".to_string(),
+                    start_index: insert.insertion_index as usize,
+                    end_index: insert.insertion_index as usize
+                        + textmetrics::utf16_len(&insert.text),
+                },
+                fields: fields.clone(),
+            }),
+            ..Default::default()
+        });
+        styled.push(request);
+        styled.extend(update);
+    }
+    styled
+}
+
+/// Adds a right-to-left paragraph style after every text insertion on the
+/// slides that need one: all of them for [`TextDirection::Rtl`], or those
+/// whose text is mostly right-to-left for [`TextDirection::Auto`].
+fn with_text_direction(
+    requests: Vec<UpdateRequest>,
+    direction: TextDirection,
+) -> Vec<UpdateRequest> {
+    // A slide's text always follows the request that creates the slide
+    let mut slides: Vec<Vec<UpdateRequest>> = Vec::new();
+    for request in requests {
+        match slides.last_mut() {
+            Some(slide) if request.create_slide.is_none() => slide.push(request),
+            _ => slides.push(vec![request]),
+        }
+    }
+
+    let mut directed = Vec::new();
+    for slide in slides {
+        let rtl = match direction {
+            TextDirection::Ltr => false,
+            TextDirection::Rtl => true,
+            TextDirection::Auto => {
+                let text = slide
+                    .iter()
+                    .filter_map(|request| request.insert_text.as_ref())
+                    .map(|insert| insert.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                textmetrics::is_mostly_rtl(&text)
+            }
+        };
+        for request in slide {
+            let update = request
+                .insert_text
+                .as_ref()
+                .filter(|_| rtl)
+                .map(right_to_left);
+            directed.push(request);
+            directed.extend(update);
+        }
+    }
+    directed
+}
+
+/// Makes the inserted text's paragraphs right-to-left. `START` alignment is
+/// the right edge for right-to-left paragraphs.
+fn right_to_left(insert: &InsertTextRequest) -> UpdateRequest {
+    UpdateRequest {
+        update_paragraph_style: Some(UpdateParagraphStyleRequest {
+            object_id: insert.object_id.clone(),
+            cell_location: insert.cell_location.clone(),
+            style: ParagraphStyle {
+                direction: "RIGHT_TO_LEFT".to_string(),
+                alignment: "START".to_string(),
+            },
+            text_range: TextRange {
+                range_type: "FIXED_RANGE".to_string(),
+                start_index: insert.insertion_index as usize,
+                end_index: insert.insertion_index as usize + textmetrics::utf16_len(&insert.text),
+            },
+            fields: "direction,alignment".to_string(),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Sets a solid background color on each of the given slides.
+fn background_requests(slide_ids: &[String], rgb_color: RgbColor) -> Vec<UpdateRequest> {
+    slide_ids
+        .iter()
+        .map(|slide_id| UpdateRequest {
+            update_page_properties: Some(UpdatePagePropertiesRequest {
+                object_id: slide_id.clone(),
+                page_properties: PageProperties {
+                    page_background_fill: PageBackgroundFill {
+                        solid_fill: SolidFill {
+                            color: OpaqueColor {
+                                rgb_color: rgb_color.clone(),
+                            },
+                        },
+                    },
+                },
+                fields: "pageBackgroundFill.solidFill.color".to_string(),
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Converts the request style to the API's, with the `fields` mask naming
+/// exactly the fields that are set; fields outside the mask are left alone.
+fn api_text_style(style: &TextStyle) -> (ApiTextStyle, String) {
+    let api_style = ApiTextStyle {
+        font_size: style.font_size_pt.map(|magnitude| Dimension {
+            magnitude,
+            unit: "PT".to_string(),
+        }),
+        font_family: style.font_family.clone(),
+        bold: style.bold,
+        italic: None,
+        link: None,
+        foreground_color: style
+            .foreground_color
+            .as_deref()
+            .and_then(parse_hex_color)
+            .map(|rgb_color| OptionalColor {
+                opaque_color: OpaqueColor { rgb_color },
+            }),
+    };
+    let fields = [
+        ("fontSize", api_style.font_size.is_some()),
+        ("fontFamily", api_style.font_family.is_some()),
+        ("bold", api_style.bold.is_some()),
+        ("foregroundColor", api_style.foreground_color.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, set)| *set)
+    .map(|(field, _)| field)
+    .collect::<Vec<_>>()
+    .join(",");
+    (api_style, fields)
+}
+
+/// Font used for `` `code` `` spans in rich text.
+const CODE_FONT_FAMILY: &str = "Courier New";
+
+/// Which inline formatting to render in slide text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct InlineOptions {
+    /// `**bold**`, `*italic*` and `` `code` ``, from `rich_text`.
+    emphasis: bool,
+    /// Bare URLs and `[label](url)` links, from `link_detection`.
+    links: bool,
+}
+
+impl InlineOptions {
+    fn any(self) -> bool {
+        self.emphasis || self.links
+    }
+}
+
+/// Inline formatting applied to a range of slide text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InlineStyle {
+    Bold,
+    Italic,
+    Code,
+    Link(String),
+}
+
+/// A styled span of the plain text, in UTF-16 code units as the API counts.
+#[derive(Debug, PartialEq, Eq)]
+struct StyledRange {
+    start: usize,
+    end: usize,
+    style: InlineStyle,
+}
+
+/// Removes inline Markdown markers, returning the plain text and the ranges
+/// to style.
+///
+/// Bare URLs are linked as they are. Emphasis markers only count when they
+/// enclose text that neither starts nor ends with whitespace, so `2 * 3 * 4`
+/// stays as written. Markers nested inside a styled span are removed and the
+/// outer style wins, except that links keep working inside emphasis.
+fn parse_inline(text: &str, options: InlineOptions) -> (String, Vec<StyledRange>) {
+    let mut plain = String::with_capacity(text.len());
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    let mut rest = text;
+    let mut previous: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        let url = match previous {
+            Some(previous) if previous.is_alphanumeric() => None,
+            _ if options.links => bare_url(rest),
+            _ => None,
+        };
+        let span = match url {
+            Some(url) => Some((InlineStyle::Link(url.to_string()), url, &rest[url.len()..])),
+            None => inline_span(rest, options),
+        };
+        if let Some((style, content, after)) = span {
+            let (content, nested) = match style {
+                InlineStyle::Code => (content.to_string(), Vec::new()),
+                InlineStyle::Link(_) if url.is_some() => (content.to_string(), Vec::new()),
+                _ => parse_inline(content, options),
+            };
+            let start = offset;
+            offset += textmetrics::utf16_len(&content);
+            previous = content.chars().last();
+            plain.push_str(&content);
+            ranges.push(StyledRange {
+                start,
+                end: offset,
+                style,
+            });
+            // Links inside emphasis stay clickable
+            ranges.extend(
+                nested
+                    .into_iter()
+                    .filter(|range| matches!(range.style, InlineStyle::Link(_)))
+                    .map(|range| StyledRange {
+                        start: start + range.start,
+                        end: start + range.end,
+                        ..range
+                    }),
+            );
+            rest = after;
+        } else {
+            plain.push(c);
+            offset += c.len_utf16();
+            previous = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    (plain, ranges)
+}
+
+/// Matches a Markdown link or emphasized span at the start of the text,
+/// returning its style, its content and the text after it.
+fn inline_span(text: &str, options: InlineOptions) -> Option<(InlineStyle, &str, &str)> {
+    if options.links && text.starts_with('[') {
+        return markdown_link(text);
+    }
+    if !options.emphasis {
+        return None;
+    }
+    if let Some(after) = text.strip_prefix('`') {
+        let end = after.find('`')?;
+        return (end > 0).then(|| (InlineStyle::Code, &after[..end], &after[end + 1..]));
+    }
+    if let Some(after) = text.strip_prefix("**") {
+        return after
+            .match_indices("**")
+            .map(|(end, _)| end)
+            .find(|&end| is_emphasis_content(&after[..end]))
+            .map(|end| (InlineStyle::Bold, &after[..end], &after[end + 2..]));
+    }
+    if let Some(after) = text.strip_prefix('*') {
+        // A lone `*` closes italics; one that is part of `**` does not
+        let bytes = after.as_bytes();
+        return after
+            .match_indices('*')
+            .map(|(end, _)| end)
+            .filter(|&end| {
+                bytes.get(end + 1) != Some(&b'*') && (end == 0 || bytes[end - 1] != b'*')
+            })
+            .find(|&end| is_emphasis_content(&after[..end]))
+            .map(|end| (InlineStyle::Italic, &after[..end], &after[end + 1..]));
+    }
+    None
+}
+
+fn is_emphasis_content(content: &str) -> bool {
+    !content.is_empty()
+        && !content.starts_with(char::is_whitespace)
+        && !content.ends_with(char::is_whitespace)
+}
+
+/// Matches `[label](url)` with a non-empty label and an http(s) URL.
+fn markdown_link(text: &str) -> Option<(InlineStyle, &str, &str)> {
+    let (label, rest) = text.strip_prefix('[')?.split_once("](")?;
+    let (url, after) = rest.split_once(')')?;
+    let is_link = !label.trim().is_empty()
+        && !label.contains(['[', '\n'])
+        && bare_url(url).is_some_and(|bare| bare.len() == url.len());
+    is_link.then(|| (InlineStyle::Link(url.to_string()), label, after))
+}
+
+/// Matches an http(s) URL, leaving out trailing punctuation that most
+/// likely ends the sentence rather than the URL.
+fn bare_url(text: &str) -> Option<&str> {
+    let scheme = ["https://", "http://"]
+        .into_iter()
+        .find(|scheme| text.starts_with(scheme))?;
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let mut url = &text[..end];
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'']);
+        // Keep a closing parenthesis only when the URL opened one
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(inner) if inner.matches('(').count() < trimmed.matches(')').count() => inner,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    (url.len() > scheme.len()).then_some(url)
+}
+
+/// Inserts each text without its Markdown markers, followed by an
+/// `updateTextStyle` for every styled range.
+///
+/// Bullets created on an inserted text are moved to the same paragraphs of
+/// the text without markers; removing markers never removes a line break.
+fn with_inline_formatting(
+    requests: Vec<UpdateRequest>,
+    options: InlineOptions,
+) -> Vec<UpdateRequest> {
+    let mut rendered = Vec::with_capacity(requests.len());
+    let mut last_insert: Option<(String, String, String)> = None;
+    for mut request in requests {
+        match (request.create_paragraph_bullets.as_mut(), &last_insert) {
+            (Some(bullets), Some((object_id, original, plain)))
+                if *object_id == bullets.object_id =>
+            {
+                let range = &mut bullets.text_range;
+                (range.start_index, range.end_index) =
+                    same_paragraphs(original, plain, range.start_index, range.end_index);
+            }
+            _ => {}
+        }
+
+        let mut updates = Vec::new();
+        if let Some(insert) = request.insert_text.as_mut() {
+            let (plain, ranges) = parse_inline(&insert.text, options);
+            let original = std::mem::replace(&mut insert.text, plain.clone());
+            last_insert = Some((insert.object_id.clone(), original, plain));
+            let base = insert.insertion_index as usize;
+            updates.extend(ranges.into_iter().map(|range| {
+                let (style, fields) = inline_style(range.style);
+                UpdateRequest {
+                    update_text_style: Some(UpdateTextStyleRequest {
+                        object_id: insert.object_id.clone(),
+                        cell_location: insert.cell_location.clone(),
+                        style,
+                        text_range: TextRange {
+                            range_type: "FIXED_RANGE".to_string(),
+                            start_index: base + range.start,
+                            end_index: base + range.end,
+                        },
+                        fields: fields.to_string(),
+                    }),
+                    ..Default::default()
+                }
+            }));
+        }
+        rendered.push(request);
+        rendered.extend(updates);
+    }
+    rendered
+}
+
+/// Maps a range covering whole paragraphs of one text to the same paragraphs
+/// of another text with as many lines.
+fn same_paragraphs(from: &str, to: &str, start: usize, end: usize) -> (usize, usize) {
+    let from_lines = line_ranges(from);
+    let to_lines = line_ranges(to);
+    let line_of = |index: usize| {
+        from_lines
+            .iter()
+            .position(|&(line_start, line_end)| line_start <= index && index <= line_end)
+            .unwrap_or(from_lines.len() - 1)
+    };
+    (to_lines[line_of(start)].0, to_lines[line_of(end)].1)
+}
+
+/// Start and end of every line, in UTF-16 code units, excluding line breaks.
+fn line_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut offset = 0;
+    text.split('\n')
+        .map(|line| {
+            let start = offset;
+            let end = start + textmetrics::utf16_len(line);
+            offset = end + 1;
+            (start, end)
+        })
+        .collect()
+}
+
+/// Bullet style used for Markdown list items.
+const BULLET_PRESET: &str = "BULLET_DISC_CIRCLE_SQUARE";
+
+/// Deepest nesting level the Slides API supports for bullets.
+const MAX_BULLET_LEVEL: usize = 8;
+
+/// Replaces `- ` and `* ` list markers with one tab per nesting level, as
+/// `createParagraphBullets` expects, and returns the ranges of consecutive
+/// list paragraphs in UTF-16 code units.
+///
+/// Nesting follows indentation: a list line indented further than the one
+/// before it goes one level deeper. Other lines are left as they are.
+fn bulleted_text(text: &str) -> (String, Vec<(usize, usize)>) {
+    let mut output = Vec::new();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut indents: Vec<usize> = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        let item = list_item(line);
+        let line = match item {
+            Some((indent, content)) => {
+                while indents.last().is_some_and(|&last| last > indent) {
+                    indents.pop();
+                }
+                if indents.last().is_none_or(|&last| last < indent) {
+                    indents.push(indent);
+                }
+                let level = (indents.len() - 1).min(MAX_BULLET_LEVEL);
+                format!("{}{}", "\t".repeat(level), content)
+            }
+            None => {
+                indents.clear();
+                line.to_string()
+            }
+        };
+
+        let start = offset;
+        let end = start + textmetrics::utf16_len(&line);
+        offset = end + 1;
+        if item.is_some() {
+            match ranges.last_mut() {
+                Some(range) if range.1 + 1 == start => range.1 = end,
+                _ => ranges.push((start, end)),
+            }
+        }
+        output.push(line);
+    }
+    (output.join("\n"), ranges)
+}
+
+/// Splits a `- ` or `* ` list line into its indentation width and content.
+fn list_item(line: &str) -> Option<(usize, &str)> {
+    let content = line.trim_start_matches([' ', '\t']);
+    let indent = line[..line.len() - content.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let content = content
+        .strip_prefix("- ")
+        .or_else(|| content.strip_prefix("* "))?;
+    Some((indent, content))
+}
+
+fn create_bullets(object_id: &str, start: usize, end: usize) -> UpdateRequest {
+    UpdateRequest {
+        create_paragraph_bullets: Some(CreateParagraphBulletsRequest {
+            object_id: object_id.to_string(),
+            text_range: TextRange {
+                range_type: "FIXED_RANGE".to_string(),
+                start_index: start,
+                end_index: end,
+            },
+            bullet_preset: BULLET_PRESET.to_string(),
+        }),
+        ..Default::default()
+    }
+}
+
+/// The text style and `fields` mask that render one inline style.
+fn inline_style(style: InlineStyle) -> (ApiTextStyle, &'static str) {
+    match style {
+        InlineStyle::Bold => (
+            ApiTextStyle {
+                bold: Some(true),
+                ..Default::default()
+            },
+            "bold",
+        ),
+        InlineStyle::Italic => (
+            ApiTextStyle {
+                italic: Some(true),
+                ..Default::default()
+            },
+            "italic",
+        ),
+        InlineStyle::Code => (
+            ApiTextStyle {
+                font_family: Some(CODE_FONT_FAMILY.to_string()),
+                ..Default::default()
+            },
+            "fontFamily",
+        ),
+        InlineStyle::Link(url) => (
+            ApiTextStyle {
+                link: Some(Link { url }),
+                ..Default::default()
+            },
+            "link",
+        ),
+    }
+}
+
+/// Default 16:9 slide size, in EMU.
+const SLIDE_WIDTH_EMU: f64 = 9_144_000.0;
+const SLIDE_HEIGHT_EMU: f64 = 5_143_500.0;
+
+/// Space left around an image or table on its slide, in EMU (half an inch).
+const SLIDE_MARGIN_EMU: f64 = 457_200.0;
+
+/// Image formats Google Slides can insert.
+const IMAGE_EXTENSIONS: &[&str] = &[".png", ".jpg", ".jpeg", ".gif"];
+
+/// Finds an image that makes up a chunk: either a bare image URL that is the
+/// whole text, or a `![alt](url)` line at its start. Returns the URL and any
+/// text after the Markdown image.
+///
+/// Only http(s) URLs ending in a supported image extension and `drive:<fileId>`
+/// references count; anything else, including `data:` URLs, which Google
+/// cannot fetch, stays text.
+fn image_url(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim();
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let first = first.trim();
+    let url = match first.strip_prefix("![") {
+        Some(image) => image.split_once("](")?.1.strip_suffix(')')?,
+        None if rest.trim().is_empty() => first,
+        None => return None,
+    };
+    (is_image_url(url) || drive::file_id(url).is_some()).then(|| (url, rest.trim()))
+}
+
+fn is_image_url(url: &str) -> bool {
+    let Some(path) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let path = path
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(path)
+        .to_ascii_lowercase();
+    !url.contains(char::is_whitespace)
+        && path.contains('/')
+        && IMAGE_EXTENSIONS
+            .iter()
+            .any(|extension| path.ends_with(extension))
+}
+
+/// Finds a YouTube video that makes up a chunk: a `youtube.com/watch?v=` or
+/// `youtu.be/` URL that is the whole text. Returns the video ID and the start
+/// offset from a `t=` or `start=` parameter.
+///
+/// Other video URLs are not recognized and stay text, where link detection
+/// makes them clickable.
+fn youtube_video(text: &str) -> Option<(&str, Option<u32>)> {
+    let url = text.trim();
+    if url.contains(char::is_whitespace) {
+        return None;
+    }
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let params = || query.split('&').filter_map(|param| param.split_once('='));
+    let id = match path.split_once('/')? {
+        ("youtu.be", id) => id,
+        ("youtube.com" | "www.youtube.com" | "m.youtube.com", "watch") => {
+            params().find(|(key, _)| *key == "v")?.1
+        }
+        _ => return None,
+    };
+    let is_video_id = id.len() == YOUTUBE_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_video_id {
+        return None;
+    }
+    let start = params()
+        .find(|(key, _)| *key == "t" || *key == "start")
+        .and_then(|(_, value)| youtube_timestamp(value));
+    Some((id, start))
+}
+
+/// Length of a YouTube video ID.
+const YOUTUBE_ID_LENGTH: usize = 11;
+
+/// Parses a YouTube start time, either plain seconds (`90`, `90s`) or
+/// hours, minutes and seconds (`1h2m3s`, `1m30s`), into seconds.
+fn youtube_timestamp(value: &str) -> Option<u32> {
+    if let Ok(seconds) = value.parse() {
+        return Some(seconds);
+    }
+    let mut total: u32 = 0;
+    let mut digits = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let amount: u32 = digits.parse().ok()?;
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        digits.clear();
+    }
+    (!value.is_empty() && digits.is_empty()).then_some(total)
+}
+
+/// Builds the `createVideo` request for a video slide, followed by an
+/// `updateVideoProperties` request that sets where playback starts.
+fn video_requests(
+    slide_id: &str,
+    video_id: &str,
+    start: Option<u32>,
+    page: (f64, f64),
+) -> Vec<UpdateRequest> {
+    let object_id = format!("{}_video", slide_id);
+    let mut requests = vec![UpdateRequest {
+        create_video: Some(CreateVideoRequest {
+            object_id: object_id.clone(),
+            element_properties: video_element(slide_id, page),
+            source: "YOUTUBE".to_string(),
+            id: video_id.to_string(),
+        }),
+        ..Default::default()
+    }];
+    if let Some(start) = start {
+        requests.push(UpdateRequest {
+            update_video_properties: Some(UpdateVideoPropertiesRequest {
+                object_id,
+                video_properties: VideoProperties { start },
+                fields: "start".to_string(),
+            }),
+            ..Default::default()
+        });
+    }
+    requests
+}
+
+/// Places a 16:9 video, as large as fits inside the slide's margins, in the
+/// middle of a page of `(width, height)` EMU.
+fn video_element(slide_id: &str, (page_width, page_height): (f64, f64)) -> PageElementProperties {
+    let max_width = page_width - 2.0 * SLIDE_MARGIN_EMU;
+    let max_height = page_height - 2.0 * SLIDE_MARGIN_EMU;
+    let width = max_width.min(max_height * 16.0 / 9.0);
+    let height = width * 9.0 / 16.0;
+    PageElementProperties {
+        page_object_id: slide_id.to_string(),
+        size: Size {
+            width: emu(width),
+            height: emu(height),
+        },
+        transform: AffineTransform {
+            scale_x: 1.0,
+            scale_y: 1.0,
+            translate_x: (page_width - width) / 2.0,
+            translate_y: (page_height - height) / 2.0,
+            unit: "EMU".to_string(),
+        },
+    }
+}
+
+/// Builds one `createImage` request per image slide, paired with the slide's
+/// index. Each image is fitted inside the slide's margins, keeping its aspect
+/// ratio.
+fn image_requests(chunks: &[Chunk], id_prefix: &str) -> Vec<(usize, UpdateRequest)> {
+    chunks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, chunk)| {
+            let Some(Media::Image(url)) = &chunk.media else {
+                return None;
+            };
+            let slide_id = slide_id(id_prefix, index);
+            let request = UpdateRequest {
+                create_image: Some(CreateImageRequest {
+                    object_id: format!("{}_image", slide_id),
+                    url: url.clone(),
+                    element_properties: fitted_element(slide_id),
+                }),
+                ..Default::default()
+            };
+            Some((index, request))
+        })
+        .collect()
+}
+
+/// Places an element so it fills the slide inside its margins.
+fn fitted_element(page_object_id: String) -> PageElementProperties {
+    PageElementProperties {
+        page_object_id,
+        size: Size {
+            width: emu(SLIDE_WIDTH_EMU - 2.0 * SLIDE_MARGIN_EMU),
+            height: emu(SLIDE_HEIGHT_EMU - 2.0 * SLIDE_MARGIN_EMU),
+        },
+        transform: AffineTransform {
+            scale_x: 1.0,
+            scale_y: 1.0,
+            translate_x: SLIDE_MARGIN_EMU,
+            translate_y: SLIDE_MARGIN_EMU,
+            unit: "EMU".to_string(),
+        },
+    }
+}
+
+/// Most rows, and most columns, a table slide may have.
+const MAX_TABLE_SIZE: usize = 20;
+
+/// Parses a chunk that is nothing but a pipe-delimited Markdown table into
+/// its rows of cells, header first.
+///
+/// The second line must be the `|---|---|` separator, which is not part of
+/// the data. Rows shorter than the widest row are padded with empty cells.
+fn markdown_table(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines = text.trim().lines().map(str::trim).collect::<Vec<_>>();
+    if lines.len() < 2 || !lines.iter().all(|line| line.contains('|')) {
+        return None;
+    }
+    let is_separator = table_cells(lines[1]).iter().all(|cell| {
+        let dashes = cell.strip_prefix(':').unwrap_or(cell);
+        let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    });
+    if !is_separator {
+        return None;
+    }
+
+    let mut rows = std::iter::once(lines[0])
+        .chain(lines[2..].iter().copied())
+        .map(table_cells)
+        .collect::<Vec<_>>();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(columns, String::new());
+    }
+    Some(rows)
+}
+
+/// Splits a table row into trimmed cells at unescaped pipes, ignoring the
+/// optional pipes at either end.
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(inner) if !inner.ends_with('\\') => inner,
+        _ => line,
+    };
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cells.last_mut().unwrap().push('|');
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// Rejects a table too large to read on one slide.
+fn check_table_size(index: usize, rows: &[Vec<String>]) -> std::result::Result<(), AppError> {
+    let columns = rows.first().map_or(0, Vec::len);
+    if rows.len() > MAX_TABLE_SIZE || columns > MAX_TABLE_SIZE {
+        return Err(AppError::InvalidRequest(format!(
+            "Table on slide {} is {}x{}; tables can have at most {} rows and {} columns",
+            index + 1,
+            rows.len(),
+            columns,
+            MAX_TABLE_SIZE,
+            MAX_TABLE_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the `createTable` request for a table slide, followed by one
+/// `insertText` per non-empty cell.
+fn table_requests(slide_id: &str, rows: &[Vec<String>]) -> Vec<UpdateRequest> {
+    let table_id = format!("{}_table", slide_id);
+    let mut requests = vec![UpdateRequest {
+        create_table: Some(CreateTableRequest {
+            object_id: table_id.clone(),
+            element_properties: fitted_element(slide_id.to_string()),
+            rows: rows.len() as i32,
+            columns: rows.first().map_or(0, Vec::len) as i32,
+        }),
+        ..Default::default()
+    }];
+    for (row_index, row) in rows.iter().enumerate() {
+        for (column_index, cell) in row.iter().enumerate() {
+            // The API rejects inserting empty text.
+            if cell.is_empty() {
+                continue;
+            }
+            let mut request = insert_text(table_id.clone(), cell);
+            if let Some(insert) = request.insert_text.as_mut() {
+                insert.cell_location = Some(TableCellLocation {
+                    row_index: row_index as i32,
+                    column_index: column_index as i32,
+                });
+            }
+            requests.push(request);
+        }
+    }
+    requests
+}
+
+fn emu(magnitude: f64) -> Dimension {
+    Dimension {
+        magnitude,
+        unit: "EMU".to_string(),
+    }
+}
+
+/// Builds the requests that write each chunk's notes into its slide's
+/// speaker notes shape.
+fn notes_requests(chunks: &[Chunk], slides: &[Slide], id_prefix: &str) -> Vec<UpdateRequest> {
+    chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| !chunk.notes.is_empty())
+        .filter_map(|(index, chunk)| {
+            let slide_id = slide_id(id_prefix, index);
+            let slide = slides.iter().find(|slide| slide.object_id == slide_id)?;
+            let notes_page = slide.slide_properties.notes_page.as_ref()?;
+            Some(insert_text(
+                notes_page.notes_properties.speaker_notes_object_id.clone(),
+                &chunk.notes,
+            ))
+        })
+        .collect()
+}
+
+/// Maps a failed batchUpdate response to an application error.
+///
+/// A 404 means the presentation was deleted (e.g. from Drive in another tab)
+/// after it was created, which is reported distinctly from other failures.
+fn batch_update_error(status: u16, error_text: &str) -> AppError {
+    if status == 404 {
+        AppError::PresentationDeleted
+    } else {
+        AppError::GoogleSlides(format!("Failed to update slides: {error_text}"))
+    }
+}
+
+/// Maps a failed fetch of a presentation to an application error.
+///
+/// A 404 here usually means the `presentation_id` the user passed is wrong or
+/// not shared with them, which is reported differently from a presentation
+/// that disappears once slides are being added.
+fn get_presentation_error(status: u16, presentation_id: &str, error_text: &str) -> AppError {
+    if status == 404 {
+        AppError::PresentationNotFound(presentation_id.to_string())
+    } else {
+        AppError::GoogleSlides(format!("Failed to fetch presentation: {error_text}"))
+    }
+}
+
+/// Reports a presentation that can no longer be found, after it was already
+/// fetched or created, as deleted in the meantime.
+fn deleted_since_fetched(error: AppError) -> AppError {
+    match error {
+        AppError::PresentationNotFound(_) => AppError::PresentationDeleted,
+        error => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::resolve;
+    use rstest::rstest;
+
+    /// Drive client that answers every delete with `result`.
+    struct FakeDrive {
+        result: std::result::Result<(), &'static str>,
+        deleted: std::cell::RefCell<Vec<String>>,
+    }
+
+    impl FakeDrive {
+        fn new(result: std::result::Result<(), &'static str>) -> Self {
+            Self {
+                result,
+                deleted: Default::default(),
+            }
+        }
+    }
+
+    impl FileDeleter for FakeDrive {
+        async fn delete_file(&self, file_id: &str) -> Result<()> {
+            self.deleted.borrow_mut().push(file_id.to_string());
+            self.result.map_err(worker::Error::from)
+        }
+    }
+
+    /// Slides client that records every call. `failing_batch` is the index
+    /// of the batchUpdate call to fail, counting from 0, with the HTTP status
+    /// `failing_status`.
+    struct FakeSlides {
+        failing_batch: Option<usize>,
+        failing_status: u16,
+        /// Presentation that every fetch reports as not found.
+        missing_presentation: Option<&'static str>,
+        /// Object IDs of the slides every fetched presentation has.
+        slides: &'static [&'static str],
+        share_result: std::result::Result<(), &'static str>,
+        /// Bodies of the presentations.create calls.
+        presentations: std::cell::RefCell<Vec<serde_json::Value>>,
+        batches: std::cell::RefCell<Vec<serde_json::Value>>,
+        calls: std::cell::RefCell<Vec<String>>,
+    }
+
+    impl FakeSlides {
+        fn new() -> Self {
+            Self {
+                failing_batch: None,
+                failing_status: 500,
+                missing_presentation: None,
+                slides: &["default_slide"],
+                share_result: Ok(()),
+                presentations: Default::default(),
+                batches: Default::default(),
+                calls: Default::default(),
+            }
+        }
+
+        fn record(&self, call: String) {
+            self.calls.borrow_mut().push(call);
+        }
+
+        /// Object IDs of the requests of `kind` sent in every batch, in order.
+        fn object_ids(&self, kind: &str) -> Vec<String> {
+            self.batches
+                .borrow()
+                .iter()
+                .flat_map(|batch| batch.as_array().unwrap().clone())
+                .filter_map(|request| Some(request[kind]["objectId"].as_str()?.to_string()))
+                .collect()
+        }
+    }
+
+    impl FileDeleter for FakeSlides {
+        async fn delete_file(&self, file_id: &str) -> Result<()> {
+            self.record(format!("delete {file_id}"));
+            Ok(())
+        }
+    }
+
+    impl SlidesApi for FakeSlides {
+        async fn create_presentation(
+            &self,
+            request: &CreatePresentationRequest,
+        ) -> std::result::Result<Presentation, AppError> {
+            self.record(format!("create {}", request.title));
+            self.presentations
+                .borrow_mut()
+                .push(serde_json::to_value(request).unwrap());
+            self.get_presentation("deck1").await
+        }
+
+        async fn get_presentation(
+            &self,
+            presentation_id: &str,
+        ) -> std::result::Result<Presentation, AppError> {
+            if self.missing_presentation == Some(presentation_id) {
+                return Err(get_presentation_error(404, presentation_id, "not found"));
+            }
+            Ok(Presentation {
+                presentation_id: presentation_id.to_string(),
+                title: "Deck".to_string(),
+                slides: self.slides.iter().map(|id| slide(id)).collect(),
+                page_size: None,
+            })
+        }
+
+        async fn batch_update(
+            &self,
+            presentation_id: &str,
+            requests: Vec<UpdateRequest>,
+        ) -> std::result::Result<BatchUpdateResponse, AppError> {
+            self.record(format!("batch {presentation_id}"));
+            let mut batches = self.batches.borrow_mut();
+            if self.failing_batch == Some(batches.len()) {
+                return Err(batch_update_error(self.failing_status, "quota"));
+            }
+            batches.push(serde_json::to_value(requests).unwrap());
+            Ok(BatchUpdateResponse {
+                replies: Vec::new(),
+            })
+        }
+
+        async fn copy_file(
+            &self,
+            file_id: &str,
+            _name: Option<&str>,
+        ) -> std::result::Result<String, AppError> {
+            self.record(format!("copy {file_id}"));
+            Ok("copy1".to_string())
+        }
+
+        async fn share_file(
+            &self,
+            file_id: &str,
+            email: &str,
+            _role: ShareRole,
+            _send_notification_email: bool,
+        ) -> Result<()> {
+            self.record(format!("share {file_id} {email}"));
+            self.share_result.map_err(worker::Error::from)
+        }
+
+        async fn image_url(&self, file_id: &str) -> Result<String> {
+            Ok(format!("https://example.com/{file_id}.png"))
+        }
+    }
+
+    #[rstest]
+    #[case::deleted(
+        Ok(()),
+        true,
+        &["deck1"],
+        "Google Slides API error: Failed to update slides: quota; the partially created presentation was deleted"
+    )]
+    #[case::delete_failed(
+        Err("forbidden"),
+        true,
+        &["deck1"],
+        "Google Slides API error: Failed to update slides: quota; the partially created presentation deck1 could not be deleted: forbidden"
+    )]
+    #[case::cleanup_disabled(
+        Ok(()),
+        false,
+        &[],
+        "Google Slides API error: Failed to update slides: quota; the partially created presentation deck1 was kept"
+    )]
+    fn test_discard_presentation_reports_cleanup(
+        #[case] result: std::result::Result<(), &'static str>,
+        #[case] cleanup: bool,
+        #[case] deleted: &[&str],
+        #[case] message: &str,
+    ) {
+        let drive = FakeDrive::new(result);
+        let error = batch_update_error(500, "quota");
+        let error = resolve(discard_presentation(&drive, "deck1", error, cleanup));
+        assert_eq!(error.to_string(), message);
+        // The cleanup outcome does not change what kind of failure it was
+        assert_eq!(error.code(), "google_api_error");
+        assert_eq!(*drive.deleted.borrow(), deleted);
+    }
+
+    fn slide(object_id: &str) -> Slide {
+        Slide {
+            object_id: object_id.to_string(),
+            slide_properties: SlideProperties {
+                layout_object_id: "layout".to_string(),
+                notes_page: None,
+            },
+        }
+    }
+
+    #[rstest]
+    fn test_create_slides_sends_requests_for_chunks() {
+        let api = FakeSlides::new();
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro\nDemo","splitter":{"type":"newline"}}"#,
+        );
+        let result = resolve(create_slides(&api, &request, &Limits::default())).unwrap();
+
+        // Slides first, then what fills them, then the default slide goes
+        assert_eq!(
+            *api.calls.borrow(),
+            ["create Deck", "batch deck1", "batch deck1", "batch deck1"]
+        );
+        assert_eq!(api.object_ids("deleteObject"), ["default_slide"]);
+        assert_eq!(api.object_ids("createSlide"), ["slide_0", "slide_1"]);
+        assert_eq!(
+            api.object_ids("insertText"),
+            ["slide_0_body", "slide_1_body"]
+        );
+        assert_eq!(api.object_ids("deleteObject"), ["default_slide"]);
+        assert_eq!(result.presentation_id, "deck1");
+        assert_eq!(result.slide_count, 2);
+        let created: Vec<_> = result
+            .created_slides
+            .iter()
+            .map(|slide| slide.object_id.as_str())
+            .collect();
+        assert_eq!(created, ["slide_0", "slide_1"]);
+    }
+
+    #[rstest]
+    fn test_create_slides_skips_marked_chunks() {
+        let api = FakeSlides::new();
+        let request = parse_request(r#"{"title":"Deck","content":["[skip]\nDraft","Shown"]}"#);
+        let result = resolve(create_slides(&api, &request, &Limits::default())).unwrap();
+
+        assert_eq!(result.skipped_slides, 1);
+        assert_eq!(api.object_ids("updateSlideProperties"), ["slide_0"]);
+        let batches = api.batches.borrow();
+        let texts: Vec<_> = batches
+            .iter()
+            .flat_map(|batch| batch.as_array().unwrap())
+            .filter_map(|request| request["insertText"]["text"].as_str())
+            .collect();
+        assert_eq!(texts, ["Draft", "Shown"]);
+    }
+
+    #[rstest]
+    fn test_slide_requests_skip_marked_slides() {
+        let chunks = [
+            Chunk {
+                skipped: true,
+                ..Chunk::untitled("Draft".to_string())
+            },
+            Chunk::untitled("Shown".to_string()),
+        ];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let json = serde_json::to_value(&requests).unwrap();
+        assert_eq!(
+            json[1],
+            serde_json::json!({
+                "updateSlideProperties": {
+                    "objectId": "slide_0",
+                    "slideProperties": {"isSkipped": true},
+                    "fields": "isSkipped"
+                }
+            })
+        );
+        assert!(requests[1].continues_previous());
+        let skipped = requests
+            .iter()
+            .filter(|request| request.update_slide_properties.is_some())
+            .count();
+        assert_eq!(skipped, 1);
+    }
+
+    #[rstest]
+    fn test_create_decks_reports_each_deck() {
+        let api = FakeSlides::new();
+        let request: CreateSlidesBatchRequest = serde_json::from_str(
+            r#"{"decks":[
+                {"title":"One","content":["a"]},
+                {"title":"Two","content":["b"],"locale":"x"},
+                {"title":"Four","content":["d"]}
+            ]}"#,
+        )
+        .unwrap();
+        let results = resolve(create_decks(&api, &request, &Limits::default())).unwrap();
+
+        let titles: Vec<_> = results
+            .iter()
+            .map(|result| result.as_ref().map(|deck| deck.title.as_str()).ok())
+            .collect();
+        assert_eq!(titles, [Some("One"), None, Some("Four")]);
+        let err = results[1].as_ref().unwrap_err().to_string();
+        assert!(err.contains("locale"), "{err}");
+        let creates: Vec<_> = api
+            .calls
+            .borrow()
+            .iter()
+            .filter(|call| call.starts_with("create"))
+            .cloned()
+            .collect();
+        assert_eq!(creates, ["create One", "create Four"]);
+    }
+
+    #[rstest]
+    #[case::rate_limited(429, 1, Some(500))]
+    #[case::server_error(503, 2, Some(1_000))]
+    #[case::backs_off(500, 3, Some(2_000))]
+    #[case::last_attempt(429, BATCH_UPDATE_ATTEMPTS, None)]
+    #[case::success(200, 1, None)]
+    #[case::bad_request(400, 1, None)]
+    #[case::deleted(404, 1, None)]
+    fn test_retry_delay(#[case] status: u16, #[case] attempt: u32, #[case] delay_ms: Option<u64>) {
+        assert_eq!(
+            retry_delay(status, attempt),
+            delay_ms.map(Duration::from_millis)
+        );
+    }
+
+    #[rstest]
+    fn test_create_decks_rejects_dry_run_before_creating() {
+        let api = FakeSlides::new();
+        let request: CreateSlidesBatchRequest = serde_json::from_str(
+            r#"{"decks":[
+                {"title":"One","content":["a"]},
+                {"title":"Two","content":["b"],"dry_run":true}
+            ]}"#,
+        )
+        .unwrap();
+        let err = resolve(create_decks(&api, &request, &Limits::default())).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("decks[1]: dry_run"), "{err}");
+        assert!(api.calls.borrow().is_empty());
+    }
+
+    #[rstest]
+    #[case::empty(0)]
+    #[case::too_many(MAX_BATCH_DECKS + 1)]
+    fn test_create_decks_rejects_batch_size(#[case] count: usize) {
+        let api = FakeSlides::new();
+        let request = CreateSlidesBatchRequest {
+            decks: (0..count)
+                .map(|_| parse_request(r#"{"title":"Deck","content":["a"]}"#))
+                .collect(),
+        };
+        let err = resolve(create_decks(&api, &request, &Limits::default())).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("1 to 10 decks"), "{err}");
+        assert!(api.calls.borrow().is_empty());
+    }
+
+    #[rstest]
+    fn test_create_slides_copies_theme() {
+        let api = FakeSlides::new();
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro","splitter":{"type":"newline"},"theme_presentation_id":"theme1"}"#,
+        );
+        let result = resolve(create_slides(&api, &request, &Limits::default())).unwrap();
+        assert_eq!(
+            *api.calls.borrow(),
+            ["copy theme1", "batch copy1", "batch copy1", "batch copy1"]
+        );
+        assert_eq!(result.presentation_id, "copy1");
+        assert!(result.themed);
+    }
+
+    #[rstest]
+    fn test_create_slides_deletes_presentation_on_failure() {
+        let api = FakeSlides {
+            failing_batch: Some(0),
+            ..FakeSlides::new()
+        };
+        let request =
+            parse_request(r#"{"title":"Deck","content":"Intro","splitter":{"type":"newline"}}"#);
+        let err = resolve(create_slides(&api, &request, &Limits::default())).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("the partially created presentation was deleted")
+        );
+        assert_eq!(
+            *api.calls.borrow(),
+            ["create Deck", "batch deck1", "delete deck1"]
+        );
+    }
+
+    #[rstest]
+    fn test_create_slides_does_not_delete_deleted_presentation() {
+        let api = FakeSlides {
+            failing_batch: Some(1),
+            failing_status: 404,
+            ..FakeSlides::new()
+        };
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro\nDemo","splitter":{"type":"newline"}}"#,
+        );
+        let err = resolve(create_slides(&api, &request, &Limits::default())).unwrap_err();
+        assert!(matches!(err, AppError::PresentationDeleted), "{err}");
+        assert_eq!(err.status_code(), 404);
+        assert_eq!(
+            *api.calls.borrow(),
+            ["create Deck", "batch deck1", "batch deck1"]
+        );
+    }
+
+    #[rstest]
+    fn test_create_slides_keeps_replaced_slides_on_failure() {
+        // The first batch creates the slides, the second fills them
+        let api = FakeSlides {
+            failing_batch: Some(1),
+            slides: &["old_1", "old_2"],
+            ..FakeSlides::new()
+        };
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro\nDemo","splitter":{"type":"newline"},"mode":"replace","presentation_id":"existing"}"#,
+        );
+        let err = resolve(create_slides(&api, &request, &Limits::default())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Google Slides API error: batch 2 of 2 failed: Failed to update slides: quota"
+        );
+        assert_eq!(*api.calls.borrow(), ["batch existing", "batch existing"]);
+        assert!(api.object_ids("deleteObject").is_empty());
+    }
+
+    #[rstest]
+    fn test_create_slides_replaces_old_slides_last() {
+        let api = FakeSlides {
+            slides: &["slide_0", "slide_1"],
+            ..FakeSlides::new()
+        };
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro","splitter":{"type":"newline"},"mode":"replace","presentation_id":"existing"}"#,
+        );
+        let result = resolve(create_slides(&api, &request, &Limits::default())).unwrap();
+        // The old slides were made by an earlier run, so the new IDs differ
+        assert_eq!(api.object_ids("createSlide"), ["r1_slide_0"]);
+        assert_eq!(result.created_slides[0].object_id, "r1_slide_0");
+        let batches = api.batches.borrow();
+        assert_eq!(
+            batches.last().unwrap(),
+            &serde_json::json!([
+                {"deleteObject": {"objectId": "slide_0"}},
+                {"deleteObject": {"objectId": "slide_1"}}
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::new_presentation(&["p"], "")]
+    #[case::generated(&["slide_0", "p"], "r1_")]
+    #[case::title_slide(&["title_slide"], "r1_")]
+    #[case::closing_slide(&["closing_slide"], "r1_")]
+    #[case::refilled(&["r1_slide_0", "r1_closing_slide"], "")]
+    #[case::both(&["slide_0", "r1_slide_0"], "r2_")]
+    #[case::not_an_index(&["slide_", "slide_intro", "slide_1b"], "")]
+    fn test_slide_id_prefix_avoids_existing_slides(
+        #[case] existing: &[&str],
+        #[case] prefix: &str,
+    ) {
+        let slides: Vec<_> = existing.iter().map(|id| slide(id)).collect();
+        assert_eq!(slide_id_prefix(&slides), prefix);
+    }
+
+    #[rstest]
+    fn test_create_slides_sequential_batches_keep_slides_with_their_text() {
+        let api = FakeSlides::new();
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro\nDemo","splitter":{"type":"newline"},"sequential_batches":true}"#,
+        );
+        resolve(create_slides(&api, &request, &Limits::default())).unwrap();
+        assert_eq!(
+            *api.calls.borrow(),
+            ["create Deck", "batch deck1", "batch deck1"]
+        );
+        let batches = api.batches.borrow();
+        let kinds: Vec<_> = batches[0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|request| request.as_object().unwrap().keys().next().unwrap().clone())
+            .collect();
+        assert_eq!(kinds[..2], ["createSlide", "insertText"]);
+    }
+
+    #[rstest]
+    fn test_create_slides_reports_share_errors() {
+        let api = FakeSlides {
+            share_result: Err("forbidden"),
+            ..FakeSlides::new()
+        };
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro","splitter":{"type":"newline"},"share_with":["a@example.com"]}"#,
+        );
+        let result = resolve(create_slides(&api, &request, &Limits::default())).unwrap();
+        assert_eq!(result.share_errors.len(), 1);
+        assert_eq!(result.share_errors[0].email, "a@example.com");
+        assert!(
+            api.calls
+                .borrow()
+                .contains(&"share deck1 a@example.com".to_string())
+        );
+    }
+
+    #[rstest]
+    fn test_dry_run_builds_requests_without_google() {
+        let request = parse_request(
+            r##"{"title":"Deck","content":"# One\nFirst\n# Two\nSecond","splitter":{"type":"markdown_headings","max_level":1},"title_slide":{},"preset":"dark","dry_run":true}"##,
+        );
+        let result = dry_run(&request, &Limits::default()).unwrap();
+        assert_eq!(result.title, "Deck");
+        assert_eq!(result.slide_count, 2);
+        assert_eq!(result.slides[0].title.as_deref(), Some("One"));
+        assert_eq!(result.slides[1].body, "Second");
+        assert!(!result.batch_update_truncated);
+        let requests = result.batch_update["requests"].as_array().unwrap();
+        assert_eq!(requests.len(), result.request_count);
+        assert_eq!(requests[0]["createSlide"]["objectId"], "title_slide");
+        assert!(
+            requests
+                .iter()
+                .any(|request| request.get("updatePageProperties").is_some())
+        );
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+    }
+
+    #[rstest]
+    fn test_dry_run_serializes_slide_stats_inline() {
+        let request = parse_request(r#"{"title":"Deck","content":["a b c"],"dry_run":true}"#);
+        let json = serde_json::to_value(dry_run(&request, &Limits::default()).unwrap()).unwrap();
+        assert_eq!(
+            json["slides"][0],
+            serde_json::json!({"index": 0, "words": 3, "chars": 5, "title": null, "body": "a b c", "notes": ""})
+        );
+    }
+
+    #[rstest]
+    #[case::invalid(r#"{"title":"Deck","content":[]}"#, "at least one slide")]
+    #[case::over_limit(
+        r#"{"title":"Deck","content":["a","b","c"],"max_slides":2}"#,
+        "Too many slides: 3 (max 2)"
+    )]
+    fn test_dry_run_applies_request_checks(#[case] json: &str, #[case] message: &str) {
+        let err = dry_run(&parse_request(json), &Limits::default()).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains(message), "{err}");
+    }
+
+    #[rstest]
+    fn test_dry_run_warns_about_what_it_leaves_out() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":["https://example.com/a.png","Text\n???\nnotes","c"],"max_slides":2,"overflow":"truncate"}"#,
+        );
+        let warnings = dry_run(&request, &Limits::default()).unwrap().warnings;
+        assert_eq!(
+            warnings,
+            [
+                "1 chunk(s) beyond max_slides were dropped",
+                "images are inserted in later requests, not shown in batch_update",
+                "speaker notes are added in a later request, not shown in batch_update",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::fits(10_000, 3, false)]
+    #[case::cut(120, 1, true)]
+    #[case::nothing_fits(10, 0, true)]
+    fn test_truncated_batch_update(
+        #[case] max_bytes: usize,
+        #[case] kept: usize,
+        #[case] truncated: bool,
+    ) {
+        let requests = ["a", "b", "c"].map(|text| insert_text("slide_0_body".to_string(), text));
+        let (json, was_truncated) = truncated_batch_update(&requests, max_bytes);
+        assert_eq!(json["requests"].as_array().unwrap().len(), kept);
+        assert_eq!(was_truncated, truncated);
+    }
+
+    fn direction_requests(chunks: &[Chunk], direction: TextDirection) -> serde_json::Value {
+        let layout = SlideLayoutReference::default();
+        let options = SlideOptions {
+            layout: &layout,
+            text_style: None,
+            background_color: None,
+            inline: InlineOptions::default(),
+            title_slide: None,
+            closing_slide: None,
+            autofit: false,
+            slide_numbers: None,
+            text_direction: direction,
+            sequential_batches: false,
+        };
+        serde_json::to_value(content_requests(chunks, "", None, &options)).unwrap()
+    }
+
+    #[rstest]
+    fn test_rtl_paragraph_style_follows_each_insert() {
+        let chunks = [Chunk {
+            title: Some("כותרת".to_string()),
+            ..Chunk::untitled("שלום".to_string())
+        }];
+        let json = direction_requests(&chunks, TextDirection::Rtl);
+        let requests = json.as_array().unwrap();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[1]["insertText"]["objectId"], "slide_0_title");
+        assert_eq!(
+            requests[2],
+            serde_json::json!({"updateParagraphStyle": {
+                "objectId": "slide_0_title",
+                "style": {"direction": "RIGHT_TO_LEFT", "alignment": "START"},
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 0, "endIndex": 5},
+                "fields": "direction,alignment"
+            }})
+        );
+        assert_eq!(
+            requests[4]["updateParagraphStyle"]["objectId"],
+            "slide_0_body"
+        );
+    }
+
+    #[rstest]
+    fn test_auto_direction_is_decided_per_slide() {
+        let chunks = [
+            Chunk::untitled("Hello everyone".to_string()),
+            Chunk::untitled("مرحبا بالجميع".to_string()),
+            Chunk::untitled("שלום לכולם".to_string()),
+        ];
+        let json = direction_requests(&chunks, TextDirection::Auto);
+        let styled = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|request| request.get("updateParagraphStyle"))
+            .map(|style| style["objectId"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(styled, ["slide_1_body", "slide_2_body"]);
+    }
+
+    #[rstest]
+    fn test_ltr_direction_adds_no_paragraph_styles() {
+        let chunks = [Chunk::untitled("שלום".to_string())];
+        let json = direction_requests(&chunks, TextDirection::Ltr);
+        assert!(
+            json.as_array()
+                .unwrap()
+                .iter()
+                .all(|request| request.get("updateParagraphStyle").is_none())
+        );
+    }
+
+    #[rstest]
+    fn test_rtl_table_cells_keep_cell_location() {
+        let rows = vec![vec!["א".to_string(), "ב".to_string()]];
+        let json = direction_requests(&[Chunk::table(rows)], TextDirection::Rtl);
+        let cells = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|request| request.get("updateParagraphStyle"))
+            .map(|style| style["cellLocation"]["columnIndex"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(cells, [0, 1]);
+    }
+
+    #[rstest]
+    #[case::default(r#"{}"#, TextDirection::Ltr)]
+    #[case::rtl(r#"{"text_direction":"rtl"}"#, TextDirection::Rtl)]
+    #[case::auto(r#"{"text_direction":"auto"}"#, TextDirection::Auto)]
+    fn test_text_direction_field(#[case] fields: &str, #[case] expected: TextDirection) {
+        let mut request: serde_json::Value =
+            serde_json::from_str(r#"{"title":"Deck","content":["a"]}"#).unwrap();
+        let fields: serde_json::Value = serde_json::from_str(fields).unwrap();
+        for (key, value) in fields.as_object().unwrap() {
+            request[key] = value.clone();
+        }
+        let request: CreateSlidesRequest = serde_json::from_value(request).unwrap();
+        assert_eq!(request.text_direction, expected);
+    }
+
+    #[rstest]
+    fn test_slide_url_deep_links_to_slide() {
+        assert_eq!(
+            slide_url("abc", "slide_2"),
+            "https://docs.google.com/presentation/d/abc/edit#slide=id.slide_2"
+        );
+    }
+
+    #[rstest]
+    fn test_created_slide_ids_follow_deck_order() {
+        let chunks = [
+            Chunk::untitled("One".to_string()),
+            Chunk::untitled("Two".to_string()),
+        ];
+        let layout = SlideLayoutReference::default();
+        let options = SlideOptions {
+            layout: &layout,
+            text_style: None,
+            background_color: None,
+            inline: InlineOptions::default(),
+            title_slide: Some(TitleSlideText {
+                title: "Deck".to_string(),
+                subtitle: String::new(),
+            }),
+            closing_slide: Some(DEFAULT_CLOSING_TEXT.to_string()),
+            autofit: true,
+            slide_numbers: Some(SlideNumbers { skip_first: false }),
+            text_direction: TextDirection::Ltr,
+            sequential_batches: false,
+        };
+        let requests = content_requests(&chunks, "", None, &options);
+        assert_eq!(
+            created_slide_ids(&requests),
+            ["title_slide", "slide_0", "slide_1", "closing_slide"]
+        );
+    }
+
+    #[rstest]
+    #[case::replace(r#"{"mode":"replace","presentation_id":"abc"}"#, None)]
+    #[case::create_by_default(r#"{}"#, None)]
+    #[case::replace_without_id(r#"{"mode":"replace"}"#, Some("presentation_id is required"))]
+    #[case::blank_id(
+        r#"{"mode":"replace","presentation_id":" "}"#,
+        Some("presentation_id must not be empty")
+    )]
+    #[case::id_without_replace(
+        r#"{"presentation_id":"abc"}"#,
+        Some("only used when mode is replace")
+    )]
+    #[case::with_theme(
+        r#"{"mode":"replace","presentation_id":"abc","theme_presentation_id":"t"}"#,
+        Some("cannot be combined with mode replace")
+    )]
+    fn test_mode_validation(#[case] fields: &str, #[case] error: Option<&str>) {
+        let mut request: serde_json::Value =
+            serde_json::from_str(r#"{"title":"Deck","content":["a"]}"#).unwrap();
+        let fields: serde_json::Value = serde_json::from_str(fields).unwrap();
+        for (key, value) in fields.as_object().unwrap() {
+            request[key] = value.clone();
+        }
+        let request: CreateSlidesRequest = serde_json::from_value(request).unwrap();
+        match error {
+            None => assert!(request.validate().is_ok()),
+            Some(message) => {
+                let err = request.validate().unwrap_err();
+                assert!(err.to_string().contains(message), "{err}");
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::defaults(r#"{}"#, ShareRole::Reader, true)]
+    #[case::writer_silently(
+        r#"{"share_with":["sam@example.com","lee@example.org"],"share_role":"writer","send_notification_email":false}"#,
+        ShareRole::Writer,
+        false
+    )]
+    fn test_share_fields(#[case] fields: &str, #[case] role: ShareRole, #[case] notify: bool) {
+        let mut request: serde_json::Value =
+            serde_json::from_str(r#"{"title":"Deck","content":["a"]}"#).unwrap();
+        let fields: serde_json::Value = serde_json::from_str(fields).unwrap();
+        for (key, value) in fields.as_object().unwrap() {
+            request[key] = value.clone();
+        }
+        let request: CreateSlidesRequest = serde_json::from_value(request).unwrap();
+        assert!(request.validate().is_ok());
+        assert_eq!(request.share_role, role);
+        assert_eq!(request.send_notification_email, notify);
+    }
+
+    #[rstest]
+    #[case::not_an_email(
+        r#"["sam@example.com","sam"]"#,
+        "sam in share_with is not an email address"
+    )]
+    #[case::empty(r#"[""]"#, "share_with")]
+    #[case::spaces(r#"["sam lee@example.com"]"#, "not an email address")]
+    fn test_share_with_rejects_invalid_emails(#[case] emails: &str, #[case] message: &str) {
+        let request = parse_request(&format!(
+            r#"{{"title":"Deck","content":["a"],"share_with":{emails}}}"#
+        ));
+        let err = request.validate().unwrap_err();
+        assert!(err.to_string().contains(message), "{err}");
+    }
+
+    #[rstest]
+    #[case::none(r#"{"title":"Deck","content":["a"]}"#, serde_json::json!({"title": "Deck"}))]
+    #[case::locale(
+        r#"{"title":"Deck","content":["a"],"locale":"fr-CA"}"#,
+        serde_json::json!({"title": "Deck", "locale": "fr-CA"})
+    )]
+    fn test_create_slides_sends_locale(#[case] json: &str, #[case] expected: serde_json::Value) {
+        let api = FakeSlides::new();
+        resolve(create_slides(
+            &api,
+            &parse_request(json),
+            &Limits::default(),
+        ))
+        .unwrap();
+        assert_eq!(*api.presentations.borrow(), [expected]);
+    }
+
+    #[rstest]
+    #[case::theme(
+        r#""theme_presentation_id":"theme1""#,
+        "locale cannot be combined with theme_presentation_id"
+    )]
+    #[case::replace(
+        r#""mode":"replace","presentation_id":"existing""#,
+        "locale cannot be combined with presentation_id"
+    )]
+    fn test_locale_rejected_for_existing_presentations(
+        #[case] fields: &str,
+        #[case] message: &str,
+    ) {
+        let api = FakeSlides::new();
+        let request = parse_request(&format!(
+            r#"{{"title":"Deck","content":["a"],"locale":"fr-CA",{fields}}}"#
+        ));
+        let err = resolve(create_slides(&api, &request, &Limits::default())).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains(message), "{err}");
+        assert!(api.calls.borrow().is_empty());
+    }
+
+    #[rstest]
+    #[case::language("en")]
+    #[case::region("pt-BR")]
+    #[case::script("zh-Hant-TW")]
+    #[case::three_letters("fil")]
+    fn test_locale_accepts_language_tags(#[case] locale: &str) {
+        assert!(validate_locale(locale).is_ok());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::one_letter("e")]
+    #[case::underscore("en_US")]
+    #[case::empty_subtag("en-")]
+    #[case::long_subtag("en-abcdefghi")]
+    #[case::digit_language("e1")]
+    fn test_request_rejects_invalid_locale(#[case] locale: &str) {
+        let api = FakeSlides::new();
+        let request = parse_request(&format!(
+            r#"{{"title":"Deck","content":["a"],"locale":{locale:?}}}"#
+        ));
+        let err = resolve(create_slides(&api, &request, &Limits::default())).unwrap_err();
+        assert!(err.to_string().contains("locale must be a BCP-47"), "{err}");
+        assert!(api.calls.borrow().is_empty());
+    }
+
+    #[rstest]
+    fn test_share_role_rejects_owner() {
+        assert!(
+            serde_json::from_str::<CreateSlidesRequest>(
+                r#"{"title":"Deck","content":["a"],"share_role":"owner"}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[rstest]
+    #[case::default(r#"{"title":"Deck","content":["a"]}"#, true)]
+    #[case::disabled(
+        r#"{"title":"Deck","content":["a"],"cleanup_on_failure":false}"#,
+        false
+    )]
+    fn test_cleanup_on_failure_field(#[case] json: &str, #[case] expected: bool) {
+        let request: CreateSlidesRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.cleanup_on_failure, expected);
+    }
+
+    #[rstest]
+    fn test_batch_update_not_found_is_presentation_deleted() {
+        let body = r#"{"error":{"code":404,"message":"Requested entity was not found.","status":"NOT_FOUND"}}"#;
+        assert!(matches!(
+            batch_update_error(404, body),
+            AppError::PresentationDeleted
+        ));
+    }
+
+    #[rstest]
+    fn test_get_presentation_not_found_names_the_presentation() {
+        let body = r#"{"error":{"code":404,"message":"Requested entity was not found.","status":"NOT_FOUND"}}"#;
+        let err = get_presentation_error(404, "wrong-id", body);
+        assert!(matches!(&err, AppError::PresentationNotFound(id) if id == "wrong-id"));
+        assert_eq!(err.status_code(), 404);
+        assert_eq!(err.code(), "presentation_not_found");
+        assert!(!err.to_string().contains("deleted"), "{err}");
+    }
+
+    #[rstest]
+    fn test_get_presentation_other_failure_is_google_error() {
+        let err = get_presentation_error(500, "deck1", "backend error");
+        assert_eq!(
+            err.to_string(),
+            "Google Slides API error: Failed to fetch presentation: backend error"
+        );
+    }
+
+    #[rstest]
+    fn test_presentation_missing_after_fetch_is_deleted() {
+        let err = deleted_since_fetched(AppError::PresentationNotFound("deck1".to_string()));
+        assert!(matches!(err, AppError::PresentationDeleted));
+        let err = deleted_since_fetched(AppError::GoogleSlides("quota".to_string()));
+        assert!(matches!(err, AppError::GoogleSlides(_)));
+    }
+
+    #[rstest]
+    fn test_replace_with_unknown_presentation_is_not_found() {
+        let api = FakeSlides {
+            missing_presentation: Some("wrong-id"),
+            ..FakeSlides::new()
+        };
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Intro","splitter":{"type":"newline"},"mode":"replace","presentation_id":"wrong-id"}"#,
+        );
+        let err = resolve(create_slides(&api, &request, &Limits::default())).unwrap_err();
+        assert!(matches!(err, AppError::PresentationNotFound(_)), "{err}");
+        assert!(api.batches.borrow().is_empty());
+    }
+
+    #[rstest]
+    fn test_replace_text_requests_use_placeholder_syntax() {
+        let replacements = HashMap::from([
+            ("title".to_string(), "Q3 Status".to_string()),
+            ("owner".to_string(), "Sam".to_string()),
+        ]);
+        let tokens = ["owner".to_string(), "title".to_string()];
+        let syntax = PlaceholderSyntax {
+            open: "<<".to_string(),
+            close: ">>".to_string(),
+        };
+        let json =
+            serde_json::to_value(replace_text_requests(&tokens, &replacements, &syntax)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"replaceAllText": {"containsText": {"text": "<<owner>>", "matchCase": true}, "replaceText": "Sam"}},
+                {"replaceAllText": {"containsText": {"text": "<<title>>", "matchCase": true}, "replaceText": "Q3 Status"}}
+            ])
+        );
+    }
+
+    #[rstest]
+    fn test_replacement_counts_report_missing_tokens() {
+        let response: BatchUpdateResponse = serde_json::from_str(
+            r#"{"presentationId":"p","replies":[{"replaceAllText":{"occurrencesChanged":2}},{"replaceAllText":{}},{"replaceAllText":{"occurrencesChanged":1}}]}"#,
+        )
+        .unwrap();
+        let tokens = ["section_1", "section_9", "title"]
+            .map(String::from)
+            .to_vec();
+        let (replaced, skipped) = replacement_counts(tokens, &response.replies);
+        assert_eq!(
+            replaced,
+            BTreeMap::from([("section_1".to_string(), 2), ("title".to_string(), 1)])
+        );
+        assert_eq!(skipped, vec!["section_9"]);
+    }
+
+    #[rstest]
+    #[case::defaults(r#"{"template_id":"t","replacements":{"title":"Deck"}}"#, true)]
+    #[case::custom_syntax(
+        r#"{"template_id":"t","replacements":{},"placeholder":{"open":"[","close":"]"}}"#,
+        true
+    )]
+    #[case::empty_template_id(r#"{"template_id":"","replacements":{}}"#, false)]
+    #[case::empty_open(
+        r#"{"template_id":"t","replacements":{},"placeholder":{"open":"","close":"}}"}}"#,
+        false
+    )]
+    fn test_template_request_validation(#[case] json: &str, #[case] valid: bool) {
+        let request: CreateFromTemplateRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    fn test_default_placeholder_syntax() {
+        assert_eq!(
+            PlaceholderSyntax::default().token("section_1"),
+            "{{section_1}}"
+        );
+    }
+
+    #[rstest]
+    #[case::absent(
+        r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#,
+        true
+    )]
+    #[case::set(
+        r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"theme_presentation_id":"abc123"}"#,
+        true
+    )]
+    #[case::blank(
+        r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"theme_presentation_id":" "}"#,
+        false
+    )]
+    fn test_theme_presentation_id_validation(#[case] json: &str, #[case] valid: bool) {
+        assert_eq!(parse_request(json).validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case::bad_request(400)]
+    #[case::forbidden(403)]
+    #[case::server_error(500)]
+    fn test_batch_update_other_errors_keep_details(#[case] status: u16) {
+        let err = batch_update_error(status, "boom");
+        assert!(matches!(err, AppError::GoogleSlides(_)));
+        assert!(err.to_string().contains("boom"));
+    }
+
+    #[rstest]
+    fn test_request_rejects_invalid_regex_splitter() {
+        let request: CreateSlidesRequest = serde_json::from_str(
+            r#"{"title":"Deck","content":"a","splitter":{"type":"regex","pattern":"(","keep_delimiter":false}}"#,
+        )
+        .unwrap();
+        let err = request.validate().unwrap_err();
+        assert!(err.to_string().contains("invalid pattern"));
+    }
+
+    #[rstest]
+    #[case::max_words(
+        r#"{"type":"max_words","max_words":0}"#,
+        "max_words must be at least 1"
+    )]
+    #[case::max_chars(
+        r#"{"type":"max_chars","max_chars":0}"#,
+        "max_chars must be at least 1"
+    )]
+    #[case::max_bytes(
+        r#"{"type":"max_bytes","max_bytes":3}"#,
+        "max_bytes must be at least 4"
+    )]
+    fn test_request_rejects_zero_chunk_size(#[case] splitter: &str, #[case] message: &str) {
+        let request: CreateSlidesRequest = serde_json::from_str(&format!(
+            r#"{{"title":"Deck","content":"some text","splitter":{splitter}}}"#
+        ))
+        .unwrap();
+        let err = request.validate().unwrap_err();
+        assert!(err.to_string().contains(message));
+    }
+
+    fn request_with_title(title: &str, use_frontmatter_title: bool) -> CreateSlidesRequest {
+        CreateSlidesRequest {
+            title: title.to_string(),
+            use_frontmatter_title,
+            content: Content::Text("Body".to_string()),
+            splitter: Some(Splitter::NewLine),
+            min_words: None,
+            normalize_whitespace: false,
+            title_from_first_line: false,
+            layout: SlideLayoutReference::default(),
+            limit: SlideLimit::default(),
+            dedupe: false,
+            notes_marker: default_notes_marker(),
+            notes: Vec::new(),
+            strip_markdown: false,
+            numbering_prefix: None,
+            long_slide_words: DEFAULT_LONG_SLIDE_WORDS,
+            theme_presentation_id: None,
+            text_style: None,
+            background_color: None,
+            preset: None,
+            title_slide: None,
+            closing_slide: None,
+            sections: false,
+            autofit: true,
+            slide_numbers: false,
+            skip_first: false,
+            rich_text: false,
+            link_detection: true,
+            cleanup_on_failure: true,
+            mode: Mode::Create,
+            presentation_id: None,
+            share_with: Vec::new(),
+            share_role: ShareRole::Reader,
+            send_notification_email: true,
+            dry_run: false,
+            text_direction: TextDirection::Ltr,
+            locale: None,
+            sequential_batches: false,
+        }
+    }
+
+    #[rstest]
+    #[case::request_title_kept("Mine", false, Some("From YAML"), "Mine")]
+    #[case::empty_title_uses_front_matter("", false, Some("From YAML"), "From YAML")]
+    #[case::flag_prefers_front_matter("Mine", true, Some("From YAML"), "From YAML")]
+    #[case::flag_without_front_matter_title("Mine", true, None, "Mine")]
+    fn test_resolve_title(
+        #[case] title: &str,
+        #[case] use_frontmatter_title: bool,
+        #[case] front_matter_title: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let request = request_with_title(title, use_frontmatter_title);
+        let front_matter = front_matter_title.map(|title| FrontMatter {
+            fields: [("title".to_string(), title.to_string())].into(),
+        });
+        assert_eq!(
+            resolve_title(&request, front_matter.as_ref()).unwrap(),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_resolve_title_requires_some_title() {
+        let request = request_with_title("", false);
+        let err = resolve_title(&request, None).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+    }
+
+    #[rstest]
+    fn test_resolve_title_rejects_long_front_matter_title() {
+        let request = request_with_title("", false);
+        let front_matter = FrontMatter {
+            fields: [("title".to_string(), "x".repeat(101))].into(),
+        };
+        assert!(resolve_title(&request, Some(&front_matter)).is_err());
+    }
+
+    #[rstest]
+    #[case::surrounding_whitespace("  Quarterly review \n", "Quarterly review")]
+    #[case::inner_whitespace_runs("Quarterly \t\n  review", "Quarterly review")]
+    #[case::nul_between_words("Quarterly\u{0}review", "Quarterly review")]
+    #[case::zero_width("\u{feff}Quarterly\u{200b} review\u{2060}", "Quarterly review")]
+    #[case::emoji_joiner_kept("Team 👩\u{200d}💻", "Team 👩\u{200d}💻")]
+    #[case::only_invisible("\u{200b}\u{0} ", "")]
+    fn test_clean_title(#[case] title: &str, #[case] expected: &str) {
+        assert_eq!(clean_title(title), expected);
+    }
+
+    #[rstest]
+    #[case::nul("a\u{0}b", "ab")]
+    #[case::escape_and_bell("\u{1b}[1mBold\u{7}", "[1mBold")]
+    #[case::crlf("one\r\ntwo", "one\ntwo")]
+    #[case::newlines_and_tabs_kept("one\n\ttwo", "one\n\ttwo")]
+    #[case::delete_kept("a\u{7f}b", "a\u{7f}b")]
+    fn test_strip_control_chars(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(strip_control_chars(text), expected);
+    }
+
+    #[rstest]
+    #[case::padded_hundred_chars(format!("   {}   ", "x".repeat(100)), true)]
+    #[case::hundred_and_one_chars("x".repeat(101), false)]
+    #[case::collapsed_to_hundred(format!("{} {}", "x".repeat(49), "y".repeat(50)).replace(' ', "   "), true)]
+    fn test_title_length_counts_cleaned_title(#[case] title: String, #[case] valid: bool) {
+        let request = request_with_title(&title, false);
+        let result = request.validate();
+        assert_eq!(result.is_ok(), valid, "{result:?}");
+        if let Err(err) = result {
+            assert!(
+                err.to_string()
+                    .contains("title must be at most 100 characters")
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_resolve_title_cleans_title() {
+        let request = request_with_title("\u{0}\u{200b} ", false);
+        let front_matter = FrontMatter {
+            fields: [("title".to_string(), " From\tYAML ".to_string())].into(),
+        };
+        // A title that is nothing but invisible characters counts as empty
+        assert_eq!(
+            resolve_title(&request, Some(&front_matter)).unwrap(),
+            "From YAML"
+        );
+        assert!(resolve_title(&request, None).is_err());
+    }
+
+    #[rstest]
+    fn test_split_content_strips_control_chars() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":"one\u0000\r\ntwo\u0007","splitter":{"type":"newline"}}"#,
+        );
+        let (_, chunks) = split_content(&request).unwrap();
+        assert_eq!(chunks, vec!["one", "two"]);
+
+        let request = parse_request(r#"{"title":"Deck","content":["a\u0000b"]}"#);
+        let (_, chunks) = split_content(&request).unwrap();
+        assert_eq!(chunks, vec!["ab"]);
+    }
+
+    #[rstest]
+    fn test_title_may_be_omitted() {
+        let request: CreateSlidesRequest = serde_json::from_str(
+            r#"{"content":"---\ntitle: Deck\n---\nBody","splitter":{"type":"newline"}}"#,
+        )
+        .unwrap();
+        assert!(request.validate().is_ok());
+        let (front_matter, _) = split_content(&request).unwrap();
+        assert_eq!(
+            resolve_title(&request, front_matter.as_ref()).unwrap(),
+            "Deck"
+        );
+    }
+
+    #[rstest]
+    fn test_notes_requests_target_speaker_notes_shapes() {
+        let chunks = vec![
+            Chunk::untitled("No notes".to_string()),
+            Chunk {
+                notes: "Mention the roadmap".to_string(),
+                ..Chunk::untitled("Plans".to_string())
+            },
+        ];
+        let slides: Vec<Slide> = serde_json::from_str(
+            r#"[
+                {"objectId": "slide_0", "slideProperties": {"layoutObjectId": "l", "notesPage": {"notesProperties": {"speakerNotesObjectId": "n0"}}}},
+                {"objectId": "slide_1", "slideProperties": {"layoutObjectId": "l", "notesPage": {"notesProperties": {"speakerNotesObjectId": "n1"}}}}
+            ]"#,
+        )
+        .unwrap();
+        let requests = notes_requests(&chunks, &slides, "");
+        assert_eq!(requests.len(), 1);
+        let insert = requests[0].insert_text.as_ref().unwrap();
+        assert_eq!(insert.object_id, "n1");
+        assert_eq!(insert.text, "Mention the roadmap");
+    }
+
+    #[rstest]
+    fn test_notes_marker_defaults_and_rejects_empty() {
+        let request: CreateSlidesRequest =
+            serde_json::from_str(r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#)
+                .unwrap();
+        assert_eq!(request.notes_marker, "???");
+
+        let request: CreateSlidesRequest = serde_json::from_str(
+            r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"notes_marker":""}"#,
+        )
+        .unwrap();
+        assert!(request.validate().is_err());
+    }
+
+    #[rstest]
+    fn test_post_processing_defaults_to_off() {
+        let request: CreateSlidesRequest = serde_json::from_str(
+            r#"{"title":"Deck","content":"**a**","splitter":{"type":"newline"}}"#,
+        )
+        .unwrap();
+        assert!(!request.strip_markdown);
+        assert!(!request.dedupe);
+    }
+
+    #[rstest]
+    fn test_text_style_follows_each_insert() {
+        let chunks = [Chunk {
+            title: Some("Café".to_string()),
+            body: "Ship 🚀".to_string(),
+            notes: String::new(),
+            media: None,
+            skipped: false,
+        }];
+        let style = TextStyle {
+            font_size_pt: Some(28.0),
+            font_family: Some("Roboto".to_string()),
+            bold: Some(true),
+            foreground_color: Some("#FF3300".to_string()),
+        };
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let json = serde_json::to_value(with_text_style(requests, &style)).unwrap();
+        let expected_style = serde_json::json!({
+            "fontSize": {"magnitude": 28.0, "unit": "PT"},
+            "fontFamily": "Roboto",
+            "bold": true,
+            "foregroundColor": {"opaqueColor": {"rgbColor": {"red": 1.0, "green": 0.2, "blue": 0.0}}}
+        });
+        assert_eq!(json.as_array().unwrap().len(), 5);
+        assert_eq!(
+            json[2],
+            serde_json::json!({"updateTextStyle": {
+                "objectId": "slide_0_title",
+                "style": expected_style,
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 0, "endIndex": 4},
+                "fields": "fontSize,fontFamily,bold,foregroundColor"
+            }})
+        );
+        assert_eq!(
+            json[4],
+            serde_json::json!({"updateTextStyle": {
+                "objectId": "slide_0_body",
+                "style": expected_style,
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 0, "endIndex": 7},
+                "fields": "fontSize,fontFamily,bold,foregroundColor"
+            }})
+        );
+    }
+
+    #[rstest]
+    fn test_text_style_mask_names_only_set_fields() {
+        let style = TextStyle {
+            font_size_pt: Some(24.0),
+            ..TextStyle::default()
+        };
+        let requests = vec![insert_text("shape".to_string(), "Hi")];
+        let json = serde_json::to_value(with_text_style(requests, &style)).unwrap();
+        assert_eq!(
+            json[1],
+            serde_json::json!({"updateTextStyle": {
+                "objectId": "shape",
+                "style": {"fontSize": {"magnitude": 24.0, "unit": "PT"}},
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 0, "endIndex": 2},
+                "fields": "fontSize"
+            }})
+        );
+    }
+
+    #[rstest]
+    fn test_empty_text_style_adds_nothing() {
+        let requests = vec![insert_text("shape".to_string(), "Hi")];
+        assert_eq!(with_text_style(requests, &TextStyle::default()).len(), 1);
+    }
+
+    #[rstest]
+    #[case::valid("#1a73E8", true)]
+    #[case::no_hash("1A73E8", false)]
+    #[case::short("#FFF", false)]
+    #[case::not_hex("#GGGGGG", false)]
+    #[case::alpha("#1A73E8FF", false)]
+    fn test_text_style_color_validation(#[case] color: &str, #[case] valid: bool) {
+        let json = format!(
+            r#"{{"title":"Deck","content":"a","splitter":{{"type":"newline"}},"text_style":{{"foreground_color":"{color}"}}}}"#
+        );
+        assert_eq!(parse_request(&json).validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case::valid("#202124", true)]
+    #[case::no_hash("202124", false)]
+    #[case::short("#000", false)]
+    fn test_background_color_validation(#[case] color: &str, #[case] valid: bool) {
+        let json = format!(
+            r#"{{"title":"Deck","content":"a","splitter":{{"type":"newline"}},"background_color":"{color}"}}"#
+        );
+        let result = parse_request(&json).validate();
+        assert_eq!(result.is_ok(), valid);
+        if let Err(err) = result {
+            assert!(
+                err.to_string()
+                    .contains("background_color must be a #RRGGBB hex color")
+            );
+        }
+    }
+
+    #[rstest]
+    #[case::black("#000000", 0.0, 0.0, 0.0)]
+    #[case::white("#FFFFFF", 1.0, 1.0, 1.0)]
+    #[case::mixed_case("#ff3300", 1.0, 0.2, 0.0)]
+    #[case::mid_grey("#808080", 128.0 / 255.0, 128.0 / 255.0, 128.0 / 255.0)]
+    fn test_parse_hex_color(
+        #[case] color: &str,
+        #[case] red: f64,
+        #[case] green: f64,
+        #[case] blue: f64,
+    ) {
+        assert_eq!(parse_hex_color(color), Some(RgbColor { red, green, blue }));
+    }
+
+    #[rstest]
+    fn test_dark_preset_gives_white_text_on_dark_background() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":"Hello","splitter":{"type":"newline"},"preset":"dark"}"#,
+        );
+        let chunks = [Chunk::untitled("Hello".to_string())];
+        let options = SlideOptions {
+            layout: &request.layout,
+            text_style: request.effective_text_style(),
+            background_color: request.effective_background_color(),
+            inline: InlineOptions::default(),
+            title_slide: None,
+            closing_slide: None,
+            autofit: false,
+            slide_numbers: None,
+            text_direction: TextDirection::Ltr,
+            sequential_batches: false,
+        };
+        let json = serde_json::to_value(content_requests(&chunks, "", None, &options)).unwrap();
+        let white = serde_json::json!({"red": 1.0, "green": 1.0, "blue": 1.0});
+        assert_eq!(
+            json[2]["updateTextStyle"]["style"],
+            serde_json::json!({"foregroundColor": {"opaqueColor": {"rgbColor": white}}})
+        );
+        assert_eq!(json[2]["updateTextStyle"]["fields"], "foregroundColor");
+        let background = &json[3]["updatePageProperties"]["pageProperties"]["pageBackgroundFill"];
+        let dark_grey = serde_json::to_value(parse_hex_color("#202124").unwrap()).unwrap();
+        assert_eq!(background["solidFill"]["color"]["rgbColor"], dark_grey);
+    }
+
+    #[rstest]
+    fn test_explicit_style_overrides_preset_values() {
+        let request = parse_request(
+            r##"{"title":"Deck","content":"a","splitter":{"type":"newline"},"preset":"high_contrast",
+                "text_style":{"font_size_pt":40,"foreground_color":"#00FF00"},"background_color":"#111111"}"##,
+        );
+        assert_eq!(
+            request.effective_text_style(),
+            Some(TextStyle {
+                font_size_pt: Some(40.0),
+                font_family: None,
+                bold: Some(true),
+                foreground_color: Some("#00FF00".to_string()),
+            })
+        );
+        assert_eq!(request.effective_background_color(), Some("#111111"));
+    }
+
+    #[rstest]
+    fn test_no_preset_keeps_request_style() {
+        let request =
+            parse_request(r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#);
+        assert_eq!(request.effective_text_style(), None);
+        assert_eq!(request.effective_background_color(), None);
+    }
+
+    #[rstest]
+    fn test_presets_are_listed_with_valid_colors() {
+        let json = serde_json::to_value(StylePreset::descriptors()).unwrap();
+        let names = json["presets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|preset| preset["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["light", "dark", "high_contrast"]);
+        for preset in StylePreset::ALL {
+            assert!(parse_hex_color(preset.background_color()).is_some());
+            assert!(preset.text_style().validate().is_ok());
+        }
+    }
+
+    #[rstest]
+    #[case::all_fields(
+        Some("Q3 results"),
+        Some("Sam Lee"),
+        Some("2024-10-01"),
+        "Q3 results\nSam Lee\n2024-10-01"
+    )]
+    #[case::auto_date(None, Some("Sam Lee"), Some("auto"), "Sam Lee\n2026-01-02")]
+    #[case::skips_blank(Some("  "), None, Some("Friday"), "Friday")]
+    #[case::nothing(None, None, None, "")]
+    fn test_title_slide_subtitle_text(
+        #[case] subtitle: Option<&str>,
+        #[case] author: Option<&str>,
+        #[case] date: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let title_slide = TitleSlide {
+            subtitle: subtitle.map(str::to_string),
+            author: author.map(str::to_string),
+            date: date.map(str::to_string),
+        };
+        assert_eq!(
+            title_slide.subtitle_text(|| "2026-01-02".to_string()),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::epoch(0, "1970-01-01")]
+    #[case::leap_day(951_782_400_000, "2000-02-29")]
+    #[case::end_of_day(1_704_153_599_999, "2024-01-01")]
+    #[case::recent(1_791_158_400_000, "2026-10-05")]
+    fn test_iso_date(#[case] millis: u64, #[case] expected: &str) {
+        assert_eq!(iso_date(millis), expected);
+    }
+
+    #[rstest]
+    fn test_title_slide_opens_deck_and_shifts_content() {
+        let chunks = [Chunk::untitled("Body".to_string())];
+        let layout = SlideLayoutReference::default();
+        let options = SlideOptions {
+            layout: &layout,
+            text_style: None,
+            background_color: Some("#000000"),
+            inline: InlineOptions::default(),
+            title_slide: Some(TitleSlideText {
+                title: "Quarterly Review".to_string(),
+                subtitle: "Sam Lee\n2026-01-02".to_string(),
+            }),
+            closing_slide: None,
+            autofit: false,
+            slide_numbers: None,
+            text_direction: TextDirection::Ltr,
+            sequential_batches: false,
+        };
+        let json = serde_json::to_value(content_requests(&chunks, "", None, &options)).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({"createSlide": {
+                "objectId": "title_slide",
+                "insertionIndex": 0,
+                "slideLayoutReference": {"predefinedLayout": "TITLE"},
+                "placeholderIdMappings": [
+                    {"layoutPlaceholder": {"type": "CENTERED_TITLE", "index": 0}, "objectId": "title_slide_title"},
+                    {"layoutPlaceholder": {"type": "SUBTITLE", "index": 0}, "objectId": "title_slide_subtitle"}
+                ]
+            }})
+        );
+        assert_eq!(json[1]["insertText"]["text"], "Quarterly Review");
+        assert_eq!(json[2]["insertText"]["objectId"], "title_slide_subtitle");
+        assert_eq!(json[2]["insertText"]["text"], "Sam Lee\n2026-01-02");
+        assert_eq!(json[3]["createSlide"]["objectId"], "slide_0");
+        assert_eq!(json[3]["createSlide"]["insertionIndex"], 1);
+        let backgrounds = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|request| request["updatePageProperties"]["objectId"].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(backgrounds, ["title_slide", "slide_0"]);
+    }
+
+    #[rstest]
+    fn test_section_slides_use_section_header_layout() {
+        let chunks = [
+            Chunk::section("Part One".to_string()),
+            Chunk::untitled("Text".to_string()),
+        ];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({"createSlide": {
+                "objectId": "slide_0",
+                "insertionIndex": 0,
+                "slideLayoutReference": {"predefinedLayout": "SECTION_HEADER"},
+                "placeholderIdMappings": [
+                    {"layoutPlaceholder": {"type": "TITLE", "index": 0}, "objectId": "slide_0_title"}
+                ]
+            }})
+        );
+        assert_eq!(
+            json[1]["insertText"],
+            serde_json::json!({"objectId": "slide_0_title", "insertionIndex": 0, "text": "Part One", "cellLocation": null})
+        );
+        assert_eq!(json[2]["createSlide"]["insertionIndex"], 1);
+        assert_eq!(json.as_array().unwrap().len(), 4);
+    }
+
+    #[rstest]
+    fn test_section_slides_count_towards_limit() {
+        let limit = SlideLimit {
+            max_slides: Some(2),
+            overflow: Overflow::Merge,
+        };
+        let specs = splitter::section_specs(vec!["# Part\nOne".to_string(), "Two".to_string()]);
+        assert_eq!(
+            limit.apply(specs, 0, &Limits::default()).unwrap(),
+            (
+                vec![
+                    SlideSpec::Section("Part".to_string()),
+                    SlideSpec::Content("One\nTwo".to_string())
+                ],
+                0
+            )
+        );
+    }
+
+    #[rstest]
+    fn test_merging_into_section_keeps_heading() {
+        let mut spec = SlideSpec::Section("Part".to_string());
+        spec.merge(SlideSpec::Content("Text".to_string()));
+        assert_eq!(spec, SlideSpec::Content("# Part\nText".to_string()));
+    }
+
+    fn batch_sizes(batches: &[Vec<UpdateRequest>]) -> Vec<usize> {
+        batches.iter().map(Vec::len).collect()
+    }
+
+    #[rstest]
+    #[case::under_limit(3, vec![3])]
+    #[case::exact_multiple(100, vec![50, 50])]
+    #[case::remainder(120, vec![50, 50, 20])]
+    fn test_batches_split_by_request_count(#[case] count: usize, #[case] expected: Vec<usize>) {
+        let requests = (0..count)
+            .map(|index| UpdateRequest {
+                delete_object: Some(DeleteObjectRequest {
+                    object_id: format!("p{index}"),
+                }),
+                ..Default::default()
+            })
+            .collect();
+        let batches = batches(requests, BatchLimits::default());
+        assert_eq!(batch_sizes(&batches), expected);
+        assert_eq!(
+            batches[0][1].delete_object.as_ref().unwrap().object_id,
+            "p1"
+        );
+    }
+
+    #[rstest]
+    fn test_batches_keep_slide_requests_together() {
+        // Each slide is createSlide, title text and body text
+        let chunks = (0..5)
+            .map(|index| Chunk {
+                title: Some(format!("Title {index}")),
+                ..Chunk::untitled(format!("Body {index}"))
+            })
+            .collect::<Vec<_>>();
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let limits = BatchLimits {
+            max_requests: 4,
+            max_bytes: usize::MAX,
+        };
+        let batches = batches(requests, limits);
+        assert_eq!(batch_sizes(&batches), vec![3, 3, 3, 3, 3]);
+        for (index, batch) in batches.iter().enumerate() {
+            let slide_id = format!("slide_{index}");
+            let create_slide = batch[0].create_slide.as_ref().unwrap();
+            assert_eq!(create_slide.object_id.as_deref(), Some(slide_id.as_str()));
+            assert_eq!(create_slide.insertion_index, Some(index as i32));
+            assert!(batch[1..].iter().all(|request| {
+                request
+                    .insert_text
+                    .as_ref()
+                    .is_some_and(|insert| insert.object_id.starts_with(&slide_id))
+            }));
+        }
+    }
+
+    #[rstest]
+    fn test_batches_split_by_size_and_keep_oversized_groups_whole() {
+        let chunks = [
+            Chunk::untitled("a".repeat(1000)),
+            Chunk::untitled("b".repeat(50)),
+            Chunk::untitled("c".repeat(50)),
+        ];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let bytes = |requests: &[UpdateRequest]| {
+            requests
+                .iter()
+                .map(|request| serde_json::to_vec(request).unwrap().len())
+                .sum::<usize>()
+        };
+        // Room for the two short slides, but not for the long one
+        let limits = BatchLimits {
+            max_requests: 50,
+            max_bytes: bytes(&requests[2..]),
+        };
+        assert!(bytes(&requests[..2]) > limits.max_bytes);
+        let batches = batches(requests, limits);
+        assert_eq!(batch_sizes(&batches), vec![2, 4]);
+    }
+
+    #[rstest]
+    fn test_slides_first_creates_slides_in_order_before_their_content() {
+        let chunks = (0..3)
+            .map(|index| Chunk {
+                title: Some(format!("Title {index}")),
+                ..Chunk::untitled(format!("Body {index}"))
+            })
+            .collect::<Vec<_>>();
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let limits = BatchLimits {
+            max_requests: 2,
+            max_bytes: usize::MAX,
+        };
+        let (slides, content) = slides_first(requests, limits);
+        assert_eq!(batch_sizes(&slides), vec![2, 1]);
+        let slide_ids: Vec<_> = slides
+            .iter()
+            .flat_map(|batch| created_slide_ids(batch))
+            .collect();
+        assert_eq!(slide_ids, ["slide_0", "slide_1", "slide_2"]);
+        // Each slide's title and body text stay in one batch
+        assert_eq!(batch_sizes(&content), vec![2, 2, 2]);
+        for (index, batch) in content.iter().enumerate() {
+            assert!(batch.iter().all(|request| {
+                request
+                    .insert_text
+                    .as_ref()
+                    .is_some_and(|insert| insert.object_id.starts_with(&format!("slide_{index}")))
+            }));
+        }
+        assert!(order_independent(&content));
+    }
+
+    #[rstest]
+    fn test_order_independent_rejects_deck_wide_requests() {
+        let replace = UpdateRequest {
+            replace_all_text: Some(ReplaceAllTextRequest {
+                contains_text: SubstringMatchCriteria {
+                    text: "{{name}}".to_string(),
+                    match_case: true,
+                },
+                replace_text: "Sam".to_string(),
+            }),
+            ..Default::default()
+        };
+        let text = insert_text("slide_0_body".to_string(), "Body");
+        assert!(order_independent(&[vec![text]]));
+        let text = insert_text("slide_0_body".to_string(), "Body");
+        assert!(!order_independent(&[vec![text], vec![replace]]));
+        let slides = slide_requests(
+            &[Chunk::untitled("Body".to_string())],
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        );
+        assert!(!order_independent(&[slides]));
+    }
+
+    #[rstest]
+    fn test_order_independent_rejects_batches_using_shapes_created_by_others() {
+        let mut requests = two_column_requests(
+            "slide_0",
+            "Left\n\nRight",
+            (9_144_000.0, 5_143_500.0),
+            false,
+        );
+        assert!(requests[0].create_shape.is_some());
+        assert!(order_independent(std::slice::from_ref(&requests)));
+        let text = requests.split_off(1);
+        assert!(!order_independent(&[requests, text]));
+    }
+
+    #[rstest]
+    #[case::several_slides(vec!["slide_3", "slide_4", "slide_5"], "batch 2 of 3 (slides slide_3 to slide_5) failed: boom")]
+    #[case::one_slide(vec!["slide_3"], "batch 2 of 3 (slide slide_3) failed: boom")]
+    #[case::no_slides(vec![], "batch 2 of 3 failed: boom")]
+    fn test_batch_failure_names_batch_and_slides(
+        #[case] slide_ids: Vec<&str>,
+        #[case] expected: &str,
+    ) {
+        let slide_ids = slide_ids
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let err = batch_failure(1, 3, &slide_ids, AppError::GoogleSlides("boom".to_string()));
+        assert!(err.to_string().ends_with(expected), "{err}");
+    }
+
+    #[rstest]
+    fn test_batch_failure_passes_on_deleted_presentation() {
+        let slide_ids = vec!["slide_0".to_string()];
+        let err = batch_failure(1, 3, &slide_ids, AppError::PresentationDeleted);
+        assert!(matches!(err, AppError::PresentationDeleted));
+    }
+
+    #[rstest]
+    fn test_autofit_requests_target_body_placeholders() {
+        let chunks = [
+            Chunk::untitled("A long body".to_string()),
+            Chunk {
+                title: Some("Only a title".to_string()),
+                ..Chunk::untitled(String::new())
+            },
+            Chunk::image("https://example.com/cat.png".to_string()),
+        ];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let json = serde_json::to_value(autofit_requests(&requests)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"updateShapeProperties": {
+                "objectId": "slide_0_body",
+                "shapeProperties": {"autofit": {"autofitType": "TEXT_AUTOFIT_SHRINK_ON_OVERFLOW"}},
+                "fields": "autofit.autofitType"
+            }}])
+        );
+    }
+
+    #[rstest]
+    #[case::default(
+        r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#,
+        true
+    )]
+    #[case::disabled(
+        r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"autofit":false}"#,
+        false
+    )]
+    fn test_autofit_defaults_to_true(#[case] json: &str, #[case] expected: bool) {
+        assert_eq!(parse_request(json).autofit, expected);
+    }
+
+    #[rstest]
+    fn test_slide_numbers_anchor_to_page_corner() {
+        // 4:3 page
+        let page_size = Size {
+            width: emu(9_144_000.0),
+            height: emu(6_858_000.0),
+        };
+        let slide_ids = ["title_slide", "slide_0", "slide_1"].map(str::to_string);
+        let requests = slide_number_requests(
+            &slide_ids,
+            Some(&page_size),
+            SlideNumbers { skip_first: true },
+            Some(&StylePreset::Dark.text_style()),
+        );
+        let json = serde_json::to_value(requests).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({"createShape": {
+                "objectId": "slide_0_number",
+                "shapeType": "TEXT_BOX",
+                "elementProperties": {
+                    "pageObjectId": "slide_0",
+                    "size": {
+                        "width": {"magnitude": 914400.0, "unit": "EMU"},
+                        "height": {"magnitude": 320040.0, "unit": "EMU"}
+                    },
+                    "transform": {
+                        "scaleX": 1.0,
+                        "scaleY": 1.0,
+                        "translateX": 8046720.0,
+                        "translateY": 6355080.0,
+                        "unit": "EMU"
+                    }
+                }
+            }})
+        );
+        assert_eq!(json[1]["insertText"]["text"], "2 / 3");
+        assert_eq!(
+            json[2]["updateTextStyle"]["style"]["fontSize"],
+            serde_json::json!({"magnitude": 10.0, "unit": "PT"})
+        );
+        assert_eq!(
+            json[2]["updateTextStyle"]["fields"],
+            "fontSize,foregroundColor"
+        );
+        assert_eq!(json[4]["insertText"]["text"], "3 / 3");
+        assert_eq!(json.as_array().unwrap().len(), 6);
+    }
+
+    #[rstest]
+    fn test_slide_numbers_default_to_widescreen_page() {
+        let slide_ids = ["slide_0".to_string()];
+        let json = serde_json::to_value(slide_number_requests(
+            &slide_ids,
+            None,
+            SlideNumbers { skip_first: false },
+            None,
+        ))
+        .unwrap();
+        let transform = &json[0]["createShape"]["elementProperties"]["transform"];
+        assert_eq!(transform["translateY"], 4_640_580.0);
+        assert_eq!(json[1]["insertText"]["text"], "1 / 1");
+    }
+
+    #[rstest]
+    fn test_closing_slide_is_last_and_styled() {
+        let chunks = [
+            Chunk::untitled("One".to_string()),
+            Chunk::untitled("Two".to_string()),
+        ];
+        let layout = SlideLayoutReference::default();
+        let options = SlideOptions {
+            layout: &layout,
+            text_style: Some(StylePreset::Dark.text_style()),
+            background_color: None,
+            inline: InlineOptions::default(),
+            title_slide: Some(TitleSlideText {
+                title: "Deck".to_string(),
+                subtitle: String::new(),
+            }),
+            closing_slide: Some("Questions?".to_string()),
+            autofit: false,
+            slide_numbers: None,
+            text_direction: TextDirection::Ltr,
+            sequential_batches: false,
+        };
+        let json = serde_json::to_value(content_requests(&chunks, "", None, &options)).unwrap();
+        let requests = json.as_array().unwrap();
+        let created = requests
+            .iter()
+            .filter_map(|request| request.get("createSlide"))
+            .collect::<Vec<_>>();
+        let closing = created.last().unwrap();
+        assert_eq!(closing["objectId"], "closing_slide");
+        assert_eq!(closing["insertionIndex"], 3);
+        assert!(
+            created
+                .iter()
+                .all(|slide| slide["insertionIndex"].as_i64() <= Some(3))
+        );
+        assert_eq!(
+            closing["slideLayoutReference"]["predefinedLayout"],
+            "SECTION_HEADER"
+        );
+        assert_eq!(
+            closing["placeholderIdMappings"][0]["objectId"],
+            "closing_slide_title"
+        );
+
+        let position = requests
+            .iter()
+            .position(|request| request["insertText"]["objectId"] == "closing_slide_title")
+            .unwrap();
+        assert_eq!(requests[position]["insertText"]["text"], "Questions?");
+        assert_eq!(
+            requests[position + 1]["updateTextStyle"]["objectId"],
+            "closing_slide_title"
+        );
+    }
+
+    #[rstest]
+    fn test_background_requests() {
+        let color = parse_hex_color("#FF3300").unwrap();
+        let slide_ids = ["slide_0".to_string(), "slide_1".to_string()];
+        let json = serde_json::to_value(background_requests(&slide_ids, color)).unwrap();
+        let background = |slide: &str| {
+            serde_json::json!({"updatePageProperties": {
+                "objectId": slide,
+                "pageProperties": {"pageBackgroundFill": {"solidFill": {"color": {
+                    "rgbColor": {"red": 1.0, "green": 0.2, "blue": 0.0}
+                }}}},
+                "fields": "pageBackgroundFill.solidFill.color"
+            }})
+        };
+        assert_eq!(
+            json,
+            serde_json::json!([background("slide_0"), background("slide_1")])
+        );
+    }
+
+    const EMPHASIS: InlineOptions = InlineOptions {
+        emphasis: true,
+        links: false,
+    };
+
+    const LINKS: InlineOptions = InlineOptions {
+        emphasis: false,
+        links: true,
+    };
+
+    fn link(start: usize, end: usize, url: &str) -> StyledRange {
+        range(start, end, InlineStyle::Link(url.to_string()))
+    }
+
+    #[rstest]
+    #[case::bare("See https://example.com now", "See https://example.com now", vec![link(4, 23, "https://example.com")])]
+    #[case::emoji_before(
+        "🚀🚀 http://a.io/x",
+        "🚀🚀 http://a.io/x",
+        vec![link(5, 18, "http://a.io/x")]
+    )]
+    #[case::sentence_end(
+        "Docs: https://docs.rs/serde.",
+        "Docs: https://docs.rs/serde.",
+        vec![link(6, 27, "https://docs.rs/serde")]
+    )]
+    #[case::parenthesized(
+        "(https://en.wikipedia.org/wiki/Rust_(language))",
+        "(https://en.wikipedia.org/wiki/Rust_(language))",
+        vec![link(1, 46, "https://en.wikipedia.org/wiki/Rust_(language)")]
+    )]
+    #[case::markdown_label(
+        "Read [the guide](https://x.dev/guide) 📘",
+        "Read the guide 📘",
+        vec![link(5, 14, "https://x.dev/guide")]
+    )]
+    #[case::markdown_after_emoji(
+        "👍 [ok](https://ok.dev)",
+        "👍 ok",
+        vec![link(3, 5, "https://ok.dev")]
+    )]
+    #[case::mid_word("xhttps://example.com", "xhttps://example.com", vec![])]
+    #[case::scheme_only("http:// nothing", "http:// nothing", vec![])]
+    #[case::not_http("[mail](mailto:a@b.c) ftp://x.y", "[mail](mailto:a@b.c) ftp://x.y", vec![])]
+    #[case::emphasis_ignored("**https://x.dev**", "**https://x.dev**", vec![link(2, 17, "https://x.dev**")])]
+    fn test_parse_inline_links(
+        #[case] text: &str,
+        #[case] expected_plain: &str,
+        #[case] expected_ranges: Vec<StyledRange>,
+    ) {
+        assert_eq!(
+            parse_inline(text, LINKS),
+            (expected_plain.to_string(), expected_ranges)
+        );
+    }
+
+    #[rstest]
+    fn test_links_and_emphasis_together() {
+        let options = InlineOptions {
+            emphasis: true,
+            links: true,
+        };
+        let (plain, ranges) = parse_inline(
+            "**Note:** see [*docs*](https://d.io) or https://a.b/c*d*",
+            options,
+        );
+        assert_eq!(plain, "Note: see docs or https://a.b/c*d*");
+        assert_eq!(
+            ranges,
+            vec![
+                range(0, 5, InlineStyle::Bold),
+                link(10, 14, "https://d.io"),
+                link(18, 34, "https://a.b/c*d*"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_link_inside_bold_stays_linked() {
+        let options = InlineOptions {
+            emphasis: true,
+            links: true,
+        };
+        assert_eq!(
+            parse_inline("Go **https://x.dev**", options),
+            (
+                "Go https://x.dev".to_string(),
+                vec![
+                    range(3, 16, InlineStyle::Bold),
+                    link(3, 16, "https://x.dev")
+                ]
+            )
+        );
+    }
+
+    #[rstest]
+    fn test_link_requests_after_emoji() {
+        let requests = vec![insert_text("shape".to_string(), "🎉 Go to https://x.dev")];
+        let json = serde_json::to_value(with_inline_formatting(requests, LINKS)).unwrap();
+        assert_eq!(
+            json[1],
+            serde_json::json!({"updateTextStyle": {
+                "objectId": "shape",
+                "style": {"link": {"url": "https://x.dev"}},
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 9, "endIndex": 22},
+                "fields": "link"
+            }})
+        );
+    }
+
+    #[rstest]
+    fn test_link_detection_defaults_on() {
+        let request =
+            parse_request(r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#);
+        assert!(request.link_detection);
+    }
+
+    fn range(start: usize, end: usize, style: InlineStyle) -> StyledRange {
+        StyledRange { start, end, style }
+    }
+
+    #[rstest]
+    #[case::plain("No markers", "No markers", vec![])]
+    #[case::bold_and_italic(
+        "**Bold** and *italic*",
+        "Bold and italic",
+        vec![range(0, 4, InlineStyle::Bold), range(9, 15, InlineStyle::Italic)]
+    )]
+    #[case::code_keeps_markers("Run `a*b*c` now", "Run a*b*c now", vec![range(4, 9, InlineStyle::Code)])]
+    #[case::multibyte_before(
+        "Café **déjà** vu",
+        "Café déjà vu",
+        vec![range(5, 9, InlineStyle::Bold)]
+    )]
+    #[case::surrogate_pairs(
+        "🚀 *go* 🚀 **now**",
+        "🚀 go 🚀 now",
+        vec![range(3, 5, InlineStyle::Italic), range(9, 12, InlineStyle::Bold)]
+    )]
+    #[case::zwj_sequence(
+        "👨‍👩‍👧‍👦 **together**",
+        "👨‍👩‍👧‍👦 together",
+        vec![range(12, 20, InlineStyle::Bold)]
+    )]
+    #[case::nested_outer_wins(
+        "**a *b* c** d",
+        "a b c d",
+        vec![range(0, 5, InlineStyle::Bold)]
+    )]
+    #[case::bold_inside_italic(
+        "*a **b** c*",
+        "a b c",
+        vec![range(0, 5, InlineStyle::Italic)]
+    )]
+    #[case::arithmetic("2 * 3 * 4", "2 * 3 * 4", vec![])]
+    #[case::unclosed("**open and `tick", "**open and `tick", vec![])]
+    #[case::empty_markers("**** `` **", "**** `` **", vec![])]
+    #[case::triple("***x***", "*x*", vec![range(0, 2, InlineStyle::Bold)])]
+    fn test_parse_inline_emphasis(
+        #[case] text: &str,
+        #[case] expected_plain: &str,
+        #[case] expected_ranges: Vec<StyledRange>,
+    ) {
+        let (plain, ranges) = parse_inline(text, EMPHASIS);
+        assert_eq!(plain, expected_plain);
+        assert_eq!(ranges, expected_ranges);
+        for range in &ranges {
+            assert!(range.start < range.end && range.end <= textmetrics::utf16_len(&plain));
+        }
+    }
+
+    #[rstest]
+    fn test_markdown_emphasis_requests() {
+        let requests = vec![insert_text("shape".to_string(), "Use `cargo` **now**")];
+        let json = serde_json::to_value(with_inline_formatting(requests, EMPHASIS)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"insertText": {"objectId": "shape", "insertionIndex": 0, "text": "Use cargo now", "cellLocation": null}},
+                {"updateTextStyle": {
+                    "objectId": "shape",
+                    "style": {"fontFamily": "Courier New"},
+                    "textRange": {"type": "FIXED_RANGE", "startIndex": 4, "endIndex": 9},
+                    "fields": "fontFamily"
+                }},
+                {"updateTextStyle": {
+                    "objectId": "shape",
+                    "style": {"bold": true},
+                    "textRange": {"type": "FIXED_RANGE", "startIndex": 10, "endIndex": 13},
+                    "fields": "bold"
+                }}
+            ])
+        );
+    }
+
+    /// Every index sent to the API counts UTF-16 code units; the family emoji
+    /// is 7 chars but 11 code units.
+    #[rstest]
+    fn test_ranges_after_zwj_sequence_count_utf16_units() {
+        let text = "👨‍👩‍👧‍👦 **bold** https://example.com\n- item";
+        let options = InlineOptions {
+            emphasis: true,
+            links: true,
+        };
+        let requests = with_inline_formatting(body_text_requests("shape", text), options);
+        let requests = with_text_style(
+            requests,
+            &TextStyle {
+                bold: Some(false),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&requests).unwrap();
+        let ranges: Vec<_> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|request| {
+                let (kind, body) = request.as_object()?.iter().next()?;
+                let range = body.get("textRange")?;
+                Some((
+                    kind.as_str(),
+                    range["startIndex"].as_u64(),
+                    range["endIndex"].as_u64(),
+                ))
+            })
+            .collect();
+        // "👨‍👩‍👧‍👦 bold https://example.com\nitem": bold at 12..16, the link
+        // at 17..36, the bullet paragraph at 37..41, the whole text 0..41
+        assert_eq!(
+            ranges,
+            [
+                ("updateTextStyle", Some(0), Some(41)),
+                ("updateTextStyle", Some(12), Some(16)),
+                ("updateTextStyle", Some(17), Some(36)),
+                ("createParagraphBullets", Some(37), Some(41)),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::prose_only("Just text\nMore text", "Just text\nMore text", vec![])]
+    #[case::flat_list("- One\n* Two", "One\nTwo", vec![(0, 7)])]
+    #[case::nested_two_spaces(
+        "- A\n  - B\n    - C\n- D",
+        "A\n\tB\n\t\tC\nD",
+        vec![(0, 10)]
+    )]
+    #[case::nested_four_spaces("- A\n    - B\n- C", "A\n\tB\nC", vec![(0, 6)])]
+    #[case::tab_indent("- A\n\t- B", "A\n\tB", vec![(0, 4)])]
+    #[case::prose_between_lists(
+        "Intro\n- A\n- B\nMiddle\n- C",
+        "Intro\nA\nB\nMiddle\nC",
+        vec![(6, 9), (17, 18)]
+    )]
+    #[case::utf16_offsets("Ünïcode 🚀\n- 🚀 go", "Ünïcode 🚀\n🚀 go", vec![(11, 16)])]
+    #[case::zwj_sequence("👨‍👩‍👧‍👦\n- kids", "👨‍👩‍👧‍👦\nkids", vec![(12, 16)])]
+    #[case::not_a_marker("-not a list\n*emphasis*", "-not a list\n*emphasis*", vec![])]
+    fn test_bulleted_text(
+        #[case] text: &str,
+        #[case] expected_text: &str,
+        #[case] expected_ranges: Vec<(usize, usize)>,
+    ) {
+        assert_eq!(
+            bulleted_text(text),
+            (expected_text.to_string(), expected_ranges)
+        );
+    }
+
+    #[rstest]
+    fn test_bulleted_text_caps_nesting() {
+        let text = (0..12)
+            .map(|depth| format!("{}- item", " ".repeat(depth * 2)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (bulleted, _) = bulleted_text(&text);
+        let deepest = bulleted.lines().last().unwrap();
+        assert_eq!(deepest, format!("{}item", "\t".repeat(MAX_BULLET_LEVEL)));
+    }
+
+    #[rstest]
+    fn test_slide_requests_bullet_only_list_paragraphs() {
+        let chunks = [Chunk::untitled(
+            "Intro\n- A\n  - B\nMiddle\n- C".to_string(),
+        )];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(json[1]["insertText"]["text"], "Intro\nA\n\tB\nMiddle\nC");
+        assert_eq!(
+            json[2],
+            serde_json::json!({"createParagraphBullets": {
+                "objectId": "slide_0_body",
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 18, "endIndex": 19},
+                "bulletPreset": "BULLET_DISC_CIRCLE_SQUARE"
+            }})
+        );
+        assert_eq!(
+            json[3],
+            serde_json::json!({"createParagraphBullets": {
+                "objectId": "slide_0_body",
+                "textRange": {"type": "FIXED_RANGE", "startIndex": 6, "endIndex": 10},
+                "bulletPreset": "BULLET_DISC_CIRCLE_SQUARE"
+            }})
+        );
+        assert_eq!(json.as_array().unwrap().len(), 4);
+    }
+
+    #[rstest]
+    fn test_markdown_emphasis_moves_bullets_with_their_paragraphs() {
+        let chunks = [Chunk::untitled(
+            "**Plan**\n- *fast* path\n- `slow`".to_string(),
+        )];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        let json = serde_json::to_value(with_inline_formatting(requests, EMPHASIS)).unwrap();
+        assert_eq!(json[1]["insertText"]["text"], "Plan\nfast path\nslow");
+        let bullets = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|request| request.get("createParagraphBullets"))
+            .collect::<Vec<_>>();
+        assert_eq!(bullets.len(), 1);
+        assert_eq!(
+            bullets[0]["textRange"],
+            serde_json::json!({"type": "FIXED_RANGE", "startIndex": 5, "endIndex": 19})
+        );
+    }
+
+    #[rstest]
+    fn test_apply_notes_by_index() {
+        let mut chunks = vec![
+            Chunk::untitled("Intro".to_string()),
+            Chunk {
+                notes: "From the marker".to_string(),
+                ..Chunk::untitled("Plans".to_string())
+            },
+            Chunk::untitled("Close".to_string()),
+        ];
+        let notes = ["Say hello", "And from the request"].map(String::from);
+        apply_notes(&mut chunks, &notes).unwrap();
+        let notes = chunks
+            .iter()
+            .map(|chunk| chunk.notes.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            ["Say hello", "From the marker\n\nAnd from the request", ""]
+        );
+    }
+
+    #[rstest]
+    fn test_apply_notes_skips_empty_entries() {
+        let mut chunks = vec![Chunk::untitled("Intro".to_string())];
+        apply_notes(&mut chunks, &[" ".to_string()]).unwrap();
+        assert!(chunks[0].notes.is_empty());
+    }
+
+    #[rstest]
+    fn test_apply_notes_rejects_more_notes_than_slides() {
+        let mut chunks = vec![Chunk::untitled("Intro".to_string())];
+        let err = apply_notes(&mut chunks, &["a".to_string(), "b".to_string()]).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("2 notes given for 1 slides"));
+    }
+
+    #[rstest]
+    fn test_apply_numbering_prefix() {
+        let mut chunks = vec![
+            Chunk::untitled("Install".to_string()),
+            Chunk::untitled("Configure\nEdit the file".to_string()),
+        ];
+        apply_numbering_prefix(&mut chunks, "Step {n}: ");
+        let bodies = chunks
+            .iter()
+            .map(|chunk| chunk.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            vec!["Step 1: Install", "Step 2: Configure\nEdit the file"]
+        );
+    }
+
+    #[rstest]
+    fn test_apply_numbering_prefix_goes_on_title() {
+        let mut chunks = vec![Chunk {
+            title: Some("Install".to_string()),
+            body: "Run the installer".to_string(),
+            notes: String::new(),
+            media: None,
+            skipped: false,
+        }];
+        apply_numbering_prefix(&mut chunks, "{n}. ");
+        assert_eq!(chunks[0].title.as_deref(), Some("1. Install"));
+        assert_eq!(chunks[0].body, "Run the installer");
+    }
+
+    #[rstest]
+    fn test_slide_requests_fill_title_and_body_placeholders() {
+        let chunks = vec![
+            Chunk {
+                title: Some("Intro".to_string()),
+                body: "Welcome".to_string(),
+                notes: String::new(),
+                media: None,
+                skipped: false,
+            },
+            Chunk::untitled("Just a body".to_string()),
+        ];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"createSlide": {
+                    "objectId": "slide_0",
+                    "insertionIndex": 0,
+                    "slideLayoutReference": {"predefinedLayout": "TITLE_AND_BODY"},
+                    "placeholderIdMappings": [
+                        {"layoutPlaceholder": {"type": "TITLE", "index": 0}, "objectId": "slide_0_title"},
+                        {"layoutPlaceholder": {"type": "BODY", "index": 0}, "objectId": "slide_0_body"}
+                    ]
+                }},
+                {"insertText": {"objectId": "slide_0_title", "insertionIndex": 0, "text": "Intro", "cellLocation": null}},
+                {"insertText": {"objectId": "slide_0_body", "insertionIndex": 0, "text": "Welcome", "cellLocation": null}},
+                {"createSlide": {
+                    "objectId": "slide_1",
+                    "insertionIndex": 1,
+                    "slideLayoutReference": {"predefinedLayout": "TITLE_AND_BODY"},
+                    "placeholderIdMappings": [
+                        {"layoutPlaceholder": {"type": "TITLE", "index": 0}, "objectId": "slide_1_title"},
+                        {"layoutPlaceholder": {"type": "BODY", "index": 0}, "objectId": "slide_1_body"}
+                    ]
+                }},
+                {"insertText": {"objectId": "slide_1_body", "insertionIndex": 0, "text": "Just a body", "cellLocation": null}}
+            ])
+        );
+    }
+
+    #[rstest]
+    fn test_slide_requests_skip_empty_body() {
+        let chunks = vec![Chunk {
+            title: Some("Only a title".to_string()),
+            body: String::new(),
+            notes: String::new(),
+            media: None,
+            skipped: false,
+        }];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].insert_text.is_some());
+    }
+
+    #[rstest]
+    #[case::step("Step {n}: ", true)]
+    #[case::number_only("{n}. ", true)]
+    #[case::missing_placeholder("Step: ", false)]
+    #[case::empty("", false)]
+    fn test_validate_numbering_prefix(#[case] template: &str, #[case] valid: bool) {
+        assert_eq!(validate_numbering_prefix(template).is_ok(), valid);
+    }
+
+    #[rstest]
+    fn test_request_rejects_prefix_without_placeholder() {
+        let request: CreateSlidesRequest = serde_json::from_str(
+            r#"{"title":"Tutorial","content":"a\nb","splitter":{"type":"newline"},"numbering_prefix":"Step: "}"#,
+        )
+        .unwrap();
+        let err = request.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("numbering_prefix must contain {n}")
+        );
+    }
+
+    fn preview_request(content: &str, splitter: Splitter) -> PreviewSplitRequest {
+        PreviewSplitRequest {
+            content: content.to_string(),
+            splitter,
+            limit: SlideLimit::default(),
+            normalize_whitespace: false,
+        }
+    }
+
+    #[rstest]
+    fn test_preview_split_prepares_text_like_create() {
+        let content = "---\ntitle: Deck\n---\none\r\n\r\ntwo\u{7}";
+        let preview = preview_split(
+            &preview_request(content, Splitter::EmptyLine),
+            &Limits::default(),
+        )
+        .unwrap();
+        let request = CreateSlidesRequest {
+            content: Content::Text(content.to_string()),
+            splitter: Some(Splitter::EmptyLine),
+            ..parse_request(r#"{"title":"Deck","content":"x","splitter":{"type":"newline"}}"#)
+        };
+        let (_, created) = split_content(&request).unwrap();
+        assert_eq!(preview.chunks, ["one", "two"]);
+        assert_eq!(preview.chunks, created);
+    }
+
+    #[rstest]
+    fn test_preview_split_returns_chunks_and_count() {
+        let response = preview_split(
+            &preview_request("one\n\ntwo", Splitter::EmptyLine),
+            &Limits::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            PreviewSplitResponse {
+                chunks: vec!["one".to_string(), "two".to_string()],
+                count: 2,
+                chunks_dropped: 0,
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_preview_split_needs_only_content_and_splitter() {
+        let request: PreviewSplitRequest =
+            serde_json::from_str(r#"{"content":"a b c","splitter":{"type":"newline"}}"#).unwrap();
+        assert_eq!(
+            preview_split(&request, &Limits::default()).unwrap().count,
+            1
+        );
+    }
+
+    #[rstest]
+    #[case::invalid_splitter(
+        preview_request("text", Splitter::MaxWords { max_words: 0, respect_sentences: false, overlap: 0, segmentation: Default::default(), balanced: false }),
+        "max_words must be at least 1"
+    )]
+    #[case::empty_content(preview_request("", Splitter::NewLine), "content")]
+    #[case::no_chunks(
+        preview_request("\n\n", Splitter::NewLine),
+        "No content chunks generated"
+    )]
+    #[case::too_many_chunks(
+        preview_request(&"line\n".repeat(MAX_SLIDES + 1), Splitter::NewLine),
+        "Too many slides: 101 (max 100)"
+    )]
+    fn test_preview_split_rejects(#[case] request: PreviewSplitRequest, #[case] message: &str) {
+        let err = preview_split(&request, &Limits::default()).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains(message), "{err}");
+    }
+
+    #[rstest]
+    fn test_preview_split_allows_max_slides() {
+        let content = "line\n".repeat(MAX_SLIDES);
+        let response = preview_split(
+            &preview_request(&content, Splitter::NewLine),
+            &Limits::default(),
+        )
+        .unwrap();
+        assert_eq!(response.count, MAX_SLIDES);
+    }
+
+    #[rstest]
+    fn test_slide_stats_count_title_and_body_but_not_notes() {
+        let chunks = vec![
+            Chunk {
+                title: Some("Two words".to_string()),
+                body: "three more words".to_string(),
+                notes: "ignored notes here".to_string(),
+                media: None,
+                skipped: false,
+            },
+            Chunk::untitled("one".to_string()),
+        ];
+        let stats = slide_stats(&chunks, DEFAULT_LONG_SLIDE_WORDS);
+        assert_eq!(
+            stats,
+            vec![
+                SlideStats {
+                    index: 0,
+                    words: 5,
+                    chars: 25,
+                    warning: None,
+                },
+                SlideStats {
+                    index: 1,
+                    words: 1,
+                    chars: 3,
+                    warning: None,
+                },
+            ]
+        );
+        assert_eq!(
+            slide_totals(&stats),
+            SlideTotals {
+                words: 6,
+                chars: 28
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::below(2, None)]
+    #[case::at_threshold(3, None)]
+    #[case::above(4, Some(SlideWarning::Long))]
+    fn test_slide_stats_flag_long_slides(
+        #[case] words: usize,
+        #[case] expected: Option<SlideWarning>,
+    ) {
+        let chunks = vec![Chunk::untitled(vec!["word"; words].join(" "))];
+        assert_eq!(slide_stats(&chunks, 3)[0].warning, expected);
+    }
+
+    #[rstest]
+    fn test_slide_stats_serialize_warning_only_when_set() {
+        let chunks = vec![
+            Chunk::untitled("short".to_string()),
+            Chunk::untitled("far too long".to_string()),
+        ];
+        let json = serde_json::to_value(slide_stats(&chunks, 1)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"index": 0, "words": 1, "chars": 5},
+                {"index": 1, "words": 3, "chars": 12, "warning": "long"},
+            ])
+        );
+    }
+
+    #[rstest]
+    fn test_long_slide_words_defaults_and_rejects_zero() {
+        let request: CreateSlidesRequest =
+            serde_json::from_str(r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#)
+                .unwrap();
+        assert_eq!(request.long_slide_words, DEFAULT_LONG_SLIDE_WORDS);
+
+        let request: CreateSlidesRequest = serde_json::from_str(
+            r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"long_slide_words":0}"#,
+        )
+        .unwrap();
+        assert!(request.validate().is_err());
+    }
+
+    fn numbered_chunks(count: usize) -> Vec<String> {
+        (1..=count).map(|n| n.to_string()).collect()
+    }
+
+    fn limit(overflow: Overflow) -> SlideLimit {
+        SlideLimit {
+            max_slides: None,
+            overflow,
+        }
+    }
+
+    #[rstest]
+    #[case::error(Overflow::Error)]
+    #[case::truncate(Overflow::Truncate)]
+    #[case::merge(Overflow::Merge)]
+    fn test_slide_limit_keeps_chunks_at_limit(#[case] overflow: Overflow) {
+        let (chunks, dropped) = limit(overflow)
+            .apply(numbered_chunks(100), 0, &Limits::default())
+            .unwrap();
+        assert_eq!(chunks, numbered_chunks(100));
+        assert_eq!(dropped, 0);
+    }
+
+    #[rstest]
+    #[case::one_over(101, "Too many slides: 101 (max 100)")]
+    #[case::far_over(1000, "Too many slides: 1000 (max 100)")]
+    fn test_slide_limit_error_rejects_overflow(#[case] count: usize, #[case] message: &str) {
+        let err = limit(Overflow::Error)
+            .apply(numbered_chunks(count), 0, &Limits::default())
+            .unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains(message), "{err}");
+    }
+
+    #[rstest]
+    #[case::unset(None, MAX_SLIDES)]
+    #[case::lower(Some("20"), 20)]
+    #[case::padded(Some(" 150 "), 150)]
+    #[case::at_ceiling(Some("250"), MAX_SLIDES_CEILING)]
+    #[case::above_ceiling(Some("1000"), MAX_SLIDES_CEILING)]
+    #[case::zero(Some("0"), MAX_SLIDES)]
+    #[case::negative(Some("-5"), MAX_SLIDES)]
+    #[case::not_a_number(Some("lots"), MAX_SLIDES)]
+    fn test_limits_from_env_var(#[case] value: Option<&str>, #[case] expected: usize) {
+        assert_eq!(Limits::from_env_var(value).max_slides, expected);
+    }
+
+    #[rstest]
+    #[case::at_limit(20, Ok(20))]
+    #[case::one_over(21, Err("Too many slides: 21 (max 20)"))]
+    fn test_slide_limit_follows_env_override(
+        #[case] count: usize,
+        #[case] expected: std::result::Result<usize, &str>,
+    ) {
+        let limits = Limits::from_env_var(Some("20"));
+        let result = limit(Overflow::Error).apply(numbered_chunks(count), 0, &limits);
+        match expected {
+            Ok(len) => assert_eq!(result.unwrap().0.len(), len),
+            Err(message) => assert!(result.unwrap_err().to_string().contains(message)),
+        }
+    }
+
+    #[rstest]
+    #[case::default_server(MAX_SLIDES, Err("max_slides 150 exceeds the server limit of 100"))]
+    #[case::raised_server(200, Ok(150))]
+    fn test_requested_max_slides_within_server_limit(
+        #[case] server_max: usize,
+        #[case] expected: std::result::Result<usize, &str>,
+    ) {
+        let limit = SlideLimit {
+            max_slides: Some(150),
+            overflow: Overflow::Truncate,
+        };
+        let limits = Limits {
+            max_slides: server_max,
+        };
+        let result = limit.apply(numbered_chunks(300), 0, &limits);
+        match expected {
+            Ok(len) => assert_eq!(result.unwrap().0.len(), len),
+            Err(message) => assert!(result.unwrap_err().to_string().contains(message)),
+        }
+    }
+
+    #[rstest]
+    #[case::one_over(101, 1)]
+    #[case::far_over(1000, 900)]
+    fn test_slide_limit_truncate_drops_overflow(#[case] count: usize, #[case] expected: usize) {
+        let (chunks, dropped) = limit(Overflow::Truncate)
+            .apply(numbered_chunks(count), 0, &Limits::default())
+            .unwrap();
+        assert_eq!(chunks, numbered_chunks(100));
+        assert_eq!(dropped, expected);
+    }
+
+    #[rstest]
+    #[case::one_over(101, "100\n101")]
+    #[case::far_over(250, &(100..=250).map(|n| n.to_string()).collect::<Vec<_>>().join("\n"))]
+    fn test_slide_limit_merge_appends_overflow_to_last_slide(
+        #[case] count: usize,
+        #[case] last: &str,
+    ) {
+        let (chunks, dropped) = limit(Overflow::Merge)
+            .apply(numbered_chunks(count), 0, &Limits::default())
+            .unwrap();
+        assert_eq!(chunks.len(), 100);
+        assert_eq!(chunks[..99], numbered_chunks(99));
+        assert_eq!(chunks[99], last);
+        assert_eq!(dropped, 0);
+    }
+
+    #[rstest]
+    #[case::error(Overflow::Error)]
+    #[case::truncate(Overflow::Truncate)]
+    #[case::merge(Overflow::Merge)]
+    fn test_slide_limit_rejects_no_chunks(#[case] overflow: Overflow) {
+        let err = limit(overflow)
+            .apply(Vec::<String>::new(), 0, &Limits::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("No content chunks generated"));
+    }
+
+    #[rstest]
+    #[case::error(Overflow::Error, None)]
+    #[case::truncate(Overflow::Truncate, Some((MAX_SLIDES - 1, 1)))]
+    fn test_slide_limit_counts_reserved_slides(
+        #[case] overflow: Overflow,
+        #[case] expected: Option<(usize, usize)>,
+    ) {
+        let result = limit(overflow).apply(numbered_chunks(MAX_SLIDES), 1, &Limits::default());
+        assert_eq!(
+            result.ok().map(|(chunks, dropped)| (chunks.len(), dropped)),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_slide_limit_without_room_for_content() {
+        let limit = SlideLimit {
+            max_slides: Some(1),
+            overflow: Overflow::Merge,
+        };
+        let err = limit
+            .apply(numbered_chunks(2), 1, &Limits::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("including the title slide"));
+    }
+
+    #[rstest]
+    fn test_slide_limit_uses_requested_max_slides() {
+        let limit = SlideLimit {
+            max_slides: Some(3),
+            overflow: Overflow::Truncate,
+        };
+        assert_eq!(
+            limit
+                .apply(numbered_chunks(5), 0, &Limits::default())
+                .unwrap(),
+            (numbered_chunks(3), 2)
+        );
+    }
+
+    #[rstest]
+    #[case::defaults(r#"{}"#, Some(SlideLimit::default()))]
+    #[case::custom(
+        r#"{"max_slides":10,"overflow":"merge"}"#,
+        Some(SlideLimit { max_slides: Some(10), overflow: Overflow::Merge })
+    )]
+    #[case::zero(r#"{"max_slides":0}"#, None)]
+    #[case::above_default_limit(
+        r#"{"max_slides":101}"#,
+        Some(SlideLimit { max_slides: Some(101), overflow: Overflow::Error })
+    )]
+    #[case::above_ceiling(r#"{"max_slides":251}"#, None)]
+    fn test_slide_limit_request_fields(#[case] fields: &str, #[case] expected: Option<SlideLimit>) {
+        let mut request: serde_json::Value =
+            serde_json::from_str(r#"{"title":"Deck","content":"a","splitter":{"type":"newline"}}"#)
+                .unwrap();
+        let fields: serde_json::Value = serde_json::from_str(fields).unwrap();
+        for (key, value) in fields.as_object().unwrap() {
+            request[key] = value.clone();
+        }
+        let request: CreateSlidesRequest = serde_json::from_value(request).unwrap();
+        match expected {
+            Some(limit) => {
+                assert_eq!(request.limit, limit);
+                assert!(request.validate().is_ok());
+            }
+            None => assert!(request.validate().is_err()),
+        }
+    }
+
+    #[rstest]
+    fn test_preview_split_reports_truncation() {
+        let mut request = preview_request(&"line\n".repeat(MAX_SLIDES + 5), Splitter::NewLine);
+        request.limit.overflow = Overflow::Truncate;
+        let response = preview_split(&request, &Limits::default()).unwrap();
+        assert_eq!(response.count, MAX_SLIDES);
+        assert_eq!(response.chunks_dropped, 5);
+    }
+
+    fn parse_request(json: &str) -> CreateSlidesRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[rstest]
+    fn test_text_content_is_split() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":"---\ntitle: Meta\n---\none\ntwo","splitter":{"type":"newline"}}"#,
+        );
+        assert!(matches!(request.content, Content::Text(_)));
+        assert!(request.validate().is_ok());
+        let (front_matter, chunks) = split_content(&request).unwrap();
+        assert_eq!(front_matter.unwrap().title(), Some("Meta"));
+        assert_eq!(chunks, vec!["one", "two"]);
+    }
+
+    #[rstest]
+    fn test_slide_array_content_bypasses_splitter() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":["First slide\n\nstill first","Second\u00a0slide"],"normalize_whitespace":true}"#,
+        );
+        assert!(matches!(request.content, Content::Slides(_)));
+        assert!(request.validate().is_ok());
+        let (front_matter, chunks) = split_content(&request).unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(chunks, vec!["First slide\n\nstill first", "Second slide"]);
+    }
+
+    #[rstest]
+    #[case::empty_text(
+        r#"{"title":"Deck","content":"","splitter":{"type":"newline"}}"#,
+        "content must not be empty"
+    )]
+    #[case::empty_array(r#"{"title":"Deck","content":[]}"#, "at least one slide")]
+    #[case::blank_slide(
+        r#"{"title":"Deck","content":["ok","  "]}"#,
+        "slide 2 in content is empty"
+    )]
+    #[case::control_chars_only(
+        r#"{"title":"Deck","content":"\u0000\u0001","splitter":{"type":"newline"}}"#,
+        "content must not be empty"
+    )]
+    #[case::control_chars_only_slide(
+        r#"{"title":"Deck","content":["ok","\u0000"]}"#,
+        "slide 2 in content is empty"
+    )]
+    #[case::text_without_splitter(r#"{"title":"Deck","content":"text"}"#, "splitter is required")]
+    #[case::array_with_splitter(
+        r#"{"title":"Deck","content":["one","two"],"splitter":{"type":"newline"}}"#,
+        "splitter cannot be combined"
+    )]
+    fn test_content_validation_errors(#[case] json: &str, #[case] message: &str) {
+        let err = parse_request(json).validate().unwrap_err();
+        assert!(err.to_string().contains(message), "{err}");
+    }
+
+    #[rstest]
+    #[case::number(r#"{"title":"Deck","content":42}"#)]
+    #[case::array_of_numbers(r#"{"title":"Deck","content":[1,2]}"#)]
+    fn test_content_of_other_types_is_rejected(#[case] json: &str) {
+        assert!(serde_json::from_str::<CreateSlidesRequest>(json).is_err());
+    }
+
+    #[rstest]
+    fn test_slide_array_keeps_title_validation() {
+        let request = parse_request(r#"{"content":["only slide"]}"#);
+        assert!(request.validate().is_ok());
+        let (front_matter, _) = split_content(&request).unwrap();
+        assert!(resolve_title(&request, front_matter.as_ref()).is_err());
+    }
+
+    #[rstest]
+    fn test_slide_array_respects_slide_limit() {
+        let request = parse_request(&format!(
+            r#"{{"title":"Deck","content":{}}}"#,
+            serde_json::to_string(&numbered_chunks(MAX_SLIDES + 1)).unwrap()
+        ));
+        let (_, chunks) = split_content(&request).unwrap();
+        assert!(request.limit.apply(chunks, 0, &Limits::default()).is_err());
+    }
+
+    const CREATED_PRESENTATION: &str = include_str!("../tests/fixtures/created_presentation.json");
+    const POPULATED_PRESENTATION: &str =
+        include_str!("../tests/fixtures/populated_presentation.json");
+
+    /// Placeholder type of every page element on the fixture's slides, by object ID.
+    fn fixture_placeholders(json: &str) -> std::collections::HashMap<String, String> {
+        let presentation: serde_json::Value = serde_json::from_str(json).unwrap();
+        presentation["slides"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|slide| slide["pageElements"].as_array().unwrap())
+            .map(|element| {
+                (
+                    element["objectId"].as_str().unwrap().to_string(),
+                    element["shape"]["placeholder"]["type"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn fixture_chunks() -> Vec<Chunk> {
+        vec![
+            Chunk {
+                title: Some("Intro".to_string()),
+                body: "Welcome".to_string(),
+                notes: "Thank everyone for coming".to_string(),
+                media: None,
+                skipped: false,
+            },
+            Chunk::untitled("Just a body".to_string()),
+        ]
+    }
+
+    #[rstest]
+    fn test_created_presentation_fixture_parses() {
+        let presentation: Presentation = serde_json::from_str(CREATED_PRESENTATION).unwrap();
+        assert_eq!(presentation.title, "Quarterly Review");
+        assert_eq!(presentation.slides.len(), 1);
+        assert_eq!(presentation.slides[0].object_id, "p");
+        let page_size = presentation.page_size.unwrap();
+        assert_eq!(page_size.width.magnitude, 9_144_000.0);
+        assert_eq!(page_size.height.magnitude, 5_143_500.0);
+    }
+
+    /// The default slide's placeholders are subtitle and centered title, so
+    /// text must go into the placeholders mapped on the new slides instead.
+    #[rstest]
+    fn test_slide_requests_insert_into_real_placeholders() {
+        let placeholders = fixture_placeholders(POPULATED_PRESENTATION);
+        let requests = slide_requests(
+            &fixture_chunks(),
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        );
+
+        let inserts = requests
+            .iter()
+            .filter_map(|request| request.insert_text.as_ref())
+            .map(|insert| {
+                (
+                    placeholders.get(&insert.object_id).map(String::as_str),
+                    insert.text.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inserts,
+            vec![
+                (Some("TITLE"), "Intro"),
+                (Some("BODY"), "Welcome"),
+                (Some("BODY"), "Just a body"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_notes_requests_use_populated_presentation_ids() {
+        let populated: Presentation = serde_json::from_str(POPULATED_PRESENTATION).unwrap();
+        let requests = notes_requests(&fixture_chunks(), &populated.slides, "");
+        assert_eq!(requests.len(), 1);
+        let insert = requests[0].insert_text.as_ref().unwrap();
+        assert_eq!(insert.object_id, "g2f1a0c3b9e1_0_1");
+        assert_eq!(insert.text, "Thank everyone for coming");
+    }
+
+    #[rstest]
+    #[case::default(SlideLayoutReference::default(), serde_json::json!({"predefinedLayout": "TITLE_AND_BODY"}))]
+    #[case::two_columns(
+        SlideLayoutReference::PredefinedLayout(PredefinedLayout::TitleAndTwoColumns),
+        serde_json::json!({"predefinedLayout": "TITLE_AND_TWO_COLUMNS"})
+    )]
+    #[case::layout_id(
+        SlideLayoutReference::LayoutId("g2f1a0c3b9e1_0_12".to_string()),
+        serde_json::json!({"layoutId": "g2f1a0c3b9e1_0_12"})
+    )]
+    fn test_slide_layout_reference_api_json(
+        #[case] layout: SlideLayoutReference,
+        #[case] expected: serde_json::Value,
+    ) {
+        let requests = slide_requests(&[Chunk::untitled("Body".to_string())], "", &layout, None);
+        let json = serde_json::to_value(&requests[0]).unwrap();
+        assert_eq!(json["createSlide"]["slideLayoutReference"], expected);
+    }
+
+    #[rstest]
+    #[case::paragraphs("aaaa\nbb\ncccc", ("aaaa\nbb", "cccc"))]
+    #[case::blank_line_between("one two\n\nthree four", ("one two", "three four"))]
+    #[case::words_only("alpha beta gamma delta", ("alpha beta", "gamma delta"))]
+    #[case::line_break_preferred_over_nearer_space("a b c d e f g\nh", ("a b c d e f g", "h"))]
+    #[case::single_word("indivisible", ("indivisible", ""))]
+    #[case::multibyte("été été\nœuf œuf", ("été été", "œuf œuf"))]
+    fn test_split_columns(#[case] text: &str, #[case] expected: (&str, &str)) {
+        assert_eq!(split_columns(text), expected);
+    }
+
+    #[rstest]
+    #[case::titled_16_9((SLIDE_WIDTH_EMU, SLIDE_HEIGHT_EMU), true, 4_000_500.0, 1_285_875.0, 3_400_425.0)]
+    #[case::untitled_16_9((SLIDE_WIDTH_EMU, SLIDE_HEIGHT_EMU), false, 4_000_500.0, 457_200.0, 4_229_100.0)]
+    #[case::titled_4_3((9_144_000.0, 6_858_000.0), true, 4_000_500.0, 1_714_500.0, 4_686_300.0)]
+    fn test_column_element_math(
+        #[case] page: (f64, f64),
+        #[case] titled: bool,
+        #[case] width: f64,
+        #[case] top: f64,
+        #[case] height: f64,
+    ) {
+        let left = column_element("slide_0", 0, page, titled);
+        let right = column_element("slide_0", 1, page, titled);
+        for column in [&left, &right] {
+            assert_eq!(column.page_object_id, "slide_0");
+            assert_eq!(column.size.width.magnitude, width);
+            assert_eq!(column.size.height.magnitude, height);
+            assert_eq!(column.transform.translate_y, top);
+        }
+        assert_eq!(left.transform.translate_x, SLIDE_MARGIN_EMU);
+        assert_eq!(
+            right.transform.translate_x,
+            SLIDE_MARGIN_EMU + width + COLUMN_GAP_EMU
+        );
+        // The right column ends at the right margin
+        assert_eq!(
+            right.transform.translate_x + width,
+            page.0 - SLIDE_MARGIN_EMU
+        );
+    }
+
+    #[rstest]
+    fn test_two_column_slide_requests() {
+        let chunks = [
+            Chunk {
+                title: Some("Pros and cons".to_string()),
+                ..Chunk::untitled("Fast\nCheap".to_string())
+            },
+            Chunk::untitled("Left\nRight".to_string()),
+        ];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::TwoColumn,
+            None,
+        ))
+        .unwrap();
+        let requests = json.as_array().unwrap();
+        assert_eq!(
+            requests[0]["createSlide"]["slideLayoutReference"],
+            serde_json::json!({"predefinedLayout": "TITLE_ONLY"})
+        );
+        let shapes: Vec<_> = requests
+            .iter()
+            .filter_map(|request| request["createShape"]["objectId"].as_str())
+            .collect();
+        assert_eq!(
+            shapes,
+            [
+                "slide_0_column_0",
+                "slide_0_column_1",
+                "slide_1_column_0",
+                "slide_1_column_1"
+            ]
+        );
+        let inserted: Vec<_> = requests
+            .iter()
+            .filter(|request| request["insertText"].is_object())
+            .map(|request| {
+                (
+                    request["insertText"]["objectId"].as_str().unwrap(),
+                    request["insertText"]["text"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            inserted,
+            [
+                ("slide_0_title", "Pros and cons"),
+                ("slide_0_column_0", "Fast"),
+                ("slide_0_column_1", "Cheap"),
+                ("slide_1_column_0", "Left"),
+                ("slide_1_column_1", "Right"),
+            ]
+        );
+        let untitled = requests
+            .iter()
+            .filter_map(|request| request["createSlide"]["slideLayoutReference"].as_object())
+            .nth(1)
+            .unwrap();
+        assert_eq!(untitled["predefinedLayout"], "BLANK");
+        assert!(!json.to_string().contains("two_column"));
+    }
+
+    #[rstest]
+    fn test_two_column_skips_empty_column() {
+        let chunks = [Chunk::untitled("Unbroken".to_string())];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::TwoColumn, None);
+        let shapes = requests
+            .iter()
+            .filter(|request| request.create_shape.is_some())
+            .count();
+        assert_eq!(shapes, 1);
+    }
+
+    #[rstest]
+    #[case::omitted("", Some(SlideLayoutReference::default()))]
+    #[case::two_column(r#","layout":"two_column""#, Some(SlideLayoutReference::TwoColumn))]
+    #[case::snake_case_predefined(
+        r#","layout":{"predefined_layout":"ONE_COLUMN_TEXT"}"#,
+        Some(SlideLayoutReference::PredefinedLayout(PredefinedLayout::OneColumnText))
+    )]
+    #[case::camel_case_predefined(
+        r#","layout":{"predefinedLayout":"SECTION_TITLE_AND_DESCRIPTION"}"#,
+        Some(SlideLayoutReference::PredefinedLayout(
+            PredefinedLayout::SectionTitleAndDescription
+        ))
+    )]
+    #[case::layout_id(
+        r#","layout":{"layout_id":"g123"}"#,
+        Some(SlideLayoutReference::LayoutId("g123".to_string()))
+    )]
+    #[case::layout_without_body_placeholder(r#","layout":{"predefined_layout":"BLANK"}"#, None)]
+    #[case::title_slide_layout(r#","layout":{"predefined_layout":"TITLE"}"#, None)]
+    #[case::both_keys(
+        r#","layout":{"predefined_layout":"TITLE_AND_BODY","layout_id":"g123"}"#,
+        None
+    )]
+    fn test_layout_request_field(
+        #[case] layout: &str,
+        #[case] expected: Option<SlideLayoutReference>,
+    ) {
+        let json =
+            format!(r#"{{"title":"Deck","content":"a","splitter":{{"type":"newline"}}{layout}}}"#);
+        let request = serde_json::from_str::<CreateSlidesRequest>(&json);
+        assert_eq!(request.ok().map(|request| request.layout), expected);
+    }
+
+    #[rstest]
+    fn test_layout_rejects_empty_layout_id() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"layout":{"layout_id":" "}}"#,
+        );
+        let err = request.validate().unwrap_err();
+        assert!(err.to_string().contains("layout_id must not be empty"));
+    }
+
+    #[rstest]
+    fn test_slide_requests_for_first_line_titles() {
+        let chunks = ["Agenda\nIntro\nDemo", "Questions?"]
+            .into_iter()
+            .map(Chunk::from_first_line)
+            .collect::<Vec<_>>();
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"createSlide": {
+                    "objectId": "slide_0",
+                    "insertionIndex": 0,
+                    "slideLayoutReference": {"predefinedLayout": "TITLE_AND_BODY"},
+                    "placeholderIdMappings": [
+                        {"layoutPlaceholder": {"type": "TITLE", "index": 0}, "objectId": "slide_0_title"},
+                        {"layoutPlaceholder": {"type": "BODY", "index": 0}, "objectId": "slide_0_body"}
+                    ]
+                }},
+                {"insertText": {"objectId": "slide_0_title", "insertionIndex": 0, "text": "Agenda", "cellLocation": null}},
+                {"insertText": {"objectId": "slide_0_body", "insertionIndex": 0, "text": "Intro\nDemo", "cellLocation": null}},
+                {"createSlide": {
+                    "objectId": "slide_1",
+                    "insertionIndex": 1,
+                    "slideLayoutReference": {"predefinedLayout": "TITLE_ONLY"},
+                    "placeholderIdMappings": [
+                        {"layoutPlaceholder": {"type": "TITLE", "index": 0}, "objectId": "slide_1_title"}
+                    ]
+                }},
+                {"insertText": {"objectId": "slide_1_title", "insertionIndex": 0, "text": "Questions?", "cellLocation": null}}
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::bare_url("https://example.com/cat.png", Some(("https://example.com/cat.png", "")))]
+    #[case::query_string(
+        "  http://example.com/a/b.JPG?size=large \n",
+        Some(("http://example.com/a/b.JPG?size=large", ""))
+    )]
+    #[case::markdown(
+        "![A cat](https://example.com/cat.gif)",
+        Some(("https://example.com/cat.gif", ""))
+    )]
+    #[case::markdown_with_caption(
+        "![](https://example.com/chart.jpeg)\nRevenue by quarter",
+        Some(("https://example.com/chart.jpeg", "Revenue by quarter"))
+    )]
+    #[case::url_with_more_text("https://example.com/cat.png\nMore text", None)]
+    #[case::url_in_sentence("See https://example.com/cat.png", None)]
+    #[case::not_an_image("https://example.com/page.html", None)]
+    #[case::no_path("https://cat.png", None)]
+    #[case::data_url("data:image/png;base64,iVBORw0KGgo=", None)]
+    #[case::markdown_data_url("![x](data:image/png;base64,iVBORw0KGgo=)", None)]
+    #[case::ftp("ftp://example.com/cat.png", None)]
+    #[case::drive_file("drive:1AbC-d_EF23", Some(("drive:1AbC-d_EF23", "")))]
+    #[case::markdown_drive_file(
+        "![Logo](drive:1AbC)\nOur logo",
+        Some(("drive:1AbC", "Our logo"))
+    )]
+    #[case::drive_without_id("drive:", None)]
+    #[case::markdown_with_title(r#"![x](https://example.com/cat.png "Cat")"#, None)]
+    fn test_image_url(#[case] text: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(image_url(text), expected);
+    }
+
+    #[rstest]
+    fn test_image_slides_use_blank_layout_and_separate_image_requests() {
+        let chunks = [
+            Chunk::untitled("Intro".to_string()),
+            Chunk::image("https://example.com/cat.png".to_string()),
+        ];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(
+            json[2],
+            serde_json::json!({"createSlide": {
+                "objectId": "slide_1",
+                "insertionIndex": 1,
+                "slideLayoutReference": {"predefinedLayout": "BLANK"}
+            }})
+        );
+        assert_eq!(json.as_array().unwrap().len(), 3);
+
+        let images = image_requests(&chunks, "");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].0, 1);
+        assert_eq!(
+            serde_json::to_value(&images[0].1).unwrap(),
+            serde_json::json!({"createImage": {
+                "objectId": "slide_1_image",
+                "url": "https://example.com/cat.png",
+                "elementProperties": {
+                    "pageObjectId": "slide_1",
+                    "size": {
+                        "width": {"magnitude": 8229600.0, "unit": "EMU"},
+                        "height": {"magnitude": 4229100.0, "unit": "EMU"}
+                    },
+                    "transform": {
+                        "scaleX": 1.0,
+                        "scaleY": 1.0,
+                        "translateX": 457200.0,
+                        "translateY": 457200.0,
+                        "unit": "EMU"
+                    }
+                }
+            }})
+        );
+    }
+
+    #[rstest]
+    #[case::watch("https://www.youtube.com/watch?v=dQw4w9WgXcQ", Some(("dQw4w9WgXcQ", None)))]
+    #[case::watch_without_www("https://youtube.com/watch?v=dQw4w9WgXcQ", Some(("dQw4w9WgXcQ", None)))]
+    #[case::mobile("http://m.youtube.com/watch?v=dQw4w9WgXcQ", Some(("dQw4w9WgXcQ", None)))]
+    #[case::other_params_first(
+        "https://www.youtube.com/watch?list=PL1&v=dQw4w9WgXcQ&t=42",
+        Some(("dQw4w9WgXcQ", Some(42)))
+    )]
+    #[case::short_link("https://youtu.be/dQw4w9WgXcQ", Some(("dQw4w9WgXcQ", None)))]
+    #[case::short_link_seconds("https://youtu.be/dQw4w9WgXcQ?t=90s", Some(("dQw4w9WgXcQ", Some(90))))]
+    #[case::short_link_minutes(
+        "https://youtu.be/dQw4w9WgXcQ?si=abc&t=1m30s",
+        Some(("dQw4w9WgXcQ", Some(90)))
+    )]
+    #[case::start_param(
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ&start=5",
+        Some(("dQw4w9WgXcQ", Some(5)))
+    )]
+    #[case::bad_timestamp(
+        "https://youtu.be/dQw4w9WgXcQ?t=soon",
+        Some(("dQw4w9WgXcQ", None))
+    )]
+    #[case::fragment("https://youtu.be/dQw4w9WgXcQ#comments", Some(("dQw4w9WgXcQ", None)))]
+    #[case::surrounding_whitespace("  https://youtu.be/dQw4w9WgXcQ\n", Some(("dQw4w9WgXcQ", None)))]
+    #[case::with_text("Watch https://youtu.be/dQw4w9WgXcQ", None)]
+    #[case::short_id("https://youtu.be/dQw4w9W", None)]
+    #[case::bad_id_chars("https://youtu.be/dQw4w9WgX!Q", None)]
+    #[case::watch_without_id("https://www.youtube.com/watch?list=PL1", None)]
+    #[case::channel("https://www.youtube.com/@channel", None)]
+    #[case::lookalike_host("https://notyoutube.com/watch?v=dQw4w9WgXcQ", None)]
+    #[case::vimeo("https://vimeo.com/76979871", None)]
+    fn test_youtube_video(#[case] text: &str, #[case] expected: Option<(&str, Option<u32>)>) {
+        assert_eq!(youtube_video(text), expected);
+    }
+
+    #[rstest]
+    #[case::seconds("42", Some(42))]
+    #[case::seconds_suffix("42s", Some(42))]
+    #[case::minutes_seconds("1m30s", Some(90))]
+    #[case::hours("1h2m3s", Some(3723))]
+    #[case::minutes_only("2m", Some(120))]
+    #[case::trailing_digits("1m30", None)]
+    #[case::unknown_unit("5d", None)]
+    #[case::empty("", None)]
+    #[case::overflow("99999999999", None)]
+    fn test_youtube_timestamp(#[case] value: &str, #[case] expected: Option<u32>) {
+        assert_eq!(youtube_timestamp(value), expected);
+    }
+
+    #[rstest]
+    #[case::widescreen((SLIDE_WIDTH_EMU, SLIDE_HEIGHT_EMU), 7_518_400.0, 4_229_100.0)]
+    #[case::standard((9_144_000.0, 6_858_000.0), 8_229_600.0, 4_629_150.0)]
+    fn test_video_element_is_centered(
+        #[case] page: (f64, f64),
+        #[case] width: f64,
+        #[case] height: f64,
+    ) {
+        let element = video_element("slide_0", page);
+        assert_eq!(element.size.width.magnitude, width);
+        assert_eq!(element.size.height.magnitude, height);
+        assert_eq!(element.transform.translate_x * 2.0 + width, page.0);
+        assert_eq!(element.transform.translate_y * 2.0 + height, page.1);
+        assert!(element.transform.translate_x >= SLIDE_MARGIN_EMU);
+        assert!(element.transform.translate_y >= SLIDE_MARGIN_EMU);
+    }
+
+    #[rstest]
+    fn test_video_slide_requests() {
+        let chunks = [Chunk::video("dQw4w9WgXcQ".to_string(), Some(42))];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"createSlide": {
+                    "objectId": "slide_0",
+                    "insertionIndex": 0,
+                    "slideLayoutReference": {"predefinedLayout": "BLANK"}
+                }},
+                {"createVideo": {
+                    "objectId": "slide_0_video",
+                    "elementProperties": {
+                        "pageObjectId": "slide_0",
+                        "size": {
+                            "width": {"magnitude": 7518400.0, "unit": "EMU"},
+                            "height": {"magnitude": 4229100.0, "unit": "EMU"}
+                        },
+                        "transform": {
+                            "scaleX": 1.0,
+                            "scaleY": 1.0,
+                            "translateX": 812800.0,
+                            "translateY": 457200.0,
+                            "unit": "EMU"
+                        }
+                    },
+                    "source": "YOUTUBE",
+                    "id": "dQw4w9WgXcQ"
+                }},
+                {"updateVideoProperties": {
+                    "objectId": "slide_0_video",
+                    "videoProperties": {"start": 42},
+                    "fields": "start"
+                }}
+            ])
+        );
+        let batches = batches(
+            slide_requests(&chunks, "", &SlideLayoutReference::default(), None),
+            BatchLimits::default(),
+        );
+        assert_eq!(batches.len(), 1);
+    }
+
+    #[rstest]
+    fn test_video_without_start_has_no_properties_request() {
+        let chunks = [Chunk::video("dQw4w9WgXcQ".to_string(), None)];
+        let requests = slide_requests(&chunks, "", &SlideLayoutReference::default(), None);
+        assert!(requests[1].create_video.is_some());
+        assert!(
+            requests
+                .iter()
+                .all(|request| request.update_video_properties.is_none())
+        );
+    }
+
+    #[rstest]
+    fn test_youtube_chunks_become_video_slides() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":"https://youtu.be/dQw4w9WgXcQ?t=42\n\nhttps://vimeo.com/76979871","splitter":{"type":"empty_line"}}"#,
+        );
+        let chunks = prepare_slides(&request, &Limits::default()).unwrap().chunks;
+        assert_eq!(
+            chunks[0].media,
+            Some(Media::Video {
+                id: "dQw4w9WgXcQ".to_string(),
+                start: Some(42)
+            })
+        );
+        // Other video sites stay text, where the URL becomes a link
+        assert_eq!(chunks[1].media, None);
+        assert_eq!(chunks[1].body, "https://vimeo.com/76979871");
+    }
+
+    #[rstest]
+    fn test_numbering_prefix_skips_image_slides() {
+        let mut chunks = vec![
+            Chunk::image("https://example.com/cat.png".to_string()),
+            Chunk::untitled("Text".to_string()),
+        ];
+        apply_numbering_prefix(&mut chunks, "{n}. ");
+        assert_eq!(chunks[0].body, "");
+        assert_eq!(chunks[1].body, "2. Text");
+    }
+
+    #[rstest]
+    fn test_title_only_layout_is_not_a_request_layout() {
+        let request = parse_request(
+            r#"{"title":"Deck","content":"a","splitter":{"type":"newline"},"layout":{"predefined_layout":"TITLE_ONLY"}}"#,
+        );
+        assert!(request.validate().is_err());
+    }
+
+    #[rstest]
+    fn test_table_slide_requests() {
+        let rows = markdown_table("| Name | Role |\n|------|:----:|\n| Ada | Eng |\n| Lin | PM |")
+            .unwrap();
+        let chunks = [Chunk::table(rows)];
+        let json = serde_json::to_value(slide_requests(
+            &chunks,
+            "",
+            &SlideLayoutReference::default(),
+            None,
+        ))
+        .unwrap();
+        let cell = |text: &str, row: i32, column: i32| {
+            serde_json::json!({"insertText": {
+                "objectId": "slide_0_table",
+                "insertionIndex": 0,
+                "text": text,
+                "cellLocation": {"rowIndex": row, "columnIndex": column}
+            }})
+        };
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"createSlide": {
+                    "objectId": "slide_0",
+                    "insertionIndex": 0,
+                    "slideLayoutReference": {"predefinedLayout": "BLANK"}
+                }},
+                {"createTable": {
+                    "objectId": "slide_0_table",
+                    "elementProperties": {
+                        "pageObjectId": "slide_0",
+                        "size": {
+                            "width": {"magnitude": 8229600.0, "unit": "EMU"},
+                            "height": {"magnitude": 4229100.0, "unit": "EMU"}
+                        },
+                        "transform": {
+                            "scaleX": 1.0,
+                            "scaleY": 1.0,
+                            "translateX": 457200.0,
+                            "translateY": 457200.0,
+                            "unit": "EMU"
+                        }
+                    },
+                    "rows": 3,
+                    "columns": 2
+                }},
+                cell("Name", 0, 0),
+                cell("Role", 0, 1),
+                cell("Ada", 1, 0),
+                cell("Eng", 1, 1),
+                cell("Lin", 2, 0),
+                cell("PM", 2, 1)
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::padded_ragged_rows(
+        "| a | b | c |\n|---|---|---|\n| 1 |\n| 2 | 3 |",
+        Some(vec![vec!["a", "b", "c"], vec!["1", "", ""], vec!["2", "3", ""]])
+    )]
+    #[case::no_outer_pipes("a | b\n--- | ---\n1 | 2", Some(vec![vec!["a", "b"], vec!["1", "2"]]))]
+    #[case::header_only("| a | b |\n|---|---|", Some(vec![vec!["a", "b"]]))]
+    #[case::escaped_pipe("| a |\n|---|\n| x \\| y |", Some(vec![vec!["a"], vec!["x | y"]]))]
+    #[case::no_separator("| a | b |\n| 1 | 2 |", None)]
+    #[case::text_after_table("| a |\n|---|\n| 1 |\nSome prose", None)]
+    #[case::single_line("| a | b |", None)]
+    #[case::plain_text("Not a table", None)]
+    fn test_markdown_table(#[case] text: &str, #[case] expected: Option<Vec<Vec<&str>>>) {
+        let expected = expected.map(|rows| {
+            rows.into_iter()
+                .map(|row| row.into_iter().map(str::to_string).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(markdown_table(text), expected);
+    }
+
+    #[rstest]
+    #[case::max_size(20, 20, true)]
+    #[case::too_many_rows(21, 2, false)]
+    #[case::too_many_columns(2, 21, false)]
+    fn test_check_table_size(#[case] rows: usize, #[case] columns: usize, #[case] ok: bool) {
+        let table = vec![vec![String::new(); columns]; rows];
+        let result = check_table_size(2, &table);
+        assert_eq!(result.is_ok(), ok);
+        if let Err(err) = result {
+            assert!(err.to_string().contains("Table on slide 3"));
+            assert!(err.to_string().contains("at most 20 rows and 20 columns"));
+        }
+    }
+
+    #[rstest]
+    fn test_table_cell_styles_target_the_cell() {
+        let requests = table_requests("slide_0", &[vec!["**Total**".to_string()]]);
+        let options = InlineOptions {
+            emphasis: true,
+            links: false,
+        };
+        let json = serde_json::to_value(with_inline_formatting(requests, options)).unwrap();
+        assert_eq!(
+            json[2]["updateTextStyle"]["cellLocation"],
+            serde_json::json!({"rowIndex": 0, "columnIndex": 0})
+        );
+    }
 }
@@ -1,7 +1,146 @@
+use crate::textmetrics;
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, iter, sync::LazyLock};
+use thiserror::Error;
+
+/// Longest regex pattern accepted by [`Splitter::Regex`].
+pub const MAX_PATTERN_LEN: usize = 512;
+
+/// Deepest nesting of [`Splitter::Chain`] accepted by [`Splitter::validate`].
+pub const MAX_CHAIN_DEPTH: usize = 3;
+
+/// `max_words` used by [`Splitter::MaxWords`] when the request omits it.
+pub const DEFAULT_MAX_WORDS: usize = 50;
+
+/// `max_chars` used by [`Splitter::MaxChars`] when the request omits it.
+pub const DEFAULT_MAX_CHARS: usize = 500;
+
+/// Smallest `max_bytes`, so that any UTF-8 encoded character fits in a chunk.
+pub const MIN_MAX_BYTES: usize = 4;
+
+/// Upper bound on the compiled size of a [`Splitter::Regex`] pattern.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Chunks produced lazily by [`Splitter::split_iter`].
+type Chunks<'a> = Box<dyn Iterator<Item = String> + 'a>;
+
+/// Errors describing an unusable splitter configuration.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SplitError {
+    #[error("pattern must be at most {MAX_PATTERN_LEN} characters, got {0}")]
+    PatternTooLong(usize),
+
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("overlap ({overlap}) must be less than {field} ({size})")]
+    OverlapTooLarge {
+        field: &'static str,
+        overlap: usize,
+        size: usize,
+    },
+
+    #[error("overlap cannot be combined with {0}")]
+    OverlapUnsupported(&'static str),
+
+    #[error("{0} cannot be combined with {1}")]
+    IncompatibleOptions(&'static str, &'static str),
+
+    #[error("{0} must be at least 1")]
+    ZeroSize(&'static str),
+
+    #[error("{field} must be at least {min}")]
+    SizeTooSmall { field: &'static str, min: usize },
+
+    #[error("chain must contain at least one splitter")]
+    EmptyChain,
+
+    #[error("chains may be nested at most {MAX_CHAIN_DEPTH} levels deep")]
+    ChainTooDeep,
+}
+
+/// How [`Splitter::MaxWords`] finds word boundaries.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WordSegmentation {
+    /// Words are separated by whitespace.
+    #[default]
+    Whitespace,
+    /// Unicode word boundaries, so unspaced scripts like Chinese and Japanese
+    /// are not treated as a single word.
+    Unicode,
+}
+
+impl WordSegmentation {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Splits the text into words, one string slice per word.
+    fn words(self, text: &str) -> Vec<&str> {
+        match self {
+            WordSegmentation::Whitespace => textmetrics::words(text).collect(),
+            WordSegmentation::Unicode => textmetrics::unicode_word_spans(text),
+        }
+    }
+
+    /// Counts the words in the text.
+    fn count(self, text: &str) -> usize {
+        match self {
+            WordSegmentation::Whitespace => textmetrics::words(text).count(),
+            WordSegmentation::Unicode => textmetrics::unicode_word_spans(text).len(),
+        }
+    }
+
+    /// Joins consecutive words back into slide text.
+    fn join(self, words: &[&str]) -> String {
+        match self {
+            WordSegmentation::Whitespace => words.join(" "),
+            // Spans keep their original spacing and punctuation.
+            WordSegmentation::Unicode => words.concat().trim().to_string(),
+        }
+    }
+}
+
+/// What [`Splitter::MaxChars`] counts as one character.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CharUnit {
+    /// Unicode scalar values (Rust `char`s).
+    #[default]
+    Chars,
+    /// Extended grapheme clusters, so emoji sequences and accents stay whole.
+    Graphemes,
+}
+
+impl CharUnit {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Counts the units in the text.
+    fn count(self, text: &str) -> usize {
+        match self {
+            CharUnit::Chars => textmetrics::chars(text),
+            CharUnit::Graphemes => textmetrics::graphemes(text).count(),
+        }
+    }
+
+    /// Byte offset where unit `n` starts, or `None` if the text has `n` units or fewer.
+    fn nth_boundary(self, text: &str, n: usize) -> Option<usize> {
+        match self {
+            CharUnit::Chars => text.char_indices().nth(n).map(|(i, _)| i),
+            CharUnit::Graphemes => {
+                let end = textmetrics::prefix_by_graphemes(text, n).len();
+                (end < text.len()).then_some(end)
+            }
+        }
+    }
+}
 
 /// Represents the different strategies for splitting text into chunks.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(tag = "type")]
 pub enum Splitter {
     #[default]
@@ -10,157 +149,3644 @@ pub enum Splitter {
     #[serde(rename = "empty_line")]
     EmptyLine,
     #[serde(rename = "max_words")]
-    MaxWords { max_words: usize },
+    MaxWords {
+        #[serde(default = "default_max_words")]
+        max_words: usize,
+        #[serde(default, skip_serializing_if = "is_false")]
+        respect_sentences: bool,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        overlap: usize,
+        #[serde(default, skip_serializing_if = "WordSegmentation::is_default")]
+        segmentation: WordSegmentation,
+        #[serde(default, skip_serializing_if = "is_false")]
+        balanced: bool,
+    },
     #[serde(rename = "max_chars")]
-    MaxChars { max_chars: usize },
+    MaxChars {
+        #[serde(default = "default_max_chars")]
+        max_chars: usize,
+        #[serde(default, skip_serializing_if = "is_false")]
+        word_boundary: bool,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        overlap: usize,
+        #[serde(default, skip_serializing_if = "CharUnit::is_default")]
+        unit: CharUnit,
+    },
+    #[serde(rename = "max_bytes")]
+    MaxBytes { max_bytes: usize },
+    #[serde(rename = "markdown_headings")]
+    MarkdownHeadings { max_level: u8 },
+    #[serde(rename = "org_headings")]
+    OrgHeadings { max_level: u8 },
+    #[serde(rename = "horizontal_rule")]
+    HorizontalRule,
+    #[serde(rename = "bullet_list")]
+    BulletList,
+    #[serde(rename = "outline")]
+    Outline,
+    #[serde(rename = "block_quote")]
+    BlockQuote {
+        #[serde(default, skip_serializing_if = "is_false")]
+        include_prose: bool,
+    },
+    #[serde(rename = "regex")]
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        keep_delimiter: bool,
+    },
+    #[serde(rename = "chain")]
+    Chain { splitters: Vec<Splitter> },
+    #[serde(rename = "with_cap")]
+    WithCap {
+        splitter: Box<Splitter>,
+        max_chunk_words: usize,
+    },
+}
+
+/// Describes one splitter strategy and its configuration fields for clients.
+#[derive(Debug, Serialize)]
+pub struct SplitterDescriptor {
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    #[serde(rename = "name")]
+    pub display_name: &'static str,
+    pub description: &'static str,
+    #[serde(
+        rename = "config",
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "config_map"
+    )]
+    pub config_fields: Vec<ConfigField>,
+}
+
+/// One configuration field of a splitter.
+#[derive(Debug)]
+pub struct ConfigField {
+    pub name: &'static str,
+    /// Accepted values, e.g. `number` or `"chars" | "graphemes"`.
+    pub kind: &'static str,
+    /// Value used when the field is omitted, as JSON.
+    pub default: Option<String>,
+    pub note: Option<String>,
+}
+
+impl ConfigField {
+    fn new(name: &'static str, kind: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            default: None,
+            note: None,
+        }
+    }
+
+    /// Records the default, serialized exactly as the request would spell it.
+    fn default_value(mut self, value: impl Serialize) -> Self {
+        self.default =
+            Some(serde_json::to_string(&value).expect("config defaults are always serializable"));
+        self
+    }
+
+    fn note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Renders the field as `kind (default: value) - note`.
+    fn summary(&self) -> String {
+        let mut summary = self.kind.to_string();
+        if let Some(default) = &self.default {
+            summary.push_str(&format!(" (default: {default})"));
+        }
+        if let Some(note) = &self.note {
+            summary.push_str(&format!(" - {note}"));
+        }
+        summary
+    }
+}
+
+/// Serializes config fields as a JSON object, keeping declaration order.
+fn config_map<S: serde::Serializer>(
+    fields: &[ConfigField],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(fields.iter().map(|field| (field.name, field.summary())))
+}
+
+fn default_max_words() -> usize {
+    DEFAULT_MAX_WORDS
+}
+
+fn default_max_chars() -> usize {
+    DEFAULT_MAX_CHARS
+}
+
+impl Splitter {
+    /// Metadata for every variant, in declaration order, as served by
+    /// `GET /api/splitters`. Defaults and limits come from the same constants
+    /// the splitters use, so the two cannot drift apart.
+    pub fn descriptors() -> Vec<SplitterDescriptor> {
+        vec![
+            SplitterDescriptor {
+                type_name: "newline",
+                display_name: "New Line Splitter",
+                description: "Splits text by individual lines",
+                config_fields: vec![],
+            },
+            SplitterDescriptor {
+                type_name: "empty_line",
+                display_name: "Empty Line Splitter",
+                description: "Splits text by empty lines (paragraphs); whitespace-only lines count as empty",
+                config_fields: vec![],
+            },
+            SplitterDescriptor {
+                type_name: "max_words",
+                display_name: "Max Words Splitter",
+                description: "Splits text by maximum word count per slide",
+                config_fields: vec![
+                    ConfigField::new("max_words", "number").default_value(DEFAULT_MAX_WORDS),
+                    ConfigField::new("respect_sentences", "boolean")
+                        .default_value(false)
+                        .note("end slides on sentence boundaries"),
+                    ConfigField::new("overlap", "number")
+                        .default_value(0)
+                        .note("words repeated from the previous slide, less than max_words"),
+                    ConfigField::new("segmentation", "\"whitespace\" | \"unicode\"")
+                        .default_value(WordSegmentation::default())
+                        .note("unicode finds words in Chinese and Japanese text"),
+                    ConfigField::new("balanced", "boolean")
+                        .default_value(false)
+                        .note("spread words evenly so slide sizes differ by at most one"),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "max_chars",
+                display_name: "Max Characters Splitter",
+                description: "Splits text by maximum character count per slide",
+                config_fields: vec![
+                    ConfigField::new("max_chars", "number").default_value(DEFAULT_MAX_CHARS),
+                    ConfigField::new("word_boundary", "boolean")
+                        .default_value(false)
+                        .note("break between words instead of mid-word"),
+                    ConfigField::new("overlap", "number")
+                        .default_value(0)
+                        .note("characters repeated from the previous slide, less than max_chars"),
+                    ConfigField::new("unit", "\"chars\" | \"graphemes\"")
+                        .default_value(CharUnit::default())
+                        .note("graphemes keep emoji and accented letters whole"),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "max_bytes",
+                display_name: "Max Bytes Splitter",
+                description: "Splits text into slides of at most the given UTF-8 byte size, breaking at whitespace where possible and never inside a character",
+                config_fields: vec![
+                    ConfigField::new("max_bytes", "number")
+                        .note(format!("at least {MIN_MAX_BYTES}")),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "markdown_headings",
+                display_name: "Markdown Headings Splitter",
+                description: "Starts a new slide at each Markdown heading up to the given level",
+                config_fields: vec![
+                    ConfigField::new("max_level", "number").note("1-6, where 1 = only # headings"),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "org_headings",
+                display_name: "Org Headings Splitter",
+                description: "Starts a new slide at each org-mode heading (* Heading) up to the given level, dropping TODO/DONE keywords, priorities, tags and property drawers",
+                config_fields: vec![
+                    ConfigField::new("max_level", "number").note("1 = only top-level * headings"),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "horizontal_rule",
+                display_name: "Horizontal Rule Splitter",
+                description: "Splits text at lines consisting of three or more dashes (---), outside code blocks",
+                config_fields: vec![],
+            },
+            SplitterDescriptor {
+                type_name: "bullet_list",
+                display_name: "Bullet List Splitter",
+                description: "Puts each top-level Markdown bullet (-, *, +) and its nested items on one slide; text between lists gets its own slide",
+                config_fields: vec![],
+            },
+            SplitterDescriptor {
+                type_name: "outline",
+                display_name: "Outline Splitter",
+                description: "Puts each unindented line of a tab- or space-indented outline on one slide together with everything indented beneath it",
+                config_fields: vec![],
+            },
+            SplitterDescriptor {
+                type_name: "block_quote",
+                display_name: "Block Quote Splitter",
+                description: "Puts each Markdown blockquote (> lines, markers removed) on one slide, together with a following attribution line starting with — or --",
+                config_fields: vec![
+                    ConfigField::new("include_prose", "boolean")
+                        .default_value(false)
+                        .note(
+                            "also emit text outside blockquotes as slides instead of skipping it",
+                        ),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "regex",
+                display_name: "Regex Splitter",
+                description: "Splits text at every match of a regular expression (^ and $ match at line boundaries)",
+                config_fields: vec![
+                    ConfigField::new("pattern", "string")
+                        .note(format!("at most {MAX_PATTERN_LEN} characters")),
+                    ConfigField::new("keep_delimiter", "boolean")
+                        .default_value(false)
+                        .note("keep the match at the start of the next slide"),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "chain",
+                display_name: "Chain Splitter",
+                description: "Applies each splitter in turn to the chunks produced by the previous one",
+                config_fields: vec![
+                    ConfigField::new("splitters", "array of splitter objects").note(format!(
+                        "at least one, nested chains up to {MAX_CHAIN_DEPTH} levels"
+                    )),
+                ],
+            },
+            SplitterDescriptor {
+                type_name: "with_cap",
+                display_name: "Capped Splitter",
+                description: "Runs another splitter and re-splits any slide over the word cap at sentence boundaries, breaking overlong sentences between words",
+                config_fields: vec![
+                    ConfigField::new("splitter", "splitter object"),
+                    ConfigField::new("max_chunk_words", "number").note("at least 1"),
+                ],
+            },
+        ]
+    }
+}
+
+impl Splitter {
+    /// Checks that the configuration can be used to split text.
+    pub fn validate(&self) -> Result<(), SplitError> {
+        match self {
+            Splitter::MaxWords {
+                max_words,
+                respect_sentences,
+                overlap,
+                balanced,
+                ..
+            } => {
+                if *balanced && *respect_sentences {
+                    return Err(SplitError::IncompatibleOptions(
+                        "balanced",
+                        "respect_sentences",
+                    ));
+                }
+                validate_size(
+                    "max_words",
+                    *max_words,
+                    *overlap,
+                    respect_sentences
+                        .then_some("respect_sentences")
+                        .or(balanced.then_some("balanced")),
+                )
+            }
+            Splitter::MaxChars {
+                max_chars,
+                word_boundary,
+                overlap,
+                ..
+            } => validate_size(
+                "max_chars",
+                *max_chars,
+                *overlap,
+                word_boundary.then_some("word_boundary"),
+            ),
+            Splitter::MaxBytes { max_bytes } if *max_bytes < MIN_MAX_BYTES => {
+                Err(SplitError::SizeTooSmall {
+                    field: "max_bytes",
+                    min: MIN_MAX_BYTES,
+                })
+            }
+            Splitter::Regex { pattern, .. } => build_regex(pattern).map(|_| ()),
+            Splitter::Chain { splitters } => {
+                if splitters.is_empty() {
+                    return Err(SplitError::EmptyChain);
+                }
+                if self.chain_depth() > MAX_CHAIN_DEPTH {
+                    return Err(SplitError::ChainTooDeep);
+                }
+                splitters.iter().try_for_each(Splitter::validate)
+            }
+            Splitter::WithCap {
+                splitter,
+                max_chunk_words,
+            } => {
+                if *max_chunk_words == 0 {
+                    return Err(SplitError::ZeroSize("max_chunk_words"));
+                }
+                splitter.validate()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of nested [`Splitter::Chain`] levels, 0 for plain splitters.
+    fn chain_depth(&self) -> usize {
+        match self {
+            Splitter::Chain { splitters } => {
+                1 + splitters
+                    .iter()
+                    .map(Splitter::chain_depth)
+                    .max()
+                    .unwrap_or(0)
+            }
+            Splitter::WithCap { splitter, .. } => splitter.chain_depth(),
+            _ => 0,
+        }
+    }
+
+    /// Splits the given text according to the selected strategy.
+    ///
+    /// Configurations rejected by [`Splitter::validate`] never panic here; a
+    /// zero chunk size simply yields no chunks.
+    pub fn split(&self, text: &str) -> Vec<String> {
+        self.split_iter(text).collect()
+    }
+
+    /// Lazily splits the given text, producing the same chunks as [`Splitter::split`].
+    ///
+    /// The size-based strategies stream over the input without materializing
+    /// every word or character. Strategies that need to look ahead, such as
+    /// sentence boundaries, balancing or code block handling, buffer their
+    /// chunks internally.
+    ///
+    /// Directive lines are applied first: each section between
+    /// `::slide` lines is split on its own, so chunk sizes restart at every
+    /// forced boundary.
+    pub fn split_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = String> + 'a {
+        match directive_sections(text) {
+            None => self.split_section(text),
+            Some(sections) => Box::new(
+                sections
+                    .into_iter()
+                    .flat_map(move |section| self.split_section(&section).collect::<Vec<_>>()),
+            ),
+        }
+    }
+
+    /// Splits text that contains no directives with the selected strategy.
+    fn split_section<'a>(&'a self, text: &'a str) -> Chunks<'a> {
+        let chunks: Chunks<'a> = match self {
+            Splitter::NewLine => Box::new(split_lines(text)),
+            Splitter::EmptyLine => Box::new(split_paragraphs(text).into_iter()),
+            Splitter::MaxWords { max_words: 0, .. }
+            | Splitter::MaxChars { max_chars: 0, .. }
+            | Splitter::MaxBytes { max_bytes: 0 } => Box::new(iter::empty()),
+            Splitter::MaxWords {
+                max_words,
+                respect_sentences,
+                overlap,
+                segmentation,
+                balanced,
+            } => split_around_code(
+                text,
+                *max_words,
+                |chunk| segmentation.count(chunk),
+                move |prose| -> Chunks<'a> {
+                    if *respect_sentences {
+                        let words = segmentation.words(prose);
+                        let chunks = split_words_at_sentences(&words, *max_words, *segmentation);
+                        return Box::new(chunks.into_iter());
+                    }
+                    if *balanced {
+                        let words = segmentation.words(prose);
+                        let chunks = balanced_chunks(&words, *max_words)
+                            .into_iter()
+                            .map(|chunk| segmentation.join(chunk))
+                            .collect::<Vec<_>>();
+                        return Box::new(chunks.into_iter());
+                    }
+                    if *segmentation == WordSegmentation::Whitespace {
+                        return Box::new(word_chunks(prose, *max_words, *overlap));
+                    }
+                    let words = segmentation.words(prose);
+                    let chunks = overlapping_chunks(&words, *max_words, *overlap)
+                        .into_iter()
+                        .map(|chunk| segmentation.join(chunk))
+                        .filter(|chunk| !chunk.is_empty())
+                        .collect::<Vec<_>>();
+                    Box::new(chunks.into_iter())
+                },
+            ),
+            Splitter::MaxChars {
+                max_chars,
+                word_boundary,
+                overlap,
+                unit,
+            } => split_around_code(
+                text,
+                *max_chars,
+                |chunk| unit.count(chunk),
+                move |prose| -> Chunks<'a> {
+                    if *word_boundary {
+                        Box::new(split_chars_at_words(prose, *max_chars, *unit))
+                    } else {
+                        Box::new(
+                            unit_chunks(prose, *max_chars, *overlap, *unit).map(str::to_string),
+                        )
+                    }
+                },
+            ),
+            Splitter::MaxBytes { max_bytes } => Box::new(split_bytes_at_words(text, *max_bytes)),
+            Splitter::MarkdownHeadings { max_level } => {
+                Box::new(split_markdown_headings(text, *max_level).into_iter())
+            }
+            Splitter::OrgHeadings { max_level } => {
+                Box::new(split_org_headings(text, *max_level).into_iter())
+            }
+            Splitter::HorizontalRule => Box::new(split_horizontal_rules(text).into_iter()),
+            Splitter::BulletList => Box::new(split_bullet_lists(text).into_iter()),
+            Splitter::Outline => Box::new(split_outline(text).into_iter()),
+            Splitter::BlockQuote { include_prose } => {
+                Box::new(split_block_quotes(text, *include_prose).into_iter())
+            }
+            Splitter::Regex {
+                pattern,
+                keep_delimiter,
+            } => Box::new(split_regex(text, pattern, *keep_delimiter).into_iter()),
+            Splitter::Chain { splitters } => {
+                let mut chunks = Vec::new();
+                push_trimmed(&mut chunks, text);
+                splitters
+                    .iter()
+                    .fold(Box::new(chunks.into_iter()), |chunks, splitter| {
+                        Box::new(chunks.flat_map(move |chunk| splitter.split(&chunk)))
+                    })
+            }
+            Splitter::WithCap {
+                splitter,
+                max_chunk_words,
+            } => Box::new(
+                splitter
+                    .split_section(text)
+                    .flat_map(move |chunk| cap_chunk(chunk, *max_chunk_words)),
+            ),
+        };
+        chunks
+    }
+
+    /// Splits the text into chunks with a title where the strategy implies one.
+    pub fn split_chunks(&self, text: &str) -> Vec<Chunk> {
+        self.split(text)
+            .into_iter()
+            .map(|chunk| self.title_chunk(chunk))
+            .collect()
+    }
+
+    /// Separates a chunk produced by this splitter into title and body.
+    ///
+    /// `EmptyLine` paragraphs use their first line as the title, and
+    /// `MarkdownHeadings` and `OrgHeadings` sections their heading text. Other strategies, and
+    /// chunks without an obvious title, have no title.
+    pub fn title_chunk(&self, chunk: String) -> Chunk {
+        let Some((first, rest)) = chunk.split_once('\n') else {
+            return Chunk::untitled(chunk);
+        };
+        let title = match self {
+            Splitter::EmptyLine if !is_code_fence(first) => first.trim(),
+            Splitter::MarkdownHeadings { .. } if markdown_heading_level(first).is_some() => {
+                heading_text(first)
+            }
+            Splitter::OrgHeadings { .. } if org_heading_level(first).is_some() => {
+                org_heading_text(first)
+            }
+            _ => return Chunk::untitled(chunk),
+        };
+        Chunk {
+            title: Some(title.to_string()),
+            body: rest.trim().to_string(),
+            notes: String::new(),
+            media: None,
+            skipped: false,
+        }
+    }
+}
+
+/// One slide's worth of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Text for the slide's title placeholder, if the chunk has one.
+    pub title: Option<String>,
+    /// Text for the slide's body placeholder.
+    pub body: String,
+    /// Speaker notes; empty when the chunk has none.
+    pub notes: String,
+    /// Content shown instead of a title and body.
+    pub media: Option<Media>,
+    /// Hidden when presenting, for slides not ready to show.
+    pub skipped: bool,
+}
+
+/// Content that takes the place of the usual title and body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Media {
+    /// A section header slide showing only the chunk's title.
+    Section,
+    /// An image URL or `drive:<fileId>` reference.
+    Image(String),
+    /// Table cells by row, header first; every row has the same length.
+    Table(Vec<Vec<String>>),
+    /// A YouTube video by ID, played from `start` seconds in when set.
+    Video { id: String, start: Option<u32> },
+}
+
+impl Chunk {
+    /// A chunk whose whole text goes into the body.
+    pub fn untitled(body: String) -> Self {
+        Self {
+            title: None,
+            body,
+            notes: String::new(),
+            media: None,
+            skipped: false,
+        }
+    }
+
+    /// A chunk that is a single image.
+    pub fn image(url: String) -> Self {
+        Self {
+            media: Some(Media::Image(url)),
+            ..Self::untitled(String::new())
+        }
+    }
+
+    /// A section header slide for a heading.
+    pub fn section(heading: String) -> Self {
+        Self {
+            title: Some(heading),
+            media: Some(Media::Section),
+            ..Self::untitled(String::new())
+        }
+    }
+
+    /// A chunk that is a single YouTube video.
+    pub fn video(id: String, start: Option<u32>) -> Self {
+        Self {
+            media: Some(Media::Video { id, start }),
+            ..Self::untitled(String::new())
+        }
+    }
+
+    /// A chunk that is a single table.
+    pub fn table(rows: Vec<Vec<String>>) -> Self {
+        Self {
+            media: Some(Media::Table(rows)),
+            ..Self::untitled(String::new())
+        }
+    }
+
+    /// A chunk whose first line is the title and the remaining lines the body,
+    /// which is empty for single-line text.
+    pub fn from_first_line(text: &str) -> Self {
+        let (title, body) = text.trim().split_once('\n').unwrap_or((text.trim(), ""));
+        Self {
+            title: Some(title.trim().to_string()),
+            body: body.trim().to_string(),
+            notes: String::new(),
+            media: None,
+            skipped: false,
+        }
+    }
+}
+
+/// One slide to create, before its text is laid out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlideSpec {
+    /// A section header showing just the heading.
+    Section(String),
+    /// An ordinary slide made from a chunk.
+    Content(String),
+}
+
+impl SlideSpec {
+    /// The slide as Markdown, with a section written back as its H1 heading.
+    pub fn into_text(self) -> String {
+        match self {
+            Self::Section(heading) => format!("# {heading}"),
+            Self::Content(text) => text,
+        }
+    }
+}
+
+/// Turns every H1 heading into a section slide of its own, followed by
+/// content slides for the rest of its chunk.
+///
+/// Headings inside fenced code blocks are left alone, and text that only
+/// surrounds a heading with whitespace produces no content slide.
+pub fn section_specs(chunks: Vec<String>) -> Vec<SlideSpec> {
+    let mut specs = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let mut content = Vec::new();
+        for (line, in_code) in fenced_lines(&chunk) {
+            if in_code || markdown_heading_level(line) != Some(1) {
+                content.push(line);
+                continue;
+            }
+            push_content(&mut specs, &content);
+            content.clear();
+            specs.push(SlideSpec::Section(heading_text(line).to_string()));
+        }
+        push_content(&mut specs, &content);
+    }
+    specs
+}
+
+fn push_content(specs: &mut Vec<SlideSpec>, lines: &[&str]) {
+    let text = lines.join("\n");
+    if !text.trim().is_empty() {
+        specs.push(SlideSpec::Content(text.trim().to_string()));
+    }
+}
+
+/// Line separating slide content from speaker notes, as used by remark.
+pub const DEFAULT_NOTES_MARKER: &str = "???";
+
+/// First line marking a chunk as a draft slide that is skipped when presenting.
+pub const SKIP_MARKER: &str = "[skip]";
+
+/// Returns the rest of a chunk whose first non-blank line is [`SKIP_MARKER`],
+/// in any case, or `None` for chunks without it.
+pub fn strip_skip_marker(chunk: &str) -> Option<&str> {
+    let chunk = chunk.trim_start();
+    let (first, rest) = chunk.split_once('\n').unwrap_or((chunk, ""));
+    first
+        .trim()
+        .eq_ignore_ascii_case(SKIP_MARKER)
+        .then_some(rest.trim_start())
+}
+
+/// Splits a chunk at the first line consisting of `marker` into the visible
+/// text and the speaker notes.
+///
+/// The marker line belongs to neither part, and later marker lines are kept
+/// as part of the notes. Markers inside code blocks are ignored. Without a
+/// marker the notes are empty.
+pub fn split_notes<'a>(chunk: &'a str, marker: &str) -> (&'a str, &'a str) {
+    let mut offset = 0;
+    for block in code_blocks(chunk) {
+        if let Block::Prose(prose) = block {
+            let mut line_start = offset;
+            for line in prose.split_inclusive('\n') {
+                let line_end = line_start + line.len();
+                if line.trim() == marker {
+                    return (chunk[..line_start].trim(), chunk[line_end..].trim());
+                }
+                line_start = line_end;
+            }
+        }
+        offset += match block {
+            Block::Prose(text) | Block::Code(text) => text.len(),
+        };
+    }
+    (chunk.trim(), "")
+}
+
+/// Makes every non-blank line a chunk, keeping each code block as one chunk.
+fn split_lines(text: &str) -> impl Iterator<Item = String> + '_ {
+    code_blocks(text)
+        .into_iter()
+        .flat_map(|block| -> Box<dyn Iterator<Item = &str>> {
+            match block {
+                Block::Prose(prose) => Box::new(prose.lines()),
+                Block::Code(code) => Box::new(iter::once(code)),
+            }
+        })
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Splits on runs of blank lines, where whitespace-only lines count as blank.
+///
+/// Blank lines inside fenced code blocks do not split.
+fn split_paragraphs(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+
+    for (line, in_code) in fenced_lines(text) {
+        if !in_code && line.trim().is_empty() {
+            push_lines(&mut chunks, &current);
+            current.clear();
+        } else {
+            current.push(line);
+        }
+    }
+    push_lines(&mut chunks, &current);
+
+    chunks
+}
+
+/// Merges every chunk with fewer than `min_words` words into the chunk before it.
+///
+/// A short first chunk has no predecessor and is kept as is, so non-empty
+/// input never produces empty output.
+pub fn merge_short_chunks(chunks: Vec<String>, min_words: usize) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        match merged.last_mut() {
+            Some(previous) if textmetrics::words(&chunk).count() < min_words => {
+                previous.push('\n');
+                previous.push_str(&chunk);
+            }
+            _ => merged.push(chunk),
+        }
+    }
+    merged
+}
+
+/// Drops every chunk that equals an earlier one once surrounding whitespace
+/// is trimmed, keeping first occurrences in order.
+///
+/// Returns the remaining chunks and how many duplicates were removed.
+pub fn dedupe_chunks(chunks: Vec<String>) -> (Vec<String>, usize) {
+    let total = chunks.len();
+    let mut seen = HashSet::new();
+    let unique = chunks
+        .into_iter()
+        .filter(|chunk| seen.insert(chunk.trim().to_string()))
+        .collect::<Vec<_>>();
+    let removed = total - unique.len();
+    (unique, removed)
+}
+
+/// Converts NBSP, tabs and other spaces to plain spaces and collapses runs of
+/// them into one, keeping line breaks.
+///
+/// Leading indentation and fenced code blocks are left untouched, so list
+/// nesting and code layout survive; trailing whitespace is removed.
+pub fn normalize_whitespace(text: &str) -> String {
+    fenced_lines(text)
+        .map(|(line, in_code)| {
+            if in_code {
+                return line.to_string();
+            }
+            let content = line.trim_start();
+            let indent = &line[..line.len() - content.len()];
+            let words = textmetrics::words(content).collect::<Vec<_>>();
+            format!("{indent}{}", words.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Bullet that replaces Markdown list markers in plain-text output.
+const PLAIN_BULLET: char = '•';
+
+/// Converts a Markdown chunk to plain text for slides.
+///
+/// Emphasis markers and heading hashes are dropped, links and images are
+/// replaced by their text, list markers become [`PLAIN_BULLET`], and code
+/// keeps its content without backticks or fences.
+pub fn strip_markdown(chunk: &str) -> String {
+    let lines = fenced_lines(chunk)
+        .filter_map(|(line, in_code)| match (in_code, is_code_fence(line)) {
+            (true, true) => None,
+            (true, false) => Some(line.to_string()),
+            (false, _) => Some(strip_markdown_line(line)),
+        })
+        .collect::<Vec<_>>();
+    lines.join("\n").trim().to_string()
+}
+
+/// Strips block-level syntax from one line, then its inline formatting.
+fn strip_markdown_line(line: &str) -> String {
+    if markdown_heading_level(line).is_some() {
+        return strip_inline_markdown(heading_text(line));
+    }
+    if is_bullet_item(line) {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        let text = strip_inline_markdown(trimmed[1..].trim_start());
+        return format!("{indent}{PLAIN_BULLET} {text}");
+    }
+    strip_inline_markdown(line)
+}
+
+/// Inline Markdown constructs, applied in order to text outside code spans.
+static INLINE_MARKDOWN: LazyLock<[(Regex, &str); 6]> = LazyLock::new(|| {
+    [
+        (r"!\[([^\]]*)\]\([^)]*\)", "$1"),
+        (r"\[([^\]]*)\]\([^)]*\)", "$1"),
+        (r"\*\*(\S(?:.*?\S)?)\*\*", "$1"),
+        (r"(^|\W)__(\S(?:.*?\S)?)__(\W|$)", "$1$2$3"),
+        (r"\*(\S(?:[^*]*?\S)?)\*", "$1"),
+        (r"(^|\W)_(\S(?:[^_]*?\S)?)_(\W|$)", "$1$2$3"),
+    ]
+    .map(|(pattern, replacement)| {
+        (
+            Regex::new(pattern).expect("inline Markdown patterns are valid"),
+            replacement,
+        )
+    })
+});
+
+/// Strikethrough markers, removed after emphasis.
+const STRIKETHROUGH: &str = "~~";
+
+/// Removes inline formatting, keeping the content of code spans verbatim.
+fn strip_inline_markdown(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find('`') {
+        let ticks = rest[open..].len() - rest[open..].trim_start_matches('`').len();
+        let fence = &rest[open..open + ticks];
+        let after = &rest[open + ticks..];
+        let Some(close) = after.find(fence) else {
+            break;
+        };
+        output.push_str(&strip_emphasis(&rest[..open]));
+        output.push_str(after[..close].trim());
+        rest = &after[close + ticks..];
+    }
+    output.push_str(&strip_emphasis(rest));
+
+    output
+}
+
+/// Removes links, images and emphasis from text that contains no code spans.
+fn strip_emphasis(text: &str) -> String {
+    let mut text = text.to_string();
+    for (regex, replacement) in INLINE_MARKDOWN.iter() {
+        // Repeat so nested markers of the same kind are all removed.
+        while regex.is_match(&text) {
+            text = regex.replace_all(&text, *replacement).into_owned();
+        }
+    }
+    text.replace(STRIKETHROUGH, "")
+}
+
+/// Used by serde to omit boolean options left at their `false` default.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Used by serde to omit numeric options left at their `0` default.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// Checks that the chunk size is non-zero and that `overlap` is smaller than
+/// it and only used in a raw mode.
+fn validate_size(
+    field: &'static str,
+    size: usize,
+    overlap: usize,
+    boundary_option: Option<&'static str>,
+) -> Result<(), SplitError> {
+    if size == 0 {
+        return Err(SplitError::ZeroSize(field));
+    }
+    if overlap == 0 {
+        return Ok(());
+    }
+    if overlap >= size {
+        return Err(SplitError::OverlapTooLarge {
+            field,
+            overlap,
+            size,
+        });
+    }
+    match boundary_option {
+        Some(option) => Err(SplitError::OverlapUnsupported(option)),
+        None => Ok(()),
+    }
+}
+
+/// Cuts `units` into chunks of `size`, each repeating the last `overlap` units
+/// of the previous chunk at its start.
+///
+/// Dropping the first `overlap` units of every chunk but the first and
+/// concatenating the rest reconstructs `units`.
+fn overlapping_chunks<T>(units: &[T], size: usize, overlap: usize) -> Vec<&[T]> {
+    if overlap == 0 {
+        return units.chunks(size).collect();
+    }
+
+    let step = size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < units.len() {
+        let end = (start + size).min(units.len());
+        chunks.push(&units[start..end]);
+        if end == units.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+/// Splits units into the fewest chunks of at most `size`, with chunk sizes
+/// differing by at most one. Longer chunks come first.
+fn balanced_chunks<T>(units: &[T], size: usize) -> Vec<&[T]> {
+    let count = units.len().div_ceil(size);
+    if count == 0 {
+        return Vec::new();
+    }
+    let (base, extra) = (units.len() / count, units.len() % count);
+
+    let mut chunks = Vec::with_capacity(count);
+    let mut start = 0;
+    for index in 0..count {
+        let end = start + base + usize::from(index < extra);
+        chunks.push(&units[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+/// Streams whitespace-separated words in chunks of `size`, each starting
+/// `size - overlap` words after the previous one, like [`overlapping_chunks`].
+fn word_chunks(text: &str, size: usize, overlap: usize) -> impl Iterator<Item = String> + '_ {
+    let step = size.saturating_sub(overlap).max(1);
+    let mut rest = Some(text);
+
+    iter::from_fn(move || {
+        let current = rest?;
+        let mut words = textmetrics::words(current);
+        let mut chunk = String::new();
+        for word in words.by_ref().take(size) {
+            if !chunk.is_empty() {
+                chunk.push(' ');
+            }
+            chunk.push_str(word);
+        }
+        rest = match words.next() {
+            Some(_) => nth_word_start(current, step).map(|start| &current[start..]),
+            None => None,
+        };
+        (!chunk.is_empty()).then_some(chunk)
+    })
+}
+
+/// Byte offset where the `n`th (0-based) whitespace-separated word starts.
+fn nth_word_start(text: &str, n: usize) -> Option<usize> {
+    let mut starts = text.char_indices().filter_map({
+        let mut previous_is_space = true;
+        move |(index, c)| {
+            let is_start = previous_is_space && !c.is_whitespace();
+            previous_is_space = c.is_whitespace();
+            is_start.then_some(index)
+        }
+    });
+    starts.nth(n)
+}
+
+/// Streams slices of `size` units, each starting `size - overlap` units after
+/// the previous one, like [`overlapping_chunks`].
+fn unit_chunks(
+    text: &str,
+    size: usize,
+    overlap: usize,
+    unit: CharUnit,
+) -> impl Iterator<Item = &str> {
+    let step = size.saturating_sub(overlap).max(1);
+    let mut rest = Some(text).filter(|text| !text.is_empty());
+
+    iter::from_fn(move || {
+        let current = rest?;
+        match unit.nth_boundary(current, size) {
+            Some(end) => {
+                let next = unit.nth_boundary(current, step).unwrap_or(current.len());
+                rest = Some(&current[next..]);
+                Some(&current[..end])
+            }
+            None => {
+                rest = None;
+                Some(current)
+            }
+        }
+    })
+}
+
+/// How far past `max_words` a chunk may run to finish its sentence, in percent.
+const SENTENCE_OVERRUN_PERCENT: usize = 20;
+
+/// Re-splits a chunk with more than `max_words` words at sentence boundaries,
+/// breaking a sentence that is longer than the cap on its own into word chunks.
+///
+/// Chunks within the cap, and chunks containing a code block, are returned
+/// unchanged; re-split chunks have their words joined by single spaces.
+fn cap_chunk(chunk: String, max_words: usize) -> Vec<String> {
+    if max_words == 0 {
+        return Vec::new();
+    }
+    if textmetrics::words(&chunk).count() <= max_words || chunk.lines().any(is_code_fence) {
+        return vec![chunk];
+    }
+
+    let words = textmetrics::words(&chunk).collect::<Vec<_>>();
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    for sentence in words.split_inclusive(|word| ends_sentence(word)) {
+        if !current.is_empty() && current.len() + sentence.len() > max_words {
+            chunks.push(current.join(" "));
+            current.clear();
+        }
+        if sentence.len() > max_words {
+            chunks.extend(sentence.chunks(max_words).map(|words| words.join(" ")));
+        } else {
+            current.extend_from_slice(sentence);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current.join(" "));
+    }
+
+    chunks
 }
 
-impl Splitter {
-    /// Splits the given text according to the selected strategy.
-    pub fn split(&self, text: &str) -> Vec<String> {
-        match self {
-            Splitter::NewLine => text
-                .lines()
-                .map(str::trim)
-                .filter(|line| !line.is_empty())
-                .map(str::to_string)
-                .collect(),
-            Splitter::EmptyLine => text
-                .split("\n\n")
-                .map(str::trim)
-                .filter(|l| !l.is_empty())
-                .map(str::to_string)
-                .collect(),
-            Splitter::MaxWords { max_words } => {
-                let words = text.split_whitespace().collect::<Vec<_>>();
-                words
-                    .chunks(*max_words)
-                    .map(|chunk| chunk.join(" "))
-                    .filter(|chunk| !chunk.is_empty())
-                    .collect()
-            }
-            Splitter::MaxChars { max_chars } => {
-                let chars = text.chars().collect::<Vec<_>>();
-                chars
-                    .chunks(*max_chars)
-                    .map(|chunk| chunk.iter().collect::<String>())
-                    .filter(|chunk| !chunk.is_empty())
-                    .collect()
-            }
+/// Whether a word ends a sentence, allowing trailing quotes and brackets.
+fn ends_sentence(word: &str) -> bool {
+    word.trim_end()
+        .trim_end_matches(['"', '\'', '”', '’', ')', ']', '」', '』'])
+        .ends_with(['.', '!', '?', '…', '。', '！', '？'])
+}
+
+/// Groups words into chunks of about `max_words` that end on sentence boundaries.
+///
+/// A chunk may run up to [`SENTENCE_OVERRUN_PERCENT`] over the limit to finish
+/// its sentence, otherwise it backs up to the last sentence end inside the
+/// limit. A single sentence longer than both becomes its own oversized chunk.
+fn split_words_at_sentences(
+    words: &[&str],
+    max_words: usize,
+    segmentation: WordSegmentation,
+) -> Vec<String> {
+    let overrun = max_words * SENTENCE_OVERRUN_PERCENT / 100;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < words.len() {
+        let limit = (start + max_words).min(words.len());
+        let stretched = (limit + overrun).min(words.len());
+
+        let end = if limit == words.len() || ends_sentence(words[limit - 1]) {
+            limit
+        } else if let Some(end) = (limit..stretched).find(|&i| ends_sentence(words[i])) {
+            end + 1
+        } else if let Some(end) = (start..limit - 1).rev().find(|&i| ends_sentence(words[i])) {
+            end + 1
+        } else {
+            // One sentence longer than the limit: emit all of it.
+            (limit..words.len())
+                .find(|&i| ends_sentence(words[i]))
+                .map_or(words.len(), |end| end + 1)
+        };
+
+        chunks.push(segmentation.join(&words[start..end]));
+        start = end;
+    }
+
+    chunks
+}
+
+/// Splits into chunks of at most `max_chars` units ending at whitespace.
+///
+/// A word longer than `max_chars` is the only thing broken mid-word.
+/// Whitespace at chunk boundaries is trimmed.
+fn split_chars_at_words(
+    text: &str,
+    max_chars: usize,
+    unit: CharUnit,
+) -> impl Iterator<Item = String> + '_ {
+    let mut rest = text.trim_start();
+
+    iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let cut = match unit.nth_boundary(rest, max_chars) {
+            None => rest.len(),
+            Some(limit) if rest[limit..].starts_with(char::is_whitespace) => limit,
+            // `rest` starts with a non-whitespace char, so any match is past 0.
+            Some(limit) => rest[..limit].rfind(char::is_whitespace).unwrap_or(limit),
+        };
+        let chunk = rest[..cut].trim_end();
+        rest = rest[cut..].trim_start();
+        Some(chunk.to_string())
+    })
+}
+
+/// Splits into chunks of at most `max_bytes` UTF-8 bytes, preferring to end at
+/// whitespace and never cutting inside a character.
+///
+/// The limit is strict, so unlike the other size-based splitters this one
+/// does split code blocks that do not fit.
+fn split_bytes_at_words(text: &str, max_bytes: usize) -> impl Iterator<Item = String> + '_ {
+    let mut rest = if max_bytes == 0 {
+        ""
+    } else {
+        text.trim_start()
+    };
+
+    iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let cut = if rest.len() <= max_bytes {
+            rest.len()
+        } else {
+            let mut limit = max_bytes;
+            while !rest.is_char_boundary(limit) {
+                limit -= 1;
+            }
+            if limit == 0 {
+                // Only reachable below MIN_MAX_BYTES: emit the character whole.
+                limit = rest.chars().next().map_or(rest.len(), char::len_utf8);
+            }
+            if rest[limit..].starts_with(char::is_whitespace) {
+                limit
+            } else {
+                // `rest` starts with a non-whitespace char, so any match is past 0.
+                rest[..limit].rfind(char::is_whitespace).unwrap_or(limit)
+            }
+        };
+        let chunk = rest[..cut].trim_end();
+        rest = rest[cut..].trim_start();
+        Some(chunk.to_string())
+    })
+}
+
+/// Returns the level of an ATX Markdown heading line (`#` through `######`).
+fn markdown_heading_level(line: &str) -> Option<u8> {
+    let trimmed = line.trim_start();
+    let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[hashes..];
+    let is_heading =
+        (1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(char::is_whitespace));
+    is_heading.then(|| u8::try_from(hashes).ok()).flatten()
+}
+
+/// Returns the text of a heading line without its opening and closing hashes.
+fn heading_text(line: &str) -> &str {
+    let text = line.trim().trim_start_matches('#').trim_start();
+    // Closing hashes only count when separated by a space, as in `# C#`.
+    match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(char::is_whitespace) => {
+            stripped.trim_end()
+        }
+        _ => text,
+    }
+}
+
+/// Starts a new chunk at every heading of level `max_level` or higher.
+///
+/// The heading line stays as the first line of its chunk, and any content
+/// before the first heading becomes a leading chunk of its own. Lines inside
+/// code blocks, such as shell comments, are never treated as headings.
+fn split_markdown_headings(text: &str, max_level: u8) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+
+    for (line, in_code) in fenced_lines(text) {
+        let is_split_heading = markdown_heading_level(line).is_some_and(|level| level <= max_level);
+        if !in_code && is_split_heading {
+            push_lines(&mut chunks, &current);
+            current.clear();
+        }
+        current.push(line);
+    }
+    push_lines(&mut chunks, &current);
+
+    chunks
+}
+
+/// A line of the form `::name` that controls splitting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    /// `::slide` forces a slide boundary.
+    Slide,
+    /// `::skip` drops everything up to the next `::endskip`.
+    Skip,
+    /// `::endskip` ends a skipped block.
+    EndSkip,
+}
+
+/// Parses a directive line; unknown `::` names are ordinary text.
+fn directive(line: &str) -> Option<Directive> {
+    match line.trim() {
+        "::slide" => Some(Directive::Slide),
+        "::skip" => Some(Directive::Skip),
+        "::endskip" => Some(Directive::EndSkip),
+        _ => None,
+    }
+}
+
+/// Applies directive lines, returning the sections between `::slide` lines
+/// with skipped blocks removed, or `None` if the text has no directives.
+///
+/// Directive lines never appear in the output. A `::skip` without a matching
+/// `::endskip` drops the rest of the text, and directives inside code blocks
+/// are left alone.
+fn directive_sections(text: &str) -> Option<Vec<String>> {
+    let directives =
+        || fenced_lines(text).map(|(line, in_code)| (line, directive(line).filter(|_| !in_code)));
+    if !text.contains("::") || directives().all(|(_, directive)| directive.is_none()) {
+        return None;
+    }
+
+    let mut sections = Vec::new();
+    let mut current = Vec::new();
+    let mut skipping = false;
+    for (line, directive) in directives() {
+        match directive {
+            Some(Directive::Skip) => skipping = true,
+            Some(Directive::EndSkip) => skipping = false,
+            _ if skipping => {}
+            Some(Directive::Slide) => {
+                sections.push(current.join("\n"));
+                current.clear();
+            }
+            None => current.push(line),
+        }
+    }
+    sections.push(current.join("\n"));
+
+    Some(sections)
+}
+
+/// Keywords org-mode puts in front of a heading to track its state.
+const ORG_TODO_KEYWORDS: &[&str] = &["TODO", "DONE"];
+
+/// Returns the level of an org-mode heading (`*`, `**`, ... then a space),
+/// or `None` for other lines. Org headings always start in the first column.
+fn org_heading_level(line: &str) -> Option<u8> {
+    let stars = line.chars().take_while(|&c| c == '*').count();
+    let is_heading = stars > 0 && line[stars..].starts_with([' ', '\t']);
+    is_heading.then(|| u8::try_from(stars).unwrap_or(u8::MAX))
+}
+
+/// Returns the text of an org-mode heading without its stars, TODO/DONE
+/// keyword, priority cookie such as `[#A]`, and trailing `:tags:`.
+fn org_heading_text(line: &str) -> &str {
+    let mut text = line.trim_start_matches('*').trim();
+    let keyword = ORG_TODO_KEYWORDS.iter().find_map(|keyword| {
+        text.strip_prefix(keyword)
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    });
+    if let Some(rest) = keyword {
+        text = rest.trim_start();
+    }
+    let mut cookie = text.chars();
+    if cookie.next() == Some('[')
+        && cookie.next() == Some('#')
+        && cookie.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && cookie.next() == Some(']')
+    {
+        text = cookie.as_str().trim_start();
+    }
+    match text.rsplit_once(char::is_whitespace) {
+        Some((heading, tags)) if is_org_tags(tags) => heading.trim_end(),
+        _ => text,
+    }
+}
+
+/// Whether a word is an org-mode tag list such as `:work:urgent:`.
+fn is_org_tags(word: &str) -> bool {
+    let Some(tags) = word
+        .strip_prefix(':')
+        .and_then(|rest| rest.strip_suffix(':'))
+    else {
+        return false;
+    };
+    !tags.is_empty()
+        && tags.split(':').all(|tag| {
+            !tag.is_empty()
+                && tag
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%'))
+        })
+}
+
+/// Whether a line opens (`true`) or closes (`false`) an org-mode block such
+/// as `#+BEGIN_SRC`, or `None` if it does neither.
+fn org_block_delimiter(line: &str) -> Option<bool> {
+    let line = line.trim_start().to_ascii_lowercase();
+    if line.starts_with("#+begin_") {
+        Some(true)
+    } else if line.starts_with("#+end_") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Starts a new chunk at every org-mode heading of level `max_level` or higher.
+///
+/// Like [`split_markdown_headings`], the heading stays as the first line of
+/// its chunk, but cleaned with [`org_heading_text`]. A `:PROPERTIES:` drawer
+/// directly under a heading is dropped, and lines inside `#+BEGIN_...` blocks
+/// are never treated as headings.
+fn split_org_headings(text: &str, max_level: u8) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    let mut in_block = false;
+    let mut in_drawer = false;
+    let mut after_heading = false;
+
+    for line in text.lines() {
+        if in_drawer {
+            in_drawer = !line.trim().eq_ignore_ascii_case(":END:");
+            continue;
+        }
+        if after_heading && line.trim().eq_ignore_ascii_case(":PROPERTIES:") {
+            in_drawer = true;
+            after_heading = false;
+            continue;
+        }
+        after_heading = false;
+
+        if in_block {
+            in_block = org_block_delimiter(line) != Some(false);
+            current.push(line.to_string());
+            continue;
+        }
+        in_block = org_block_delimiter(line) == Some(true);
+
+        let Some(level) = org_heading_level(line) else {
+            current.push(line.to_string());
+            continue;
+        };
+        if level <= max_level {
+            push_trimmed(&mut chunks, &current.join("\n"));
+            current.clear();
+        }
+        current.push(format!(
+            "{} {}",
+            "*".repeat(usize::from(level)),
+            org_heading_text(line)
+        ));
+        after_heading = true;
+    }
+    push_trimmed(&mut chunks, &current.join("\n"));
+
+    chunks
+}
+
+/// Whether a line opens or closes a fenced code block (```` ``` ```` or `~~~`).
+fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// A run of text that is either ordinary prose or a fenced code block.
+#[derive(Debug, PartialEq, Eq)]
+enum Block<'a> {
+    Prose(&'a str),
+    /// Everything from an opening fence through its closing fence.
+    Code(&'a str),
+}
+
+/// Pre-tokenizes text into prose and fenced code blocks, so that splitters can
+/// treat each code block as an atomic unit.
+///
+/// An unclosed fence runs to the end of the text.
+fn code_blocks(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut in_fence = false;
+
+    for line in text.split_inclusive('\n') {
+        let line_end = offset + line.len();
+        if is_code_fence(line) {
+            if in_fence {
+                blocks.push(Block::Code(&text[start..line_end]));
+                start = line_end;
+            } else {
+                if start < offset {
+                    blocks.push(Block::Prose(&text[start..offset]));
+                }
+                start = offset;
+            }
+            in_fence = !in_fence;
+        }
+        offset = line_end;
+    }
+    if start < text.len() {
+        let rest = &text[start..];
+        blocks.push(if in_fence {
+            Block::Code(rest)
+        } else {
+            Block::Prose(rest)
+        });
+    }
+
+    blocks
+}
+
+/// Iterates over lines, flagging those that belong to a fenced code block
+/// (fence lines included).
+fn fenced_lines(text: &str) -> impl Iterator<Item = (&str, bool)> {
+    code_blocks(text).into_iter().flat_map(|block| {
+        let (text, in_code) = match block {
+            Block::Prose(prose) => (prose, false),
+            Block::Code(code) => (code, true),
+        };
+        text.lines().map(move |line| (line, in_code))
+    })
+}
+
+/// Runs a size-limited split on the prose between code blocks, keeping each
+/// code block whole.
+///
+/// A code block shares a chunk with the neighboring prose when the combined
+/// size stays within `limit`; one larger than `limit` becomes its own
+/// oversized chunk. Text without code blocks is split exactly as `split` would.
+fn split_around_code<'a>(
+    text: &'a str,
+    limit: usize,
+    size: impl Fn(&str) -> usize,
+    split: impl Fn(&'a str) -> Chunks<'a>,
+) -> Chunks<'a> {
+    if !text.lines().any(is_code_fence) {
+        return split(text);
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    for block in code_blocks(text) {
+        let pieces = match block {
+            Block::Prose(prose) => split(prose),
+            Block::Code(code) => {
+                let mut pieces = Vec::new();
+                push_trimmed(&mut pieces, code);
+                Box::new(pieces.into_iter())
+            }
+        };
+        for (index, piece) in pieces.enumerate() {
+            // Only the first piece of a block borders the previous block.
+            match chunks.last_mut() {
+                Some(last) if index == 0 && size(&format!("{last}\n{piece}")) <= limit => {
+                    last.push('\n');
+                    last.push_str(&piece);
+                }
+                _ => chunks.push(piece),
+            }
+        }
+    }
+
+    Box::new(chunks.into_iter())
+}
+
+/// Whether a line consists solely of three or more dashes.
+fn is_horizontal_rule(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= 3 && trimmed.chars().all(|c| c == '-')
+}
+
+/// Splits on `---` lines (Marp/reveal.js style), ignoring rules inside code fences.
+fn split_horizontal_rules(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+
+    for (line, in_code) in fenced_lines(text) {
+        if !in_code && is_horizontal_rule(line) {
+            push_lines(&mut chunks, &current);
+            current.clear();
+            continue;
+        }
+        current.push(line);
+    }
+    push_lines(&mut chunks, &current);
+
+    chunks
+}
+
+/// Columns a tab advances to when measuring indentation.
+const TAB_WIDTH: usize = 4;
+
+/// Width of a line's leading whitespace, with tabs advancing to the next tab stop.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .fold(0, |width, c| {
+            if c == '\t' {
+                (width / TAB_WIDTH + 1) * TAB_WIDTH
+            } else {
+                width + 1
+            }
+        })
+}
+
+/// Whether a line is a Markdown bullet item (`-`, `*` or `+` followed by a space).
+fn is_bullet_item(line: &str) -> bool {
+    let mut chars = line.trim_start().chars();
+    matches!(chars.next(), Some('-' | '*' | '+')) && chars.next().is_none_or(char::is_whitespace)
+}
+
+/// Emits each top-level bullet together with its nested items as one chunk.
+///
+/// Indentation inside a chunk is preserved. Text between lists becomes a
+/// chunk of its own, and lines inside code fences never start a new item.
+fn split_bullet_lists(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    // Indentation of the current group's top-level bullet, if inside a list.
+    let mut list_indent = None;
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if in_fence || line.trim().is_empty() {
+            in_fence ^= is_code_fence(line);
+            current.push(line);
+            continue;
+        }
+        in_fence = is_code_fence(line);
+
+        let indent = indent_width(line);
+        let nested = list_indent.is_some_and(|top| indent > top);
+        if is_bullet_item(line) && !nested {
+            push_indented_lines(&mut chunks, &current);
+            current.clear();
+            list_indent = Some(indent);
+        } else if !nested && list_indent.is_some() {
+            // An unindented non-bullet line ends the list.
+            push_indented_lines(&mut chunks, &current);
+            current.clear();
+            list_indent = None;
+        }
+        current.push(line);
+    }
+    push_indented_lines(&mut chunks, &current);
+
+    chunks
+}
+
+/// Like [`push_lines`], but keeps the indentation of the first line.
+fn push_indented_lines(chunks: &mut Vec<String>, lines: &[&str]) {
+    let start = lines.iter().position(|line| !line.trim().is_empty());
+    if let Some(start) = start {
+        chunks.push(lines[start..].join("\n").trim_end().to_string());
+    }
+}
+
+/// Returns a blockquote line's text with its `>` markers removed, including
+/// those of nested quotes, or `None` if the line is not quoted.
+fn blockquote_text(line: &str) -> Option<&str> {
+    let mut rest = line.trim_start().strip_prefix('>')?;
+    loop {
+        let text = rest.strip_prefix(' ').unwrap_or(rest);
+        match text.strip_prefix('>') {
+            Some(nested) => rest = nested,
+            None => return Some(text),
+        }
+    }
+}
+
+/// Whether a line credits a quote, e.g. `— Ada Lovelace` or `-- Anonymous`.
+fn is_attribution(line: &str) -> bool {
+    let line = line.trim_start();
+    (line.starts_with('—') || line.starts_with("--")) && !is_horizontal_rule(line)
+}
+
+/// Emits each blockquote, with its markers stripped, as one chunk.
+///
+/// A blank line ends a quote, while a `>` line with no text keeps quote
+/// paragraphs together. An attribution on the line right after a quote
+/// belongs to it. Other text becomes chunks of its own when `include_prose`
+/// is set and is dropped otherwise; lines inside code fences are never quotes.
+fn split_block_quotes(text: &str, include_prose: bool) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut quote = Vec::new();
+    let mut prose = Vec::new();
+
+    for (line, in_code) in fenced_lines(text) {
+        if let Some(quoted) = blockquote_text(line).filter(|_| !in_code) {
+            if include_prose {
+                push_lines(&mut chunks, &prose);
+            }
+            prose.clear();
+            quote.push(quoted);
+            continue;
+        }
+        if !quote.is_empty() {
+            let attributed = !in_code && is_attribution(line);
+            if attributed {
+                quote.push(line.trim());
+            }
+            push_lines(&mut chunks, &quote);
+            quote.clear();
+            if attributed {
+                continue;
+            }
+        }
+        prose.push(line);
+    }
+    push_lines(&mut chunks, &quote);
+    if include_prose {
+        push_lines(&mut chunks, &prose);
+    }
+
+    chunks
+}
+
+/// Emits each top-level line of an indented outline together with the
+/// lines indented beneath it as one chunk.
+///
+/// The indentation unit is detected from the text: a tab if lines are
+/// indented with tabs, otherwise the smallest indent used. Lines are
+/// re-indented by one unit per level relative to the least indented line;
+/// a line whose indent is not a whole number of units keeps the level of the
+/// line before it. Blank lines stay with the group they appear in.
+fn split_outline(text: &str) -> Vec<String> {
+    let indents = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(indent_width);
+    let Some(base) = indents.clone().min() else {
+        return Vec::new();
+    };
+    let width = indents
+        .map(|indent| indent - base)
+        .filter(|&indent| indent > 0)
+        .min()
+        .unwrap_or(1);
+    let unit = if text.lines().any(|line| line.starts_with('\t')) {
+        "\t".to_string()
+    } else {
+        " ".repeat(width)
+    };
+
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            current.push(String::new());
+            continue;
+        }
+        let relative = indent_width(line) - base;
+        if relative == 0 {
+            push_indented_lines(
+                &mut chunks,
+                &current.iter().map(String::as_str).collect::<Vec<_>>(),
+            );
+            current.clear();
+            depth = 0;
+        } else if relative.is_multiple_of(width) {
+            depth = relative / width;
+        } else {
+            depth = depth.max(1);
+        }
+        current.push(format!("{}{}", unit.repeat(depth), line.trim_start()));
+    }
+    push_indented_lines(
+        &mut chunks,
+        &current.iter().map(String::as_str).collect::<Vec<_>>(),
+    );
+
+    chunks
+}
+
+/// Compiles a user-supplied pattern with `^`/`$` matching at line boundaries.
+fn build_regex(pattern: &str) -> Result<Regex, SplitError> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(SplitError::PatternTooLong(pattern.len()));
+    }
+    RegexBuilder::new(pattern)
+        .multi_line(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| SplitError::InvalidPattern(e.to_string()))
+}
+
+/// Splits at every match of `pattern`.
+///
+/// With `keep_delimiter` the matched text starts the following chunk,
+/// otherwise it is dropped. Patterns are checked by [`Splitter::validate`];
+/// an invalid one leaves the text unsplit.
+fn split_regex(text: &str, pattern: &str, keep_delimiter: bool) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let Ok(regex) = build_regex(pattern) else {
+        push_trimmed(&mut chunks, text);
+        return chunks;
+    };
+
+    let mut start = 0;
+    for found in regex.find_iter(text) {
+        push_trimmed(&mut chunks, &text[start..found.start()]);
+        start = if keep_delimiter {
+            found.start()
+        } else {
+            found.end()
+        };
+    }
+    push_trimmed(&mut chunks, &text[start..]);
+
+    chunks
+}
+
+/// Joins lines into a trimmed chunk, skipping it if nothing is left.
+fn push_lines(chunks: &mut Vec<String>, lines: &[&str]) {
+    push_trimmed(chunks, &lines.join("\n"));
+}
+
+/// Pushes the trimmed text as a chunk unless it is empty.
+fn push_trimmed(chunks: &mut Vec<String>, text: &str) {
+    let chunk = text.trim();
+    if !chunk.is_empty() {
+        chunks.push(chunk.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    // NewLine splitter test cases
+    #[rstest]
+    #[case::basic_lines("Line 1\nLine 2\nLine 3", vec!["Line 1", "Line 2", "Line 3"])]
+    #[case::empty_string("", vec![])]
+    #[case::only_whitespace("   \n   \n   ", vec![])]
+    #[case::with_tabs_and_spaces("  Line 1  \n\t Line 2 \t\n   Line 3   ", vec!["Line 1", "Line 2", "Line 3"])]
+    #[case::newlines_only("\n\n\n", vec![])]
+    #[case::mixed_content("Line 1\nLine 2\n\nLine 3\nLine 4\n\n\nLine 5", vec!["Line 1", "Line 2", "Line 3", "Line 4", "Line 5"])]
+    fn test_newline_splitter(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let splitter = Splitter::NewLine;
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    // EmptyLine splitter test cases
+    #[rstest]
+    #[case::basic_paragraphs("Line 1\nLine 2\n\nLine 3\nLine 4", vec!["Line 1\nLine 2", "Line 3\nLine 4"])]
+    #[case::empty_string("", vec![])]
+    #[case::single_paragraph("This is a single paragraph\nwith multiple lines\nbut no empty lines", vec!["This is a single paragraph\nwith multiple lines\nbut no empty lines"])]
+    #[case::multiple_empty_lines("Para 1\n\n\n\nPara 2\n\n\n\n\nPara 3", vec!["Para 1", "Para 2", "Para 3"])]
+    #[case::trailing_content("Line 1\nLine 2\n\nLine 3\nLine 4\n\n\nLine 5", vec!["Line 1\nLine 2", "Line 3\nLine 4", "Line 5"])]
+    #[case::space_only_separator("Para 1\n \nPara 2", vec!["Para 1", "Para 2"])]
+    #[case::tab_only_separator("Para 1\n\t\nPara 2", vec!["Para 1", "Para 2"])]
+    #[case::mixed_whitespace_separator("Para 1\n \t  \n\t \nPara 2", vec!["Para 1", "Para 2"])]
+    #[case::crlf_separator("Para 1\r\n\r\nPara 2", vec!["Para 1", "Para 2"])]
+    #[case::whitespace_only_input(" \n\t\n  ", vec![])]
+    #[case::indentation_inside_paragraph_kept("  Line 1\n  Line 2", vec!["Line 1\n  Line 2"])]
+    fn test_empty_line_splitter(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let splitter = Splitter::EmptyLine;
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    // MaxWords splitter test cases
+    #[rstest]
+    #[case::basic_chunking("The quick brown fox jumps over the lazy dog", 3, vec!["The quick brown", "fox jumps over", "the lazy dog"])]
+    #[case::exact_division("one two three four", 2, vec!["one two", "three four"])]
+    #[case::larger_than_text("only three words", 10, vec!["only three words"])]
+    #[case::empty_string("", 5, vec![])]
+    #[case::extra_whitespace("  word1   word2    word3   word4  ", 2, vec!["word1 word2", "word3 word4"])]
+    #[case::single_word_chunks("one two three", 1, vec!["one", "two", "three"])]
+    fn test_max_words_splitter(
+        #[case] input: &str,
+        #[case] max_words: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::backs_up_to_sentence_end(
+        "One two three. Four five six seven eight nine.",
+        4,
+        vec!["One two three.", "Four five six seven eight nine."]
+    )]
+    #[case::runs_over_to_finish_sentence(
+        "a b c d e f g h i j k. l m",
+        10,
+        vec!["a b c d e f g h i j k.", "l m"]
+    )]
+    #[case::exact_boundary("One two. Three four.", 2, vec!["One two.", "Three four."])]
+    #[case::overlong_sentence_kept_whole(
+        "This sentence is far longer than the limit allows. Short one.",
+        3,
+        vec!["This sentence is far longer than the limit allows.", "Short one."]
+    )]
+    #[case::quotes_and_other_terminators(
+        "He said \"stop!\" Then left? Yes.",
+        3,
+        vec!["He said \"stop!\"", "Then left? Yes."]
+    )]
+    #[case::no_terminators("just some words without punctuation", 2, vec!["just some words without punctuation"])]
+    #[case::empty_string("", 5, vec![])]
+    fn test_max_words_respect_sentences(
+        #[case] input: &str,
+        #[case] max_words: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: true,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::chinese("我喜欢读书。你呢？", 3, vec!["我喜欢", "读书。你", "呢？"])]
+    #[case::japanese("東京はとても大きい都市です。", 4, vec!["東京はと", "ても大き", "い都市で", "す。"])]
+    #[case::mixed_cjk_latin("我们用 Rust 写 Cloudflare Worker。", 3, vec!["我们用", "Rust 写 Cloudflare", "Worker。"])]
+    #[case::latin_keeps_punctuation("Hello, world! How are you?", 2, vec!["Hello, world!", "How are", "you?"])]
+    #[case::empty_string("", 5, vec![])]
+    fn test_max_words_unicode_segmentation(
+        #[case] input: &str,
+        #[case] max_words: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Unicode,
+            balanced: false,
+        };
+        assert_eq!(splitter.split(input), expected);
+    }
+
+    #[rstest]
+    #[case::chinese(
+        "在北京的时候，我每天早上都去公园散步，然后回家吃早饭，下午去图书馆看书。",
+        10
+    )]
+    #[case::japanese(
+        "今日は天気がいいので、友達と一緒に公園へ行って、お弁当を食べました。",
+        10
+    )]
+    #[case::mixed(
+        "我们的团队使用 Rust 和 TypeScript 开发 web 应用，每周发布一次新版本。",
+        8
+    )]
+    fn test_max_words_unicode_segmentation_chunk_counts(
+        #[case] input: &str,
+        #[case] max_words: usize,
+    ) {
+        let whitespace = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        let unicode = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Unicode,
+            balanced: false,
+        };
+
+        // Whitespace splitting sees (nearly) one giant word and makes one slide.
+        assert_eq!(whitespace.split(input).len(), 1);
+
+        // Unicode splitting yields one chunk per `max_words` words and loses no text.
+        let words = textmetrics::unicode_word_spans(input).len();
+        let chunks = unicode.split(input);
+        assert_eq!(chunks.len(), words.div_ceil(max_words));
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat().replace(' ', ""), input.replace(' ', ""));
+    }
+
+    #[rstest]
+    fn test_max_words_unicode_respects_cjk_sentences() {
+        let splitter = Splitter::MaxWords {
+            max_words: 4,
+            respect_sentences: true,
+            overlap: 0,
+            segmentation: WordSegmentation::Unicode,
+            balanced: false,
+        };
+        assert_eq!(
+            splitter.split("我很好。今天天气不错！"),
+            vec!["我很好。", "今天天气不错！"]
+        );
+    }
+
+    #[rstest]
+    #[case::tiny_limit(1)]
+    #[case::small_limit(3)]
+    #[case::medium_limit(7)]
+    #[case::large_limit(50)]
+    fn test_max_words_respect_sentences_property(#[case] max_words: usize) {
+        let text = "Rust is fast. It is also safe! Do you like it? The borrow checker \
+                    takes some getting used to, but it pays off in the end. Yes. \
+                    A final sentence without any terminator";
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: true,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        let result = splitter.split(text);
+
+        // Property: words are preserved in order
+        assert_eq!(
+            result.join(" "),
+            textmetrics::words(text).collect::<Vec<_>>().join(" ")
+        );
+
+        let (last, rest) = result.split_last().unwrap();
+        assert!(!last.is_empty());
+        for chunk in rest {
+            // Property: no chunk is empty
+            assert!(!chunk.is_empty());
+
+            // Property: a chunk ends at a sentence terminator, or is a single
+            // overlong sentence cut where the limit forced it
+            let words = textmetrics::words(chunk).collect::<Vec<_>>();
+            let single_sentence = words[..words.len() - 1]
+                .iter()
+                .all(|word| !ends_sentence(word));
+            assert!(
+                ends_sentence(words[words.len() - 1]) || single_sentence,
+                "chunk {chunk:?} ends mid-sentence"
+            );
+        }
+    }
+
+    // Balanced MaxWords test cases
+    #[rstest]
+    #[case::one_over_limit(51, 50, vec![26, 25])]
+    #[case::even_split(100, 50, vec![50, 50])]
+    #[case::under_limit(10, 50, vec![10])]
+    #[case::three_chunks(11, 4, vec![4, 4, 3])]
+    #[case::single_word_chunks(3, 1, vec![1, 1, 1])]
+    #[case::empty(0, 5, vec![])]
+    fn test_max_words_balanced_sizes(
+        #[case] total: usize,
+        #[case] max_words: usize,
+        #[case] expected: Vec<usize>,
+    ) {
+        let text = (0..total)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: true,
+        };
+        let sizes = splitter
+            .split(&text)
+            .iter()
+            .map(|chunk| textmetrics::words(chunk).count())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, expected);
+    }
+
+    #[rstest]
+    fn test_max_words_balanced_property(
+        #[values(1, 2, 5, 13, 50)] max_words: usize,
+        #[values(1, 7, 51, 99, 250)] total: usize,
+    ) {
+        let words = (0..total).map(|i| format!("w{i}")).collect::<Vec<_>>();
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: true,
+        };
+        let result = splitter.split(&words.join(" "));
+        let sizes = result
+            .iter()
+            .map(|chunk| textmetrics::words(chunk).count())
+            .collect::<Vec<_>>();
+
+        // Property: as few chunks as greedy splitting, none over the limit
+        assert_eq!(sizes.len(), total.div_ceil(max_words));
+        assert!(sizes.iter().all(|&size| size <= max_words));
+
+        // Property: chunk sizes differ by at most one
+        let (min, max) = (sizes.iter().min().unwrap(), sizes.iter().max().unwrap());
+        assert!(max - min <= 1);
+
+        // Property: words are preserved in order
+        assert_eq!(result.join(" "), words.join(" "));
+    }
+
+    #[rstest]
+    #[case::with_respect_sentences(true, 0, "balanced cannot be combined with respect_sentences")]
+    #[case::with_overlap(false, 1, "overlap cannot be combined with balanced")]
+    fn test_max_words_balanced_validation(
+        #[case] respect_sentences: bool,
+        #[case] overlap: usize,
+        #[case] message: &str,
+    ) {
+        let splitter = Splitter::MaxWords {
+            max_words: 5,
+            respect_sentences,
+            overlap,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: true,
+        };
+        assert_eq!(splitter.validate().unwrap_err().to_string(), message);
+    }
+
+    // MaxChars splitter test cases
+    #[rstest]
+    #[case::basic_chunking("abcdefghijklmnop", 5, vec!["abcde", "fghij", "klmno", "p"])]
+    #[case::exact_division("12345678", 4, vec!["1234", "5678"])]
+    #[case::empty_string("", 5, vec![])]
+    #[case::larger_than_text("short", 20, vec!["short"])]
+    #[case::single_char_chunks("abc", 1, vec!["a", "b", "c"])]
+    #[case::unicode_support("Hello 🌍 World 🚀 Test 🦀", 6, vec!["Hello ", "🌍 Worl", "d 🚀 Te", "st 🦀"])]
+    fn test_max_chars_splitter(
+        #[case] input: &str,
+        #[case] max_chars: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxChars {
+            max_chars,
+            word_boundary: false,
+            overlap: 0,
+            unit: CharUnit::Chars,
+        };
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::breaks_between_words("Hello World", 8, vec!["Hello", "World"])]
+    #[case::boundary_at_limit("Hello World", 5, vec!["Hello", "World"])]
+    #[case::packs_multiple_words("one two three four", 9, vec!["one two", "three", "four"])]
+    #[case::long_word_broken("abcdefghij xy", 4, vec!["abcd", "efgh", "ij", "xy"])]
+    #[case::trims_boundaries("  lead   and   trail  ", 5, vec!["lead", "and", "trail"])]
+    #[case::newlines_are_whitespace("line one\nline two", 9, vec!["line one", "line two"])]
+    #[case::empty_string("", 5, vec![])]
+    #[case::whitespace_only("   \n\t ", 5, vec![])]
+    #[case::unicode_support("Hello 🌍 World 🚀 Test 🦀", 6, vec!["Hello", "🌍", "World", "🚀 Test", "🦀"])]
+    #[case::multibyte_words("café naïve über", 6, vec!["café", "naïve", "über"])]
+    fn test_max_chars_word_boundary(
+        #[case] input: &str,
+        #[case] max_chars: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxChars {
+            max_chars,
+            word_boundary: true,
+            overlap: 0,
+            unit: CharUnit::Chars,
+        };
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    // Overlap test cases
+    #[rstest]
+    #[case::one_word("one two three four five six", 3, 1, vec!["one two three", "three four five", "five six"])]
+    #[case::two_words("a b c d e f g", 4, 2, vec!["a b c d", "c d e f", "e f g"])]
+    #[case::fits_in_first_chunk("a b c", 4, 2, vec!["a b c"])]
+    #[case::ends_exactly_on_window("a b c d e", 3, 1, vec!["a b c", "c d e"])]
+    fn test_max_words_overlap(
+        #[case] input: &str,
+        #[case] max_words: usize,
+        #[case] overlap: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        assert_eq!(splitter.split(input), expected);
+    }
+
+    #[rstest]
+    #[case::basic("abcdefgh", 4, 1, vec!["abcd", "defg", "gh"])]
+    #[case::multibyte("🌍🚀🦀é", 2, 1, vec!["🌍🚀", "🚀🦀", "🦀é"])]
+    fn test_max_chars_overlap(
+        #[case] input: &str,
+        #[case] max_chars: usize,
+        #[case] overlap: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxChars {
+            max_chars,
+            word_boundary: false,
+            overlap,
+            unit: CharUnit::Chars,
+        };
+        assert_eq!(splitter.split(input), expected);
+    }
+
+    #[rstest]
+    fn test_overlap_reconstructs_original(
+        #[values(1, 2, 3, 5, 8)] size: usize,
+        #[values(0, 1, 2, 4, 7)] overlap: usize,
+    ) {
+        if overlap >= size {
+            return;
+        }
+        let text = "The quick brown fox jumps over the lazy dog and keeps on running far away";
+
+        let words = Splitter::MaxWords {
+            max_words: size,
+            respect_sentences: false,
+            overlap,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        }
+        .split(text);
+        let mut rebuilt = textmetrics::words(&words[0]).collect::<Vec<_>>();
+        for chunk in &words[1..] {
+            rebuilt.extend(textmetrics::words(chunk).skip(overlap));
+        }
+        assert_eq!(rebuilt, textmetrics::words(text).collect::<Vec<_>>());
+
+        let chars = Splitter::MaxChars {
+            max_chars: size,
+            word_boundary: false,
+            overlap,
+            unit: CharUnit::Chars,
+        }
+        .split(text);
+        let mut rebuilt = chars[0].clone();
+        for chunk in &chars[1..] {
+            rebuilt.extend(chunk.chars().skip(overlap));
+        }
+        assert_eq!(rebuilt, text);
+    }
+
+    #[rstest]
+    #[case::equal_to_size(Splitter::MaxWords { max_words: 3, respect_sentences: false, overlap: 3, segmentation: WordSegmentation::Whitespace, balanced: false }, "overlap (3) must be less than max_words (3)")]
+    #[case::larger_than_size(Splitter::MaxChars { max_chars: 5, word_boundary: false, overlap: 9, unit: CharUnit::Chars }, "overlap (9) must be less than max_chars (5)")]
+    #[case::with_respect_sentences(Splitter::MaxWords { max_words: 5, respect_sentences: true, overlap: 1, segmentation: WordSegmentation::Whitespace, balanced: false }, "overlap cannot be combined with respect_sentences")]
+    #[case::with_word_boundary(Splitter::MaxChars { max_chars: 5, word_boundary: true, overlap: 1, unit: CharUnit::Chars }, "overlap cannot be combined with word_boundary")]
+    fn test_overlap_validation(#[case] splitter: Splitter, #[case] message: &str) {
+        assert_eq!(splitter.validate().unwrap_err().to_string(), message);
+    }
+
+    #[rstest]
+    fn test_overlap_validation_accepts_smaller_overlap() {
+        let splitter = Splitter::MaxWords {
+            max_words: 5,
+            respect_sentences: false,
+            overlap: 4,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        assert_eq!(splitter.validate(), Ok(()));
+    }
+
+    // Chain splitter test cases
+    #[rstest]
+    fn test_chain_splits_long_paragraphs_further() {
+        let text = "Short intro paragraph.\n\n\
+                    one two three four five six seven eight nine ten\n\n\
+                    Closing words here.";
+        let splitter = Splitter::Chain {
+            splitters: vec![
+                Splitter::EmptyLine,
+                Splitter::MaxWords {
+                    max_words: 4,
+                    respect_sentences: false,
+                    overlap: 0,
+                    segmentation: WordSegmentation::Whitespace,
+                    balanced: false,
+                },
+            ],
+        };
+        let result = splitter.split(text);
+        assert_eq!(
+            result,
+            vec![
+                "Short intro paragraph.",
+                "one two three four",
+                "five six seven eight",
+                "nine ten",
+                "Closing words here."
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_chain_single_splitter_matches_plain() {
+        let text = "Line 1\nLine 2\n\nLine 3";
+        let chain = Splitter::Chain {
+            splitters: vec![Splitter::NewLine],
+        };
+        assert_eq!(chain.split(text), Splitter::NewLine.split(text));
+    }
+
+    #[rstest]
+    fn test_nested_chain() {
+        let text = "# A\npara one\n\npara two\n# B\nlast";
+        let splitter = Splitter::Chain {
+            splitters: vec![
+                Splitter::MarkdownHeadings { max_level: 1 },
+                Splitter::Chain {
+                    splitters: vec![Splitter::EmptyLine],
+                },
+            ],
+        };
+        assert_eq!(
+            splitter.split(text),
+            vec!["# A\npara one", "para two", "# B\nlast"]
+        );
+    }
+
+    #[rstest]
+    fn test_chain_validation() {
+        assert_eq!(
+            Splitter::Chain { splitters: vec![] }.validate(),
+            Err(SplitError::EmptyChain)
+        );
+
+        let mut nested = Splitter::NewLine;
+        for _ in 0..MAX_CHAIN_DEPTH {
+            nested = Splitter::Chain {
+                splitters: vec![nested],
+            };
+        }
+        assert_eq!(nested.validate(), Ok(()));
+
+        let too_deep = Splitter::Chain {
+            splitters: vec![nested],
+        };
+        assert_eq!(too_deep.validate(), Err(SplitError::ChainTooDeep));
+    }
+
+    #[rstest]
+    fn test_chain_validates_inner_splitters() {
+        let splitter = Splitter::Chain {
+            splitters: vec![
+                Splitter::EmptyLine,
+                Splitter::Regex {
+                    pattern: "(".to_string(),
+                    keep_delimiter: false,
+                },
+            ],
+        };
+        assert!(matches!(
+            splitter.validate(),
+            Err(SplitError::InvalidPattern(_))
+        ));
+    }
+
+    // Short chunk merging test cases
+    #[rstest]
+    #[case::trailing_chunk_merged(
+        vec!["one two three", "four five six", "seven"],
+        2,
+        vec!["one two three", "four five six\nseven"]
+    )]
+    #[case::middle_chunk_merged(
+        vec!["one two three", "four", "five six seven"],
+        2,
+        vec!["one two three\nfour", "five six seven"]
+    )]
+    #[case::consecutive_short_chunks(
+        vec!["one two", "three", "four"],
+        2,
+        vec!["one two\nthree\nfour"]
+    )]
+    #[case::short_first_chunk_kept(vec!["one", "two three four"], 2, vec!["one", "two three four"])]
+    #[case::only_chunk_kept(vec!["tiny"], 5, vec!["tiny"])]
+    #[case::threshold_zero_noop(vec!["a", "b"], 0, vec!["a", "b"])]
+    #[case::exactly_threshold_kept(vec!["a b", "c d"], 2, vec!["a b", "c d"])]
+    #[case::empty(vec![], 3, vec![])]
+    fn test_merge_short_chunks(
+        #[case] chunks: Vec<&str>,
+        #[case] min_words: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let chunks = chunks.into_iter().map(str::to_string).collect();
+        assert_eq!(merge_short_chunks(chunks, min_words), expected);
+    }
+
+    #[rstest]
+    #[case::heading_with_text(
+        vec!["# Part One\nIntro text"],
+        vec![SlideSpec::Section("Part One".to_string()), SlideSpec::Content("Intro text".to_string())]
+    )]
+    #[case::heading_only(vec!["# Part One\n\n"], vec![SlideSpec::Section("Part One".to_string())])]
+    #[case::heading_mid_chunk(
+        vec!["Before\n# Part Two\nAfter"],
+        vec![
+            SlideSpec::Content("Before".to_string()),
+            SlideSpec::Section("Part Two".to_string()),
+            SlideSpec::Content("After".to_string()),
+        ]
+    )]
+    #[case::lower_levels_stay_content(
+        vec!["## Topic\nText"],
+        vec![SlideSpec::Content("## Topic\nText".to_string())]
+    )]
+    #[case::heading_in_code_block(
+        vec!["```\n# comment\n```"],
+        vec![SlideSpec::Content("```\n# comment\n```".to_string())]
+    )]
+    #[case::empty(vec![], vec![])]
+    fn test_section_specs(#[case] chunks: Vec<&str>, #[case] expected: Vec<SlideSpec>) {
+        let chunks = chunks.into_iter().map(str::to_string).collect();
+        assert_eq!(section_specs(chunks), expected);
+    }
+
+    #[rstest]
+    #[case::no_duplicates(vec!["a", "b"], vec!["a", "b"], 0)]
+    #[case::repeated_chorus(
+        vec!["Verse one", "Chorus", "Verse two", "Chorus", "Chorus"],
+        vec!["Verse one", "Chorus", "Verse two"],
+        2
+    )]
+    #[case::compares_trimmed(vec!["Chorus", "  Chorus\n"], vec!["Chorus"], 1)]
+    #[case::case_sensitive(vec!["Chorus", "chorus"], vec!["Chorus", "chorus"], 0)]
+    #[case::inner_whitespace_matters(vec!["a b", "a  b"], vec!["a b", "a  b"], 0)]
+    #[case::empty(vec![], vec![], 0)]
+    fn test_dedupe_chunks(
+        #[case] chunks: Vec<&str>,
+        #[case] expected: Vec<&str>,
+        #[case] expected_removed: usize,
+    ) {
+        let chunks = chunks.into_iter().map(str::to_string).collect();
+        assert_eq!(
+            dedupe_chunks(chunks),
+            (
+                expected.into_iter().map(String::from).collect(),
+                expected_removed
+            )
+        );
+    }
+
+    // Titled chunk test cases
+    #[rstest]
+    #[case::paragraph_first_line(
+        Splitter::EmptyLine,
+        "Intro\nFirst point\nSecond point\n\nAlone",
+        vec![(Some("Intro"), "First point\nSecond point"), (None, "Alone")]
+    )]
+    #[case::paragraph_starting_with_code(
+        Splitter::EmptyLine,
+        "```\ncode\n```",
+        vec![(None, "```\ncode\n```")]
+    )]
+    #[case::heading_text(
+        Splitter::MarkdownHeadings { max_level: 2 },
+        "Preamble\nmore\n# Title\nBody text\n## Sub ##\nDetails",
+        vec![(None, "Preamble\nmore"), (Some("Title"), "Body text"), (Some("Sub"), "Details")]
+    )]
+    #[case::heading_only(
+        Splitter::MarkdownHeadings { max_level: 1 },
+        "# Lonely",
+        vec![(None, "# Lonely")]
+    )]
+    #[case::other_strategies_untitled(
+        Splitter::NewLine,
+        "a\nb",
+        vec![(None, "a"), (None, "b")]
+    )]
+    #[case::horizontal_rule_untitled(
+        Splitter::HorizontalRule,
+        "Title\nbody\n---\nNext",
+        vec![(None, "Title\nbody"), (None, "Next")]
+    )]
+    fn test_split_chunks(
+        #[case] splitter: Splitter,
+        #[case] input: &str,
+        #[case] expected: Vec<(Option<&str>, &str)>,
+    ) {
+        let chunks = splitter.split_chunks(input);
+        let actual = chunks
+            .iter()
+            .map(|chunk| (chunk.title.as_deref(), chunk.body.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn test_split_chunks_matches_split_when_untitled() {
+        let splitter = Splitter::EmptyLine;
+        let text = "One\n\nTwo\n\nThree";
+        let bodies = splitter
+            .split_chunks(text)
+            .into_iter()
+            .map(|chunk| chunk.body)
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, splitter.split(text));
+    }
+
+    // Speaker notes test cases
+    #[rstest]
+    #[case::multi_line("Title\nLine one\nLine two", "Title", "Line one\nLine two")]
+    #[case::single_line("Just a title", "Just a title", "")]
+    #[case::surrounding_whitespace("\n  Title  \n\n  Body\n", "Title", "Body")]
+    fn test_chunk_from_first_line(#[case] text: &str, #[case] title: &str, #[case] body: &str) {
+        let chunk = Chunk::from_first_line(text);
+        assert_eq!(chunk.title.as_deref(), Some(title));
+        assert_eq!(chunk.body, body);
+    }
+
+    #[rstest]
+    #[case::with_notes("Slide text\n???\nSay hello", "Slide text", "Say hello")]
+    #[case::no_marker("Slide text\nMore", "Slide text\nMore", "")]
+    #[case::only_first_marker_splits("A\n???\nB\n???\nC", "A", "B\n???\nC")]
+    #[case::marker_with_whitespace("A\n  ???  \nB", "A", "B")]
+    #[case::marker_inside_text_ignored("What???\nB", "What???\nB", "")]
+    #[case::marker_in_code_block_ignored("```\n???\n```\n???\nNotes", "```\n???\n```", "Notes")]
+    #[case::notes_only("???\nJust notes", "", "Just notes")]
+    #[case::crlf("A\r\n???\r\nB", "A", "B")]
+    fn test_split_notes(#[case] chunk: &str, #[case] visible: &str, #[case] notes: &str) {
+        assert_eq!(split_notes(chunk, DEFAULT_NOTES_MARKER), (visible, notes));
+    }
+
+    #[rstest]
+    #[case::marker("[skip]\nDraft", Some("Draft"))]
+    #[case::any_case("  [SKIP]  \r\nDraft", Some("Draft"))]
+    #[case::after_blank_lines("\n\n[skip]\nDraft\nMore", Some("Draft\nMore"))]
+    #[case::marker_only("[skip]", Some(""))]
+    #[case::not_first_line("Draft\n[skip]", None)]
+    #[case::inside_text("[skip] Draft", None)]
+    fn test_strip_skip_marker(#[case] chunk: &str, #[case] expected: Option<&str>) {
+        assert_eq!(strip_skip_marker(chunk), expected);
+    }
+
+    #[rstest]
+    fn test_split_notes_custom_marker() {
+        assert_eq!(
+            split_notes("Text\nNote:\nRemember", "Note:"),
+            ("Text", "Remember")
+        );
+    }
+
+    // Whitespace normalization test cases
+    #[rstest]
+    #[case::nbsp("one\u{00A0}two\u{202F}three", "one two three")]
+    #[case::tabs_inside_line("name\tvalue\t\tother", "name value other")]
+    #[case::space_runs("too    many   spaces", "too many spaces")]
+    #[case::trailing_whitespace("text  \t", "text")]
+    #[case::line_breaks_kept("a  b\n\nc\u{00A0} d", "a b\n\nc d")]
+    #[case::crlf_becomes_lf("a\r\nb", "a\nb")]
+    #[case::indentation_kept("- item\n\t-  child", "- item\n\t- child")]
+    #[case::code_block_untouched("```\nlet  x =\t1;\n```", "```\nlet  x =\t1;\n```")]
+    #[case::clean_text_unchanged("Already clean.\nTwo lines.", "Already clean.\nTwo lines.")]
+    fn test_normalize_whitespace(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(normalize_whitespace(input), expected);
+    }
+
+    #[rstest]
+    fn test_normalize_whitespace_keeps_line_splitters() {
+        let text = "First\u{00A0}\u{00A0}line\nSecond\tline\n\nNext   paragraph";
+        let normalized = normalize_whitespace(text);
+        assert_eq!(
+            Splitter::NewLine.split(&normalized),
+            vec!["First line", "Second line", "Next paragraph"]
+        );
+        assert_eq!(
+            Splitter::EmptyLine.split(&normalized).len(),
+            Splitter::EmptyLine.split(text).len()
+        );
+    }
+
+    #[rstest]
+    fn test_normalize_whitespace_keeps_max_words_counts_for_clean_text(
+        #[values(1, 3, 7, 50)] max_words: usize,
+    ) {
+        let text = "The quick brown fox jumps over the lazy dog.\nIt was not amused.\n\nThe end.";
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        let counts = |text: &str| {
+            splitter
+                .split(text)
+                .iter()
+                .map(|chunk| textmetrics::words(chunk).count())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(counts(&normalize_whitespace(text)), counts(text));
+    }
+
+    // Markdown stripping test cases
+    #[rstest]
+    #[case::bold("Some **bold** text", "Some bold text")]
+    #[case::italic_asterisk("Some *italic* text", "Some italic text")]
+    #[case::italic_underscore("Some _italic_ text", "Some italic text")]
+    #[case::bold_underscore("__bold__ start", "bold start")]
+    #[case::nested_emphasis("***very*** and **bold _and italic_**", "very and bold and italic")]
+    #[case::strikethrough("~~old~~ new", "old new")]
+    #[case::snake_case_kept("call my_func_name now", "call my_func_name now")]
+    #[case::lone_asterisk_kept("2 * 3 = 6", "2 * 3 = 6")]
+    #[case::link("See [the docs](https://example.com) here", "See the docs here")]
+    #[case::link_with_emphasis("[**Rust** book](https://doc.rust-lang.org/book/)", "Rust book")]
+    #[case::image("![Logo](logo.png) Company", "Logo Company")]
+    #[case::inline_code("Run `cargo test` now", "Run cargo test now")]
+    #[case::inline_code_keeps_markers("Use `**kwargs` and `_private`", "Use **kwargs and _private")]
+    #[case::double_backtick_code("``a ` b``", "a ` b")]
+    #[case::unclosed_backtick("a ` b *c*", "a ` b c")]
+    #[case::heading("## Getting *started* ##", "Getting started")]
+    #[case::heading_ending_in_hash("# Learning C#", "Learning C#")]
+    #[case::bullets("- one\n  * two\n+ three", "• one\n  • two\n• three")]
+    #[case::code_block("Intro\n```rust\nlet **x** = 1;\n```", "Intro\nlet **x** = 1;")]
+    #[case::plain_text_unchanged("Just a sentence.", "Just a sentence.")]
+    #[case::empty("", "")]
+    fn test_strip_markdown(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(strip_markdown(input), expected);
+    }
+
+    // Grapheme unit test cases
+    #[rstest]
+    #[case::zwj_family("ab👨‍👩‍👧‍👦cd", 3, vec!["ab👨‍👩‍👧‍👦", "cd"])]
+    #[case::flags("🇫🇷🇩🇪🇯🇵", 2, vec!["🇫🇷🇩🇪", "🇯🇵"])]
+    #[case::combining_diacritics("e\u{0301}e\u{0301}e\u{0301}", 2, vec!["e\u{0301}e\u{0301}", "e\u{0301}"])]
+    #[case::skin_tone("👍🏽👍🏿", 1, vec!["👍🏽", "👍🏿"])]
+    #[case::ascii_unchanged("abcdefg", 3, vec!["abc", "def", "g"])]
+    fn test_max_chars_graphemes(
+        #[case] input: &str,
+        #[case] max_chars: usize,
+        #[case] expected: Vec<&str>,
+        #[values(false, true)] word_boundary: bool,
+    ) {
+        let splitter = Splitter::MaxChars {
+            max_chars,
+            word_boundary,
+            overlap: 0,
+            unit: CharUnit::Graphemes,
+        };
+        assert_eq!(splitter.split(input), expected);
+    }
+
+    #[rstest]
+    fn test_max_chars_chars_unit_bisects_zwj_sequence() {
+        // The default unit keeps the previous behavior, which can split a family emoji.
+        let splitter = Splitter::MaxChars {
+            max_chars: 3,
+            word_boundary: false,
+            overlap: 0,
+            unit: CharUnit::Chars,
+        };
+        assert_eq!(
+            splitter.split("👨‍👩‍👧‍👦"),
+            vec!["👨\u{200d}👩", "\u{200d}👧\u{200d}", "👦"]
+        );
+    }
+
+    #[rstest]
+    fn test_max_chars_graphemes_never_splits_family(
+        #[values(1, 2, 3, 5)] max_chars: usize,
+        #[values(false, true)] word_boundary: bool,
+    ) {
+        let family = "👨‍👩‍👧‍👦";
+        let text = format!("Our {family} went on {family} holiday {family}");
+        let splitter = Splitter::MaxChars {
+            max_chars,
+            word_boundary,
+            overlap: 0,
+            unit: CharUnit::Graphemes,
+        };
+        let result = splitter.split(&text);
+        for chunk in &result {
+            assert!(textmetrics::graphemes(chunk).count() <= max_chars);
+            let zwj_sequences = chunk.matches('\u{200d}').count();
+            assert_eq!(zwj_sequences % 3, 0, "family emoji split in {chunk:?}");
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rstest::*;
+    // MaxBytes splitter test cases
+    #[rstest]
+    #[case::ascii_words("one two three four", 8, vec!["one two", "three", "four"])]
+    #[case::exact_fit("abcd efgh", 4, vec!["abcd", "efgh"])]
+    #[case::long_word_cut("abcdefghij", 4, vec!["abcd", "efgh", "ij"])]
+    #[case::multibyte_not_split("héllo", 2 + 4, vec!["héllo"])]
+    #[case::multibyte_boundary("ééé", 5, vec!["éé", "é"])]
+    #[case::four_byte_chars("🚀🚀🚀", 5, vec!["🚀", "🚀", "🚀"])]
+    #[case::cjk("日本語のテキスト", 7, vec!["日本", "語の", "テキ", "スト"])]
+    #[case::fits_entirely("short", 200, vec!["short"])]
+    #[case::empty_string("", 10, vec![])]
+    fn test_max_bytes_splitter(
+        #[case] input: &str,
+        #[case] max_bytes: usize,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MaxBytes { max_bytes };
+        assert_eq!(splitter.split(input), expected);
+    }
 
-    // NewLine splitter test cases
     #[rstest]
-    #[case::basic_lines("Line 1\nLine 2\nLine 3", vec!["Line 1", "Line 2", "Line 3"])]
+    #[case::zero(0)]
+    #[case::one(1)]
+    #[case::three(3)]
+    fn test_max_bytes_below_minimum_is_validation_error(#[case] max_bytes: usize) {
+        let splitter = Splitter::MaxBytes { max_bytes };
+        assert_eq!(
+            splitter.validate().unwrap_err().to_string(),
+            "max_bytes must be at least 4"
+        );
+        // Splitting never panics or loops, even unvalidated.
+        assert!(
+            splitter
+                .split("a 🚀 b")
+                .iter()
+                .all(|chunk| !chunk.is_empty())
+        );
+    }
+
+    // MarkdownHeadings splitter test cases
+    #[rstest]
+    #[case::h1_only(
+        "# One\nbody 1\n## Sub\nbody 2\n# Two\nbody 3",
+        1,
+        vec!["# One\nbody 1\n## Sub\nbody 2", "# Two\nbody 3"]
+    )]
+    #[case::h1_and_h2(
+        "# One\nbody 1\n## Sub\nbody 2\n# Two\nbody 3",
+        2,
+        vec!["# One\nbody 1", "## Sub\nbody 2", "# Two\nbody 3"]
+    )]
+    #[case::leading_content_kept(
+        "Intro text\n\n# First\nbody",
+        1,
+        vec!["Intro text", "# First\nbody"]
+    )]
+    #[case::heading_only_chunks("# A\n# B", 1, vec!["# A", "# B"])]
+    #[case::no_headings("just text\nmore text", 2, vec!["just text\nmore text"])]
+    #[case::hash_without_space_is_not_heading(
+        "# A\n#hashtag\nbody",
+        1,
+        vec!["# A\n#hashtag\nbody"]
+    )]
+    #[case::seven_hashes_is_not_heading("# A\n####### nope", 6, vec!["# A\n####### nope"])]
+    #[case::blank_lines_trimmed("\n\n# A\n\nbody\n\n", 1, vec!["# A\n\nbody"])]
+    #[case::empty_string("", 1, vec![])]
+    fn test_markdown_headings_splitter(
+        #[case] input: &str,
+        #[case] max_level: u8,
+        #[case] expected: Vec<&str>,
+    ) {
+        let splitter = Splitter::MarkdownHeadings { max_level };
+        let result = splitter.split(input);
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case::h1("# Title", Some(1))]
+    #[case::h3("### Title", Some(3))]
+    #[case::bare_hash("#", Some(1))]
+    #[case::indented("  ## Title", Some(2))]
+    #[case::no_space("#Title", None)]
+    #[case::too_deep("####### Title", None)]
+    #[case::plain("Title", None)]
+    fn test_markdown_heading_level(#[case] line: &str, #[case] expected: Option<u8>) {
+        assert_eq!(markdown_heading_level(line), expected);
+    }
+
+    // HorizontalRule splitter test cases
+    #[rstest]
+    #[case::basic("Slide 1\n---\nSlide 2", vec!["Slide 1", "Slide 2"])]
+    #[case::long_rule_with_whitespace("Slide 1\n  -----  \nSlide 2", vec!["Slide 1", "Slide 2"])]
+    #[case::empty_chunks_dropped("---\nSlide 1\n---\n---\nSlide 2\n---", vec!["Slide 1", "Slide 2"])]
+    #[case::chunks_trimmed("\nSlide 1\n\n---\n\nSlide 2\n", vec!["Slide 1", "Slide 2"])]
+    #[case::two_dashes_not_rule("a\n--\nb", vec!["a\n--\nb"])]
+    #[case::dashes_inside_line("a --- b\nc", vec!["a --- b\nc"])]
+    #[case::mixed_characters_not_rule("a\n---x\nb", vec!["a\n---x\nb"])]
+    #[case::inside_code_fence(
+        "Intro\n```yaml\n---\nkey: value\n```\n---\nNext",
+        vec!["Intro\n```yaml\n---\nkey: value\n```", "Next"]
+    )]
+    #[case::inside_tilde_fence("~~~\n---\n~~~", vec!["~~~\n---\n~~~"])]
     #[case::empty_string("", vec![])]
-    #[case::only_whitespace("   \n   \n   ", vec![])]
-    #[case::with_tabs_and_spaces("  Line 1  \n\t Line 2 \t\n   Line 3   ", vec!["Line 1", "Line 2", "Line 3"])]
-    #[case::newlines_only("\n\n\n", vec![])]
-    #[case::mixed_content("Line 1\nLine 2\n\nLine 3\nLine 4\n\n\nLine 5", vec!["Line 1", "Line 2", "Line 3", "Line 4", "Line 5"])]
-    fn test_newline_splitter(#[case] input: &str, #[case] expected: Vec<&str>) {
-        let splitter = Splitter::NewLine;
+    fn test_horizontal_rule_splitter(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let splitter = Splitter::HorizontalRule;
         let result = splitter.split(input);
         assert_eq!(result, expected);
     }
 
-    // EmptyLine splitter test cases
+    // BulletList splitter test cases
     #[rstest]
-    #[case::basic_paragraphs("Line 1\nLine 2\n\nLine 3\nLine 4", vec!["Line 1\nLine 2", "Line 3\nLine 4"])]
+    #[case::one_slide_per_item("- One\n- Two\n- Three", vec!["- One", "- Two", "- Three"])]
+    #[case::all_markers("- One\n* Two\n+ Three", vec!["- One", "* Two", "+ Three"])]
+    #[case::nested_children_grouped(
+        "- Fruits\n  - Apple\n  - Pear\n- Vegetables\n  * Leek",
+        vec!["- Fruits\n  - Apple\n  - Pear", "- Vegetables\n  * Leek"]
+    )]
+    #[case::deeply_nested(
+        "- A\n  - B\n    - C\n- D",
+        vec!["- A\n  - B\n    - C", "- D"]
+    )]
+    #[case::tab_indentation(
+        "- Parent\n\t- Child\n\t\t- Grandchild\n- Next",
+        vec!["- Parent\n\t- Child\n\t\t- Grandchild", "- Next"]
+    )]
+    #[case::indented_continuation(
+        "- Item\n  more about the item\n- Next",
+        vec!["- Item\n  more about the item", "- Next"]
+    )]
+    #[case::blank_lines_inside_item(
+        "- Item\n\n  - Child\n\n- Next",
+        vec!["- Item\n\n  - Child", "- Next"]
+    )]
+    #[case::prose_between_lists(
+        "Intro text\n- A\n- B\nMiddle text\nstill middle\n- C",
+        vec!["Intro text", "- A", "- B", "Middle text\nstill middle", "- C"]
+    )]
+    #[case::indented_list(
+        "  - A\n    - B\n  - C",
+        vec!["  - A\n    - B", "  - C"]
+    )]
+    #[case::horizontal_rule_is_not_bullet("---\n- A", vec!["---", "- A"])]
+    #[case::emphasis_is_not_bullet("*emphasis* text\n- A", vec!["*emphasis* text", "- A"])]
+    #[case::bullets_inside_code_fence(
+        "- Example\n  ```\n- not an item\n  ```\n- Next",
+        vec!["- Example\n  ```\n- not an item\n  ```", "- Next"]
+    )]
+    #[case::no_list("Just prose\nover two lines", vec!["Just prose\nover two lines"])]
+    #[case::empty_bullet("-\n- A", vec!["-", "- A"])]
     #[case::empty_string("", vec![])]
-    #[case::single_paragraph("This is a single paragraph\nwith multiple lines\nbut no empty lines", vec!["This is a single paragraph\nwith multiple lines\nbut no empty lines"])]
-    #[case::multiple_empty_lines("Para 1\n\n\n\nPara 2\n\n\n\n\nPara 3", vec!["Para 1", "Para 2", "Para 3"])]
-    #[case::trailing_content("Line 1\nLine 2\n\nLine 3\nLine 4\n\n\nLine 5", vec!["Line 1\nLine 2", "Line 3\nLine 4", "Line 5"])]
-    fn test_empty_line_splitter(#[case] input: &str, #[case] expected: Vec<&str>) {
-        let splitter = Splitter::EmptyLine;
+    fn test_bullet_list_splitter(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let splitter = Splitter::BulletList;
         let result = splitter.split(input);
         assert_eq!(result, expected);
     }
 
-    // MaxWords splitter test cases
     #[rstest]
-    #[case::basic_chunking("The quick brown fox jumps over the lazy dog", 3, vec!["The quick brown", "fox jumps over", "the lazy dog"])]
-    #[case::exact_division("one two three four", 2, vec!["one two", "three four"])]
-    #[case::larger_than_text("only three words", 10, vec!["only three words"])]
-    #[case::empty_string("", 5, vec![])]
-    #[case::extra_whitespace("  word1   word2    word3   word4  ", 2, vec!["word1 word2", "word3 word4"])]
-    #[case::single_word_chunks("one two three", 1, vec!["one", "two", "three"])]
-    fn test_max_words_splitter(
+    #[case::spaces("    - a", 4)]
+    #[case::tab("\t- a", 4)]
+    #[case::space_then_tab("  \t- a", 4)]
+    #[case::two_tabs("\t\t- a", 8)]
+    #[case::none("- a", 0)]
+    fn test_indent_width(#[case] line: &str, #[case] expected: usize) {
+        assert_eq!(indent_width(line), expected);
+    }
+
+    // Code block handling test cases
+    #[rstest]
+    #[case::no_fences("just prose\n", vec![Block::Prose("just prose\n")])]
+    #[case::interleaved(
+        "Intro\n```\ncode\n```\nOutro",
+        vec![Block::Prose("Intro\n"), Block::Code("```\ncode\n```\n"), Block::Prose("Outro")]
+    )]
+    #[case::starts_with_fence("```\na\n```", vec![Block::Code("```\na\n```")])]
+    #[case::unclosed_fence("Intro\n~~~\nrest", vec![Block::Prose("Intro\n"), Block::Code("~~~\nrest")])]
+    #[case::empty_string("", vec![])]
+    fn test_code_blocks(#[case] input: &str, #[case] expected: Vec<Block>) {
+        assert_eq!(code_blocks(input), expected);
+    }
+
+    const CODE_SAMPLE: &str = "Install the crate first.\n\n```rust\nfn main() {\n\n    println!(\"hello world\");\n}\n```\n\nThen run it with cargo.";
+
+    #[rstest]
+    fn test_empty_line_keeps_code_block_whole() {
+        assert_eq!(
+            Splitter::EmptyLine.split(CODE_SAMPLE),
+            vec![
+                "Install the crate first.",
+                "```rust\nfn main() {\n\n    println!(\"hello world\");\n}\n```",
+                "Then run it with cargo.",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_newline_keeps_code_block_whole() {
+        assert_eq!(
+            Splitter::NewLine.split("a\n```\nx\ny\n```\nb"),
+            vec!["a", "```\nx\ny\n```", "b"]
+        );
+    }
+
+    #[rstest]
+    fn test_markdown_headings_ignore_code_comments() {
+        let splitter = Splitter::MarkdownHeadings { max_level: 1 };
+        assert_eq!(
+            splitter.split("# Setup\n```bash\n# install deps\nnpm ci\n```\n# Usage\nRun it"),
+            vec![
+                "# Setup\n```bash\n# install deps\nnpm ci\n```",
+                "# Usage\nRun it"
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::block_joins_prose(
+        "Intro text\n```\nlet x = 1;\n```\nOutro",
+        10,
+        vec!["Intro text\n```\nlet x = 1;\n```\nOutro"]
+    )]
+    #[case::block_starts_new_chunk(
+        "one two three four\n```\na b c\n```",
+        5,
+        vec!["one two three four", "```\na b c\n```"]
+    )]
+    #[case::oversized_block_kept_whole(
+        "Before\n```\na b c d e f g h\n```\nAfter",
+        3,
+        vec!["Before", "```\na b c d e f g h\n```", "After"]
+    )]
+    #[case::prose_still_split(
+        "a b c d e\n```\nx\n```",
+        2,
+        vec!["a b", "c d", "e", "```\nx\n```"]
+    )]
+    fn test_max_words_keeps_code_block_whole(
         #[case] input: &str,
         #[case] max_words: usize,
         #[case] expected: Vec<&str>,
     ) {
-        let splitter = Splitter::MaxWords { max_words };
-        let result = splitter.split(input);
-        assert_eq!(result, expected);
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        assert_eq!(splitter.split(input), expected);
     }
 
-    // MaxChars splitter test cases
     #[rstest]
-    #[case::basic_chunking("abcdefghijklmnop", 5, vec!["abcde", "fghij", "klmno", "p"])]
-    #[case::exact_division("12345678", 4, vec!["1234", "5678"])]
-    #[case::empty_string("", 5, vec![])]
-    #[case::larger_than_text("short", 20, vec!["short"])]
-    #[case::single_char_chunks("abc", 1, vec!["a", "b", "c"])]
-    #[case::unicode_support("Hello 🌍 World 🚀 Test 🦀", 6, vec!["Hello ", "🌍 Worl", "d 🚀 Te", "st 🦀"])]
-    fn test_max_chars_splitter(
+    fn test_size_splitters_never_cut_code_block(
+        #[values(1, 3, 8, 40)] size: usize,
+        #[values(false, true)] flag: bool,
+    ) {
+        let code = "```rust\nfn main() {\n\n    println!(\"hello world\");\n}\n```";
+        let splitters = [
+            Splitter::MaxWords {
+                max_words: size,
+                respect_sentences: flag,
+                overlap: 0,
+                segmentation: WordSegmentation::Whitespace,
+                balanced: false,
+            },
+            Splitter::MaxChars {
+                max_chars: size,
+                word_boundary: flag,
+                overlap: 0,
+                unit: CharUnit::Chars,
+            },
+        ];
+        for splitter in splitters {
+            let result = splitter.split(CODE_SAMPLE);
+            assert_eq!(
+                result.iter().filter(|chunk| chunk.contains(code)).count(),
+                1,
+                "{splitter:?} cut the code block: {result:?}"
+            );
+            assert!(
+                result
+                    .iter()
+                    .all(|chunk| chunk.matches("```").count() % 2 == 0)
+            );
+        }
+    }
+
+    // Streaming test cases
+    /// Builds a multi-megabyte Markdown document mixing every construct the
+    /// splitters care about.
+    fn large_document() -> String {
+        let section = "# Heading\n\nSome prose with ünïcödé, 日本語 and 🚀 emoji. It has sentences!\n\
+                       - bullet one\n  - nested bullet\n\n```\nlet  code = 1;\n\n```\n---\n\tTabbed  line\n\n";
+        section.repeat(2 * 1024 * 1024 / section.len() + 1)
+    }
+
+    #[rstest]
+    fn test_split_iter_matches_split_on_large_input() {
+        let text = large_document();
+        assert!(text.len() > 2 * 1024 * 1024);
+        let splitters = [
+            Splitter::NewLine,
+            Splitter::EmptyLine,
+            Splitter::MaxWords {
+                max_words: 50,
+                respect_sentences: false,
+                overlap: 5,
+                segmentation: WordSegmentation::Whitespace,
+                balanced: false,
+            },
+            Splitter::MaxWords {
+                max_words: 50,
+                respect_sentences: true,
+                overlap: 0,
+                segmentation: WordSegmentation::Whitespace,
+                balanced: false,
+            },
+            Splitter::MaxChars {
+                max_chars: 500,
+                word_boundary: false,
+                overlap: 20,
+                unit: CharUnit::Chars,
+            },
+            Splitter::MaxChars {
+                max_chars: 500,
+                word_boundary: true,
+                overlap: 0,
+                unit: CharUnit::Chars,
+            },
+            Splitter::MaxBytes { max_bytes: 400 },
+            Splitter::MarkdownHeadings { max_level: 1 },
+            Splitter::HorizontalRule,
+            Splitter::BulletList,
+            Splitter::Regex {
+                pattern: "^# ".to_string(),
+                keep_delimiter: true,
+            },
+            Splitter::Chain {
+                splitters: vec![Splitter::HorizontalRule, Splitter::NewLine],
+            },
+        ];
+        for splitter in splitters {
+            let streamed = splitter.split_iter(&text).collect::<Vec<_>>();
+            assert!(!streamed.is_empty(), "{splitter:?}");
+            assert!(
+                streamed.iter().all(|chunk| !chunk.is_empty()),
+                "{splitter:?}"
+            );
+            assert_eq!(streamed, splitter.split(&text), "{splitter:?}");
+        }
+    }
+
+    #[rstest]
+    fn test_split_iter_streams_size_splitters() {
+        // Prose without code blocks is split lazily: taking the first chunks
+        // only looks at the start of the text.
+        let text = "word ".repeat(1024 * 1024);
+        let words = Splitter::MaxWords {
+            max_words: 3,
+            respect_sentences: false,
+            overlap: 1,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        assert_eq!(
+            words.split_iter(&text).take(2).collect::<Vec<_>>(),
+            vec!["word word word", "word word word"]
+        );
+        let chars = Splitter::MaxChars {
+            max_chars: 7,
+            word_boundary: false,
+            overlap: 0,
+            unit: CharUnit::Chars,
+        };
+        assert_eq!(chars.split_iter(&text).next().unwrap(), "word wo");
+    }
+
+    #[rstest]
+    #[case::no_words("   ", 0, None)]
+    #[case::first("one two", 0, Some(0))]
+    #[case::second("one  two", 1, Some(5))]
+    #[case::leading_space("  one two three", 2, Some(10))]
+    #[case::past_end("one two", 2, None)]
+    fn test_nth_word_start(#[case] text: &str, #[case] n: usize, #[case] expected: Option<usize>) {
+        assert_eq!(nth_word_start(text, n), expected);
+    }
+
+    // Regex splitter test cases
+    #[rstest]
+    #[case::drop_delimiter(
+        "Slide 1: Intro\nHello\nSlide 2: Next\nWorld",
+        r"^Slide \d+:",
+        false,
+        vec!["Intro\nHello", "Next\nWorld"]
+    )]
+    #[case::keep_delimiter(
+        "Slide 1: Intro\nHello\nSlide 2: Next\nWorld",
+        r"^Slide \d+:",
+        true,
+        vec!["Slide 1: Intro\nHello", "Slide 2: Next\nWorld"]
+    )]
+    #[case::sentinel("a @@ b @@ c", "@@", false, vec!["a", "b", "c"])]
+    #[case::leading_content_kept("Intro\n## A\nbody", "^##", true, vec!["Intro", "## A\nbody"])]
+    #[case::no_match("nothing here", "@@", false, vec!["nothing here"])]
+    #[case::empty_string("", "@@", false, vec![])]
+    fn test_regex_splitter(
         #[case] input: &str,
-        #[case] max_chars: usize,
+        #[case] pattern: &str,
+        #[case] keep_delimiter: bool,
         #[case] expected: Vec<&str>,
     ) {
-        let splitter = Splitter::MaxChars { max_chars };
+        let splitter = Splitter::Regex {
+            pattern: pattern.to_string(),
+            keep_delimiter,
+        };
         let result = splitter.split(input);
         assert_eq!(result, expected);
     }
 
+    #[rstest]
+    fn test_regex_validate_rejects_invalid_pattern() {
+        let splitter = Splitter::Regex {
+            pattern: "(unclosed".to_string(),
+            keep_delimiter: false,
+        };
+        assert!(matches!(
+            splitter.validate(),
+            Err(SplitError::InvalidPattern(_))
+        ));
+    }
+
+    #[rstest]
+    fn test_regex_validate_rejects_long_pattern() {
+        let splitter = Splitter::Regex {
+            pattern: "a".repeat(MAX_PATTERN_LEN + 1),
+            keep_delimiter: false,
+        };
+        assert_eq!(
+            splitter.validate(),
+            Err(SplitError::PatternTooLong(MAX_PATTERN_LEN + 1))
+        );
+    }
+
+    #[rstest]
+    fn test_regex_validate_accepts_valid_pattern() {
+        let splitter = Splitter::Regex {
+            pattern: r"^Slide \d+:".to_string(),
+            keep_delimiter: false,
+        };
+        assert_eq!(splitter.validate(), Ok(()));
+    }
+
+    #[rstest]
+    fn test_regex_split_with_invalid_pattern_does_not_panic() {
+        let splitter = Splitter::Regex {
+            pattern: "[".to_string(),
+            keep_delimiter: false,
+        };
+        assert_eq!(splitter.split(" text "), vec!["text"]);
+    }
+
     // Edge cases and error conditions
     #[rstest]
-    fn test_zero_chunk_size_panics() {
-        let max_words_result = std::panic::catch_unwind(|| {
-            let splitter = Splitter::MaxWords { max_words: 0 };
-            splitter.split("some words here")
-        });
-        assert!(max_words_result.is_err());
+    fn test_zero_chunk_size_is_validation_error(
+        #[values(false, true)] boundary: bool,
+        #[values(0, 1)] overlap: usize,
+    ) {
+        let splitter = Splitter::MaxWords {
+            max_words: 0,
+            respect_sentences: boundary,
+            overlap,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
+        assert_eq!(
+            splitter.validate().unwrap_err().to_string(),
+            "max_words must be at least 1"
+        );
+        assert!(splitter.split("some words here").is_empty());
 
-        let max_chars_result = std::panic::catch_unwind(|| {
-            let splitter = Splitter::MaxChars { max_chars: 0 };
-            splitter.split("hello")
-        });
-        assert!(max_chars_result.is_err());
+        let splitter = Splitter::MaxChars {
+            max_chars: 0,
+            word_boundary: boundary,
+            overlap,
+            unit: CharUnit::Chars,
+        };
+        assert_eq!(
+            splitter.validate().unwrap_err().to_string(),
+            "max_chars must be at least 1"
+        );
+        assert!(splitter.split("hello").is_empty());
+    }
+
+    #[rstest]
+    fn test_zero_chunk_size_inside_chain_is_validation_error() {
+        let splitter = Splitter::Chain {
+            splitters: vec![
+                Splitter::EmptyLine,
+                Splitter::MaxWords {
+                    max_words: 0,
+                    respect_sentences: false,
+                    overlap: 0,
+                    segmentation: WordSegmentation::Whitespace,
+                    balanced: false,
+                },
+            ],
+        };
+        assert_eq!(splitter.validate(), Err(SplitError::ZeroSize("max_words")));
     }
 
     // Serialization test cases
     #[rstest]
     #[case::newline(Splitter::NewLine, r#"{"type":"newline"}"#)]
     #[case::empty_line(Splitter::EmptyLine, r#"{"type":"empty_line"}"#)]
-    #[case::max_words(Splitter::MaxWords { max_words: 10 }, r#"{"type":"max_words","max_words":10}"#)]
-    #[case::max_chars(Splitter::MaxChars { max_chars: 100 }, r#"{"type":"max_chars","max_chars":100}"#)]
+    #[case::max_words(Splitter::MaxWords { max_words: 10, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false }, r#"{"type":"max_words","max_words":10}"#)]
+    #[case::max_words_respect_sentences(Splitter::MaxWords { max_words: 10, respect_sentences: true, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false }, r#"{"type":"max_words","max_words":10,"respect_sentences":true}"#)]
+    #[case::max_words_unicode(Splitter::MaxWords { max_words: 10, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Unicode, balanced: false }, r#"{"type":"max_words","max_words":10,"segmentation":"unicode"}"#)]
+    #[case::max_words_balanced(Splitter::MaxWords { max_words: 10, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: true }, r#"{"type":"max_words","max_words":10,"balanced":true}"#)]
+    #[case::max_words_overlap(Splitter::MaxWords { max_words: 10, respect_sentences: false, overlap: 2, segmentation: WordSegmentation::Whitespace, balanced: false }, r#"{"type":"max_words","max_words":10,"overlap":2}"#)]
+    #[case::max_chars(Splitter::MaxChars { max_chars: 100, word_boundary: false, overlap: 0, unit: CharUnit::Chars }, r#"{"type":"max_chars","max_chars":100}"#)]
+    #[case::max_chars_word_boundary(Splitter::MaxChars { max_chars: 100, word_boundary: true, overlap: 0, unit: CharUnit::Chars }, r#"{"type":"max_chars","max_chars":100,"word_boundary":true}"#)]
+    #[case::max_chars_graphemes(Splitter::MaxChars { max_chars: 100, word_boundary: false, overlap: 0, unit: CharUnit::Graphemes }, r#"{"type":"max_chars","max_chars":100,"unit":"graphemes"}"#)]
+    #[case::markdown_headings(Splitter::MarkdownHeadings { max_level: 2 }, r#"{"type":"markdown_headings","max_level":2}"#)]
+    #[case::horizontal_rule(Splitter::HorizontalRule, r#"{"type":"horizontal_rule"}"#)]
+    #[case::bullet_list(Splitter::BulletList, r#"{"type":"bullet_list"}"#)]
+    #[case::max_bytes(Splitter::MaxBytes { max_bytes: 200 }, r#"{"type":"max_bytes","max_bytes":200}"#)]
+    #[case::chain(Splitter::Chain { splitters: vec![Splitter::EmptyLine, Splitter::MaxWords { max_words: 60, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false }] }, r#"{"type":"chain","splitters":[{"type":"empty_line"},{"type":"max_words","max_words":60}]}"#)]
+    #[case::regex(Splitter::Regex { pattern: "^##".to_string(), keep_delimiter: true }, r#"{"type":"regex","pattern":"^##","keep_delimiter":true}"#)]
     fn test_serialization(#[case] splitter: Splitter, #[case] expected_json: &str) {
         let json = serde_json::to_string(&splitter).unwrap();
         assert_eq!(json, expected_json);
     }
 
+    #[rstest]
+    #[case::plain("> Quote", Some("Quote"))]
+    #[case::no_space(">Quote", Some("Quote"))]
+    #[case::empty_marker(">", Some(""))]
+    #[case::nested(">> Inner", Some("Inner"))]
+    #[case::spaced_nested("> > Inner", Some("Inner"))]
+    #[case::indented("  > Quote", Some("Quote"))]
+    #[case::keeps_inner_spacing(">   code-like", Some("  code-like"))]
+    #[case::prose("Not > quoted", None)]
+    fn test_blockquote_text(#[case] line: &str, #[case] expected: Option<&str>) {
+        assert_eq!(blockquote_text(line), expected);
+    }
+
+    #[rstest]
+    #[case::em_dash("— Ada Lovelace", true)]
+    #[case::double_dash("-- Anonymous", true)]
+    #[case::indented("  — Someone", true)]
+    #[case::horizontal_rule("---", false)]
+    #[case::bullet("- item", false)]
+    #[case::prose("Said someone", false)]
+    fn test_is_attribution(#[case] line: &str, #[case] expected: bool) {
+        assert_eq!(is_attribution(line), expected);
+    }
+
+    #[rstest]
+    #[case::single_quote("> Stay hungry.", vec!["Stay hungry."])]
+    #[case::consecutive_lines("> Line one\n> line two", vec!["Line one\nline two"])]
+    #[case::blank_line_separates("> First\n\n> Second", vec!["First", "Second"])]
+    #[case::multi_paragraph(
+        "> Para one\n>\n> Para two",
+        vec!["Para one\n\nPara two"]
+    )]
+    #[case::nested(
+        "> Outer says:\n>> Inner quote\n> Outer again",
+        vec!["Outer says:\nInner quote\nOuter again"]
+    )]
+    #[case::nested_multi_paragraph(
+        "> Outer\n>\n>> Inner one\n>>\n>> Inner two",
+        vec!["Outer\n\nInner one\n\nInner two"]
+    )]
+    #[case::em_dash_attribution(
+        "> Simplicity is prerequisite for reliability.\n— Edsger Dijkstra",
+        vec!["Simplicity is prerequisite for reliability.\n— Edsger Dijkstra"]
+    )]
+    #[case::double_dash_attribution("> Quote\n-- Someone", vec!["Quote\n-- Someone"])]
+    #[case::attribution_after_blank_is_prose("> Quote\n\n— Someone", vec!["Quote"])]
+    #[case::rule_is_not_attribution("> Quote\n---\n> Next", vec!["Quote", "Next"])]
+    #[case::prose_skipped(
+        "Intro text\n\n> One\n\nBetween\n\n> Two\nOutro",
+        vec!["One", "Two"]
+    )]
+    #[case::no_quotes("Just prose\nand more", vec![])]
+    #[case::quote_in_code_block("```\n> not a quote\n```\n> Real", vec!["Real"])]
+    fn test_split_block_quotes(#[case] text: &str, #[case] expected: Vec<&str>) {
+        let splitter = Splitter::BlockQuote {
+            include_prose: false,
+        };
+        assert_eq!(splitter.split(text), expected);
+    }
+
+    #[rstest]
+    #[case::prose_around_quotes(
+        "Intro text\n\n> One\n— Author\nBetween\n> Two\nOutro",
+        vec!["Intro text", "One\n— Author", "Between", "Two", "Outro"]
+    )]
+    #[case::prose_paragraphs_stay_together(
+        "First para\n\nSecond para\n> Quote",
+        vec!["First para\n\nSecond para", "Quote"]
+    )]
+    #[case::code_block_is_prose(
+        "```\n> code\n```\n> Quote",
+        vec!["```\n> code\n```", "Quote"]
+    )]
+    fn test_split_block_quotes_include_prose(#[case] text: &str, #[case] expected: Vec<&str>) {
+        let splitter = Splitter::BlockQuote {
+            include_prose: true,
+        };
+        assert_eq!(splitter.split(text), expected);
+    }
+
+    #[rstest]
+    #[case::top_level("* Heading", Some(1))]
+    #[case::third_level("*** Deep", Some(3))]
+    #[case::tab_separator("**\tTabbed", Some(2))]
+    #[case::no_space("*bold* text", None)]
+    #[case::indented_list_item("  * item", None)]
+    #[case::stars_only("***", None)]
+    fn test_org_heading_level(#[case] line: &str, #[case] expected: Option<u8>) {
+        assert_eq!(org_heading_level(line), expected);
+    }
+
+    #[rstest]
+    #[case::plain("* Introduction", "Introduction")]
+    #[case::todo("** TODO Write report", "Write report")]
+    #[case::done_with_priority("* DONE [#A] Ship it", "Ship it")]
+    #[case::priority_only("* [#B] Review", "Review")]
+    #[case::tags("* Planning :work:q3:", "Planning")]
+    #[case::everything("*** TODO [#C] Call Bob   :phone:@home:", "Call Bob")]
+    #[case::keyword_prefix_of_word("* TODOs for today", "TODOs for today")]
+    #[case::colon_in_text("* Ratio 1:2", "Ratio 1:2")]
+    #[case::lowercase_not_keyword("* todo later", "todo later")]
+    fn test_org_heading_text(#[case] line: &str, #[case] expected: &str) {
+        assert_eq!(org_heading_text(line), expected);
+    }
+
+    #[rstest]
+    #[case::top_level_only(
+        "* One\nBody one\n** Sub\nSub body\n* Two\nBody two",
+        1,
+        vec!["* One\nBody one\n** Sub\nSub body", "* Two\nBody two"]
+    )]
+    #[case::two_levels(
+        "* One\nBody one\n** Sub\nSub body\n* Two",
+        2,
+        vec!["* One\nBody one", "** Sub\nSub body", "* Two"]
+    )]
+    #[case::preamble("#+TITLE: Notes\n\n* First\nText", 1, vec!["#+TITLE: Notes", "* First\nText"])]
+    #[case::keywords_stripped("* TODO [#A] Task :urgent:\nDetails", 1, vec!["* Task\nDetails"])]
+    #[case::drawer_dropped(
+        "* Heading\n:PROPERTIES:\n:ID: 42\n:END:\nBody",
+        1,
+        vec!["* Heading\nBody"]
+    )]
+    #[case::indented_drawer_dropped(
+        "** Heading\n   :PROPERTIES:\n   :CUSTOM_ID: intro\n   :END:\n   Body",
+        1,
+        vec!["** Heading\n   Body"]
+    )]
+    #[case::drawer_not_under_heading_kept(
+        "* Heading\nBody\n:PROPERTIES:\n:END:",
+        1,
+        vec!["* Heading\nBody\n:PROPERTIES:\n:END:"]
+    )]
+    #[case::star_in_src_block(
+        "* Code\n#+BEGIN_SRC python\n* not a heading\n#+END_SRC\n* Next",
+        1,
+        vec!["* Code\n#+BEGIN_SRC python\n* not a heading\n#+END_SRC", "* Next"]
+    )]
+    fn test_split_org_headings(
+        #[case] text: &str,
+        #[case] max_level: u8,
+        #[case] expected: Vec<&str>,
+    ) {
+        assert_eq!(Splitter::OrgHeadings { max_level }.split(text), expected);
+    }
+
+    #[rstest]
+    fn test_split_org_headings_fixture() {
+        let text = include_str!("../tests/fixtures/outline.org");
+        let splitter = Splitter::OrgHeadings { max_level: 2 };
+        let titles = splitter
+            .split_chunks(text)
+            .into_iter()
+            .map(|chunk| (chunk.title, chunk.body))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                (None, "#+TITLE: Q3 Planning\n#+AUTHOR: Sam Lee".to_string()),
+                (
+                    Some("Goals".to_string()),
+                    "Grow weekly active users by 10%.".to_string()
+                ),
+                (
+                    Some("Launch the mobile app".to_string()),
+                    "SCHEDULED: <2024-07-15 Mon>\nShip to both stores before the conference.\n*** Beta feedback\n- Crash on login fixed\n- Dark mode requested".to_string()
+                ),
+                (
+                    Some("Migrate billing".to_string()),
+                    "Move invoices to the new provider.".to_string()
+                ),
+                (
+                    Some("Risks".to_string()),
+                    "#+BEGIN_SRC sh\n* not a heading, just a glob\nls *\n#+END_SRC".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::slide("::slide", Some(Directive::Slide))]
+    #[case::surrounding_whitespace("  ::skip ", Some(Directive::Skip))]
+    #[case::endskip("::endskip", Some(Directive::EndSkip))]
+    #[case::unknown("::note", None)]
+    #[case::inline("see ::slide", None)]
+    #[case::uppercase("::SLIDE", None)]
+    fn test_directive(#[case] line: &str, #[case] expected: Option<Directive>) {
+        assert_eq!(directive(line), expected);
+    }
+
+    #[rstest]
+    #[case::no_directives("a\n::note\nb", None)]
+    #[case::slide("a\n::slide\nb", Some(vec!["a", "b"]))]
+    #[case::skip("a\n::skip\nhidden\n::endskip\nb", Some(vec!["a\nb"]))]
+    #[case::unterminated_skip("a\n::skip\nhidden\n::slide\nmore", Some(vec!["a"]))]
+    #[case::stray_endskip("a\n::endskip\nb", Some(vec!["a\nb"]))]
+    #[case::in_code_block("```\n::slide\n```", None)]
+    fn test_directive_sections(#[case] text: &str, #[case] expected: Option<Vec<&str>>) {
+        assert_eq!(
+            directive_sections(text),
+            expected.map(|sections| sections.into_iter().map(String::from).collect())
+        );
+    }
+
+    #[rstest]
+    #[case::newline(Splitter::NewLine, "one\n::slide\ntwo", vec!["one", "two"])]
+    #[case::empty_line_breaks_paragraph(
+        Splitter::EmptyLine,
+        "first half\n::slide\nsecond half\n\nnext",
+        vec!["first half", "second half", "next"]
+    )]
+    #[case::max_words_resets_count(
+        Splitter::MaxWords { max_words: 3, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false },
+        "a b\n::slide\nc d e f",
+        vec!["a b", "c d e", "f"]
+    )]
+    #[case::max_words_overlap_does_not_cross(
+        Splitter::MaxWords { max_words: 3, respect_sentences: false, overlap: 1, segmentation: WordSegmentation::Whitespace, balanced: false },
+        "a b c d\n::slide\ne f",
+        vec!["a b c", "c d", "e f"]
+    )]
+    #[case::max_chars_resets_count(
+        Splitter::MaxChars { max_chars: 4, word_boundary: false, overlap: 0, unit: CharUnit::Chars },
+        "abc\n::slide\ndefghi",
+        vec!["abc", "defg", "hi"]
+    )]
+    #[case::skip_inside_chunk(
+        Splitter::MaxWords { max_words: 10, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false },
+        "keep this\n::skip\ndraft words\n::endskip\nand this",
+        vec!["keep this and this"]
+    )]
+    #[case::unknown_directive_is_text(Splitter::NewLine, "::note\ntext", vec!["::note", "text"])]
+    #[case::chain(
+        Splitter::Chain { splitters: vec![Splitter::EmptyLine, Splitter::NewLine] },
+        "a\n::slide\nb\n\nc",
+        vec!["a", "b", "c"]
+    )]
+    fn test_split_with_directives(
+        #[case] splitter: Splitter,
+        #[case] text: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        assert_eq!(splitter.split(text), expected);
+    }
+
+    fn capped(splitter: Splitter, max_chunk_words: usize) -> Splitter {
+        Splitter::WithCap {
+            splitter: Box::new(splitter),
+            max_chunk_words,
+        }
+    }
+
+    #[rstest]
+    #[case::empty_line(Splitter::EmptyLine)]
+    #[case::newline(Splitter::NewLine)]
+    fn test_with_cap_leaves_small_chunks_untouched(#[case] splitter: Splitter) {
+        let text =
+            "First  paragraph,\nwith a line break.\n\n  Second one.\n\n```\ncode  block\n```";
+        let expected = splitter.split(text);
+        assert_eq!(capped(splitter, 50).split(text), expected);
+    }
+
+    #[rstest]
+    fn test_with_cap_splits_paragraph_three_times_the_cap() {
+        let sentence = "This sentence has exactly six words.";
+        let paragraph = [sentence; 6].join(" ");
+        let text = format!("Short intro.\n\n{paragraph}\n\nShort outro.");
+
+        let chunks = capped(Splitter::EmptyLine, 12).split(&text);
+
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(chunks[0], "Short intro.");
+        assert_eq!(chunks[4], "Short outro.");
+        for chunk in &chunks[1..4] {
+            assert_eq!(*chunk, format!("{sentence} {sentence}"));
+        }
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| textmetrics::words(chunk).count() <= 12)
+        );
+    }
+
+    #[rstest]
+    #[case::packs_sentences(
+        "One two. Three four. Five six seven.",
+        4,
+        vec!["One two. Three four.", "Five six seven."]
+    )]
+    #[case::long_sentence_falls_back_to_words(
+        "Short one. a b c d e f g h i j. Tail here.",
+        4,
+        vec!["Short one.", "a b c d", "e f g h", "i j.", "Tail here."]
+    )]
+    #[case::no_sentence_ends("a b c d e", 2, vec!["a b", "c d", "e"])]
+    #[case::at_cap_unchanged("a  b\nc", 3, vec!["a  b\nc"])]
+    fn test_cap_chunk(#[case] chunk: &str, #[case] max_words: usize, #[case] expected: Vec<&str>) {
+        assert_eq!(cap_chunk(chunk.to_string(), max_words), expected);
+    }
+
+    #[rstest]
+    fn test_with_cap_never_exceeds_cap() {
+        let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor incididunt ut labore et dolore magna aliqua ut enim ad minim veniam quis nostrud exercitation ullamco laboris. Nisi ut aliquip.\n\nDuis aute irure dolor.";
+        for cap in 1..=12 {
+            let chunks = capped(Splitter::EmptyLine, cap).split(text);
+            assert!(
+                chunks
+                    .iter()
+                    .all(|chunk| textmetrics::words(chunk).count() <= cap),
+                "cap {cap}: {chunks:?}"
+            );
+            assert_eq!(
+                chunks
+                    .iter()
+                    .map(|chunk| textmetrics::words(chunk).count())
+                    .sum::<usize>(),
+                textmetrics::words(text).count()
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_with_cap_validation() {
+        assert_eq!(
+            capped(Splitter::EmptyLine, 0).validate(),
+            Err(SplitError::ZeroSize("max_chunk_words"))
+        );
+        let invalid_inner = Splitter::Regex {
+            pattern: "(".to_string(),
+            keep_delimiter: false,
+        };
+        assert!(matches!(
+            capped(invalid_inner, 10).validate(),
+            Err(SplitError::InvalidPattern(_))
+        ));
+        assert_eq!(capped(Splitter::EmptyLine, 10).validate(), Ok(()));
+    }
+
+    #[rstest]
+    #[case::spaces(
+        "Intro\n  Point a\n    Detail\n  Point b\nNext\n  Point c",
+        vec!["Intro\n  Point a\n    Detail\n  Point b", "Next\n  Point c"]
+    )]
+    #[case::tabs(
+        "Intro\n\tPoint a\n\t\tDetail\nNext",
+        vec!["Intro\n\tPoint a\n\t\tDetail", "Next"]
+    )]
+    #[case::four_space_unit(
+        "A\n    b\n        c\nD",
+        vec!["A\n    b\n        c", "D"]
+    )]
+    #[case::blank_lines_stay_in_group(
+        "A\n  b\n\n  c\n\nD",
+        vec!["A\n  b\n\n  c", "D"]
+    )]
+    #[case::whole_outline_indented(
+        "    A\n      b\n    C",
+        vec!["A\n  b", "C"]
+    )]
+    #[case::leading_indented_lines(
+        "  orphan\nA\n  b",
+        vec!["  orphan", "A\n  b"]
+    )]
+    #[case::flat_lines("A\nB\nC", vec!["A", "B", "C"])]
+    #[case::mixed_tabs_and_spaces(
+        "A\n\tb\n    c\nD",
+        vec!["A\n\tb\n\tc", "D"]
+    )]
+    #[case::unrecognized_indent_continues_depth(
+        "A\n    b\n      odd\n        c",
+        vec!["A\n    b\n    odd\n        c"]
+    )]
+    #[case::unrecognized_indent_under_top_level(
+        "A\n  b\n   odd\nB",
+        vec!["A\n  b\n  odd", "B"]
+    )]
+    #[case::empty("", vec![])]
+    #[case::only_blank_lines("\n  \n", vec![])]
+    fn test_split_outline(#[case] text: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(Splitter::Outline.split(text), expected);
+    }
+
     // Deserialization test cases
     #[rstest]
     #[case::newline(r#"{"type":"newline"}"#, Splitter::NewLine)]
     #[case::empty_line(r#"{"type":"empty_line"}"#, Splitter::EmptyLine)]
-    #[case::max_words(r#"{"type":"max_words","max_words":5}"#, Splitter::MaxWords { max_words: 5 })]
-    #[case::max_chars(r#"{"type":"max_chars","max_chars":50}"#, Splitter::MaxChars { max_chars: 50 })]
+    #[case::max_words(r#"{"type":"max_words","max_words":5}"#, Splitter::MaxWords { max_words: 5, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false })]
+    #[case::max_words_respect_sentences(r#"{"type":"max_words","max_words":5,"respect_sentences":true}"#, Splitter::MaxWords { max_words: 5, respect_sentences: true, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false })]
+    #[case::max_words_unicode(r#"{"type":"max_words","max_words":5,"segmentation":"unicode"}"#, Splitter::MaxWords { max_words: 5, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Unicode, balanced: false })]
+    #[case::max_words_default(r#"{"type":"max_words"}"#, Splitter::MaxWords { max_words: DEFAULT_MAX_WORDS, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false })]
+    #[case::max_chars(r#"{"type":"max_chars","max_chars":50}"#, Splitter::MaxChars { max_chars: 50, word_boundary: false, overlap: 0, unit: CharUnit::Chars })]
+    #[case::max_chars_word_boundary(r#"{"type":"max_chars","max_chars":50,"word_boundary":true}"#, Splitter::MaxChars { max_chars: 50, word_boundary: true, overlap: 0, unit: CharUnit::Chars })]
+    #[case::max_chars_graphemes(r#"{"type":"max_chars","max_chars":50,"unit":"graphemes"}"#, Splitter::MaxChars { max_chars: 50, word_boundary: false, overlap: 0, unit: CharUnit::Graphemes })]
+    #[case::max_chars_overlap(r#"{"type":"max_chars","max_chars":50,"overlap":5}"#, Splitter::MaxChars { max_chars: 50, word_boundary: false, overlap: 5, unit: CharUnit::Chars })]
+    #[case::max_chars_default(r#"{"type":"max_chars"}"#, Splitter::MaxChars { max_chars: DEFAULT_MAX_CHARS, word_boundary: false, overlap: 0, unit: CharUnit::Chars })]
+    #[case::markdown_headings(r#"{"type":"markdown_headings","max_level":1}"#, Splitter::MarkdownHeadings { max_level: 1 })]
+    #[case::org_headings(r#"{"type":"org_headings","max_level":2}"#, Splitter::OrgHeadings { max_level: 2 })]
+    #[case::horizontal_rule(r#"{"type":"horizontal_rule"}"#, Splitter::HorizontalRule)]
+    #[case::bullet_list(r#"{"type":"bullet_list"}"#, Splitter::BulletList)]
+    #[case::outline(r#"{"type":"outline"}"#, Splitter::Outline)]
+    #[case::block_quote(r#"{"type":"block_quote"}"#, Splitter::BlockQuote { include_prose: false })]
+    #[case::block_quote_include_prose(r#"{"type":"block_quote","include_prose":true}"#, Splitter::BlockQuote { include_prose: true })]
+    #[case::max_bytes(r#"{"type":"max_bytes","max_bytes":200}"#, Splitter::MaxBytes { max_bytes: 200 })]
+    #[case::chain(r#"{"type":"chain","splitters":[{"type":"empty_line"},{"type":"chain","splitters":[{"type":"newline"}]}]}"#, Splitter::Chain { splitters: vec![Splitter::EmptyLine, Splitter::Chain { splitters: vec![Splitter::NewLine] }] })]
+    #[case::regex(r#"{"type":"regex","pattern":"^##","keep_delimiter":false}"#, Splitter::Regex { pattern: "^##".to_string(), keep_delimiter: false })]
+    #[case::with_cap(r#"{"type":"with_cap","splitter":{"type":"empty_line"},"max_chunk_words":80}"#, Splitter::WithCap { splitter: Box::new(Splitter::EmptyLine), max_chunk_words: 80 })]
+    #[case::regex_default_keep_delimiter(r#"{"type":"regex","pattern":"^##"}"#, Splitter::Regex { pattern: "^##".to_string(), keep_delimiter: false })]
     fn test_deserialization(#[case] json: &str, #[case] expected: Splitter) {
         let splitter: Splitter = serde_json::from_str(json).unwrap();
         match (&splitter, &expected) {
             (Splitter::NewLine, Splitter::NewLine) => {}
             (Splitter::EmptyLine, Splitter::EmptyLine) => {}
-            (Splitter::MaxWords { max_words: a }, Splitter::MaxWords { max_words: b }) => {
+            (Splitter::HorizontalRule, Splitter::HorizontalRule) => {}
+            (Splitter::BulletList, Splitter::BulletList) => {}
+            (Splitter::Outline, Splitter::Outline) => {}
+            (Splitter::Chain { .. }, Splitter::Chain { .. })
+            | (Splitter::MaxBytes { .. }, Splitter::MaxBytes { .. })
+            | (Splitter::BlockQuote { .. }, Splitter::BlockQuote { .. })
+            | (Splitter::OrgHeadings { .. }, Splitter::OrgHeadings { .. })
+            | (Splitter::WithCap { .. }, Splitter::WithCap { .. }) => {
+                assert_eq!(splitter, expected);
+            }
+            (
+                Splitter::MaxWords {
+                    max_words: a,
+                    respect_sentences: respect_a,
+                    overlap: overlap_a,
+                    segmentation: segmentation_a,
+                    balanced: balanced_a,
+                },
+                Splitter::MaxWords {
+                    max_words: b,
+                    respect_sentences: respect_b,
+                    overlap: overlap_b,
+                    segmentation: segmentation_b,
+                    balanced: balanced_b,
+                },
+            ) => {
+                assert_eq!(a, b);
+                assert_eq!(respect_a, respect_b);
+                assert_eq!(overlap_a, overlap_b);
+                assert_eq!(segmentation_a, segmentation_b);
+                assert_eq!(balanced_a, balanced_b);
+            }
+            (
+                Splitter::MaxChars {
+                    max_chars: a,
+                    word_boundary: boundary_a,
+                    overlap: overlap_a,
+                    unit: unit_a,
+                },
+                Splitter::MaxChars {
+                    max_chars: b,
+                    word_boundary: boundary_b,
+                    overlap: overlap_b,
+                    unit: unit_b,
+                },
+            ) => {
+                assert_eq!(a, b);
+                assert_eq!(boundary_a, boundary_b);
+                assert_eq!(overlap_a, overlap_b);
+                assert_eq!(unit_a, unit_b);
+            }
+            (
+                Splitter::Regex {
+                    pattern: a,
+                    keep_delimiter: keep_a,
+                },
+                Splitter::Regex {
+                    pattern: b,
+                    keep_delimiter: keep_b,
+                },
+            ) => {
                 assert_eq!(a, b);
+                assert_eq!(keep_a, keep_b);
             }
-            (Splitter::MaxChars { max_chars: a }, Splitter::MaxChars { max_chars: b }) => {
+            (
+                Splitter::MarkdownHeadings { max_level: a },
+                Splitter::MarkdownHeadings { max_level: b },
+            ) => {
                 assert_eq!(a, b);
             }
             _ => panic!("Deserialized splitter doesn't match expected variant"),
@@ -176,8 +3802,25 @@ mod tests {
         let test_cases = vec![
             (Splitter::NewLine, 5),
             (Splitter::EmptyLine, 3),
-            (Splitter::MaxWords { max_words: 4 }, 5), // 19 words total, 4 per chunk = 5 chunks
-            (Splitter::MaxChars { max_chars: 20 }, 6), // Roughly 6 chunks for this text
+            (
+                Splitter::MaxWords {
+                    max_words: 4,
+                    respect_sentences: false,
+                    overlap: 0,
+                    segmentation: WordSegmentation::Whitespace,
+                    balanced: false,
+                },
+                5,
+            ), // 19 words total, 4 per chunk = 5 chunks
+            (
+                Splitter::MaxChars {
+                    max_chars: 20,
+                    word_boundary: false,
+                    overlap: 0,
+                    unit: CharUnit::Chars,
+                },
+                6,
+            ), // Roughly 6 chunks for this text
         ];
 
         for (splitter, expected_chunks) in test_cases {
@@ -189,7 +3832,17 @@ mod tests {
             );
 
             match splitter {
-                Splitter::NewLine | Splitter::EmptyLine => {
+                Splitter::NewLine
+                | Splitter::EmptyLine
+                | Splitter::MarkdownHeadings { .. }
+                | Splitter::OrgHeadings { .. }
+                | Splitter::HorizontalRule
+                | Splitter::BulletList
+                | Splitter::Outline
+                | Splitter::BlockQuote { .. }
+                | Splitter::Regex { .. }
+                | Splitter::Chain { .. }
+                | Splitter::WithCap { .. } => {
                     assert_eq!(
                         result.len(),
                         expected_chunks,
@@ -198,7 +3851,9 @@ mod tests {
                         expected_chunks
                     );
                 }
-                Splitter::MaxWords { .. } | Splitter::MaxChars { .. } => {
+                Splitter::MaxWords { .. }
+                | Splitter::MaxChars { .. }
+                | Splitter::MaxBytes { .. } => {
                     assert_eq!(
                         result.len(),
                         expected_chunks,
@@ -225,8 +3880,26 @@ mod tests {
         let splitters = vec![
             Splitter::NewLine,
             Splitter::EmptyLine,
-            Splitter::MaxWords { max_words: 5 },
-            Splitter::MaxChars { max_chars: 10 },
+            Splitter::MaxWords {
+                max_words: 5,
+                respect_sentences: true,
+                overlap: 0,
+                segmentation: WordSegmentation::Whitespace,
+                balanced: false,
+            },
+            Splitter::MaxChars {
+                max_chars: 10,
+                word_boundary: true,
+                overlap: 0,
+                unit: CharUnit::Chars,
+            },
+            Splitter::MarkdownHeadings { max_level: 2 },
+            Splitter::HorizontalRule,
+            Splitter::BulletList,
+            Splitter::Regex {
+                pattern: "@@".to_string(),
+                keep_delimiter: false,
+            },
         ];
 
         for splitter in splitters {
@@ -242,7 +3915,13 @@ mod tests {
     #[case::large_chunks(20)]
     fn test_max_words_property_no_empty_chunks(#[case] max_words: usize) {
         let text = "one two three four five six seven eight nine ten";
-        let splitter = Splitter::MaxWords { max_words };
+        let splitter = Splitter::MaxWords {
+            max_words,
+            respect_sentences: false,
+            overlap: 0,
+            segmentation: WordSegmentation::Whitespace,
+            balanced: false,
+        };
         let result = splitter.split(text);
 
         // Property: No chunk should be empty
@@ -252,7 +3931,7 @@ mod tests {
 
         // Property: Each chunk should have at most max_words words
         for chunk in &result {
-            let word_count = chunk.split_whitespace().count();
+            let word_count = textmetrics::words(chunk).count();
             assert!(word_count <= max_words);
         }
     }
@@ -261,9 +3940,17 @@ mod tests {
     #[case::small_chunks(1)]
     #[case::medium_chunks(5)]
     #[case::large_chunks(20)]
-    fn test_max_chars_property_no_empty_chunks(#[case] max_chars: usize) {
-        let text = "abcdefghijklmnopqrstuvwxyz";
-        let splitter = Splitter::MaxChars { max_chars };
+    fn test_max_chars_property_no_empty_chunks(
+        #[case] max_chars: usize,
+        #[values(false, true)] word_boundary: bool,
+    ) {
+        let text = "abcdefghijklmnopqrstuvwxyz and then some shorter words";
+        let splitter = Splitter::MaxChars {
+            max_chars,
+            word_boundary,
+            overlap: 0,
+            unit: CharUnit::Chars,
+        };
         let result = splitter.split(text);
 
         // Property: No chunk should be empty
@@ -273,17 +3960,59 @@ mod tests {
 
         // Property: Each chunk should have at most max_chars characters
         for chunk in &result {
-            let char_count = chunk.chars().count();
+            let char_count = textmetrics::chars(chunk);
             assert!(char_count <= max_chars);
         }
+
+        // Property: word-boundary chunks never start or end with whitespace
+        if word_boundary {
+            for chunk in &result {
+                assert_eq!(chunk.trim(), chunk);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::minimum_chunks(4)]
+    #[case::small_chunks(7)]
+    #[case::large_chunks(64)]
+    fn test_max_bytes_property_no_empty_chunks(#[case] max_bytes: usize) {
+        let text = "Grüße aus Köln 🚀 日本語のテキスト and then some shorter words 👨‍👩‍👧‍👦";
+        let splitter = Splitter::MaxBytes { max_bytes };
+        let result = splitter.split(text);
+
+        // Property: No chunk should be empty
+        for chunk in &result {
+            assert!(!chunk.is_empty());
+        }
+
+        // Property: Each chunk should have at most max_bytes bytes
+        for chunk in &result {
+            assert!(chunk.len() <= max_bytes);
+        }
+
+        // Property: no text other than whitespace is lost
+        assert_eq!(result.concat().replace(' ', ""), text.replace(' ', ""));
     }
 
     // Round-trip testing for serialization
     #[rstest]
     #[case(Splitter::NewLine)]
     #[case(Splitter::EmptyLine)]
-    #[case(Splitter::MaxWords { max_words: 42 })]
-    #[case(Splitter::MaxChars { max_chars: 123 })]
+    #[case(Splitter::MaxWords { max_words: 42, respect_sentences: false, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false })]
+    #[case(Splitter::MaxWords { max_words: 4, respect_sentences: true, overlap: 0, segmentation: WordSegmentation::Whitespace, balanced: false })]
+    #[case(Splitter::MaxChars { max_chars: 123, word_boundary: false, overlap: 0, unit: CharUnit::Chars })]
+    #[case(Splitter::MaxChars { max_chars: 12, word_boundary: true, overlap: 0, unit: CharUnit::Chars })]
+    #[case(Splitter::MaxBytes { max_bytes: 200 })]
+    #[case(Splitter::MarkdownHeadings { max_level: 3 })]
+    #[case(Splitter::OrgHeadings { max_level: 2 })]
+    #[case(Splitter::WithCap { splitter: Box::new(Splitter::EmptyLine), max_chunk_words: 3 })]
+    #[case(Splitter::HorizontalRule)]
+    #[case(Splitter::BulletList)]
+    #[case(Splitter::Outline)]
+    #[case(Splitter::BlockQuote { include_prose: true })]
+    #[case(Splitter::Chain { splitters: vec![Splitter::NewLine, Splitter::MaxChars { max_chars: 8, word_boundary: true, overlap: 0, unit: CharUnit::Chars }] })]
+    #[case(Splitter::Regex { pattern: r"\bwith\b".to_string(), keep_delimiter: true })]
     fn test_serialization_roundtrip(#[case] original: Splitter) {
         let json = serde_json::to_string(&original).unwrap();
         let deserialized: Splitter = serde_json::from_str(&json).unwrap();
@@ -295,4 +4024,68 @@ mod tests {
 
         assert_eq!(original_result, deserialized_result);
     }
+
+    /// Variant names serde accepts for the `type` tag, read from the error it
+    /// reports for an unknown one so the list always matches the enum.
+    fn serde_type_names() -> Vec<String> {
+        let error = serde_json::from_str::<Splitter>(r#"{"type":"__unknown__"}"#)
+            .unwrap_err()
+            .to_string();
+        let expected = error.split("expected one of ").nth(1).unwrap();
+        expected
+            .split(", ")
+            .map(|name| name.split('`').nth(1).unwrap().to_string())
+            .collect()
+    }
+
+    #[rstest]
+    fn test_descriptors_cover_every_variant_once() {
+        let mut described = Splitter::descriptors()
+            .iter()
+            .map(|descriptor| descriptor.type_name.to_string())
+            .collect::<Vec<_>>();
+        let mut variants = serde_type_names();
+        described.sort();
+        variants.sort();
+        assert_eq!(described, variants);
+    }
+
+    #[rstest]
+    fn test_descriptor_config_fields_are_accepted() {
+        for descriptor in Splitter::descriptors() {
+            let mut json = serde_json::json!({ "type": descriptor.type_name });
+            for field in &descriptor.config_fields {
+                let value = match &field.default {
+                    Some(default) => serde_json::from_str(default).unwrap(),
+                    None => match field.name {
+                        "pattern" => serde_json::json!("x"),
+                        "splitters" => serde_json::json!([{ "type": "newline" }]),
+                        "max_bytes" => serde_json::json!(MIN_MAX_BYTES),
+                        "max_level" => serde_json::json!(1),
+                        "splitter" => serde_json::json!({ "type": "newline" }),
+                        "max_chunk_words" => serde_json::json!(1),
+                        other => panic!("no sample value for required field `{other}`"),
+                    },
+                };
+                json[field.name] = value;
+            }
+            let splitter: Splitter = serde_json::from_value(json).unwrap();
+            assert_eq!(splitter.validate(), Ok(()), "{}", descriptor.type_name);
+        }
+    }
+
+    #[rstest]
+    fn test_descriptor_config_summary() {
+        let max_words = Splitter::descriptors()
+            .into_iter()
+            .find(|descriptor| descriptor.type_name == "max_words")
+            .unwrap();
+        let json = serde_json::to_value(&max_words).unwrap();
+        assert_eq!(json["name"], "Max Words Splitter");
+        assert_eq!(json["config"]["max_words"], "number (default: 50)");
+        assert_eq!(
+            json["config"]["segmentation"],
+            "\"whitespace\" | \"unicode\" (default: \"whitespace\") - unicode finds words in Chinese and Japanese text"
+        );
+    }
 }
@@ -0,0 +1,62 @@
+use crate::error::AppError;
+use worker::{KvStore, RouteContext};
+
+/// KV namespace holding sessions, tokens and short-lived OAuth markers.
+pub const TOKENS: &str = "TOKENS";
+
+/// Every KV namespace the worker expects to be bound.
+pub const BINDINGS: &[&str] = &[TOKENS];
+
+/// Anything that can resolve a KV namespace binding by name.
+pub trait KvSource {
+    fn kv(&self, binding: &str) -> worker::Result<KvStore>;
+}
+
+impl<D> KvSource for RouteContext<D> {
+    fn kv(&self, binding: &str) -> worker::Result<KvStore> {
+        RouteContext::kv(self, binding)
+    }
+}
+
+/// Looks up a KV namespace, reporting a missing binding as a configuration error.
+pub fn kv_store(source: &impl KvSource, binding: &str) -> Result<KvStore, AppError> {
+    source
+        .kv(binding)
+        .map_err(|_| AppError::StorageNotConfigured(binding.to_string()))
+}
+
+/// Reports whether each expected binding is present, for `/health?deep=true`.
+pub fn binding_status(source: &impl KvSource) -> Vec<(&'static str, bool)> {
+    BINDINGS
+        .iter()
+        .map(|&binding| (binding, source.kv(binding).is_ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Environment stub without any KV bindings.
+    struct EmptyEnv;
+
+    impl KvSource for EmptyEnv {
+        fn kv(&self, binding: &str) -> worker::Result<KvStore> {
+            Err(worker::Error::from(format!("no binding named {binding}")))
+        }
+    }
+
+    #[rstest]
+    fn test_missing_binding_is_storage_not_configured() {
+        let err = kv_store(&EmptyEnv, TOKENS).unwrap_err();
+        assert!(matches!(&err, AppError::StorageNotConfigured(binding) if binding == TOKENS));
+        assert_eq!(err.status_code(), 503);
+        assert!(err.to_string().contains("storage not configured"));
+    }
+
+    #[rstest]
+    fn test_binding_status_reports_missing_bindings() {
+        assert_eq!(binding_status(&EmptyEnv), vec![(TOKENS, false)]);
+    }
+}
@@ -0,0 +1,10 @@
+//! Helpers shared by the unit tests.
+
+/// Runs a future that never waits, such as those of the fake API clients.
+pub fn resolve<F: Future>(future: F) -> F::Output {
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    match std::pin::pin!(future).poll(&mut context) {
+        std::task::Poll::Ready(output) => output,
+        std::task::Poll::Pending => panic!("future did not resolve immediately"),
+    }
+}
@@ -0,0 +1,253 @@
+//! Shared text measurements used by splitters and slide building.
+//!
+//! Every feature that counts words, characters or positions should go through
+//! these helpers so the measures cannot drift apart between call sites.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Iterates over whitespace-separated words.
+pub fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+}
+
+/// Splits text at Unicode word boundaries (UAX #29) into spans that each run
+/// from the start of one word to the start of the next.
+///
+/// Unlike [`words`], this finds words in scripts written without spaces, such
+/// as Chinese and Japanese. Punctuation and whitespace stay attached to the
+/// preceding word (leading text to the first one), so concatenating the spans
+/// reproduces the text.
+pub fn unicode_word_spans(text: &str) -> Vec<&str> {
+    let mut starts = text
+        .unicode_word_indices()
+        .map(|(start, _)| start)
+        .collect::<Vec<_>>();
+    if starts.is_empty() {
+        return Vec::new();
+    }
+    starts[0] = 0;
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| &text[start..starts.get(i + 1).copied().unwrap_or(text.len())])
+        .collect()
+}
+
+/// Iterates over extended grapheme clusters (user-perceived characters).
+pub fn graphemes(text: &str) -> impl Iterator<Item = &str> {
+    text.graphemes(true)
+}
+
+/// Length of the text in Unicode scalar values, the unit of character limits.
+pub fn chars(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Length of the text in UTF-16 code units, the unit Google Slides indexes by.
+pub fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Estimated visual width: wide (CJK, fullwidth, emoji) graphemes count as two
+/// units, everything else as one.
+pub fn visual_units(text: &str) -> usize {
+    graphemes(text)
+        .map(|grapheme| {
+            let is_wide = grapheme.chars().next().is_some_and(is_wide_char);
+            if is_wide { 2 } else { 1 }
+        })
+        .sum()
+}
+
+/// Returns the longest prefix of `text` containing at most `count` graphemes.
+pub fn prefix_by_graphemes(text: &str, count: usize) -> &str {
+    match text.grapheme_indices(true).nth(count) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Whether most letters in `text` belong to right-to-left scripts.
+///
+/// Only letters count, so digits, punctuation and embedded URLs do not
+/// outvote the text around them; text without letters is left-to-right.
+pub fn is_mostly_rtl(text: &str) -> bool {
+    let (rtl, ltr) = text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .fold((0, 0), |(rtl, ltr), c| {
+            if is_rtl_char(c) {
+                (rtl + 1, ltr)
+            } else {
+                (rtl, ltr + 1)
+            }
+        });
+    rtl > ltr
+}
+
+/// Whether a character belongs to a script written right to left.
+fn is_rtl_char(c: char) -> bool {
+    matches!(
+        u32::from(c),
+        0x0590..=0x05FF        // Hebrew
+        | 0x0600..=0x06FF      // Arabic
+        | 0x0700..=0x074F      // Syriac
+        | 0x0750..=0x077F      // Arabic Supplement
+        | 0x0780..=0x07BF      // Thaana
+        | 0x07C0..=0x07FF      // NKo
+        | 0x0800..=0x08FF      // Samaritan, Mandaic, Arabic Extended
+        | 0xFB1D..=0xFB4F      // Hebrew presentation forms
+        | 0xFB50..=0xFDFF      // Arabic presentation forms A
+        | 0xFE70..=0xFEFF // Arabic presentation forms B
+    )
+}
+
+/// Whether a character is rendered double-width in typical slide fonts.
+fn is_wide_char(c: char) -> bool {
+    matches!(
+        u32::from(c),
+        0x1100..=0x115F        // Hangul Jamo
+        | 0x2E80..=0x303E      // CJK radicals, punctuation
+        | 0x3041..=0x33FF      // Hiragana, Katakana, CJK symbols
+        | 0x3400..=0x4DBF      // CJK Extension A
+        | 0x4E00..=0x9FFF      // CJK Unified Ideographs
+        | 0xA000..=0xA4CF      // Yi
+        | 0xAC00..=0xD7A3      // Hangul syllables
+        | 0xF900..=0xFAFF      // CJK compatibility ideographs
+        | 0xFE30..=0xFE4F      // CJK compatibility forms
+        | 0xFF00..=0xFF60      // Fullwidth forms
+        | 0xFFE0..=0xFFE6      // Fullwidth signs
+        | 0x1F1E6..=0x1F1FF    // Regional indicators (flags)
+        | 0x1F300..=0x1F64F    // Pictographs, emoticons
+        | 0x1F680..=0x1F6FF    // Transport and map symbols
+        | 0x1F900..=0x1F9FF    // Supplemental symbols and pictographs
+        | 0x20000..=0x3FFFD // CJK Extensions B and beyond
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::hebrew("שלום עולם", true)]
+    #[case::arabic("مرحبا بالعالم", true)]
+    #[case::english("Hello world", false)]
+    #[case::mostly_hebrew_with_brand("ברוכים הבאים ל-Google", true)]
+    #[case::mostly_english_with_word("Say שלום to everyone here", false)]
+    #[case::digits_and_url_ignored("שלום 2026 https://example.com", false)]
+    #[case::hebrew_with_digits("שלום עולם 12345", true)]
+    #[case::no_letters("123 - 456", false)]
+    #[case::empty("", false)]
+    fn test_is_mostly_rtl(#[case] text: &str, #[case] expected: bool) {
+        assert_eq!(is_mostly_rtl(text), expected);
+    }
+
+    #[rstest]
+    #[case::empty("", 0)]
+    #[case::single("word", 1)]
+    #[case::mixed_whitespace("  one\ttwo\nthree  four ", 4)]
+    #[case::nbsp_is_whitespace("one\u{00A0}two", 2)]
+    fn test_words(#[case] text: &str, #[case] expected: usize) {
+        assert_eq!(words(text).count(), expected);
+    }
+
+    #[rstest]
+    #[case::empty("", vec![])]
+    #[case::punctuation_only("...", vec![])]
+    #[case::latin("Hello, world!", vec!["Hello, ", "world!"])]
+    #[case::leading_text("  (note) ok", vec!["  (note) ", "ok"])]
+    #[case::chinese("我喜欢读书。", vec!["我", "喜", "欢", "读", "书。"])]
+    #[case::katakana_run("コンピュータを使う", vec!["コンピュータ", "を", "使", "う"])]
+    #[case::mixed("用Rust写代码", vec!["用", "Rust", "写", "代", "码"])]
+    fn test_unicode_word_spans(#[case] text: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(unicode_word_spans(text), expected);
+    }
+
+    #[rstest]
+    #[case::latin("The quick, brown fox.")]
+    #[case::cjk("東京は日本の首都です。人口は多い。")]
+    #[case::mixed("  我们在 GitHub 上 review 代码！ ")]
+    fn test_unicode_word_spans_reproduce_text(#[case] text: &str) {
+        assert_eq!(unicode_word_spans(text).concat(), text);
+    }
+
+    #[rstest]
+    #[case::ascii("hello", 5)]
+    #[case::combining_accent("e\u{0301}te\u{0301}", 3)]
+    #[case::flag("🇫🇷🇩🇪", 2)]
+    #[case::zwj_family("👨‍👩‍👧‍👦", 1)]
+    #[case::skin_tone("👍🏽ok", 3)]
+    #[case::hangul_jamo("\u{1100}\u{1161}\u{11A8}", 1)]
+    #[case::crlf("a\r\nb", 3)]
+    fn test_graphemes(#[case] text: &str, #[case] expected: usize) {
+        assert_eq!(graphemes(text).count(), expected);
+    }
+
+    #[rstest]
+    #[case::empty("", 0)]
+    #[case::ascii("Hello, world", 12)]
+    #[case::latin1("naïve café", 10)]
+    #[case::combining_accent("e\u{0301}", 2)]
+    #[case::astral_cjk("𠀋𡈽𡌛", 3)]
+    #[case::zwj_family("👨‍👩‍👧‍👦", 7)]
+    fn test_chars(#[case] text: &str, #[case] expected: usize) {
+        assert_eq!(chars(text), expected);
+        assert_eq!(chars(text), text.chars().count());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::ascii("Hello, world")]
+    #[case::latin1("naïve café")]
+    #[case::cjk("日本語のテキスト")]
+    #[case::astral_cjk("𠀋𡈽𡌛")]
+    #[case::emoji("Hello 🌍 World 🚀")]
+    #[case::zwj_family("👨‍👩‍👧‍👦 family")]
+    #[case::flags("🇫🇷🇩🇪")]
+    fn test_utf16_len_agrees_with_encode_utf16(#[case] text: &str) {
+        assert_eq!(utf16_len(text), text.encode_utf16().count());
+    }
+
+    #[rstest]
+    #[case::ascii("The quick brown fox")]
+    #[case::punctuation("a-b_c!? 123")]
+    #[case::empty("")]
+    fn test_visual_units_of_ascii_equals_char_count(#[case] text: &str) {
+        assert_eq!(visual_units(text), chars(text));
+    }
+
+    #[rstest]
+    #[case::cjk("日本語", 6)]
+    #[case::fullwidth("ＡＢ", 4)]
+    #[case::hangul("한국", 4)]
+    #[case::mixed("a日b", 4)]
+    #[case::emoji("ok🚀", 4)]
+    #[case::combining_is_one_unit("e\u{0301}", 1)]
+    fn test_visual_units_wide(#[case] text: &str, #[case] expected: usize) {
+        assert_eq!(visual_units(text), expected);
+    }
+
+    #[rstest]
+    #[case::shorter_than_text("hello", 2, "he")]
+    #[case::exact("hello", 5, "hello")]
+    #[case::longer_than_text("hello", 10, "hello")]
+    #[case::zero("hello", 0, "")]
+    #[case::combining_kept_together("e\u{0301}abc", 1, "e\u{0301}")]
+    #[case::zwj_family_not_split("👨‍👩‍👧‍👦👍", 1, "👨‍👩‍👧‍👦")]
+    #[case::flag_not_split("🇫🇷🇩🇪", 1, "🇫🇷")]
+    fn test_prefix_by_graphemes(#[case] text: &str, #[case] count: usize, #[case] expected: &str) {
+        assert_eq!(prefix_by_graphemes(text, count), expected);
+    }
+
+    #[rstest]
+    fn test_prefix_by_graphemes_is_prefix() {
+        let text = "Zoë 👨‍👩‍👧‍👦 日本 🇫🇷!";
+        for count in 0..=graphemes(text).count() {
+            let prefix = prefix_by_graphemes(text, count);
+            assert!(text.starts_with(prefix));
+            assert_eq!(graphemes(prefix).count(), count);
+        }
+    }
+}